@@ -0,0 +1,58 @@
+// 在自己的窗口中嵌入图片查看控件：cargo run --example embed -- 图片路径
+
+use gtk4::prelude::*;
+use image_viewer::ImageViewerWidget;
+
+fn main() -> glib::ExitCode {
+    let app = gtk4::Application::builder()
+        .application_id("com.example.EmbedImageViewer")
+        .flags(gio::ApplicationFlags::HANDLES_OPEN)
+        .build();
+    app.connect_activate(|app| build(app, None));
+    app.connect_open(|app, files, _| build(app, files.first().and_then(|f| f.path())));
+    app.run()
+}
+
+fn build(app: &gtk4::Application, path: Option<std::path::PathBuf>) {
+    let viewer = ImageViewerWidget::new();
+    let zoom = gtk4::Label::new(None);
+    viewer.connect_zoom_changed({
+        let zoom = zoom.clone();
+        move |_, scale| zoom.set_text(&format!("{:.0}%", scale * 100.0))
+    });
+
+    let rotate = gtk4::Button::from_icon_name("object-rotate-right-symbolic");
+    rotate.connect_clicked({
+        let viewer = viewer.clone();
+        move |_| viewer.rotate(true)
+    });
+    let copy = gtk4::Button::from_icon_name("edit-copy-symbolic");
+    copy.connect_clicked({
+        let viewer = viewer.clone();
+        move |_| { viewer.copy_to_clipboard(); }
+    });
+    let fit = gtk4::Button::from_icon_name("zoom-fit-best-symbolic");
+    fit.connect_clicked({
+        let viewer = viewer.clone();
+        move |_| viewer.fit()
+    });
+
+    let header = gtk4::HeaderBar::new();
+    header.pack_start(&rotate);
+    header.pack_start(&fit);
+    header.pack_start(&copy);
+    header.pack_end(&zoom);
+
+    let window = gtk4::ApplicationWindow::builder()
+        .application(app)
+        .title("嵌入示例")
+        .default_width(800)
+        .default_height(600)
+        .child(viewer.widget())
+        .build();
+    window.set_titlebar(Some(&header));
+    if let Some(path) = path && let Err(e) = viewer.load_file(&path) {
+        eprintln!("无法打开 {}: {}", path.display(), e);
+    }
+    window.present();
+}
@@ -0,0 +1,115 @@
+// 普通窗口保持在最前：本次运行内有效，之后新建的普通窗口同样保持在最前
+// X11 下向窗口管理器发送 _NET_WM_STATE_ABOVE 请求；Wayland 没有对应的协议，
+// 由调用方把窗口重建为 layer-shell 的 Top 层表面（仍带标题栏与全部控件）
+// X11 函数在运行时从已载入的库中查找，只支持 Wayland 的 GTK 构建也能正常链接
+
+use gtk4::gdk;
+use gtk4::prelude::*;
+use std::cell::Cell;
+use std::ffi::{c_char, c_int, c_long, c_ulong, c_void};
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn enabled() -> bool {
+    ENABLED.get()
+}
+
+pub fn set_enabled(on: bool) {
+    ENABLED.set(on);
+}
+
+pub fn is_x11(display: &gdk::Display) -> bool {
+    display.type_().name() == "GdkX11Display"
+}
+
+unsafe extern "C" {
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+}
+
+// Xlib 的 XClientMessageEvent；XEvent 是 24 个 long 大小的联合体
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ClientMessage {
+    kind: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: *mut c_void,
+    window: c_ulong,
+    message_type: c_ulong,
+    format: c_int,
+    data: [c_long; 5],
+}
+
+#[repr(C)]
+union XEvent {
+    client: ClientMessage,
+    pad: [c_long; 24],
+}
+
+const CLIENT_MESSAGE: c_int = 33;
+const SUBSTRUCTURE_NOTIFY_MASK: c_long = 1 << 19;
+const SUBSTRUCTURE_REDIRECT_MASK: c_long = 1 << 20;
+// _NET_WM_STATE 的操作：0 移除，1 添加；来源 1 表示普通应用
+const NET_WM_STATE_REMOVE: c_long = 0;
+const NET_WM_STATE_ADD: c_long = 1;
+
+// 在进程已载入的库（GTK 与其载入的 libX11）中查找函数
+pub fn symbol(name: &std::ffi::CStr) -> Option<*mut c_void> {
+    // SAFETY: RTLD_DEFAULT（空指针）在全局符号中查找，name 以 NUL 结尾
+    let ptr = unsafe { dlsym(std::ptr::null_mut(), name.as_ptr()) };
+    (!ptr.is_null()).then_some(ptr)
+}
+
+// X11 下请求窗口管理器把已映射的窗口保持在最前或取消；找不到 X11 函数时返回 false
+pub fn set_x11(surface: &gdk::Surface, above: bool) -> bool {
+    type GetXDisplay = unsafe extern "C" fn(*mut c_void) -> *mut c_void;
+    type GetXid = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+    type InternAtom = unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_ulong;
+    type RootWindow = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+    type SendEvent = unsafe extern "C" fn(*mut c_void, c_ulong, c_int, c_long, *mut XEvent) -> c_int;
+    type Flush = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+    let (Some(get_xdisplay), Some(get_xid), Some(intern_atom), Some(root_window), Some(send_event), Some(flush)) = (
+        symbol(c"gdk_x11_display_get_xdisplay"),
+        symbol(c"gdk_x11_surface_get_xid"),
+        symbol(c"XInternAtom"),
+        symbol(c"XDefaultRootWindow"),
+        symbol(c"XSendEvent"),
+        symbol(c"XFlush"),
+    ) else {
+        return false;
+    };
+    // SAFETY: 以上符号由 GTK 的 X11 后端与 libX11 导出，签名与其头文件一致；
+    // surface 与其 display 属于 X11 后端（调用方已用 is_x11 检查），在调用期间保持有效
+    unsafe {
+        let get_xdisplay = std::mem::transmute::<*mut c_void, GetXDisplay>(get_xdisplay);
+        let get_xid = std::mem::transmute::<*mut c_void, GetXid>(get_xid);
+        let intern_atom = std::mem::transmute::<*mut c_void, InternAtom>(intern_atom);
+        let root_window = std::mem::transmute::<*mut c_void, RootWindow>(root_window);
+        let send_event = std::mem::transmute::<*mut c_void, SendEvent>(send_event);
+        let flush = std::mem::transmute::<*mut c_void, Flush>(flush);
+
+        let xdisplay = get_xdisplay(surface.display().as_ptr() as *mut c_void);
+        let xid = get_xid(surface.as_ptr() as *mut c_void);
+        if xdisplay.is_null() || xid == 0 { return false; }
+        let state = intern_atom(xdisplay, c"_NET_WM_STATE".as_ptr(), 0);
+        let above_atom = intern_atom(xdisplay, c"_NET_WM_STATE_ABOVE".as_ptr(), 0);
+        let action = if above { NET_WM_STATE_ADD } else { NET_WM_STATE_REMOVE };
+        let mut event = XEvent { pad: [0; 24] };
+        event.client = ClientMessage {
+            kind: CLIENT_MESSAGE,
+            serial: 0,
+            send_event: 1,
+            display: xdisplay,
+            window: xid,
+            message_type: state,
+            format: 32,
+            data: [action, above_atom as c_long, 0, 1, 0],
+        };
+        send_event(xdisplay, root_window(xdisplay), 0, SUBSTRUCTURE_REDIRECT_MASK | SUBSTRUCTURE_NOTIFY_MASK, &mut event);
+        flush(xdisplay);
+    }
+    true
+}
@@ -0,0 +1,185 @@
+// 动画播放（GIF、WebP、APNG）：工作线程循环逐帧解码，经容量为 2 的通道交给主线程，
+// 解码最多领先两帧，占用的内存与帧数无关
+// 主线程在收到下一帧时就转换成备用纹理，到显示时刻只把它换上；绘制时纹理变化后才重建一次缓存表面，
+// 缩放、平移期间的重绘沿用当前帧，不经过解码
+// 各帧的显示时刻从开始播放时累计，落后时跳过已经来不及显示的帧，而不是把延迟顺延下去
+
+use crate::extract;
+use gtk4::{gdk, glib};
+use image::RgbaImage;
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::{Rc, Weak};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::time::{Duration, Instant};
+
+// 帧延迟为 0 或过短（不到 20 ms）时按 100 ms 显示，与浏览器一致
+const MIN_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+// 解码跟不上时再次查看的间隔
+const POLL: Duration = Duration::from_millis(5);
+// 窗口不可见时查看是否恢复的间隔
+const HIDDEN_POLL: Duration = Duration::from_millis(250);
+// 落后超过该时间（系统卡顿、窗口恢复可见）时不再追赶，从当前时刻重新计时
+const MAX_LAG: Duration = Duration::from_secs(1);
+
+// 帧、显示时长、帧序号（从 1 开始，与文件中的帧顺序一致）
+type Decoded = Result<(RgbaImage, Duration, usize), String>;
+type Frame = (gdk::Texture, Duration, usize);
+
+enum Next {
+    Ready(Frame),
+    // 下一帧还没有解码好
+    Pending,
+    // 解码出错或文件已无法读取，停在当前帧
+    Finished,
+}
+
+struct Inner {
+    frames: Receiver<Decoded>,
+    standby: RefCell<Option<Frame>>,
+    // 下一帧的显示时刻
+    due: Cell<Instant>,
+    paused: Cell<bool>,
+    timer: RefCell<Option<glib::SourceId>>,
+    // 显示一帧（纹理, 帧序号），返回 false 时停止播放（如已载入别的图片）
+    show: std::boxed::Box<dyn Fn(gdk::Texture, usize) -> bool>,
+    // 窗口不可见时不换帧
+    hidden: std::boxed::Box<dyn Fn() -> bool>,
+}
+
+// 丢弃即停止播放，解码线程随之退出
+pub struct Player {
+    inner: Rc<Inner>,
+}
+
+impl Player {
+    // 在后台开始解码；创建时为暂停状态，set_paused(false) 后开始播放
+    pub fn new(
+        path: &Path,
+        show: impl Fn(gdk::Texture, usize) -> bool + 'static,
+        hidden: impl Fn() -> bool + 'static,
+    ) -> Self {
+        let (tx, rx) = mpsc::sync_channel(2);
+        let path = path.to_path_buf();
+        std::thread::spawn(move || decode_loop(&path, &tx));
+        let inner = Inner {
+            frames: rx,
+            standby: RefCell::new(None),
+            due: Cell::new(Instant::now()),
+            paused: Cell::new(true),
+            timer: RefCell::new(None),
+            show: std::boxed::Box::new(show),
+            hidden: std::boxed::Box::new(hidden),
+        };
+        Self { inner: Rc::new(inner) }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.get()
+    }
+
+    // 暂停时停在当前帧；继续时立即换上下一帧并从此重新计时
+    pub fn set_paused(&self, paused: bool) {
+        if self.inner.paused.replace(paused) == paused { return; }
+        if let Some(id) = self.inner.timer.borrow_mut().take() { id.remove(); }
+        if !paused {
+            self.inner.due.set(Instant::now());
+            schedule(&self.inner, Duration::ZERO);
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        if let Some(id) = self.inner.timer.borrow_mut().take() { id.remove(); }
+    }
+}
+
+fn schedule(inner: &Rc<Inner>, after: Duration) {
+    let weak: Weak<Inner> = Rc::downgrade(inner);
+    let id = glib::timeout_add_local_once(after, move || {
+        if let Some(inner) = weak.upgrade() { tick(&inner); }
+    });
+    *inner.timer.borrow_mut() = Some(id);
+}
+
+fn receive(inner: &Inner) -> Next {
+    match inner.frames.try_recv() {
+        Ok(Ok((img, delay, index))) => {
+            let delay = if delay < MIN_DELAY { DEFAULT_DELAY } else { delay };
+            Next::Ready((crate::texture_from_rgba(img), delay, index))
+        }
+        Ok(Err(e)) => {
+            eprintln!("动画解码失败，停在当前帧: {}", e);
+            Next::Finished
+        }
+        Err(TryRecvError::Empty) => Next::Pending,
+        Err(TryRecvError::Disconnected) => Next::Finished,
+    }
+}
+
+fn tick(inner: &Rc<Inner>) {
+    // 单次定时器已触发，只清除记录
+    inner.timer.borrow_mut().take();
+    let now = Instant::now();
+    if (inner.hidden)() {
+        inner.due.set(now);
+        schedule(inner, HIDDEN_POLL);
+        return;
+    }
+    if now.saturating_duration_since(inner.due.get()) > MAX_LAG { inner.due.set(now); }
+    let standby = inner.standby.borrow_mut().take();
+    let mut frame = match standby.map_or_else(|| receive(inner), Next::Ready) {
+        Next::Ready(frame) => frame,
+        Next::Pending => return schedule(inner, POLL),
+        Next::Finished => return,
+    };
+    // 这一帧的显示时段已经过去且后一帧已解码好时跳过它
+    let mut ends = inner.due.get() + frame.1;
+    let mut after = None;
+    while ends <= now {
+        match receive(inner) {
+            Next::Ready(next) => {
+                inner.due.set(ends);
+                frame = next;
+                ends = inner.due.get() + frame.1;
+            }
+            other => {
+                after = Some(other);
+                break;
+            }
+        }
+    }
+    let (texture, _, index) = frame;
+    if !(inner.show)(texture, index) { return; }
+    inner.due.set(ends);
+    // 换上后立即准备下一帧的纹理
+    match after.unwrap_or_else(|| receive(inner)) {
+        Next::Ready(next) => *inner.standby.borrow_mut() = Some(next),
+        Next::Pending => {}
+        Next::Finished => return,
+    }
+    schedule(inner, ends.saturating_duration_since(Instant::now()));
+}
+
+// 循环解码，直到主线程不再接收（播放器被丢弃）或出错
+fn decode_loop(path: &Path, tx: &SyncSender<Decoded>) {
+    loop {
+        let frames = match extract::animation_frames(path) {
+            Ok(Some(frames)) => frames,
+            Ok(None) => return,
+            Err(e) => {
+                tx.send(Err(e)).ok();
+                return;
+            }
+        };
+        let mut count = 0;
+        for (i, frame) in frames.enumerate() {
+            let failed = frame.is_err();
+            if tx.send(frame.map(|(img, delay)| (img, delay, i + 1))).is_err() || failed { return; }
+            count += 1;
+        }
+        if count == 0 { return; }
+    }
+}
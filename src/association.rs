@@ -0,0 +1,78 @@
+// 默认程序关联：把本程序注册为常见图片类型的默认打开方式
+
+use gio::prelude::*;
+
+// 桌面入口文件名（安装到 applications 目录后才能被找到）
+const DESKTOP_ID: &str = "image-viewer.desktop";
+
+// 与桌面入口的 MimeType 保持一致
+pub const MIME_TYPES: &[&str] = &[
+    "image/png", "image/jpeg", "image/gif", "image/bmp",
+    "image/webp", "image/tiff", "image/svg+xml",
+];
+
+// 操作结果：已更改的类型与失败的类型（附原因）
+pub struct Outcome {
+    pub changed: Vec<&'static str>,
+    pub failed: Vec<(&'static str, String)>,
+}
+
+impl Outcome {
+    pub fn describe(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.changed.is_empty() {
+            lines.push(format!("已更改: {}", self.changed.join(", ")));
+        }
+        for (mime, err) in &self.failed {
+            lines.push(format!("{} 失败: {}", mime, err));
+        }
+        if lines.is_empty() {
+            lines.push("没有需要更改的类型".to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+// 查找本程序的桌面入口；沙盒中或未安装时给出说明
+fn app_info() -> Result<gio::DesktopAppInfo, String> {
+    if std::path::Path::new("/.flatpak-info").exists() {
+        return Err("在 Flatpak 沙盒中运行，无法修改默认程序，请在系统设置中更改".to_string());
+    }
+    gio::DesktopAppInfo::new(DESKTOP_ID).ok_or_else(|| {
+        format!("未找到桌面入口 {}，请先将其安装到 ~/.local/share/applications 或 /usr/share/applications", DESKTOP_ID)
+    })
+}
+
+fn is_ours(mime: &str, app: &gio::DesktopAppInfo) -> bool {
+    gio::AppInfo::default_for_type(mime, false).is_some_and(|d| d.id() == app.id())
+}
+
+// 设为默认程序，已是默认的类型不计入更改
+pub fn register() -> Result<Outcome, String> {
+    let app = app_info()?;
+    let mut outcome = Outcome { changed: Vec::new(), failed: Vec::new() };
+    for &mime in MIME_TYPES {
+        if is_ours(mime, &app) { continue; }
+        match app.set_as_default_for_type(mime) {
+            Ok(()) => outcome.changed.push(mime),
+            Err(e) => outcome.failed.push((mime, e.to_string())),
+        }
+    }
+    Ok(outcome)
+}
+
+// 取消默认：仅重置当前默认为本程序的类型
+pub fn unregister() -> Result<Outcome, String> {
+    let app = app_info()?;
+    let mut outcome = Outcome { changed: Vec::new(), failed: Vec::new() };
+    for &mime in MIME_TYPES {
+        if !is_ours(mime, &app) { continue; }
+        gio::AppInfo::reset_type_associations(mime);
+        if is_ours(mime, &app) {
+            outcome.failed.push((mime, "重置后仍为默认程序（可能由系统级配置指定）".to_string()));
+        } else {
+            outcome.changed.push(mime);
+        }
+    }
+    Ok(outcome)
+}
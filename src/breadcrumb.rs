@@ -0,0 +1,100 @@
+// 标题栏路径：文件名始终完整显示，上级目录按段显示为可点击的按钮，空间不足时先压缩目录
+// 放在 WindowHandle 中，按钮之外的区域仍可拖动窗口
+
+use gtk4::prelude::*;
+use gtk4::{Button, Label, Orientation};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// 单个目录段最多显示的字符数，更长的在中间省略
+const SEGMENT_MAX_CHARS: i32 = 24;
+
+type DirectoryFn = Rc<dyn Fn(&Path)>;
+
+#[derive(Clone)]
+pub struct Breadcrumb {
+    container: gtk4::Box,
+    // 上次显示的内容，相同时不重建
+    shown: Rc<RefCell<(String, String)>>,
+    on_directory: Rc<RefCell<Option<DirectoryFn>>>,
+}
+
+impl Breadcrumb {
+    pub fn new() -> Self {
+        let container = gtk4::Box::new(Orientation::Horizontal, 0);
+        container.add_css_class("path-label");
+        container.set_hexpand(true);
+        container.set_halign(gtk4::Align::Start);
+        Self { container, shown: Rc::default(), on_directory: Rc::default() }
+    }
+
+    pub fn widget(&self) -> &gtk4::Box {
+        &self.container
+    }
+
+    // 点击目录段时回调
+    pub fn connect_directory_clicked(&self, f: impl Fn(&Path) + 'static) {
+        *self.on_directory.borrow_mut() = Some(Rc::new(f));
+    }
+
+    // 显示路径；suffix 附在文件名之后（如连拍位置）。不是文件路径的名称（标准输入等）原样显示
+    pub fn set_path(&self, path: &str, suffix: &str) {
+        if *self.shown.borrow() == (path.to_string(), suffix.to_string()) { return; }
+        *self.shown.borrow_mut() = (path.to_string(), suffix.to_string());
+        while let Some(child) = self.container.first_child() {
+            self.container.remove(&child);
+        }
+        self.container.set_tooltip_text((!path.is_empty()).then_some(path));
+
+        let p = Path::new(path);
+        let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.to_string());
+        if p.is_file() {
+            let dir = p.canonicalize().ok().and_then(|c| c.parent().map(Path::to_path_buf));
+            for (label, dir) in dir.as_deref().map(segments).unwrap_or_default() {
+                self.append_segment(&label, dir);
+            }
+        }
+        let file_label = Label::new(Some(&format!("{}{}", name, suffix)));
+        file_label.add_css_class("breadcrumb-file");
+        self.container.append(&file_label);
+    }
+
+    fn append_segment(&self, label: &str, dir: PathBuf) {
+        let btn = Button::new();
+        btn.add_css_class("flat");
+        btn.add_css_class("breadcrumb-dir");
+        btn.set_tooltip_text(Some(&dir.to_string_lossy()));
+        let text = Label::builder()
+            .label(label)
+            .ellipsize(gtk4::pango::EllipsizeMode::Middle)
+            .max_width_chars(SEGMENT_MAX_CHARS)
+            .build();
+        btn.set_child(Some(&text));
+        let on_directory = self.on_directory.clone();
+        btn.connect_clicked(move |_| {
+            let f = on_directory.borrow().clone();
+            if let Some(f) = f { f(&dir); }
+        });
+        self.container.append(&btn);
+        let separator = Label::new(Some("›"));
+        separator.add_css_class("breadcrumb-separator");
+        self.container.append(&separator);
+    }
+}
+
+// 目录的各段（显示文本, 完整路径）；主目录下的路径以 ~ 开头
+fn segments(dir: &Path) -> Vec<(String, PathBuf)> {
+    let home = glib::home_dir();
+    let (start, rest) = match dir.strip_prefix(&home) {
+        Ok(rest) => (("~".to_string(), home.clone()), rest),
+        Err(_) => (("/".to_string(), PathBuf::from("/")), dir.strip_prefix("/").unwrap_or(dir)),
+    };
+    let mut out = vec![start];
+    let mut current = out[0].1.clone();
+    for part in rest.iter() {
+        current.push(part);
+        out.push((part.to_string_lossy().to_string(), current.clone()));
+    }
+    out
+}
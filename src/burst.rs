@@ -0,0 +1,146 @@
+// 连拍/序列分组：按自然顺序排列的文件名中，只有末尾计数器不同的连续文件视为一组
+// （如 IMG_0001.JPG … IMG_0087.JPG）
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+// 相邻计数器允许的最大间隔：删掉几张模糊的照片后仍算同一组
+const MAX_GAP: u64 = 3;
+
+// 文件名拆分为：计数器之前的部分、计数器数字、扩展名（小写）
+struct Counter<'a> {
+    prefix: &'a str,
+    digits: &'a str,
+    value: u64,
+    ext: String,
+}
+
+fn parse(name: &str) -> Option<Counter<'_>> {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, ext.to_ascii_lowercase()),
+        _ => (name, String::new()),
+    };
+    let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &stem[prefix.len()..];
+    let value = digits.parse().ok()?;
+    Some(Counter { prefix, digits, value, ext })
+}
+
+// 补零的计数器宽度固定（0001），不补零的宽度随数值增长（9、10）
+fn is_padded(digits: &str) -> bool {
+    digits.len() > 1 && digits.starts_with('0')
+}
+
+fn continues(a: &Counter, b: &Counter) -> bool {
+    a.prefix == b.prefix
+        && a.ext == b.ext
+        && b.value > a.value
+        && b.value - a.value <= MAX_GAP
+        && (a.digits.len() == b.digits.len() || (!is_padded(a.digits) && !is_padded(b.digits)))
+}
+
+// 把名称列表划分为连续的分组，覆盖所有下标；不属于任何连拍的文件单独成组
+pub fn groups<S: AsRef<str>>(names: &[S]) -> Vec<Range<usize>> {
+    let counters: Vec<Option<Counter>> = names.iter().map(|n| parse(n.as_ref())).collect();
+    let mut out = Vec::new();
+    let mut start = 0;
+    for i in 1..=names.len() {
+        let joined = i < names.len() && match (&counters[i - 1], &counters[i]) {
+            (Some(a), Some(b)) => continues(a, b),
+            _ => false,
+        };
+        if !joined {
+            out.push(start..i);
+            start = i;
+        }
+    }
+    out
+}
+
+// 自然排序：数字部分按数值比较，IMG_9 排在 IMG_10 之前；其余部分不区分大小写
+// 只差大小写或补零（IMG_9 与 img_9、IMG_09）时，按第一处这样的差别排序
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    let mut tie = Ordering::Equal;
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len()).then(tie);
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let da = a.len() - a.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let db = b.len() - b.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let (na, nb) = (a[..da].trim_start_matches('0'), b[..db].trim_start_matches('0'));
+            let ord = na.len().cmp(&nb.len()).then_with(|| na.cmp(nb));
+            if ord != Ordering::Equal { return ord; }
+            tie = tie.then(da.cmp(&db));
+            (a, b) = (&a[da..], &b[db..]);
+        } else {
+            let ord = ca.to_lowercase().cmp(cb.to_lowercase());
+            if ord != Ordering::Equal { return ord; }
+            tie = tie.then(ca.cmp(&cb));
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_run_is_one_group() {
+        assert_eq!(groups(&["IMG_0001.JPG", "IMG_0002.JPG", "IMG_0003.JPG"]), vec![0..3]);
+    }
+
+    #[test]
+    fn empty_list_has_no_groups() {
+        assert!(groups::<&str>(&[]).is_empty());
+    }
+
+    #[test]
+    fn padding_must_match() {
+        // 补零宽度相同，跨过 9 → 10
+        assert_eq!(groups(&["IMG_0009.jpg", "IMG_0010.jpg"]), vec![0..2]);
+        // 不补零的计数器宽度随数值增长
+        assert_eq!(groups(&["shot9.png", "shot10.png", "shot11.png"]), vec![0..3]);
+        // 补零宽度不同，或一个补零一个不补零，属于不同的序列
+        assert_eq!(groups(&["a_09.png", "a_010.png"]), vec![0..1, 1..2]);
+        assert_eq!(groups(&["b_009.png", "b_10.png"]), vec![0..1, 1..2]);
+    }
+
+    #[test]
+    fn small_gaps_join_large_gaps_split() {
+        // 删掉几张后仍是一组；间隔超过 MAX_GAP 则断开
+        assert_eq!(groups(&["IMG_0001.JPG", "IMG_0002.JPG", "IMG_0005.JPG", "IMG_0009.JPG", "IMG_0010.JPG"]), vec![0..3, 3..5]);
+        // 计数器相同或倒退不算连续
+        assert_eq!(groups(&["x1.jpg", "x1.jpg", "x0.jpg"]), vec![0..1, 1..2, 2..3]);
+    }
+
+    #[test]
+    fn extensions_compare_case_insensitively_but_must_match() {
+        assert_eq!(groups(&["IMG_0001.JPG", "IMG_0002.jpg", "IMG_0003.Jpg"]), vec![0..3]);
+        assert_eq!(groups(&["IMG_0001.jpg", "IMG_0002.jpg", "IMG_0003.png", "IMG_0004.png"]), vec![0..2, 2..4]);
+        // RAW 与 JPEG 交替排列时每张的下一张扩展名都不同，不组成连拍
+        assert_eq!(groups(&["IMG_0001.CR2", "IMG_0001.JPG", "IMG_0002.CR2", "IMG_0002.JPG"]), vec![0..1, 1..2, 2..3, 3..4]);
+    }
+
+    #[test]
+    fn prefix_must_match_and_names_without_counter_stand_alone() {
+        assert_eq!(groups(&["a1.jpg", "a2.jpg", "b3.jpg", "b4.jpg"]), vec![0..2, 2..4]);
+        assert_eq!(groups(&["cover.jpg", "IMG_.jpg", "IMG_0001.jpg", "notes"]), vec![0..1, 1..2, 2..3, 3..4]);
+        // 没有扩展名的文件同样可以组成序列
+        assert_eq!(groups(&["frame1", "frame2"]), vec![0..2]);
+    }
+
+    #[test]
+    fn natural_order() {
+        let mut names = vec!["IMG_10.jpg", "img_9.jpg", "IMG_9.jpg", "IMG_09.jpg", "IMG_1.jpg", "a.jpg", "B.jpg"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, ["a.jpg", "B.jpg", "IMG_1.jpg", "IMG_9.jpg", "IMG_09.jpg", "img_9.jpg", "IMG_10.jpg"]);
+        // 大小写与补零只在其余部分都相同时起作用
+        assert_eq!(natural_cmp("IMG_09.a", "IMG_9.b"), Ordering::Less);
+        assert_eq!(natural_cmp("Photo_2.jpg", "photo_1.jpg"), Ordering::Greater);
+        assert_eq!(natural_cmp("x2", "x2"), Ordering::Equal);
+        assert_eq!(natural_cmp("x", "x1"), Ordering::Less);
+    }
+}
@@ -0,0 +1,247 @@
+// 运行配置：先读取配置文件，再由命令行参数覆盖，最后传递给 build_ui
+//
+// 配置文件位于 $XDG_CONFIG_HOME/image-viewer/config.toml，例如：
+//
+//     overlay-close-button = false
+
+use crate::placement::{Placement, StartPosition};
+use crate::scaling::Scaling;
+use gtk4::{cairo, gdk};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// 载入时的缩放："fit"（适应屏幕）或百分比数字（如 400）
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum InitialZoom {
+    Percent(f64),
+    Keyword(ZoomKeyword),
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ZoomKeyword {
+    Fit,
+}
+
+// 绘制时的插值方式：像素画适合 nearest
+#[derive(Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Filter {
+    #[default]
+    Bilinear,
+    Nearest,
+}
+
+impl Filter {
+    pub fn cairo(self) -> cairo::Filter {
+        match self {
+            Filter::Bilinear => cairo::Filter::Bilinear,
+            Filter::Nearest => cairo::Filter::Nearest,
+        }
+    }
+}
+
+// 方向键浏览同目录图片时的顺序
+#[derive(Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    // 按文件名（数字按数值比较）
+    #[default]
+    Name,
+    // 按修改时间，旧的在前
+    Modified,
+    // 按拍摄时间（EXIF DateTimeOriginal，没有时用修改时间），早的在前
+    Date,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 3] = [SortOrder::Name, SortOrder::Modified, SortOrder::Date];
+
+    // 配置文件与命令行中的写法
+    pub fn name(self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Modified => "modified",
+            SortOrder::Date => "date",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<SortOrder> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Name => "文件名",
+            SortOrder::Modified => "修改时间",
+            SortOrder::Date => "拍摄日期",
+        }
+    }
+}
+
+// 视图设置：可写在用户配置中，也可写在目录的 .image-viewer.toml 中覆盖（见 dirconfig）
+// 未设置的项使用下一层的值
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ViewDefaults {
+    pub zoom: Option<InitialZoom>,
+    pub filter: Option<Filter>,
+    // 普通窗口的背景色，如 "#ffffff" 或 "rgb(40,40,40)"
+    pub background: Option<String>,
+    pub sort: Option<SortOrder>,
+}
+
+impl ViewDefaults {
+    // other 中设置的项覆盖当前值
+    pub fn merge(&self, other: &ViewDefaults) -> ViewDefaults {
+        ViewDefaults {
+            zoom: other.zoom.or(self.zoom),
+            filter: other.filter.or(self.filter),
+            background: other.background.clone().or_else(|| self.background.clone()),
+            sort: other.sort.or(self.sort),
+        }
+    }
+
+    // 背景色无法解析时返回 None，由调用方使用默认背景
+    pub fn background_rgba(&self) -> Option<gdk::RGBA> {
+        self.background.as_deref().and_then(|b| gdk::RGBA::parse(b).ok())
+    }
+}
+
+// 退出双击进入的置顶后普通窗口的缩放：restore（恢复置顶前的视图）或 keep（沿用置顶时的缩放）
+#[derive(Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlayExitZoom {
+    #[default]
+    Restore,
+    Keep,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    // 展台模式：全屏、隐藏标题栏，禁止打开文件、置顶切换和关闭窗口
+    pub kiosk: bool,
+    // 全屏时使用的显示器序号
+    pub monitor: Option<u32>,
+    // 置顶模式下鼠标悬停时显示右上角关闭按钮
+    pub overlay_close_button: bool,
+    // 置顶窗口只在固定时所在的工作区显示（需要 Hyprland 或 sway）
+    pub overlay_current_workspace: bool,
+    // 滚轮缩放只在 12.5%/25%/50%/整数倍之间切换
+    pub pixel_zoom: bool,
+    // 滚轮每格缩放的百分比（1–100）
+    pub zoom_step: f64,
+    // 反转滚轮缩放方向：向下滚动放大
+    pub invert_scroll: bool,
+    // 置顶模式下一直显示方向提示，而不是在方向变化后显示两秒
+    pub always_show_orientation: bool,
+    // 启动时显示比例尺（普通窗口左下角，缩放不超过 100% 时）
+    pub scale_bar: bool,
+    // 双击进入置顶时的摆放策略：corner（离指针最远的角落）或 preserve（保持原位置）
+    pub placement: Placement,
+    pub overlay_exit_zoom: OverlayExitZoom,
+    // 双击置顶时使用的显示器：接口名（如 DP-1）或 pointer（跟随指针）；多显示器下未设置时弹出选择，选择后写回配置
+    pub overlay_monitor: Option<String>,
+    // 普通窗口按哪个显示器计算尺寸：pointer（窗口所在的显示器）或 center（第一个显示器）
+    pub window_position: StartPosition,
+    // 缩小显示的质量：fast（cairo 直接缩放）或 quality（低于 50% 时在线性光空间缩小）
+    pub scaling: Scaling,
+    // 是否播放动画（淡出等）；不设置时跟随桌面的“减少动画”偏好
+    pub animations: Option<bool>,
+    // 同时打开多个文件时每个文件使用独立窗口，而不是标签页
+    pub windows: bool,
+    // 平铺模式下监视的文件更新、窗口不在前台时发送桌面通知
+    pub notify_updates: bool,
+    // 打开目录时同时浏览所有子目录中的图片
+    pub recursive: bool,
+    // 按拍摄日期浏览时，跨越日期的那一次翻页短暂提示新的日期
+    pub date_separators: bool,
+    // 幻灯片每张显示的秒数（至少 1）
+    pub slideshow_interval: u32,
+    // 幻灯片每次自动前进时闪一下边框、响一下提示音
+    pub slideshow_flash: bool,
+    pub slideshow_sound: bool,
+    // 打开后立即开始幻灯片（--slideshow），不从配置文件读取
+    #[serde(skip)]
+    pub slideshow: bool,
+    // 手动调整窗口大小时保持图片的宽高比
+    pub lock_aspect: bool,
+    // 普通窗口的标题栏自动隐藏：收起为顶部的细线，指针靠近顶部时滑出
+    pub autohide_titlebar: bool,
+    // 估算解码内存超过该值（MiB）时先询问
+    pub memory_warning_mb: u64,
+    // 纹理边长上限（像素），不设置时向显卡查询；超过的图片缩小载入
+    pub max_texture_size: Option<u32>,
+    // 大 JPEG 先显示 1/2–1/8 缩小解码的预览，后台完整解码后替换
+    pub jpeg_preview: bool,
+    // 文字识别使用的 Tesseract 语言，如 eng、chi_sim 或 eng+chi_sim
+    pub ocr_language: String,
+    // 在标准错误输出每张图片的加载诊断
+    pub verbose: bool,
+    // 读取图片所在目录中的 .image-viewer.toml 覆盖视图设置（默认关闭）
+    pub directory_config: bool,
+    // [keys] 表：动作名 → 快捷键（如 rotate = "<Ctrl>l"），空字符串取消内置快捷键
+    pub keys: BTreeMap<String, String>,
+    // 视图设置（zoom、filter、background、sort）直接写在配置文件顶层
+    #[serde(flatten)]
+    pub view: ViewDefaults,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { kiosk: false, monitor: None, overlay_close_button: true, overlay_current_workspace: false, always_show_orientation: false, scale_bar: false,
+               placement: Placement::Corner, overlay_exit_zoom: OverlayExitZoom::Restore, overlay_monitor: None, window_position: StartPosition::Pointer, pixel_zoom: false, zoom_step: 10.0, invert_scroll: false, scaling: Scaling::Fast, animations: None, windows: false, notify_updates: true, recursive: false, date_separators: true, slideshow_interval: 5, slideshow_flash: false, slideshow_sound: false, slideshow: false, lock_aspect: false, autohide_titlebar: false, memory_warning_mb: 2048, max_texture_size: None, jpeg_preview: true,
+               ocr_language: "eng".to_string(), verbose: false, directory_config: false, keys: BTreeMap::new(), view: ViewDefaults::default() }
+    }
+}
+
+// 所有逐帧动画共用的开关：配置优先，否则读取 gtk-enable-animations（桌面的减少动画设置会关闭它）
+// 每次调用时重新读取，桌面设置在运行中改变也能立即生效；需在 GTK 初始化之后调用
+pub fn animations_enabled(setting: Option<bool>) -> bool {
+    setting.unwrap_or_else(|| gtk4::Settings::default().is_none_or(|s| s.is_gtk_enable_animations()))
+}
+
+impl Config {
+    // 滚轮一格的缩放倍数；超出范围的 zoom-step 被限制到 1%–100%
+    pub fn zoom_factor(&self) -> f64 {
+        let step = if self.zoom_step.is_finite() { self.zoom_step.clamp(1.0, 100.0) } else { 10.0 };
+        1.0 + step / 100.0
+    }
+
+    pub fn path() -> PathBuf {
+        glib::user_config_dir().join("image-viewer").join("config.toml")
+    }
+
+    // 把一项顶层设置写回配置文件：替换已有的行，没有时加在第一个表之前，其余内容和注释保持不变
+    pub fn store(key: &str, value: &str) -> Result<(), String> {
+        let path = Self::path();
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let top_end = lines.iter().position(|l| l.trim_start().starts_with('[')).unwrap_or(lines.len());
+        let line = format!("{} = {}", key, toml::Value::String(value.to_string()));
+        match lines[..top_end].iter().position(|l| l.split('=').next().is_some_and(|k| k.trim() == key)) {
+            Some(i) => lines[i] = line,
+            None => lines.insert(top_end, line),
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&path, lines.join("\n") + "\n").map_err(|e| format!("无法写入 {}: {}", path.display(), e))
+    }
+
+    // 读取配置文件；文件不存在时使用默认值，格式错误时给出警告并使用默认值
+    pub fn load() -> Self {
+        let path = Self::path();
+        let Ok(text) = std::fs::read_to_string(&path) else { return Self::default() };
+        match toml::from_str(&text) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("配置文件 {} 无效: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+// 目录缩略图总览（contact sheet）：把图片按网格排成缩略图，文件名写在下方
+// 一页放不下时分页，每页写出一个 PNG；在工作线程中运行，只用 image 与 cairo，不依赖显示
+
+use crate::decode;
+use gio::prelude::*;
+use gtk4::cairo;
+use image::RgbaImage;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_COLUMNS: u32 = 6;
+pub const DEFAULT_THUMB_SIZE: u32 = 256;
+
+#[derive(Clone, Copy)]
+pub struct Options {
+    pub columns: u32,
+    // 缩略图的最长边（像素），决定输出图片的分辨率
+    pub thumb_size: u32,
+    pub labels: bool,
+}
+
+// 写出结果
+pub struct Summary {
+    pub pages: Vec<PathBuf>,
+    pub cancelled: bool,
+}
+
+// 页面尺寸与格子位置，全部由列数和缩略图大小推出；每页不超过 A 系列纸张的纵横比
+struct Layout {
+    columns: u32,
+    rows: u32,
+    thumb: f64,
+    gap: f64,
+    label: f64,
+}
+
+impl Layout {
+    fn new(options: Options) -> Self {
+        let thumb = options.thumb_size as f64;
+        let gap = (thumb / 16.0).max(6.0);
+        let label = if options.labels { (thumb * 0.08).clamp(11.0, 24.0) } else { 0.0 };
+        let columns = options.columns.max(1);
+        let mut layout = Self { columns, rows: 1, thumb, gap, label };
+        let max_height = layout.width() * std::f64::consts::SQRT_2;
+        layout.rows = (((max_height - gap) / layout.row_height()).floor() as u32).max(1);
+        layout
+    }
+
+    fn row_height(&self) -> f64 {
+        let label = if self.label > 0.0 { self.label + self.gap / 2.0 } else { 0.0 };
+        self.thumb + label + self.gap
+    }
+
+    fn width(&self) -> f64 {
+        self.columns as f64 * (self.thumb + self.gap) + self.gap
+    }
+
+    fn per_page(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    // 放 count 张图片的页面高度（最后一页可能较矮）
+    fn height(&self, count: usize) -> f64 {
+        let rows = count.div_ceil(self.columns as usize).max(1);
+        rows as f64 * self.row_height() + self.gap
+    }
+
+    // 第 i 个格子缩略图区域的左上角
+    fn cell(&self, i: usize) -> (f64, f64) {
+        let (col, row) = (i % self.columns as usize, i / self.columns as usize);
+        (self.gap + col as f64 * (self.thumb + self.gap), self.gap + row as f64 * self.row_height())
+    }
+}
+
+// 缩略图：已应用 EXIF 方向，按比例缩到 size 以内
+fn thumbnail(path: &Path, size: u32) -> Result<RgbaImage, String> {
+    Ok(decode::decode(path)?.image.thumbnail(size, size).to_rgba8())
+}
+
+// RGBA → cairo 预乘 ARGB32
+fn rgba_surface(img: &RgbaImage) -> Option<cairo::ImageSurface> {
+    let (w, h) = img.dimensions();
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, w as i32, h as i32).ok()?;
+    let stride = surface.stride() as usize;
+    {
+        let mut data = surface.data().ok()?;
+        for (y, row) in img.rows().enumerate() {
+            for (x, px) in row.enumerate() {
+                let [r, g, b, a] = px.0;
+                let pm = |c: u8| (c as u32 * a as u32 + 127) / 255;
+                let v = ((a as u32) << 24) | (pm(r) << 16) | (pm(g) << 8) | pm(b);
+                let offset = y * stride + x * 4;
+                data[offset..offset + 4].copy_from_slice(&v.to_ne_bytes());
+            }
+        }
+    }
+    Some(surface)
+}
+
+fn draw_cell(cr: &cairo::Context, layout: &Layout, (x, y): (f64, f64), thumb: Option<&cairo::ImageSurface>, name: &str) {
+    let size = layout.thumb;
+    match thumb {
+        Some(surface) => {
+            let (w, h) = (surface.width() as f64, surface.height() as f64);
+            cr.set_source_surface(surface, x + (size - w) / 2.0, y + (size - h) / 2.0).ok();
+            cr.paint().ok();
+        }
+        // 无法解码的图片画一个空框
+        None => {
+            cr.set_source_rgb(0.8, 0.8, 0.8);
+            cr.set_line_width(1.0);
+            cr.rectangle(x + 0.5, y + 0.5, size - 1.0, size - 1.0);
+            cr.stroke().ok();
+        }
+    }
+    if layout.label <= 0.0 { return; }
+    cr.save().ok();
+    cr.rectangle(x, y + size, size, layout.label + layout.gap / 2.0);
+    cr.clip();
+    cr.set_source_rgb(0.2, 0.2, 0.2);
+    cr.set_font_size(layout.label * 0.8);
+    // 放不下的文件名截去中间部分
+    let mut text = name.to_string();
+    let chars: Vec<char> = name.chars().collect();
+    let mut keep = chars.len();
+    while keep > 4 && cr.text_extents(&text).is_ok_and(|e| e.x_advance() > size) {
+        keep -= 1;
+        let head: String = chars[..keep / 2].iter().collect();
+        let tail: String = chars[chars.len() - (keep - keep / 2)..].iter().collect();
+        text = format!("{}…{}", head, tail);
+    }
+    if let Ok(ext) = cr.text_extents(&text) {
+        cr.move_to(x + (size - ext.x_advance()) / 2.0, y + size + layout.gap / 2.0 + layout.label * 0.8);
+        cr.show_text(&text).ok();
+    }
+    cr.restore().ok();
+}
+
+// 输出文件名：stem.png，多页时 stem-1.png、stem-2.png…；与已有文件重名时在 stem 后加序号
+fn page_paths(out_dir: &Path, stem: &str, pages: usize) -> Vec<PathBuf> {
+    let names = |stem: &str| -> Vec<PathBuf> {
+        match pages {
+            1 => vec![out_dir.join(format!("{}.png", stem))],
+            n => (1..=n).map(|i| out_dir.join(format!("{}-{}.png", stem, i))).collect(),
+        }
+    };
+    let mut paths = names(stem);
+    let mut n = 2;
+    while paths.iter().any(|p| p.exists()) {
+        paths = names(&format!("{} ({})", stem, n));
+        n += 1;
+    }
+    paths
+}
+
+// 渲染并写出所有页；progress 在每张缩略图完成后调用（已完成数, 总数）
+pub fn render(
+    files: &[PathBuf],
+    out_dir: &Path,
+    stem: &str,
+    options: Options,
+    cancel: &gio::Cancellable,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<Summary, String> {
+    if files.is_empty() { return Err("目录中没有图片".to_string()); }
+    let layout = Layout::new(options);
+    let pages: Vec<&[PathBuf]> = files.chunks(layout.per_page()).collect();
+    let targets = page_paths(out_dir, stem, pages.len());
+    let mut summary = Summary { pages: Vec::new(), cancelled: false };
+    let mut done = 0;
+    for (page, target) in pages.iter().zip(targets) {
+        let surface = cairo::ImageSurface::create(
+            cairo::Format::Rgb24,
+            layout.width().ceil() as i32,
+            layout.height(page.len()).ceil() as i32,
+        ).map_err(|e| e.to_string())?;
+        let cr = cairo::Context::new(&surface).map_err(|e| e.to_string())?;
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.paint().ok();
+        for (i, path) in page.iter().enumerate() {
+            if cancel.is_cancelled() {
+                summary.cancelled = true;
+                return Ok(summary);
+            }
+            let thumb = thumbnail(path, options.thumb_size)
+                .inspect_err(|e| eprintln!("无法生成缩略图 {}: {}", path.display(), e))
+                .ok()
+                .and_then(|img| rgba_surface(&img));
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            draw_cell(&cr, &layout, layout.cell(i), thumb.as_ref(), &name);
+            done += 1;
+            progress(done, files.len());
+        }
+        drop(cr);
+        let mut file = std::fs::File::create(&target).map_err(|e| format!("无法写入 {}: {}", target.display(), e))?;
+        surface.write_to_png(&mut file).map_err(|e| format!("无法写入 {}: {}", target.display(), e))?;
+        summary.pages.push(target);
+    }
+    Ok(summary)
+}
@@ -0,0 +1,131 @@
+// convert 子命令：不创建窗口、不连接显示服务器，解码后旋转/缩放并写出
+// 退出码：0 成功，1 解码或写出失败，2 参数错误
+
+use crate::decode;
+use image::codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageEncoder, ImageFormat};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const JPEG_QUALITY: u8 = 90;
+
+struct Options {
+    input: PathBuf,
+    output: PathBuf,
+    // 缩放到该尺寸以内（保持宽高比）
+    size: Option<(u32, u32)>,
+    // 顺时针旋转角度：0/90/180/270
+    rotate: u32,
+}
+
+pub fn print_help() {
+    eprintln!("Usage: image-viewer convert <IN> <OUT> [--size WxH] [--rotate 90|180|270]");
+    eprintln!();
+    eprintln!("Decode IN (applying EXIF orientation) and write OUT without opening a window.");
+    eprintln!("The output format follows the OUT extension.");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("      --size WxH    Scale to fit within WxH, keeping the aspect ratio");
+    eprintln!("      --rotate DEG  Rotate clockwise by 90, 180 or 270 degrees");
+    eprintln!();
+    eprintln!("Exit status: 0 on success, 1 if decoding or writing fails, 2 on usage errors");
+}
+
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let (w, h) = value.split_once(['x', 'X'])?;
+    let (w, h) = (w.parse().ok()?, h.parse().ok()?);
+    (w > 0 && h > 0).then_some((w, h))
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let mut paths = Vec::new();
+    let mut size = None;
+    let mut rotate = 0;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--size" => {
+                let value = iter.next().ok_or("--size requires WxH")?;
+                size = Some(parse_size(value).ok_or_else(|| format!("invalid --size: {}", value))?);
+            }
+            "--rotate" => {
+                let value = iter.next().ok_or("--rotate requires an angle")?;
+                rotate = match value.parse::<i32>() {
+                    Ok(deg) if deg.rem_euclid(90) == 0 => deg.rem_euclid(360) as u32,
+                    _ => return Err(format!("invalid --rotate: {} (use 90, 180 or 270)", value)),
+                };
+            }
+            a if a.starts_with('-') && a.len() > 1 => return Err(format!("unknown option: {}", a)),
+            a => paths.push(PathBuf::from(a)),
+        }
+    }
+    match <[PathBuf; 2]>::try_from(paths) {
+        Ok([input, output]) => Ok(Options { input, output, size, rotate }),
+        Err(_) => Err("expected exactly one input and one output file".to_string()),
+    }
+}
+
+pub fn run(args: &[String]) -> glib::ExitCode {
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        print_help();
+        return glib::ExitCode::SUCCESS;
+    }
+    let opts = match parse(args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("convert: {}", e);
+            print_help();
+            return glib::ExitCode::from(2);
+        }
+    };
+    match convert(&opts) {
+        Ok(()) => glib::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("convert: {}", e);
+            glib::ExitCode::from(1)
+        }
+    }
+}
+
+fn convert(opts: &Options) -> Result<(), String> {
+    let format = ImageFormat::from_path(&opts.output)
+        .map_err(|_| format!("cannot tell output format from {}", opts.output.display()))?;
+    let decoded = decode::decode(&opts.input).map_err(|e| format!("{}: {}", opts.input.display(), e))?;
+    let mut img = match opts.rotate {
+        90 => decoded.image.rotate90(),
+        180 => decoded.image.rotate180(),
+        270 => decoded.image.rotate270(),
+        _ => decoded.image,
+    };
+    if let Some((w, h)) = opts.size {
+        img = img.resize(w, h, FilterType::Lanczos3);
+    }
+    // 写出失败时删除不完整的输出文件
+    write(&img, &opts.output, format, decoded.icc_profile).inspect_err(|_| {
+        let _ = std::fs::remove_file(&opts.output);
+    })
+}
+
+fn write(img: &DynamicImage, path: &Path, format: ImageFormat, icc: Option<Vec<u8>>) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    let result = match format {
+        ImageFormat::Png => encode(img, PngEncoder::new(&mut out), icc),
+        // JPEG 不支持透明通道与 16 位
+        ImageFormat::Jpeg => encode(&DynamicImage::ImageRgb8(img.to_rgb8()), JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY), icc),
+        ImageFormat::WebP => encode(&DynamicImage::ImageRgba8(img.to_rgba8()), WebPEncoder::new_lossless(&mut out), icc),
+        _ => img.write_to(&mut out, format),
+    };
+    result.map_err(|e| format!("{}: {}", path.display(), e))?;
+    out.flush().map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// 支持的编码器写回 ICC 配置文件，不支持时忽略
+fn encode(img: &DynamicImage, mut encoder: impl ImageEncoder, icc: Option<Vec<u8>>) -> image::ImageResult<()> {
+    if let Some(icc) = icc {
+        let _ = encoder.set_icc_profile(icc);
+    }
+    img.write_with_encoder(encoder)
+}
@@ -0,0 +1,138 @@
+// 图片解码的回退路径：图形界面与 convert 子命令共用，只依赖 image，不需要显示服务器
+// 开启隔离解码时 decode 与 decode_bytes 交给子进程，子进程内用 decode_bytes_here 解码
+
+use crate::{header, sandbox};
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::io::Cursor;
+use std::path::Path;
+
+// image 解码结果（已应用 EXIF 方向）
+pub struct Decoded {
+    pub image: DynamicImage,
+    // 嵌入的 ICC 配置文件；不做色彩转换，导出时原样写回
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+impl Decoded {
+    // 隔离解码只传回像素，不带 ICC 配置文件
+    fn isolated(image: image::RgbaImage) -> Self {
+        Decoded { image: DynamicImage::ImageRgba8(image), icc_profile: None }
+    }
+}
+
+fn open(path: &Path) -> Result<impl ImageDecoder, String> {
+    ImageReader::open(path)
+        .and_then(|r| r.with_guessed_format())
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| e.to_string())
+}
+
+// 读取需要应用的 EXIF 方向（无方向或为正常方向时返回 None）
+pub fn orientation(path: &Path) -> Option<Orientation> {
+    let mut decoder = open(path).ok()?;
+    decoder.orientation().ok().filter(|&o| o != Orientation::NoTransforms)
+}
+
+// EXIF 方向的原始值（1–8）；没有方向信息时为 1，无法读取文件时返回 None
+pub fn exif_orientation(path: &Path) -> Option<u8> {
+    open(path).ok()?.orientation().ok().map(Orientation::to_exif)
+}
+
+// EXIF 方向是否交换宽高（方向 5–8，含两种镜像）
+pub fn transposes(path: &Path) -> bool {
+    exif_orientation(path).is_some_and(|o| o >= 5)
+}
+
+// 应用 EXIF 方向后的尺寸，即显示出来的宽高
+pub fn oriented_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let (w, h) = header::read_dimensions(path)?;
+    Some(if transposes(path) { (h, w) } else { (w, h) })
+}
+
+// 文件中的原始色彩类型（解码前，由解码器从文件头读出）
+pub fn color_type(path: &Path) -> Option<image::ExtendedColorType> {
+    Some(open(path).ok()?.original_color_type())
+}
+
+// GTK 的加载器既不转换 CMYK/YCCK JPEG，也不应用 EXIF 方向，这类图片需要改用 decode
+pub fn needs_fallback(path: &Path) -> bool {
+    header::jpeg_ink(path).is_some() || orientation(path).is_some()
+}
+
+// 完整解码并应用 EXIF 方向
+pub fn decode(path: &Path) -> Result<Decoded, String> {
+    if sandbox::enabled() {
+        return sandbox::decode_file(path, None).map(Decoded::isolated);
+    }
+    decode_with(open(path)?)
+}
+
+// 从内存解码（标准输入等没有文件路径的来源）
+pub fn decode_bytes(data: &[u8]) -> Result<Decoded, String> {
+    if sandbox::enabled() {
+        return sandbox::decode_bytes(data, None).map(Decoded::isolated);
+    }
+    decode_bytes_here(data)
+}
+
+// 在当前进程中从内存解码
+pub fn decode_bytes_here(data: &[u8]) -> Result<Decoded, String> {
+    let decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| e.to_string())?;
+    decode_with(decoder)
+}
+
+// CMYK/YCCK JPEG 由解码器按朴素公式转换为 RGB；嵌入的 ICC 配置文件只读出交给调用方（导出时原样写回），
+// 不据此转换像素
+fn decode_with(mut decoder: impl ImageDecoder) -> Result<Decoded, String> {
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    image.apply_orientation(orientation);
+    Ok(Decoded { image, icc_profile })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个 8×8 块一种颜色，按 Adobe 的习惯反相保存（255 表示没有油墨）
+    const CMYK_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/cmyk.jpg");
+    // 各块的油墨比例（C, M, Y, K）
+    const INKS: [[f64; 4]; 5] = [
+        [0.0, 0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 0.5],
+        [0.0, 1.0, 1.0, 0.0],
+        [0.4, 0.0, 0.0, 0.2],
+    ];
+
+    // 朴素转换：R = 255 × (1 − C) × (1 − K)，G、B 同理
+    fn naive_rgb([c, m, y, k]: [f64; 4]) -> [f64; 3] {
+        [c, m, y].map(|ink| 255.0 * (1.0 - ink) * (1.0 - k))
+    }
+
+    #[test]
+    fn fixture_is_detected_as_cmyk() {
+        assert!(matches!(header::jpeg_ink(Path::new(CMYK_FIXTURE)), Some(header::JpegInk::Cmyk)));
+    }
+
+    #[test]
+    fn cmyk_jpeg_converts_to_rgb() {
+        let data = std::fs::read(CMYK_FIXTURE).unwrap();
+        let image = decode_bytes_here(&data).unwrap().image.to_rgb8();
+        assert_eq!(image.dimensions(), (8 * INKS.len() as u32, 8));
+        for (i, ink) in INKS.iter().enumerate() {
+            let pixel = image.get_pixel(i as u32 * 8 + 4, 4).0;
+            let expected = naive_rgb(*ink);
+            for (got, want) in pixel.iter().zip(expected) {
+                assert!((*got as f64 - want).abs() <= 2.0, "块 {}: 得到 {:?}，应为 {:?}", i, pixel, expected);
+            }
+        }
+    }
+}
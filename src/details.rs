@@ -0,0 +1,640 @@
+// 技术细节：只解析文件字节，列出 PNG 块、JPEG 段、WebP 块与 TIFF 的 IFD，以及 EXIF/XMP/ICC 的有无与大小
+// 不解码像素；遇到截断或畸形数据时保留已读到的部分并注明，绝不 panic
+
+use crate::header::{self, Format, Tiff};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+// JPEG 的元数据段都在扫描数据之前，读这么多足够
+const JPEG_PREFIX: u64 = 4 * 1024 * 1024;
+// TIFF 的 IFD 可能在文件末尾，不超过该大小时整个读入
+const TIFF_LIMIT: u64 = 64 * 1024 * 1024;
+// 块内容只读开头这么多字节用于识别（文本关键字、配置文件名等）
+const PEEK: u64 = 80;
+// 超过该大小的 ICC 配置文件只报告大小
+const ICC_LIMIT: u64 = 4 * 1024 * 1024;
+// 畸形文件可能让块链表很长，最多列出这么多
+const MAX_ITEMS: usize = 10_000;
+const XMP_ID: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+// libjpeg 标准量化表（质量 50）各 64 项之和，用于估算质量
+const STD_LUMA_SUM: f64 = 3688.0;
+const STD_CHROMA_SUM: f64 = 5505.0;
+
+// 一个块/段；连续同名且没有说明的块合并计数
+struct Item {
+    name: String,
+    count: u32,
+    size: u64,
+    note: String,
+}
+
+#[derive(Default)]
+pub struct Details {
+    format: &'static str,
+    file_size: u64,
+    items: Vec<Item>,
+    exif: Option<String>,
+    xmp: Option<String>,
+    icc: Option<String>,
+    // ICC 配置文件的描述；PNG 为 iCCP 中记录的名称
+    icc_name: Option<String>,
+    truncated: bool,
+}
+
+impl Details {
+    fn push(&mut self, name: &str, size: u64, note: String) {
+        if let Some(last) = self.items.last_mut()
+            && note.is_empty() && last.note.is_empty() && last.name == name
+        {
+            last.count += 1;
+            last.size += size;
+            return;
+        }
+        self.items.push(Item { name: name.to_string(), count: 1, size, note });
+    }
+
+    pub fn icc_name(&self) -> Option<&str> {
+        self.icc_name.as_deref()
+    }
+
+    fn set_icc(&mut self, size: u64, profile: Option<&[u8]>) {
+        let info = profile.and_then(icc_info);
+        self.icc = Some(match &info {
+            Some((Some(desc), version)) => format!("{}（ICC {}），{} B", desc, version, size),
+            Some((None, version)) => format!("无描述（ICC {}），{} B", version, size),
+            None => format!("{} B（无法读取描述）", size),
+        });
+        self.icc_name = info.and_then(|(desc, _)| desc);
+    }
+
+    // 复制与显示共用的纯文本
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("格式：{}，{} B", self.format, self.file_size)];
+        let names: Vec<String> = self.items.iter().map(|item| match item.count {
+            1 => item.name.clone(),
+            n => format!("{} ×{}", item.name, n),
+        }).collect();
+        // 按显示宽度对齐，汉字占两列
+        let columns = |s: &str| s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum::<usize>();
+        let width = names.iter().map(|n| columns(n)).max().unwrap_or(0);
+        for (item, name) in self.items.iter().zip(names.iter()) {
+            let pad = " ".repeat(width - columns(name));
+            let mut line = format!("  {}{} {:>10} B", name, pad, item.size);
+            if !item.note.is_empty() {
+                line.push_str("  ");
+                line.push_str(&item.note);
+            }
+            lines.push(line);
+        }
+        if self.truncated { lines.push("  （文件截断或结构无效，后续内容未列出）".to_string()); }
+        let presence = |v: &Option<String>| v.clone().unwrap_or_else(|| "无".to_string());
+        lines.push(format!("EXIF：{}", presence(&self.exif)));
+        lines.push(format!("XMP：{}", presence(&self.xmp)));
+        lines.push(format!("ICC：{}", presence(&self.icc)));
+        lines.join("\n")
+    }
+}
+
+fn read_at(file: &mut File, pos: u64, len: u64) -> Option<Vec<u8>> {
+    file.seek(SeekFrom::Start(pos)).ok()?;
+    let mut buf = Vec::new();
+    file.by_ref().take(len).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+// 以 NUL 结尾的短字符串（PNG 关键字、JPEG APP 标识等），不可打印字符换成 ?
+fn c_string(d: &[u8]) -> String {
+    let end = d.iter().position(|&b| b == 0).unwrap_or(d.len()).min(PEEK as usize);
+    d[..end].iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '?' }).collect()
+}
+
+fn name_of(fourcc: &[u8]) -> String {
+    fourcc.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '?' }).collect()
+}
+
+// ICC 配置文件的描述（v2 的 desc 或 v4 的 mluc 中第一条记录）与版本
+fn icc_info(p: &[u8]) -> Option<(Option<String>, String)> {
+    if p.get(36..40)? != b"acsp" { return None; }
+    let version = format!("v{}.{}", p.get(8)?, p.get(9)? >> 4);
+    let tags = header::be32(p, 128)? as usize;
+    let desc = (0..tags.min(1000)).find_map(|i| {
+        let entry = 132 + i * 12;
+        if p.get(entry..entry + 4)? != b"desc" { return None; }
+        let (offset, len) = (header::be32(p, entry + 4)? as usize, header::be32(p, entry + 8)? as usize);
+        let t = p.get(offset..offset.checked_add(len)?)?;
+        match t.get(0..4)? {
+            b"desc" => {
+                let count = header::be32(t, 8)? as usize;
+                Some(String::from_utf8_lossy(t.get(12..12 + count)?).trim_end_matches('\0').to_string())
+            }
+            b"mluc" => {
+                let (len, offset) = (header::be32(t, 20)? as usize, header::be32(t, 24)? as usize);
+                let units: Vec<u16> = t.get(offset..offset.checked_add(len)?)?
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            _ => None,
+        }
+    });
+    Some((desc, version))
+}
+
+fn png_ihdr(d: &[u8]) -> Option<String> {
+    let (w, h) = (header::be32(d, 0)?, header::be32(d, 4)?);
+    let color = match d.get(9)? {
+        0 => "灰度",
+        2 => "RGB",
+        3 => "索引色",
+        4 => "灰度 + Alpha",
+        6 => "RGBA",
+        _ => "未知颜色类型",
+    };
+    let interlace = if *d.get(12)? == 1 { "，Adam7 隔行" } else { "" };
+    Some(format!("{}×{}，{} 位，{}{}", w, h, d.get(8)?, color, interlace))
+}
+
+fn png(file: &mut File, d: &mut Details) {
+    let mut pos = 8u64;
+    while d.items.len() < MAX_ITEMS {
+        let Some(head) = read_at(file, pos, 8).filter(|h| h.len() == 8) else { break };
+        let (len, kind) = (header::be32(&head, 0).unwrap_or(0) as u64, name_of(&head[4..8]));
+        // 长度、类型、内容与 CRC
+        let end = pos + 12 + len;
+        if end > d.file_size {
+            d.push(&kind, len, "截断".to_string());
+            break;
+        }
+        let peek = read_at(file, pos + 8, len.min(PEEK)).unwrap_or_default();
+        let note = match kind.as_str() {
+            "IHDR" => png_ihdr(&peek).unwrap_or_default(),
+            "iCCP" => {
+                // 内容经 zlib 压缩，只报告 PNG 中记录的配置文件名
+                let name = c_string(&peek);
+                d.icc = Some(format!("\"{}\"（iCCP 压缩，{} B）", name, len));
+                d.icc_name = Some(name.clone());
+                format!("配置文件 \"{}\"", name)
+            }
+            "eXIf" => {
+                d.exif = Some(format!("eXIf 块，{} B", len));
+                String::new()
+            }
+            "iTXt" | "tEXt" | "zTXt" => {
+                let keyword = c_string(&peek);
+                match keyword.as_str() {
+                    "XML:com.adobe.xmp" => d.xmp = Some(format!("{} 块，{} B", kind, len)),
+                    "Raw profile type exif" | "Raw profile type APP1" if d.exif.is_none() => {
+                        d.exif = Some(format!("{} 块（十六进制文本），{} B", kind, len));
+                    }
+                    _ => {}
+                }
+                format!("\"{}\"", keyword)
+            }
+            "acTL" => header::be32(&peek, 0).map(|n| format!("{} 帧", n)).unwrap_or_default(),
+            _ => String::new(),
+        };
+        d.push(&kind, len, note);
+        if kind == "IEND" { return; }
+        pos = end;
+    }
+    d.truncated = true;
+}
+
+fn marker_name(marker: u8) -> String {
+    match marker {
+        0xC4 => "DHT".to_string(),
+        0xCC => "DAC".to_string(),
+        0xC0..=0xCF => format!("SOF{}", marker - 0xC0),
+        0xDA => "SOS".to_string(),
+        0xDB => "DQT".to_string(),
+        0xDD => "DRI".to_string(),
+        0xE0..=0xEF => format!("APP{}", marker - 0xE0),
+        0xFE => "COM".to_string(),
+        _ => format!("FF{:02X}", marker),
+    }
+}
+
+fn jpeg_sof(marker: u8, body: &[u8]) -> Option<String> {
+    let (h, w, n) = (header::be16(body, 1)?, header::be16(body, 3)?, *body.get(5)? as usize);
+    let sampling: Vec<String> = (0..n)
+        .map_while(|i| body.get(6 + i * 3 + 1).map(|hv| format!("{}×{}", hv >> 4, hv & 0x0F)))
+        .collect();
+    let kind = match marker {
+        0xC0 => "基线",
+        0xC1 => "扩展顺序",
+        0xC2 => "渐进",
+        0xC3 => "无损",
+        _ => "算术编码或分层",
+    };
+    Some(format!("{}×{}，{} 位，{} 个分量（采样 {}），{}", w, h, body.first()?, n, sampling.join(" "), kind))
+}
+
+// 量化表：精度、平均值与按 libjpeg 标准表推算的大致质量
+fn jpeg_dqt(body: &[u8]) -> String {
+    let mut tables = Vec::new();
+    let mut pos = 0;
+    while let Some(&pq_tq) = body.get(pos) {
+        let (precision, id) = (pq_tq >> 4, pq_tq & 0x0F);
+        let len = if precision == 0 { 64 } else { 128 };
+        let Some(values) = body.get(pos + 1..pos + 1 + len) else {
+            tables.push(format!("表 {}：截断", id));
+            break;
+        };
+        let sum: u32 = match precision {
+            0 => values.iter().map(|&v| v as u32).sum(),
+            _ => values.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]) as u32).sum(),
+        };
+        let mut text = format!("表 {}：{} 位，平均 {:.1}", id, if precision == 0 { 8 } else { 16 }, sum as f64 / 64.0);
+        if precision == 0 && sum > 0 {
+            let standard = if id == 0 { STD_LUMA_SUM } else { STD_CHROMA_SUM };
+            let s = 100.0 * sum as f64 / standard;
+            let quality = if s <= 100.0 { (200.0 - s) / 2.0 } else { 5000.0 / s };
+            text.push_str(&format!("，约相当于质量 {:.0}", quality.clamp(1.0, 100.0)));
+        }
+        tables.push(text);
+        pos += 1 + len;
+    }
+    tables.join("；")
+}
+
+fn jpeg(data: &[u8], d: &mut Details) {
+    let segments = header::jpeg_segments(data);
+    // ICC 配置文件可能分成多个 APP2 段，按序号拼接
+    let mut icc_parts: Vec<(u8, &[u8])> = Vec::new();
+    let mut exif_size = 0;
+    let mut xmp_size = 0;
+    for &(marker, body) in segments.iter().take(MAX_ITEMS) {
+        let size = body.len() as u64 + 2;
+        let note = match marker {
+            0xC4 | 0xC8 | 0xCC => String::new(),
+            0xC0..=0xCF => jpeg_sof(marker, body).unwrap_or_default(),
+            0xDB => jpeg_dqt(body),
+            0xDD => header::be16(body, 0).map(|n| format!("每 {} 个 MCU", n)).unwrap_or_default(),
+            0xDA => body.first().map(|n| format!("{} 个分量", n)).unwrap_or_default(),
+            0xE0..=0xEF => {
+                if marker == 0xE1 && body.starts_with(b"Exif\0\0") { exif_size += size; }
+                if marker == 0xE1 && body.starts_with(XMP_ID) { xmp_size += size; }
+                if marker == 0xE2 && body.starts_with(b"ICC_PROFILE\0") && body.len() >= 14 {
+                    icc_parts.push((body[12], &body[14..]));
+                }
+                format!("\"{}\"", c_string(body))
+            }
+            0xFE => format!("\"{}\"", c_string(body)),
+            _ => String::new(),
+        };
+        d.push(&marker_name(marker), size, note);
+    }
+    match segments.last() {
+        // 扫描数据（渐进式 JPEG 还包括之后的各次扫描）一直到文件末尾
+        Some(&(0xDA, body)) => {
+            let header_end = (body.as_ptr() as usize - data.as_ptr() as usize + body.len()) as u64;
+            d.push("扫描数据", d.file_size.saturating_sub(header_end), "至文件末尾".to_string());
+        }
+        _ => d.truncated = true,
+    }
+    if exif_size > 0 { d.exif = Some(format!("APP1，{} B", exif_size)); }
+    if xmp_size > 0 { d.xmp = Some(format!("APP1，{} B", xmp_size)); }
+    if !icc_parts.is_empty() {
+        icc_parts.sort_by_key(|(seq, _)| *seq);
+        let profile: Vec<u8> = icc_parts.iter().flat_map(|(_, part)| part.iter().copied()).collect();
+        d.set_icc(profile.len() as u64, Some(&profile));
+    }
+}
+
+fn webp(file: &mut File, d: &mut Details) {
+    let mut pos = 12u64;
+    while d.items.len() < MAX_ITEMS {
+        let Some(head) = read_at(file, pos, 8).filter(|h| h.len() == 8) else {
+            // 正好读到文件末尾是正常结束
+            d.truncated = pos < d.file_size;
+            return;
+        };
+        let (len, kind) = (header::le32(&head, 4).unwrap_or(0) as u64, name_of(&head[0..4]));
+        // 奇数长度的块后有一个填充字节
+        let end = pos + 8 + len + (len & 1);
+        if pos + 8 + len > d.file_size {
+            d.push(&kind, len, "截断".to_string());
+            break;
+        }
+        let note = match kind.as_str() {
+            "VP8X" => {
+                let flags = read_at(file, pos + 8, 1).and_then(|b| b.first().copied()).unwrap_or(0);
+                let names: Vec<&str> = [(0x20, "ICC"), (0x10, "Alpha"), (0x08, "EXIF"), (0x04, "XMP"), (0x02, "动画")]
+                    .into_iter()
+                    .filter(|(bit, _)| flags & bit != 0)
+                    .map(|(_, name)| name)
+                    .collect();
+                format!("标志：{}", if names.is_empty() { "无".to_string() } else { names.join(" ") })
+            }
+            "ICCP" => {
+                let profile = (len <= ICC_LIMIT).then(|| read_at(file, pos + 8, len)).flatten();
+                d.set_icc(len, profile.as_deref());
+                String::new()
+            }
+            "EXIF" => {
+                d.exif = Some(format!("EXIF 块，{} B", len));
+                String::new()
+            }
+            "XMP " => {
+                d.xmp = Some(format!("XMP 块，{} B", len));
+                String::new()
+            }
+            _ => String::new(),
+        };
+        d.push(kind.trim_end(), len, note);
+        pos = end;
+    }
+    d.truncated = true;
+}
+
+fn tiff_compression(code: u32) -> String {
+    match code {
+        1 => "无压缩".to_string(),
+        5 => "LZW".to_string(),
+        6 | 7 => "JPEG".to_string(),
+        8 | 32946 => "Deflate".to_string(),
+        32773 => "PackBits".to_string(),
+        n => format!("压缩方式 {}", n),
+    }
+}
+
+fn tiff(data: &[u8], d: &mut Details) {
+    let Some(t) = Tiff::new(data) else {
+        d.truncated = true;
+        return;
+    };
+    let mut next = t.first_ifd();
+    let mut seen = Vec::new();
+    while let Some(ifd) = next.filter(|&o| o != 0) {
+        // 环状的 IFD 链表
+        if seen.contains(&ifd) || seen.len() >= MAX_ITEMS { break; }
+        seen.push(ifd);
+        let Some(entries) = t.entries(ifd) else {
+            d.truncated = true;
+            return;
+        };
+        let find = |tag| entries.iter().find(|e| e.tag == tag);
+        let value = |tag| find(tag).and_then(|e| t.uint(e));
+        let mut note = format!("{} 个标签", entries.len());
+        if let (Some(w), Some(h)) = (value(256), value(257)) { note.push_str(&format!("，{}×{}", w, h)); }
+        if let Some(code) = value(259) { note.push_str(&format!("，{}", tiff_compression(code))); }
+        if seen.len() == 1 {
+            if let Some(exif) = value(34665) {
+                let tags = t.u16(exif as usize).map(|n| format!("，{} 个标签", n)).unwrap_or_default();
+                d.exif = Some(format!("EXIF IFD{}", tags));
+            }
+            if let Some(xmp) = find(700) { d.xmp = Some(format!("标签 700，{} B", xmp.count)); }
+            if let Some(icc) = find(34675) {
+                // 超过 4 字节的值存放在偏移处
+                let profile = t.u32(icc.value_pos)
+                    .and_then(|offset| data.get(offset as usize..(offset as usize).checked_add(icc.count as usize)?));
+                d.set_icc(icc.count as u64, profile);
+            }
+        }
+        d.push(&format!("IFD{}", seen.len() - 1), 6 + entries.len() as u64 * 12, note);
+        next = t.u32(ifd + 2 + entries.len() * 12).map(|o| o as usize);
+    }
+    if next.is_none() { d.truncated = true; }
+}
+
+// 读取文件并解析；读不到文件或格式无法识别时返回错误
+pub fn read(path: &Path) -> Result<Details, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+    let format = header::sniff_file(path).ok_or("无法识别的文件格式")?;
+    let mut d = Details { file_size, ..Details::default() };
+    match format {
+        Format::Png => {
+            d.format = "PNG";
+            png(&mut file, &mut d);
+        }
+        Format::Jpeg => {
+            d.format = "JPEG";
+            jpeg(&header::read_prefix(path, JPEG_PREFIX).unwrap_or_default(), &mut d);
+        }
+        Format::WebP => {
+            d.format = "WebP";
+            webp(&mut file, &mut d);
+        }
+        Format::Tiff => {
+            d.format = "TIFF";
+            tiff(&header::read_prefix(path, TIFF_LIMIT).unwrap_or_default(), &mut d);
+        }
+        // GIF 与 BMP 没有可列出的元数据结构
+        Format::Gif => d.format = "GIF",
+        Format::Bmp => d.format = "BMP",
+    }
+    Ok(d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 写入临时文件后解析；文件名带上进程号与用例名，并行的用例互不干扰
+    fn parse(name: &str, data: &[u8]) -> Result<Details, String> {
+        let path = std::env::temp_dir().join(format!("image-viewer-details-{}-{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        let result = read(&path);
+        std::fs::remove_file(&path).ok();
+        result
+    }
+
+    // 在每个位置截断、把每个字节改成 0xFF 后都要能解析完（结果不论），不得 panic
+    fn survives_damage(name: &str, data: &[u8]) {
+        for len in 0..data.len() {
+            if let Ok(d) = parse(name, &data[..len]) { d.to_text(); }
+        }
+        for pos in 0..data.len() {
+            let mut damaged = data.to_vec();
+            damaged[pos] = 0xFF;
+            if let Ok(d) = parse(name, &damaged) { d.to_text(); }
+        }
+    }
+
+    // 最小的 ICC 配置文件：文件头与一个 desc 标签
+    fn icc_profile() -> Vec<u8> {
+        let mut p = vec![0u8; 128];
+        p[8] = 2;
+        p[9] = 0x10;
+        p[36..40].copy_from_slice(b"acsp");
+        let text = b"Test RGB\0";
+        let mut tag = b"desc\0\0\0\0".to_vec();
+        tag.extend((text.len() as u32).to_be_bytes());
+        tag.extend(text);
+        p.extend(1u32.to_be_bytes());
+        p.extend(b"desc");
+        p.extend((128u32 + 4 + 12).to_be_bytes());
+        p.extend((tag.len() as u32).to_be_bytes());
+        p.extend(tag);
+        p
+    }
+
+    fn ifd_entry(out: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32) {
+        out.extend(tag.to_le_bytes());
+        out.extend(kind.to_le_bytes());
+        out.extend(count.to_le_bytes());
+        out.extend(value.to_le_bytes());
+    }
+
+    // 小端 TIFF：IFD0（尺寸、压缩、EXIF、XMP、ICC）→ IFD1 → 又指回 IFD0 的环状链表
+    fn tiff_fixture() -> Vec<u8> {
+        let icc = icc_profile();
+        let (ifd0, ifd1, exif, icc_at) = (8u32, 86u32, 104u32, 110u32);
+        let mut d = b"II\x2A\0".to_vec();
+        d.extend(ifd0.to_le_bytes());
+        d.extend(6u16.to_le_bytes());
+        ifd_entry(&mut d, 256, 3, 1, 16);
+        ifd_entry(&mut d, 257, 3, 1, 8);
+        ifd_entry(&mut d, 259, 3, 1, 5);
+        ifd_entry(&mut d, 700, 1, 4, u32::from_le_bytes(*b"<x/>"));
+        ifd_entry(&mut d, 34665, 4, 1, exif);
+        ifd_entry(&mut d, 34675, 7, icc.len() as u32, icc_at);
+        d.extend(ifd1.to_le_bytes());
+        d.extend(1u16.to_le_bytes());
+        ifd_entry(&mut d, 256, 3, 1, 4);
+        d.extend(ifd0.to_le_bytes());
+        d.extend(0u16.to_le_bytes());
+        d.extend(0u32.to_le_bytes());
+        assert_eq!(d.len(), icc_at as usize);
+        d.extend(icc);
+        d
+    }
+
+    fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+        out.extend((body.len() as u32).to_be_bytes());
+        out.extend(kind);
+        out.extend(body);
+        // 不检查 CRC
+        out.extend([0; 4]);
+    }
+
+    fn png_fixture() -> Vec<u8> {
+        let mut d = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut ihdr = Vec::new();
+        ihdr.extend(16u32.to_be_bytes());
+        ihdr.extend(8u32.to_be_bytes());
+        ihdr.extend([8, 6, 0, 0, 1]);
+        png_chunk(&mut d, b"IHDR", &ihdr);
+        png_chunk(&mut d, b"iCCP", b"sRGB\0\0\x78\x9c");
+        png_chunk(&mut d, b"iTXt", b"XML:com.adobe.xmp\0\0\0\0\0<x/>");
+        png_chunk(&mut d, b"eXIf", b"MM\0\x2A\0\0\0\x08\0\0");
+        png_chunk(&mut d, b"acTL", &[0, 0, 0, 2, 0, 0, 0, 0]);
+        png_chunk(&mut d, b"IDAT", &[0; 10]);
+        png_chunk(&mut d, b"IDAT", &[0; 10]);
+        png_chunk(&mut d, b"IEND", &[]);
+        d
+    }
+
+    fn jpeg_segment(out: &mut Vec<u8>, marker: u8, body: &[u8]) {
+        out.extend([0xFF, marker]);
+        out.extend((body.len() as u16 + 2).to_be_bytes());
+        out.extend(body);
+    }
+
+    fn jpeg_fixture() -> Vec<u8> {
+        let mut d = vec![0xFF, 0xD8];
+        jpeg_segment(&mut d, 0xE0, b"JFIF\0\x01\x01\x01\0\x48\0\x48\0\0");
+        let mut exif = b"Exif\0\0".to_vec();
+        exif.extend(tiff_fixture());
+        jpeg_segment(&mut d, 0xE1, &exif);
+        let mut icc = b"ICC_PROFILE\0\x01\x01".to_vec();
+        icc.extend(icc_profile());
+        jpeg_segment(&mut d, 0xE2, &icc);
+        let mut dqt = vec![0];
+        dqt.extend([16u8; 64]);
+        jpeg_segment(&mut d, 0xDB, &dqt);
+        jpeg_segment(&mut d, 0xC0, &[8, 0, 8, 0, 16, 3, 1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1]);
+        jpeg_segment(&mut d, 0xDD, &[0, 4]);
+        jpeg_segment(&mut d, 0xDA, &[3, 1, 0, 2, 0x11, 3, 0x11, 0, 63, 0]);
+        d.extend([0x12, 0x34, 0x56, 0xFF, 0xD9]);
+        d
+    }
+
+    fn riff_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+        out.extend(kind);
+        out.extend((body.len() as u32).to_le_bytes());
+        out.extend(body);
+        if body.len() % 2 == 1 { out.push(0); }
+    }
+
+    fn webp_fixture() -> Vec<u8> {
+        let mut body = b"WEBP".to_vec();
+        riff_chunk(&mut body, b"VP8X", &[0x2C, 0, 0, 0, 15, 0, 0, 7, 0, 0]);
+        riff_chunk(&mut body, b"ICCP", &icc_profile());
+        riff_chunk(&mut body, b"EXIF", &tiff_fixture());
+        riff_chunk(&mut body, b"XMP ", b"<x/>");
+        // 奇数长度，后面有一个填充字节
+        riff_chunk(&mut body, b"VP8L", &[0x2F, 1, 2, 3, 4]);
+        let mut d = b"RIFF".to_vec();
+        d.extend((body.len() as u32).to_le_bytes());
+        d.extend(body);
+        d
+    }
+
+    #[test]
+    fn png_lists_chunks_and_metadata() {
+        let text = parse("png-full", &png_fixture()).unwrap().to_text();
+        assert!(text.contains("16×8，8 位，RGBA，Adam7 隔行"), "{}", text);
+        assert!(text.contains("IDAT ×2"), "{}", text);
+        assert!(text.contains("2 帧"), "{}", text);
+        assert!(text.contains("XMP：iTXt 块"), "{}", text);
+        assert!(text.contains("EXIF：eXIf 块"), "{}", text);
+        assert!(!text.contains("截断"), "{}", text);
+    }
+
+    #[test]
+    fn jpeg_lists_segments_and_metadata() {
+        let d = parse("jpeg-full", &jpeg_fixture()).unwrap();
+        assert_eq!(d.icc_name(), Some("Test RGB"));
+        let text = d.to_text();
+        assert!(text.contains("16×8，8 位，3 个分量（采样 2×2 1×1 1×1），基线"), "{}", text);
+        assert!(text.contains("约相当于质量 86"), "{}", text);
+        assert!(text.contains("扫描数据"), "{}", text);
+        assert!(text.contains("EXIF：APP1"), "{}", text);
+    }
+
+    #[test]
+    fn webp_lists_chunks_and_metadata() {
+        let d = parse("webp-full", &webp_fixture()).unwrap();
+        assert_eq!(d.icc_name(), Some("Test RGB"));
+        let text = d.to_text();
+        assert!(text.contains("标志：ICC EXIF XMP"), "{}", text);
+        assert!(text.contains("VP8L"), "{}", text);
+        assert!(!text.contains("截断"), "{}", text);
+    }
+
+    #[test]
+    fn tiff_stops_at_cyclic_ifd_chain() {
+        let d = parse("tiff-full", &tiff_fixture()).unwrap();
+        assert_eq!(d.icc_name(), Some("Test RGB"));
+        let text = d.to_text();
+        assert!(text.contains("6 个标签，16×8，LZW"), "{}", text);
+        assert!(text.contains("IFD1"), "{}", text);
+        assert!(!text.contains("IFD2"), "{}", text);
+        assert!(text.contains("EXIF：EXIF IFD，0 个标签"), "{}", text);
+    }
+
+    #[test]
+    fn png_survives_truncation_and_corruption() {
+        survives_damage("png", &png_fixture());
+    }
+
+    #[test]
+    fn jpeg_survives_truncation_and_corruption() {
+        survives_damage("jpeg", &jpeg_fixture());
+    }
+
+    #[test]
+    fn webp_survives_truncation_and_corruption() {
+        survives_damage("webp", &webp_fixture());
+    }
+
+    #[test]
+    fn tiff_survives_truncation_and_corruption() {
+        survives_damage("tiff", &tiff_fixture());
+    }
+}
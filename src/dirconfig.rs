@@ -0,0 +1,83 @@
+// 目录视图设置：图片所在目录中的 .image-viewer.toml 覆盖用户配置中的视图设置
+// 合并顺序：内置默认值 → 用户配置 → 目录文件；出于安全考虑需在配置中开启 directory-config
+//
+//     zoom = 400
+//     filter = "nearest"
+//     background = "#ffffff"
+//     sort = "modified"
+
+use crate::config::{Config, SortOrder, ViewDefaults};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const FILE_NAME: &str = ".image-viewer.toml";
+
+// 按修改时间缓存，文件被编辑后重新读取；无效文件每个版本只警告一次
+struct Entry {
+    modified: Option<SystemTime>,
+    view: Option<ViewDefaults>,
+}
+
+pub struct DirectoryViews {
+    enabled: bool,
+    base: ViewDefaults,
+    cache: RefCell<HashMap<PathBuf, Entry>>,
+    // 在窗口中选择的浏览顺序，优先于目录文件与用户配置
+    sort: Cell<Option<SortOrder>>,
+}
+
+impl DirectoryViews {
+    pub fn new(config: &Config) -> Self {
+        Self { enabled: config.directory_config, base: config.view.clone(), cache: RefCell::default(), sort: Cell::new(None) }
+    }
+
+    pub fn set_sort(&self, sort: SortOrder) {
+        self.sort.set(Some(sort));
+    }
+
+    pub fn for_dir(&self, dir: &Path) -> ViewDefaults {
+        let mut view = self.merged_view(dir);
+        view.sort = self.sort.get().or(view.sort);
+        view
+    }
+
+    fn merged_view(&self, dir: &Path) -> ViewDefaults {
+        if !self.enabled { return self.base.clone(); }
+        let file = dir.join(FILE_NAME);
+        let modified = std::fs::metadata(&file).and_then(|m| m.modified()).ok();
+        let mut cache = self.cache.borrow_mut();
+        if cache.get(dir).is_none_or(|e| e.modified != modified) {
+            let view = modified.and_then(|_| load(&file));
+            cache.insert(dir.to_path_buf(), Entry { modified, view });
+        }
+        match cache[dir].view {
+            Some(ref view) => self.base.merge(view),
+            None => self.base.clone(),
+        }
+    }
+
+    // 标准输入等没有目录的来源只使用用户配置
+    pub fn for_file(&self, path: &Path) -> ViewDefaults {
+        match path.parent().filter(|d| path.is_file() && !d.as_os_str().is_empty()) {
+            Some(dir) => self.for_dir(dir),
+            None if path.is_file() => self.for_dir(Path::new(".")),
+            None => ViewDefaults { sort: self.sort.get().or(self.base.sort), ..self.base.clone() },
+        }
+    }
+}
+
+// 读取失败或格式错误时警告并忽略整个文件，不影响图片载入
+fn load(file: &Path) -> Option<ViewDefaults> {
+    let text = std::fs::read_to_string(file)
+        .map_err(|e| eprintln!("无法读取目录配置 {}: {}", file.display(), e))
+        .ok()?;
+    let mut view: ViewDefaults = toml::from_str(&text)
+        .map_err(|e| eprintln!("目录配置 {} 无效，已忽略: {}", file.display(), e))
+        .ok()?;
+    if view.background.is_some() && view.background_rgba().is_none() {
+        eprintln!("目录配置 {} 中的背景色 {:?} 无效，已忽略", file.display(), view.background.take().unwrap_or_default());
+    }
+    Some(view)
+}
@@ -0,0 +1,88 @@
+// 拖动越过图片边缘时的橡皮筋效果：超出的部分按 DAMPING 衰减后跟随指针，
+// 松开后以缓出动画回到边缘；动画结束时一定停在限制之内
+// 关闭动画时拖动直接停在边缘，松开后的回弹一步完成
+
+use std::time::{Duration, Instant};
+
+const DAMPING: f64 = 0.3;
+const DURATION: Duration = Duration::from_millis(180);
+
+// 各方向允许的偏移为 [-limit, limit]，超出的部分乘以 DAMPING
+pub fn rubber_band(raw: f64, limit: f64) -> f64 {
+    let clamped = raw.clamp(-limit, limit);
+    clamped + (raw - clamped) * DAMPING
+}
+
+pub fn clamp((x, y): (f64, f64), (limit_x, limit_y): (f64, f64)) -> (f64, f64) {
+    (x.clamp(-limit_x, limit_x), y.clamp(-limit_y, limit_y))
+}
+
+// 拖动中的偏移
+pub fn follow((x, y): (f64, f64), (limit_x, limit_y): (f64, f64), animate: bool) -> (f64, f64) {
+    if animate {
+        (rubber_band(x, limit_x), rubber_band(y, limit_y))
+    } else {
+        clamp((x, y), (limit_x, limit_y))
+    }
+}
+
+// 松开后回弹
+pub struct Settle {
+    started: Instant,
+    duration: Duration,
+    from: (f64, f64),
+    to: (f64, f64),
+}
+
+impl Settle {
+    pub fn new(from: (f64, f64), to: (f64, f64), animate: bool) -> Self {
+        let duration = if animate { DURATION } else { Duration::ZERO };
+        Self { started: Instant::now(), duration, from, to }
+    }
+
+    // 当前应处的偏移，以及动画是否已结束（结束时正好是目标位置）
+    pub fn position(&self) -> ((f64, f64), bool) {
+        let elapsed = self.started.elapsed();
+        if elapsed >= self.duration { return (self.to, true); }
+        let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+        let eased = 1.0 - (1.0 - t).powi(3);
+        let lerp = |a: f64, b: f64| a + (b - a) * eased;
+        ((lerp(self.from.0, self.to.0), lerp(self.from.1, self.to.1)), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    const LIMITS: (f64, f64) = (100.0, 50.0);
+
+    #[test]
+    fn drag_stops_at_the_edge_without_animations() {
+        let animate = config::animations_enabled(Some(false));
+        assert_eq!(follow((180.0, -90.0), LIMITS, animate), (100.0, -50.0));
+        assert_eq!(follow((20.0, 10.0), LIMITS, animate), (20.0, 10.0));
+    }
+
+    #[test]
+    fn drag_overshoots_with_damping_when_animated() {
+        let animate = config::animations_enabled(Some(true));
+        assert_eq!(follow((200.0, -60.0), LIMITS, animate), (130.0, -53.0));
+    }
+
+    #[test]
+    fn settle_finishes_in_one_step_without_animations() {
+        let settle = Settle::new((130.0, -53.0), (100.0, -50.0), config::animations_enabled(Some(false)));
+        assert_eq!(settle.position(), ((100.0, -50.0), true));
+    }
+
+    #[test]
+    fn animated_settle_starts_at_the_release_point() {
+        let settle = Settle::new((130.0, -53.0), (100.0, -50.0), config::animations_enabled(Some(true)));
+        let ((x, y), done) = settle.position();
+        assert!(!done);
+        assert!(x > 100.0 && x <= 130.0);
+        assert!((-53.0..-50.0).contains(&y));
+    }
+}
@@ -0,0 +1,242 @@
+// 多页/多帧图片导出：逐帧解码并写出为编号的 PNG（在工作线程中运行）
+
+use crate::header::{self, Format};
+use gio::prelude::*;
+use image::{AnimationDecoder, RgbaImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const DEFAULT_PATTERN: &str = "{stem}_{index:03}.png";
+
+// 目标文件已存在时的处理方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum Collision {
+    Overwrite,
+    Skip,
+}
+
+// 导出结果统计
+pub struct Summary {
+    pub written: usize,
+    pub skipped: usize,
+    pub cancelled: bool,
+}
+
+type Frames = Box<dyn Iterator<Item = Result<RgbaImage, String>>>;
+pub type AnimationFrames = Box<dyn Iterator<Item = Result<(RgbaImage, Duration), String>>>;
+
+// 按命名模式生成文件名，支持 {stem}、{index} 与 {index:03} 形式的补零宽度
+pub fn format_name(pattern: &str, stem: &str, index: usize) -> String {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else { break };
+        let field = &rest[start + 1..start + len];
+        let (name, spec) = field.split_once(':').unwrap_or((field, ""));
+        match name {
+            "stem" => out.push_str(stem),
+            "index" => {
+                let width = spec.trim_start_matches('0').parse().unwrap_or(0);
+                out.push_str(&format!("{:0width$}", index, width = width));
+            }
+            // 未知字段原样保留
+            _ => out.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// 检查命名模式：必须包含 {index}，且不能包含路径分隔符
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if !pattern.contains("{index") {
+        return Err("命名模式必须包含 {index}".to_string());
+    }
+    if format_name(pattern, "x", 1).contains('/') {
+        return Err("命名模式不能包含 /".to_string());
+    }
+    Ok(())
+}
+
+// 解析范围文本（从 1 开始）："" 为全部，"5" 为单帧，"3-10" 或 "3-" 为区间
+pub fn parse_range(text: &str) -> Option<(usize, Option<usize>)> {
+    let text = text.trim();
+    if text.is_empty() { return Some((1, None)); }
+    let (from, to) = match text.split_once('-') {
+        Some((a, b)) => {
+            let from = a.trim().parse().ok()?;
+            let to = if b.trim().is_empty() { None } else { Some(b.trim().parse().ok()?) };
+            (from, to)
+        }
+        None => {
+            let n = text.parse().ok()?;
+            (n, Some(n))
+        }
+    };
+    (from >= 1 && to.is_none_or(|to| to >= from)).then_some((from, to))
+}
+
+// 把 TIFF 当前页转换为 RGBA（仅支持 8/16 位灰度、RGB、CMYK）
+fn tiff_page_rgba(dec: &mut tiff::decoder::Decoder<BufReader<File>>) -> Result<RgbaImage, String> {
+    use tiff::ColorType;
+    use tiff::decoder::DecodingResult;
+    let (w, h) = dec.dimensions().map_err(|e| e.to_string())?;
+    let color = dec.colortype().map_err(|e| e.to_string())?;
+    let samples: Vec<u8> = match dec.read_image().map_err(|e| e.to_string())? {
+        DecodingResult::U8(v) => v,
+        DecodingResult::U16(v) => v.iter().map(|x| (x >> 8) as u8).collect(),
+        _ => return Err("不支持的 TIFF 采样格式".to_string()),
+    };
+    let channels = match color {
+        ColorType::Gray(8 | 16) => 1,
+        ColorType::GrayA(8 | 16) => 2,
+        ColorType::RGB(8 | 16) => 3,
+        ColorType::RGBA(8 | 16) | ColorType::CMYK(8 | 16) => 4,
+        other => return Err(format!("不支持的 TIFF 颜色类型: {:?}", other)),
+    };
+    let mut out = Vec::with_capacity(w as usize * h as usize * 4);
+    for px in samples.chunks_exact(channels) {
+        match (color, px) {
+            (ColorType::CMYK(_), &[c, m, y, k]) => {
+                let ch = |v: u8| ((255 - v as u32) * (255 - k as u32) / 255) as u8;
+                out.extend_from_slice(&[ch(c), ch(m), ch(y), 255]);
+            }
+            (_, &[g]) => out.extend_from_slice(&[g, g, g, 255]),
+            (_, &[g, a]) => out.extend_from_slice(&[g, g, g, a]),
+            (_, &[r, g, b]) => out.extend_from_slice(&[r, g, b, 255]),
+            (_, rgba) => out.extend_from_slice(rgba),
+        }
+    }
+    RgbaImage::from_raw(w, h, out).ok_or_else(|| "TIFF 数据长度不匹配".to_string())
+}
+
+// 逐页读取 TIFF
+struct TiffPages {
+    dec: tiff::decoder::Decoder<BufReader<File>>,
+    first: bool,
+    done: bool,
+}
+
+impl Iterator for TiffPages {
+    type Item = Result<RgbaImage, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+        if !self.first {
+            if !self.dec.more_images() { return None; }
+            if let Err(e) = self.dec.next_image() {
+                self.done = true;
+                return Some(Err(e.to_string()));
+            }
+        }
+        self.first = false;
+        Some(tiff_page_rgba(&mut self.dec))
+    }
+}
+
+// 动画的帧序列（GIF、WebP、APNG，已按处置方式合成）；其他格式为 None
+fn animation(path: &Path) -> Result<Option<image::Frames<'static>>, String> {
+    let reader = || File::open(path).map(BufReader::new).map_err(|e| e.to_string());
+    Ok(Some(match header::sniff_file(path) {
+        Some(Format::Gif) => image::codecs::gif::GifDecoder::new(reader()?).map_err(|e| e.to_string())?.into_frames(),
+        Some(Format::WebP) => image::codecs::webp::WebPDecoder::new(reader()?).map_err(|e| e.to_string())?.into_frames(),
+        Some(Format::Png) if header::has_multiple_frames(path) => {
+            let dec = image::codecs::png::PngDecoder::new(reader()?).map_err(|e| e.to_string())?;
+            dec.apng().map_err(|e| e.to_string())?.into_frames()
+        }
+        _ => return Ok(None),
+    }))
+}
+
+// 是否为可以播放的动画（多帧的 GIF、WebP、APNG；多页 TIFF 不算）
+pub fn is_animation(path: &Path) -> bool {
+    matches!(header::sniff_file(path), Some(Format::Gif | Format::WebP | Format::Png)) && header::has_multiple_frames(path)
+}
+
+// 逐帧读取动画及各帧的显示时长；不是动画时为 None
+pub fn animation_frames(path: &Path) -> Result<Option<AnimationFrames>, String> {
+    Ok(animation(path)?.map(|frames| -> AnimationFrames {
+        Box::new(frames.map(|f| {
+            let f = f.map_err(|e| e.to_string())?;
+            let (num, den) = f.delay().numer_denom_ms();
+            let delay = Duration::from_micros(num as u64 * 1000 / den.max(1) as u64);
+            Ok((f.into_buffer(), delay))
+        }))
+    }))
+}
+
+// 打开帧序列：动画逐帧，TIFF 逐页，其余格式为单帧
+fn open_frames(path: &Path) -> Result<Frames, String> {
+    if let Some(frames) = animation(path)? {
+        return Ok(Box::new(frames.map(|f| f.map(|f| f.into_buffer()).map_err(|e| e.to_string()))));
+    }
+    let reader = || File::open(path).map(BufReader::new).map_err(|e| e.to_string());
+    match header::sniff_file(path) {
+        Some(Format::Tiff) => {
+            let dec = tiff::decoder::Decoder::new(reader()?).map_err(|e| e.to_string())?;
+            Ok(Box::new(TiffPages { dec, first: true, done: false }))
+        }
+        _ => {
+            let img = image::open(path).map_err(|e| e.to_string())?;
+            Ok(Box::new(std::iter::once(Ok(img.to_rgba8()))))
+        }
+    }
+}
+
+// 导出帧到目录。progress 在每写出一帧后调用；ask 在首次遇到同名文件时调用，返回 None 表示取消
+pub fn extract_frames(
+    path: &Path,
+    out_dir: &Path,
+    pattern: &str,
+    (from, to): (usize, Option<usize>),
+    cancel: &gio::Cancellable,
+    mut progress: impl FnMut(usize),
+    mut ask: impl FnMut(&Path) -> Option<Collision>,
+) -> Result<Summary, String> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut policy: Option<Collision> = None;
+    let mut summary = Summary { written: 0, skipped: 0, cancelled: false };
+    for (i, frame) in open_frames(path)?.enumerate() {
+        let index = i + 1;
+        if to.is_some_and(|to| index > to) { break; }
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        let frame = frame?;
+        if index < from { continue; }
+        let target: PathBuf = out_dir.join(format_name(pattern, &stem, index));
+        if target.exists() {
+            if policy.is_none() {
+                match ask(&target) {
+                    Some(choice) => policy = Some(choice),
+                    None => {
+                        summary.cancelled = true;
+                        break;
+                    }
+                }
+            }
+            if policy == Some(Collision::Skip) {
+                summary.skipped += 1;
+                continue;
+            }
+        }
+        frame.save_with_format(&target, image::ImageFormat::Png)
+            .map_err(|e| format!("{}: {}", target.display(), e))?;
+        summary.written += 1;
+        progress(summary.written);
+    }
+    Ok(summary)
+}
+
+// 解码第 index 帧/页（从 1 开始）
+pub fn decode_frame(path: &Path, index: usize) -> Result<RgbaImage, String> {
+    if index == 0 { return Err("帧序号从 1 开始".to_string()); }
+    open_frames(path)?
+        .nth(index - 1)
+        .unwrap_or_else(|| Err(format!("图片没有第 {} 帧/页", index)))
+}
@@ -0,0 +1,96 @@
+// 载入请求的编号：每次开始载入时取新编号，异步完成（完整解码、动画帧、目录扫描）时与开始时记下的编号比较，
+// 已有更新的请求时丢弃结果。编号在整个进程内单调递增，各标签的状态之间也不会重复
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT: AtomicU64 = AtomicU64::new(1);
+
+// 一个标签最近一次载入请求的编号，0 表示还没有载入过
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Generation(u64);
+
+impl Generation {
+    // 开始一次载入请求，返回其编号
+    pub fn begin(&mut self) -> u64 {
+        self.0 = NEXT.fetch_add(1, Ordering::Relaxed);
+        self.0
+    }
+
+    pub fn current(&self) -> u64 {
+        self.0
+    }
+
+    // 编号为 id 的请求是否仍是最新的，异步结果只在此时应用
+    pub fn is_current(&self, id: u64) -> bool {
+        self.0 == id
+    }
+
+    // 请求 id 失败时恢复为之前的编号，仍在进行的旧请求（如完整解码）可以继续完成；
+    // 期间已有更新的请求时不变
+    pub fn abandon(&mut self, id: u64, previous: u64) {
+        if self.0 == id { self.0 = previous; }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Generation;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    // 依次发出两个请求，各由一个假解码器在给定延迟后返回结果；
+    // 按到达顺序处理结果，只应用仍是最新请求的结果，返回最终显示的内容
+    fn race(first_delay: u64, second_delay: u64) -> Option<&'static str> {
+        let mut generation = Generation::default();
+        let (tx, rx) = mpsc::channel();
+        for (name, delay) in [("first", first_delay), ("second", second_delay)] {
+            let id = generation.begin();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(delay));
+                tx.send((id, name)).ok();
+            });
+        }
+        drop(tx);
+        let mut shown = None;
+        for (id, name) in rx {
+            if generation.is_current(id) { shown = Some(name); }
+        }
+        shown
+    }
+
+    #[test]
+    fn newer_request_wins_when_older_finishes_last() {
+        assert_eq!(race(80, 5), Some("second"));
+    }
+
+    #[test]
+    fn newer_request_wins_when_older_finishes_first() {
+        assert_eq!(race(5, 80), Some("second"));
+    }
+
+    #[test]
+    fn numbers_increase_across_states() {
+        let (mut a, mut b) = (Generation::default(), Generation::default());
+        let first = a.begin();
+        let second = b.begin();
+        assert!(second > first);
+        assert!(!b.is_current(first));
+        assert!(a.is_current(first));
+    }
+
+    #[test]
+    fn abandon_restores_only_the_failed_request() {
+        let mut g = Generation::default();
+        let previous = g.begin();
+        let failed = g.begin();
+        g.abandon(failed, previous);
+        assert!(g.is_current(previous));
+        // 失败前已有更新的请求时不回退
+        let failed = g.begin();
+        let newer = g.begin();
+        g.abandon(failed, previous);
+        assert!(g.is_current(newer));
+    }
+}
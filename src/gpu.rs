@@ -0,0 +1,44 @@
+// 渲染器的纹理尺寸上限：优先通过 GDK 的 GL 上下文查询 GL_MAX_TEXTURE_SIZE，无法查询时按 8192 计算
+// 边长超过上限的图片交给渲染器后可能不显示甚至使 GL 渲染器崩溃，载入时改为缩小解码
+
+use gtk4::gdk;
+use gtk4::prelude::*;
+use std::sync::OnceLock;
+
+const FALLBACK: u32 = 8192;
+// cairo 图像表面的边长上限，与显卡无关
+const CAIRO_LIMIT: u32 = 32767;
+const GL_MAX_TEXTURE_SIZE: u32 = 0x0D33;
+
+#[link(name = "epoxy")]
+unsafe extern "C" {
+    // libepoxy 导出的 GL 函数指针（GTK 同样经由它调用 GL），首次调用时按当前上下文解析
+    static epoxy_glGetIntegerv: unsafe extern "C" fn(u32, *mut i32);
+}
+
+fn query_gl(display: &gdk::Display) -> Option<u32> {
+    let context = display.create_gl_context().ok()?;
+    context.realize().ok()?;
+    context.make_current();
+    let mut size = 0;
+    // SAFETY: 上下文已实现并设为当前，size 在调用期间有效
+    unsafe { epoxy_glGetIntegerv(GL_MAX_TEXTURE_SIZE, &mut size) };
+    gdk::GLContext::clear_current();
+    u32::try_from(size).ok().filter(|&s| s > 0)
+}
+
+// 上限及其来源；查询只在第一个窗口创建时进行一次
+pub fn max_texture_size(display: &gdk::Display, forced: Option<u32>) -> (u32, &'static str) {
+    static DETECTED: OnceLock<(u32, &'static str)> = OnceLock::new();
+    if let Some(size) = forced.filter(|&s| s > 0) {
+        return (size.min(CAIRO_LIMIT), "--max-texture-size");
+    }
+    *DETECTED.get_or_init(|| match query_gl(display) {
+        Some(size) => (size.min(CAIRO_LIMIT), "GL_MAX_TEXTURE_SIZE"),
+        None => (FALLBACK, "默认值（无法查询 GL 上下文）"),
+    })
+}
+
+pub fn exceeds(w: u32, h: u32, limit: u32) -> bool {
+    w.max(h) > limit
+}
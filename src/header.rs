@@ -0,0 +1,788 @@
+// 图片文件头解析：只读取少量字节获取元数据，不做完整解码
+// 所有解析函数遇到截断或畸形数据时返回 None，绝不 panic
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// 读取文件头部时的默认字节数，足以覆盖绝大多数 PNG/JPEG 的元数据段
+const PREFIX_LEN: u64 = 256 * 1024;
+
+// 合理 DPI 范围，超出视为无效元数据（如默认写入的 1 或 72000）
+const MIN_DPI: f64 = 10.0;
+const MAX_DPI: f64 = 10000.0;
+
+// 读取文件前 len 字节
+pub fn read_prefix(path: &Path, len: u64) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+    File::open(path).ok()?.take(len).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+pub fn be16(d: &[u8], pos: usize) -> Option<u16> {
+    d.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+pub fn be32(d: &[u8], pos: usize) -> Option<u32> {
+    d.get(pos..pos + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// TIFF 结构读取器（TIFF 文件和 JPEG 的 EXIF 段共用）
+pub struct Tiff<'a> {
+    data: &'a [u8],
+    little: bool,
+}
+
+// IFD 条目：标签、类型、数量、值/偏移字段位置
+pub struct IfdEntry {
+    pub tag: u16,
+    pub kind: u16,
+    pub count: u32,
+    pub value_pos: usize,
+}
+
+impl<'a> Tiff<'a> {
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        let little = match data.get(0..4)? {
+            [b'I', b'I', 42, 0] => true,
+            [b'M', b'M', 0, 42] => false,
+            _ => return None,
+        };
+        Some(Self { data, little })
+    }
+
+    pub fn u16(&self, pos: usize) -> Option<u16> {
+        let b = self.data.get(pos..pos + 2)?;
+        Some(if self.little { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) })
+    }
+
+    pub fn u32(&self, pos: usize) -> Option<u32> {
+        let b = self.data.get(pos..pos + 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if self.little { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    pub fn first_ifd(&self) -> Option<usize> {
+        self.u32(4).map(|o| o as usize)
+    }
+
+    pub fn entries(&self, ifd: usize) -> Option<Vec<IfdEntry>> {
+        let n = self.u16(ifd)? as usize;
+        let mut out = Vec::with_capacity(n);
+        for i in 0..n {
+            let pos = ifd + 2 + i * 12;
+            out.push(IfdEntry {
+                tag: self.u16(pos)?,
+                kind: self.u16(pos + 2)?,
+                count: self.u32(pos + 4)?,
+                value_pos: pos + 8,
+            });
+        }
+        Some(out)
+    }
+
+    // 读取 SHORT/LONG 类型的首个值
+    pub fn uint(&self, e: &IfdEntry) -> Option<u32> {
+        match e.kind {
+            3 => self.u16(e.value_pos).map(u32::from),
+            4 => self.u32(e.value_pos),
+            _ => None,
+        }
+    }
+
+    // 读取 ASCII 类型的值（不含结尾的 NUL）
+    fn ascii(&self, e: &IfdEntry) -> Option<&'a [u8]> {
+        if e.kind != 2 { return None; }
+        let len = e.count as usize;
+        let start = if len <= 4 { e.value_pos } else { self.u32(e.value_pos)? as usize };
+        let text = self.data.get(start..start.checked_add(len)?)?;
+        Some(text.strip_suffix(&[0]).unwrap_or(text))
+    }
+
+    // 拍摄时间：Exif 子 IFD 的 DateTimeOriginal，没有时用 IFD0 的 DateTime
+    fn capture_date(&self) -> Option<CaptureDate> {
+        let entries = self.entries(self.first_ifd()?)?;
+        let original = entries.iter().find(|e| e.tag == 0x8769)
+            .and_then(|e| self.entries(self.uint(e)? as usize))
+            .and_then(|sub| sub.iter().find(|e| e.tag == 0x9003).and_then(|e| self.ascii(e)));
+        let text = original.or_else(|| entries.iter().find(|e| e.tag == 0x0132).and_then(|e| self.ascii(e)))?;
+        parse_exif_date(text)
+    }
+
+    // 读取 RATIONAL 类型的值
+    fn rational(&self, e: &IfdEntry) -> Option<f64> {
+        let (num, den) = self.fraction(e)?;
+        Some(num as f64 / den as f64)
+    }
+
+    // RATIONAL 的分子与分母（分母为 0 时返回 None）
+    fn fraction(&self, e: &IfdEntry) -> Option<(u32, u32)> {
+        if e.kind != 5 || e.count == 0 { return None; }
+        let off = self.u32(e.value_pos)? as usize;
+        let (num, den) = (self.u32(off)?, self.u32(off + 4)?);
+        (den != 0).then_some((num, den))
+    }
+
+    // 信息面板显示的常用字段：IFD0 的厂商与型号，Exif 子 IFD 的曝光参数
+    fn exif(&self) -> Option<Exif> {
+        let entries = self.entries(self.first_ifd()?)?;
+        let text = |entries: &[IfdEntry], tag| {
+            let bytes = self.ascii(entries.iter().find(|e| e.tag == tag)?)?;
+            let text = String::from_utf8_lossy(bytes).trim().to_string();
+            (!text.is_empty()).then_some(text)
+        };
+        let sub = entries.iter().find(|e| e.tag == 0x8769)
+            .and_then(|e| self.entries(self.uint(e)? as usize))
+            .unwrap_or_default();
+        let find = |tag| sub.iter().find(|e| e.tag == tag);
+        Some(Exif {
+            make: text(&entries, 0x010F),
+            model: text(&entries, 0x0110),
+            exposure_time: find(0x829A).and_then(|e| self.fraction(e)),
+            f_number: find(0x829D).and_then(|e| self.rational(e)),
+            iso: find(0x8827).and_then(|e| self.uint(e)).filter(|&iso| iso > 0),
+            focal_length: find(0x920A).and_then(|e| self.rational(e)).filter(|&f| f > 0.0),
+            taken: self.capture_date(),
+        })
+    }
+
+    // 从 IFD0 的 XResolution/YResolution/ResolutionUnit 读取 DPI
+    fn dpi(&self) -> Option<(f64, f64)> {
+        let entries = self.entries(self.first_ifd()?)?;
+        let find = |tag| entries.iter().find(|e| e.tag == tag);
+        let x = self.rational(find(282)?)?;
+        let y = find(283).and_then(|e| self.rational(e)).unwrap_or(x);
+        // ResolutionUnit: 1 无单位, 2 英寸（默认）, 3 厘米
+        let unit = find(296).and_then(|e| self.uint(e)).unwrap_or(2);
+        match unit {
+            2 => Some((x, y)),
+            3 => Some((x * 2.54, y * 2.54)),
+            _ => None,
+        }
+    }
+}
+
+// EXIF 日期时间（相机的本地时间，不带时区）：年、月、日、时、分、秒
+pub type CaptureDate = (i32, u32, u32, u32, u32, u32);
+
+// "YYYY:MM:DD HH:MM:SS"；未知日期常写成全 0 或空格，视为缺失
+fn parse_exif_date(text: &[u8]) -> Option<CaptureDate> {
+    let text = std::str::from_utf8(text).ok()?;
+    let num = |range: std::ops::Range<usize>| text.get(range)?.parse::<u32>().ok();
+    let date = (num(0..4)? as i32, num(5..7)?, num(8..10)?, num(11..13)?, num(14..16)?, num(17..19)?);
+    let valid = (1..=12).contains(&date.1) && (1..=31).contains(&date.2) && date.3 < 24 && date.4 < 60 && date.5 < 61;
+    valid.then_some(date)
+}
+
+// 按 DPI 合理范围过滤
+fn sane_dpi((x, y): (f64, f64)) -> Option<(f64, f64)> {
+    let ok = |v: f64| v.is_finite() && (MIN_DPI..=MAX_DPI).contains(&v);
+    (ok(x) && ok(y)).then_some((x, y))
+}
+
+// PNG：pHYs 块（单位为米时换算为英寸）
+fn png_dpi(d: &[u8]) -> Option<(f64, f64)> {
+    if d.get(0..8)? != b"\x89PNG\r\n\x1a\n" { return None; }
+    let mut pos = 8;
+    loop {
+        let len = be32(d, pos)? as usize;
+        let kind = d.get(pos + 4..pos + 8)?;
+        match kind {
+            b"pHYs" => {
+                let body = pos + 8;
+                let (x, y) = (be32(d, body)?, be32(d, body + 4)?);
+                if *d.get(body + 8)? != 1 { return None; }
+                return Some((x as f64 * 0.0254, y as f64 * 0.0254));
+            }
+            // pHYs 必须出现在 IDAT 之前
+            b"IDAT" | b"IEND" => return None,
+            _ => pos = pos.checked_add(12 + len)?,
+        }
+    }
+}
+
+// JPEG：优先 JFIF APP0 的密度，其次 EXIF 中的分辨率
+fn jpeg_dpi(d: &[u8]) -> Option<(f64, f64)> {
+    let mut jfif = None;
+    let mut exif = None;
+    for (marker, body) in jpeg_segments(d) {
+        match marker {
+            0xE0 if body.starts_with(b"JFIF\0") => {
+                let unit = *body.get(7)?;
+                let (x, y) = (be16(body, 8)? as f64, be16(body, 10)? as f64);
+                jfif = match unit {
+                    1 => Some((x, y)),
+                    2 => Some((x * 2.54, y * 2.54)),
+                    _ => None,
+                };
+            }
+            0xE1 if body.starts_with(b"Exif\0\0") => {
+                exif = Tiff::new(&body[6..]).and_then(|t| t.dpi());
+            }
+            _ => {}
+        }
+    }
+    jfif.and_then(sane_dpi).or(exif)
+}
+
+// 遍历 JPEG 段（marker, 段内容），到 SOS 为止
+pub fn jpeg_segments(d: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut out = Vec::new();
+    if d.get(0..2) != Some(&[0xFF, 0xD8]) { return out; }
+    let mut pos = 2;
+    while let (Some(&0xFF), Some(&marker)) = (d.get(pos), d.get(pos + 1)) {
+        // 填充字节
+        if marker == 0xFF { pos += 1; continue; }
+        // 无长度的独立标记
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) { pos += 2; continue; }
+        let Some(len) = be16(d, pos + 2) else { break };
+        let len = len as usize;
+        if len < 2 { break; }
+        let Some(body) = d.get(pos + 4..pos + 2 + len) else { break };
+        out.push((marker, body));
+        if marker == 0xDA { break; }
+        pos += 2 + len;
+    }
+    out
+}
+
+// 只读取文件开头的一小段获取 EXIF 拍摄时间，供按拍摄日期排序
+// JPEG 的 EXIF 段可能带有缩略图而很长，日期所在的 IFD 通常位于段首，截断的段同样解析，
+// 在截断的段中找不到时再读取完整的段；TIFF 的 IFD 位置不固定，只在前缀中查找
+const DATE_PREFIX_LEN: u64 = 16 * 1024;
+
+pub fn capture_date(path: &Path) -> Option<CaptureDate> {
+    // 外层为 None 表示日期可能位于被截断的部分，需要读取更多数据
+    let parse = |d: &[u8]| -> Option<Option<CaptureDate>> {
+        match d.get(0..4) {
+            Some([0xFF, 0xD8, ..]) => {
+                // 与 jpeg_segments 相同的遍历，但保留被前缀截断的最后一段
+                let mut pos = 2;
+                while let (Some(&0xFF), Some(&marker)) = (d.get(pos), d.get(pos + 1)) {
+                    if marker == 0xFF { pos += 1; continue; }
+                    if marker == 0x01 || (0xD0..=0xD7).contains(&marker) { pos += 2; continue; }
+                    let Some(len) = be16(d, pos + 2).map(usize::from).filter(|&l| l >= 2) else { break };
+                    if marker == 0xDA { break; }
+                    let end = pos + 2 + len;
+                    let Some(body) = d.get(pos + 4..end.min(d.len())) else { break };
+                    if marker == 0xE1 && body.starts_with(b"Exif\0\0") {
+                        let date = Tiff::new(&body[6..]).and_then(|t| t.capture_date());
+                        return if date.is_none() && end > d.len() { None } else { Some(date) };
+                    }
+                    if end > d.len() { return None; }
+                    pos = end;
+                }
+                Some(None)
+            }
+            Some([b'I', b'I', 42, 0] | [b'M', b'M', 0, 42]) => Some(Tiff::new(d).and_then(|t| t.capture_date())),
+            _ => Some(None),
+        }
+    };
+    let data = read_prefix(path, DATE_PREFIX_LEN)?;
+    match parse(&data) {
+        Some(date) => date,
+        // 放宽到一个 JPEG 段的最大长度再试一次
+        None => parse(&read_prefix(path, 70 * 1024)?).flatten(),
+    }
+}
+
+// EXIF 中的相机与拍摄参数，缺失的字段为 None
+#[derive(Clone, Debug, Default)]
+pub struct Exif {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    // 曝光时间（秒），保留分数形式以便显示为 1/125
+    pub exposure_time: Option<(u32, u32)>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    // 焦距（毫米）
+    pub focal_length: Option<f64>,
+    pub taken: Option<CaptureDate>,
+}
+
+// 读取 JPEG 或 TIFF 的 EXIF；其他格式及没有 EXIF 时返回 None
+pub fn read_exif(path: &Path) -> Option<Exif> {
+    let data = read_prefix(path, PREFIX_LEN)?;
+    match data.get(0..4)? {
+        [0xFF, 0xD8, ..] => jpeg_segments(&data).into_iter()
+            .find(|(marker, body)| *marker == 0xE1 && body.starts_with(b"Exif\0\0"))
+            .and_then(|(_, body)| Tiff::new(&body[6..])?.exif()),
+        // 与 read_dpi 相同，IFD 超出前缀时读取整个文件
+        [b'I', b'I', 42, 0] | [b'M', b'M', 0, 42] => Tiff::new(&data).and_then(|t| t.exif()).or_else(|| {
+            let full = std::fs::read(path).ok()?;
+            Tiff::new(&full)?.exif()
+        }),
+        _ => None,
+    }
+}
+
+// 读取图片的 DPI（水平, 垂直），缺失或不合理时返回 None
+pub fn read_dpi(path: &Path) -> Option<(f64, f64)> {
+    let data = read_prefix(path, PREFIX_LEN)?;
+    let dpi = match data.get(0..4)? {
+        [0x89, b'P', b'N', b'G'] => png_dpi(&data),
+        [0xFF, 0xD8, ..] => jpeg_dpi(&data),
+        [b'I', b'I', 42, 0] | [b'M', b'M', 0, 42] => {
+            // TIFF 的 IFD 可能位于文件末尾，超出前缀时读取整个文件
+            Tiff::new(&data).and_then(|t| t.dpi()).or_else(|| {
+                let full = std::fs::read(path).ok()?;
+                Tiff::new(&full)?.dpi()
+            })
+        }
+        _ => None,
+    };
+    dpi.and_then(sane_dpi)
+}
+
+// 根据文件头魔数识别的格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Tiff,
+    Bmp,
+}
+
+pub fn sniff(d: &[u8]) -> Option<Format> {
+    match d {
+        [0x89, b'P', b'N', b'G', ..] => Some(Format::Png),
+        [0xFF, 0xD8, 0xFF, ..] => Some(Format::Jpeg),
+        [b'G', b'I', b'F', b'8', ..] => Some(Format::Gif),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(Format::WebP),
+        [b'I', b'I', 42, 0, ..] | [b'M', b'M', 0, 42, ..] => Some(Format::Tiff),
+        [b'B', b'M', ..] => Some(Format::Bmp),
+        _ => None,
+    }
+}
+
+pub fn sniff_file(path: &Path) -> Option<Format> {
+    sniff(&read_prefix(path, 16)?)
+}
+
+// 可以确定不是图片的常见文件类型，打开前据此给出具体的提示，而不是让解码器逐个尝试后报错
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotImage {
+    Pdf,
+    Archive,
+    Video,
+    Audio,
+    Executable,
+    Text,
+}
+
+impl NotImage {
+    pub fn message(self) -> &'static str {
+        match self {
+            NotImage::Pdf => "不支持 PDF 文件——是否想用文档查看器打开？",
+            NotImage::Archive => "这是一个压缩包（或 Office 等基于压缩包的文档），不是图片",
+            NotImage::Video => "这是一个视频文件，不是图片",
+            NotImage::Audio => "这是一个音频文件，不是图片",
+            NotImage::Executable => "这是一个可执行文件，不是图片",
+            NotImage::Text => "这是一个文本文件，不是图片",
+        }
+    }
+}
+
+// ISO BMFF（ftyp 盒）中属于图片的主品牌：AVIF、HEIF/HEIC
+const IMAGE_BRANDS: &[&[u8; 4]] = &[b"avif", b"avis", b"heic", b"heix", b"hevc", b"heim", b"heis", b"mif1", b"msf1"];
+
+// 按文件头判断是否为已知的非图片类型；图片（包括文本形式的 SVG、PNM、XPM、XBM）与无法判断的内容返回 None
+pub fn not_an_image(d: &[u8]) -> Option<NotImage> {
+    if sniff(d).is_some() { return None; }
+    match d {
+        [b'%', b'P', b'D', b'F', b'-', ..] => return Some(NotImage::Pdf),
+        [b'P', b'K', 3, 4, ..] | [0x1F, 0x8B, ..] | [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C, ..]
+        | [b'R', b'a', b'r', b'!', 0x1A, 0x07, ..] | [0xFD, b'7', b'z', b'X', b'Z', 0, ..] => return Some(NotImage::Archive),
+        [0x7F, b'E', b'L', b'F', ..] | [b'M', b'Z', ..] => return Some(NotImage::Executable),
+        [b'I', b'D', b'3', ..] | [b'f', b'L', b'a', b'C', ..] | [b'O', b'g', b'g', b'S', ..] | [0xFF, 0xFB | 0xF3 | 0xF2, ..] => {
+            return Some(NotImage::Audio);
+        }
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => return Some(NotImage::Audio),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'A', b'V', b'I', b' ', ..] | [0x1A, 0x45, 0xDF, 0xA3, ..] => return Some(NotImage::Video),
+        [_, _, _, _, b'f', b't', b'y', b'p', b0, b1, b2, b3, ..] => {
+            let brand = [*b0, *b1, *b2, *b3];
+            return (!IMAGE_BRANDS.contains(&&brand)).then_some(NotImage::Video);
+        }
+        _ => {}
+    }
+    // 文本：可打印的 UTF-8（允许在末尾截断半个字符）；排除以 < 开头的 SVG/XML 与文本形式的图片格式
+    let text_image = d.trim_ascii_start().first() == Some(&b'<')
+        || matches!(d, [b'P', b'1'..=b'7', c, ..] if c.is_ascii_whitespace())
+        || d.starts_with(b"/* XPM */")
+        || d.starts_with(b"#define");
+    if d.is_empty() || text_image { return None; }
+    let valid = match std::str::from_utf8(d) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&d[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    valid.chars().all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0C')).then_some(NotImage::Text)
+}
+
+// PNG 是否为 APNG（IDAT 之前出现 acTL 块）
+fn png_is_animated(d: &[u8]) -> bool {
+    let mut pos = 8;
+    while let (Some(len), Some(kind)) = (be32(d, pos), d.get(pos + 4..pos + 8)) {
+        match kind {
+            b"acTL" => return true,
+            b"IDAT" | b"IEND" => return false,
+            _ => match pos.checked_add(12 + len as usize) {
+                Some(next) => pos = next,
+                None => return false,
+            },
+        }
+    }
+    false
+}
+
+// WebP 扩展格式头中的动画标志
+fn webp_is_animated(d: &[u8]) -> bool {
+    d.get(12..16) == Some(b"VP8X") && d.get(20).is_some_and(|flags| flags & 0x02 != 0)
+}
+
+// 是否可能包含多帧/多页（GIF、APNG、动画 WebP、多页 TIFF）
+pub fn has_multiple_frames(path: &Path) -> bool {
+    let Some(data) = read_prefix(path, PREFIX_LEN) else { return false };
+    match sniff(&data) {
+        Some(Format::Gif) => true,
+        Some(Format::Png) => png_is_animated(&data),
+        Some(Format::WebP) => webp_is_animated(&data),
+        Some(Format::Tiff) => {
+            // IFD0 之后的下一 IFD 偏移非零即为多页
+            let next_ifd = |t: &Tiff| {
+                let ifd = t.first_ifd()?;
+                let n = t.u16(ifd)? as usize;
+                t.u32(ifd + 2 + n * 12)
+            };
+            match Tiff::new(&data).and_then(|t| next_ifd(&t)) {
+                Some(next) => next != 0,
+                None => std::fs::read(path).ok()
+                    .and_then(|full| Tiff::new(&full).and_then(|t| next_ifd(&t)))
+                    .is_some_and(|next| next != 0),
+            }
+        }
+        _ => false,
+    }
+}
+
+// 跳过 GIF 的数据子块序列（以长度 0 的子块结束），返回其后的位置
+fn gif_skip_blocks(d: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *d.get(pos)? as usize;
+        pos += 1 + len;
+        if len == 0 { return Some(pos); }
+    }
+}
+
+// GIF 的图像描述符个数；文件截断时返回已读到的帧数
+fn gif_frames(d: &[u8]) -> u32 {
+    let table = |flags: u8| if flags & 0x80 != 0 { 3 << ((flags & 7) + 1) } else { 0 };
+    let Some(&flags) = d.get(10) else { return 0 };
+    let mut pos = 13 + table(flags);
+    let mut frames = 0;
+    while let Some(&block) = d.get(pos) {
+        let next = match block {
+            0x21 => gif_skip_blocks(d, pos + 2),
+            0x2C => {
+                frames += 1;
+                d.get(pos + 9).and_then(|&flags| gif_skip_blocks(d, pos + 11 + table(flags)))
+            }
+            _ => None,
+        };
+        match next {
+            Some(next) => pos = next,
+            None => break,
+        }
+    }
+    frames
+}
+
+// APNG 的 acTL 块中记录的帧数
+fn png_frames(d: &[u8]) -> Option<u32> {
+    let mut pos = 8;
+    while let (Some(len), Some(kind)) = (be32(d, pos), d.get(pos + 4..pos + 8)) {
+        match kind {
+            b"acTL" => return be32(d, pos + 8),
+            b"IDAT" | b"IEND" => return None,
+            _ => pos = pos.checked_add(12 + len as usize)?,
+        }
+    }
+    None
+}
+
+// 动画 WebP 的 ANMF 块个数
+fn webp_frames(d: &[u8]) -> u32 {
+    let mut pos = 12;
+    let mut frames = 0;
+    while let (Some(kind), Some(len)) = (d.get(pos..pos + 4), le32(d, pos + 4)) {
+        if kind == b"ANMF" { frames += 1; }
+        // 块内容按偶数字节对齐
+        match pos.checked_add(8 + len as usize + (len as usize & 1)) {
+            Some(next) => pos = next,
+            None => break,
+        }
+    }
+    frames
+}
+
+// TIFF 的 IFD（页）个数；最多数到 10000，指向已访问 IFD 的环视为结束
+fn tiff_pages(t: &Tiff) -> u32 {
+    let mut seen = std::collections::HashSet::new();
+    let mut next = t.first_ifd();
+    while let Some(ifd) = next.filter(|&o| o != 0 && seen.len() < 10_000 && seen.insert(o)) {
+        next = t.u16(ifd).and_then(|n| t.u32(ifd + 2 + n as usize * 12)).map(|o| o as usize);
+    }
+    seen.len() as u32
+}
+
+// 帧数/页数：GIF、APNG 与动画 WebP 的帧，多页 TIFF 的页，其余格式为 1
+// 只有可能多帧的文件才整个读入
+pub fn frame_count(path: &Path) -> Option<u32> {
+    if !has_multiple_frames(path) {
+        return sniff_file(path).map(|_| 1);
+    }
+    let data = std::fs::read(path).ok()?;
+    let frames = match sniff(&data)? {
+        Format::Gif => gif_frames(&data),
+        Format::Png => png_frames(&data).unwrap_or(1),
+        Format::WebP => webp_frames(&data),
+        Format::Tiff => Tiff::new(&data).map_or(0, |t| tiff_pages(&t)),
+        _ => 1,
+    };
+    Some(frames.max(1))
+}
+
+pub fn le16(d: &[u8], pos: usize) -> Option<u16> {
+    d.get(pos..pos + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+pub fn le32(d: &[u8], pos: usize) -> Option<u32> {
+    d.get(pos..pos + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// JPEG：SOF0–SOF15 段中的高度与宽度（排除 DHT/JPG/DAC）
+fn jpeg_dimensions(d: &[u8]) -> Option<(u32, u32)> {
+    jpeg_segments(d).into_iter()
+        .find(|(m, _)| (0xC0..=0xCF).contains(m) && ![0xC4, 0xC8, 0xCC].contains(m))
+        .and_then(|(_, body)| Some((be16(body, 3)? as u32, be16(body, 1)? as u32)))
+}
+
+// TIFF：IFD0 的 ImageWidth/ImageLength
+fn tiff_dimensions(t: &Tiff) -> Option<(u32, u32)> {
+    let entries = t.entries(t.first_ifd()?)?;
+    let find = |tag| entries.iter().find(|e| e.tag == tag).and_then(|e| t.uint(e));
+    Some((find(256)?, find(257)?))
+}
+
+// WebP：有损 VP8、无损 VP8L 与扩展 VP8X 三种头
+fn webp_dimensions(d: &[u8]) -> Option<(u32, u32)> {
+    let u24 = |pos: usize| d.get(pos..pos + 3).map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]));
+    match d.get(12..16)? {
+        b"VP8X" => Some((u24(24)? + 1, u24(27)? + 1)),
+        b"VP8 " => Some((le16(d, 26)? as u32 & 0x3FFF, le16(d, 28)? as u32 & 0x3FFF)),
+        b"VP8L" => {
+            let bits = le32(d, 21)?;
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        _ => None,
+    }
+}
+
+// 只读文件头获取像素尺寸（宽, 高），用于解码前估算内存
+pub fn read_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let data = read_prefix(path, PREFIX_LEN)?;
+    match sniff(&data)? {
+        Format::Png => (data.get(12..16)? == b"IHDR").then_some((be32(&data, 16)?, be32(&data, 20)?)),
+        Format::Jpeg => jpeg_dimensions(&data),
+        Format::Gif => Some((le16(&data, 6)? as u32, le16(&data, 8)? as u32)),
+        Format::WebP => webp_dimensions(&data),
+        // BMP 高度为负表示自上而下存储
+        Format::Bmp => Some((le32(&data, 18)?, (le32(&data, 22)? as i32).unsigned_abs())),
+        // IFD 位于前缀之外时放弃，避免为估算内存读入整个大文件
+        Format::Tiff => tiff_dimensions(&Tiff::new(&data)?),
+    }
+}
+
+// JPEG 的四通道色彩空间
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JpegInk {
+    Cmyk,
+    Ycck,
+}
+
+impl JpegInk {
+    pub fn name(self) -> &'static str {
+        match self {
+            JpegInk::Cmyk => "CMYK",
+            JpegInk::Ycck => "YCCK",
+        }
+    }
+}
+
+// 检测 CMYK/YCCK JPEG：SOF 中有 4 个分量，Adobe APP14 的 transform 为 2 时是 YCCK
+pub fn jpeg_ink(path: &Path) -> Option<JpegInk> {
+    let data = read_prefix(path, PREFIX_LEN)?;
+    let segments = jpeg_segments(&data);
+    let sof = segments.iter()
+        .find(|(m, _)| (0xC0..=0xCF).contains(m) && ![0xC4, 0xC8, 0xCC].contains(m))?;
+    if *sof.1.get(5)? != 4 { return None; }
+    let transform = segments.iter()
+        .find(|(m, body)| *m == 0xEE && body.starts_with(b"Adobe"))
+        .and_then(|(_, body)| body.get(11).copied());
+    Some(if transform == Some(2) { JpegInk::Ycck } else { JpegInk::Cmyk })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 写入临时文件后读取尺寸；文件名带上进程号与用例名，并行的用例互不干扰
+    fn dimensions(name: &str, data: &[u8]) -> Option<(u32, u32)> {
+        let path = std::env::temp_dir().join(format!("image-viewer-header-{}-{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        let size = read_dimensions(&path);
+        std::fs::remove_file(&path).ok();
+        size
+    }
+
+    fn riff(chunk: &[u8]) -> Vec<u8> {
+        let mut d = b"RIFF".to_vec();
+        d.extend((chunk.len() as u32 + 4).to_le_bytes());
+        d.extend(b"WEBP");
+        d.extend(chunk);
+        d
+    }
+
+    #[test]
+    fn png_dimensions() {
+        let mut d = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        d.extend(300u32.to_be_bytes());
+        d.extend(200u32.to_be_bytes());
+        d.extend([8, 6, 0, 0, 0]);
+        assert_eq!(dimensions("png", &d), Some((300, 200)));
+        // IHDR 不完整
+        assert_eq!(dimensions("png-short", &d[..20]), None);
+    }
+
+    #[test]
+    fn jpeg_dimensions_skip_dht() {
+        // APP0、DHT（与 SOF 同一区间的 C4）之后才是渐进式的 SOF2
+        let mut d = vec![0xFF, 0xD8, 0xFF, 0xE0, 0, 4, 0, 0, 0xFF, 0xC4, 0, 3, 0];
+        d.extend([0xFF, 0xC2, 0, 11, 8, 0x01, 0x2C, 0x02, 0x58, 1, 1, 0x11, 0]);
+        assert_eq!(dimensions("jpeg", &d), Some((600, 300)));
+    }
+
+    #[test]
+    fn gif_dimensions() {
+        let mut d = b"GIF89a".to_vec();
+        d.extend(640u16.to_le_bytes());
+        d.extend(480u16.to_le_bytes());
+        assert_eq!(dimensions("gif", &d), Some((640, 480)));
+    }
+
+    #[test]
+    fn webp_dimensions_for_each_header() {
+        // VP8X：画布宽高减一，各 24 位
+        let mut vp8x = b"VP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        vp8x.extend([0x1F, 0x03, 0x00, 0xC7, 0x00, 0x00]);
+        assert_eq!(dimensions("webp-vp8x", &riff(&vp8x)), Some((800, 200)));
+        // VP8：帧标记与起始码之后是 14 位的宽高，高两位为缩放
+        let mut vp8 = b"VP8 \x0a\0\0\0\0\0\0\x9d\x01\x2a".to_vec();
+        vp8.extend((0x4000u16 | 320).to_le_bytes());
+        vp8.extend(240u16.to_le_bytes());
+        assert_eq!(dimensions("webp-vp8", &riff(&vp8)), Some((320, 240)));
+        // VP8L：签名 0x2F 之后宽高减一各 14 位
+        let mut vp8l = b"VP8L\x05\0\0\0\x2F".to_vec();
+        vp8l.extend((99u32 | (49 << 14)).to_le_bytes());
+        assert_eq!(dimensions("webp-vp8l", &riff(&vp8l)), Some((100, 50)));
+    }
+
+    #[test]
+    fn bmp_dimensions_top_down() {
+        let mut d = b"BM".to_vec();
+        d.extend([0; 16]);
+        d.extend(123u32.to_le_bytes());
+        d.extend((-45i32).to_le_bytes());
+        assert_eq!(dimensions("bmp", &d), Some((123, 45)));
+    }
+
+    #[test]
+    fn tiff_dimensions_both_byte_orders() {
+        // 宽为 SHORT、高为 LONG
+        let mut le = b"II\x2A\0\x08\0\0\0\x02\0".to_vec();
+        le.extend([0x00, 0x01, 3, 0, 1, 0, 0, 0, 0x10, 0x0E, 0, 0]);
+        le.extend([0x01, 0x01, 4, 0, 1, 0, 0, 0, 0x70, 0x11, 1, 0]);
+        le.extend([0; 4]);
+        assert_eq!(dimensions("tiff-le", &le), Some((3600, 70000)));
+        let mut be = b"MM\0\x2A\0\0\0\x08\0\x02".to_vec();
+        be.extend([0x01, 0x00, 0, 3, 0, 0, 0, 1, 0x02, 0x00, 0, 0]);
+        be.extend([0x01, 0x01, 0, 3, 0, 0, 0, 1, 0x01, 0x00, 0, 0]);
+        be.extend([0; 4]);
+        assert_eq!(dimensions("tiff-be", &be), Some((512, 256)));
+    }
+
+    #[test]
+    fn sniff_recognizes_image_magic() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n"), Some(Format::Png));
+        assert_eq!(sniff(b"\xFF\xD8\xFF\xE0"), Some(Format::Jpeg));
+        assert_eq!(sniff(b"GIF87a"), Some(Format::Gif));
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some(Format::WebP));
+        assert_eq!(sniff(b"II\x2A\0"), Some(Format::Tiff));
+        assert_eq!(sniff(b"MM\0\x2A"), Some(Format::Tiff));
+        assert_eq!(sniff(b"BM\0\0"), Some(Format::Bmp));
+        // 太短或只差一个字节
+        assert_eq!(sniff(b"\x89PN"), None);
+        assert_eq!(sniff(b"\xFF\xD8\x00"), None);
+        assert_eq!(sniff(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(sniff(b""), None);
+    }
+
+    #[test]
+    fn not_an_image_names_common_types() {
+        let cases: &[(&[u8], NotImage)] = &[
+            (b"%PDF-1.7\n", NotImage::Pdf),
+            (b"PK\x03\x04\x14\0", NotImage::Archive),
+            (b"\x1F\x8B\x08\0", NotImage::Archive),
+            (b"7z\xBC\xAF\x27\x1C", NotImage::Archive),
+            (b"\x7FELF\x02\x01", NotImage::Executable),
+            (b"MZ\x90\0", NotImage::Executable),
+            (b"ID3\x04\0", NotImage::Audio),
+            (b"fLaC\0\0", NotImage::Audio),
+            (b"RIFF\0\0\0\0WAVEfmt ", NotImage::Audio),
+            (b"RIFF\0\0\0\0AVI LIST", NotImage::Video),
+            (b"\x1A\x45\xDF\xA3\x01", NotImage::Video),
+            (b"\0\0\0\x20ftypisom\0\0\x02\0", NotImage::Video),
+            (b"hello, world\n\tsecond line\r\n", NotImage::Text),
+            // UTF-8 文本在多字节字符中间截断
+            (b"\xE4\xBD\xA0\xE5\xA5", NotImage::Text),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(not_an_image(bytes), Some(*expected), "{:?}", String::from_utf8_lossy(bytes));
+        }
+    }
+
+    #[test]
+    fn not_an_image_leaves_images_and_unknowns_alone() {
+        let cases: &[&[u8]] = &[
+            b"\x89PNG\r\n\x1a\n",
+            // AVIF 与 HEIC 的 ftyp 盒
+            b"\0\0\0\x1CftypavifMA1B",
+            b"\0\0\0\x18ftypheic\0\0\0\0",
+            // 文本形式的图片：SVG、PNM、XPM、XBM
+            b"<?xml version=\"1.0\"?><svg",
+            b"  <svg xmlns",
+            b"P3\n2 2\n255\n",
+            b"/* XPM */\nstatic char",
+            b"#define icon_width 16",
+            // 空文件、含控制字符或无效 UTF-8 的二进制内容
+            b"",
+            b"\x00\x01\x02\x03",
+            b"\xC3\x28abc",
+        ];
+        for bytes in cases {
+            assert_eq!(not_an_image(bytes), None, "{:?}", String::from_utf8_lossy(bytes));
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// 后台任务：导出帧、缩略图总览、文字识别、完整解码等在工作线程中运行的任务登记在这里
+// 有任务时标题栏右侧显示转圈与总进度，点击列出各个任务（名称、进度、取消按钮）
+// 取消通过任务的 gio::Cancellable 传给工作线程：逐项处理的任务在每一项之前检查，
+// 不能中断的一步（单张图片的解码、文字识别）完成后不再写出或显示结果
+
+use gtk4::prelude::*;
+use gtk4::{gio, glib, Button, Label, Orientation, ProgressBar};
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+struct Job {
+    id: u64,
+    name: String,
+    // 完成的比例；None 表示无法估计
+    progress: Cell<Option<f64>>,
+    cancellable: gio::Cancellable,
+}
+
+#[derive(Default)]
+struct Inner {
+    jobs: RefCell<Vec<Rc<Job>>>,
+    next_id: Cell<u64>,
+    // 任务增减或进度变化时调用
+    listeners: RefCell<Vec<Rc<dyn Fn()>>>,
+}
+
+impl Inner {
+    fn notify(&self) {
+        let listeners = self.listeners.borrow().clone();
+        for listener in listeners { listener(); }
+    }
+}
+
+// 一个窗口中的所有后台任务
+#[derive(Clone, Default)]
+pub struct Jobs(Rc<Inner>);
+
+// 登记中的任务；finish 或丢弃时从列表中移除
+pub struct JobHandle {
+    job: Rc<Job>,
+    jobs: Weak<Inner>,
+}
+
+impl Jobs {
+    pub fn start(&self, name: &str) -> JobHandle {
+        let id = self.0.next_id.get() + 1;
+        self.0.next_id.set(id);
+        let job = Rc::new(Job { id, name: name.to_string(), progress: Cell::new(None), cancellable: gio::Cancellable::new() });
+        self.0.jobs.borrow_mut().push(job.clone());
+        self.0.notify();
+        JobHandle { job, jobs: Rc::downgrade(&self.0) }
+    }
+
+    fn connect_changed(&self, f: impl Fn() + 'static) {
+        self.0.listeners.borrow_mut().push(Rc::new(f));
+    }
+
+    // 标题栏中的指示器：没有任务时隐藏
+    pub fn indicator(&self) -> gtk4::MenuButton {
+        let spinner = gtk4::Spinner::new();
+        // 可以估计进度的任务的平均进度，细条显示在转圈下方
+        let overall = ProgressBar::new();
+        overall.add_css_class("job-progress");
+        let content = gtk4::Box::new(Orientation::Vertical, 2);
+        content.set_valign(gtk4::Align::Center);
+        content.append(&spinner);
+        content.append(&overall);
+        let list = gtk4::Box::new(Orientation::Vertical, 8);
+        list.set_margin_top(6);
+        list.set_margin_bottom(6);
+        list.set_margin_start(6);
+        list.set_margin_end(6);
+        let popover = gtk4::Popover::builder().child(&list).build();
+        let button = gtk4::MenuButton::builder()
+            .child(&content)
+            .popover(&popover)
+            .tooltip_text("后台任务")
+            .visible(false)
+            .build();
+        button.add_css_class("titlebar-btn");
+        button.add_css_class("flat");
+
+        // 列表中的行（任务编号, 进度条），任务增减时重建，进度变化时只更新进度条
+        let rows: Rc<RefCell<Vec<(u64, ProgressBar)>>> = Rc::default();
+        let weak = Rc::downgrade(&self.0);
+        let (btn, spinner_ref) = (button.downgrade(), spinner.downgrade());
+        self.connect_changed(move || {
+            let (Some(inner), Some(button), Some(spinner)) = (weak.upgrade(), btn.upgrade(), spinner_ref.upgrade()) else { return };
+            let jobs = inner.jobs.borrow().clone();
+            button.set_visible(!jobs.is_empty());
+            spinner.set_spinning(!jobs.is_empty());
+            if jobs.is_empty() && let Some(popover) = button.popover() { popover.popdown(); }
+            let known: Vec<f64> = jobs.iter().filter_map(|j| j.progress.get()).collect();
+            overall.set_visible(!known.is_empty());
+            if !known.is_empty() { overall.set_fraction(known.iter().sum::<f64>() / known.len() as f64); }
+            let mut rows = rows.borrow_mut();
+            if rows.iter().map(|(id, _)| *id).ne(jobs.iter().map(|j| j.id)) {
+                while let Some(child) = list.first_child() { list.remove(&child); }
+                rows.clear();
+                for job in &jobs {
+                    let (row, bar) = job_row(job);
+                    list.append(&row);
+                    rows.push((job.id, bar));
+                }
+            }
+            for ((_, bar), job) in rows.iter().zip(&jobs) {
+                match job.progress.get() {
+                    Some(fraction) => bar.set_fraction(fraction),
+                    None => bar.pulse(),
+                }
+            }
+        });
+        button
+    }
+}
+
+fn job_row(job: &Rc<Job>) -> (gtk4::Box, ProgressBar) {
+    let name = Label::builder().label(&job.name).halign(gtk4::Align::Start).hexpand(true)
+        .ellipsize(gtk4::pango::EllipsizeMode::Middle).max_width_chars(32).build();
+    let bar = ProgressBar::new();
+    let info = gtk4::Box::new(Orientation::Vertical, 4);
+    info.append(&name);
+    info.append(&bar);
+    let cancel = Button::builder().icon_name("process-stop-symbolic").tooltip_text("取消").valign(gtk4::Align::Center).build();
+    cancel.add_css_class("flat");
+    cancel.set_sensitive(!job.cancellable.is_cancelled());
+    let cancellable = job.cancellable.clone();
+    cancel.connect_clicked(move |btn| {
+        cancellable.cancel();
+        btn.set_sensitive(false);
+    });
+    let row = gtk4::Box::new(Orientation::Horizontal, 8);
+    row.set_size_request(260, -1);
+    row.append(&info);
+    row.append(&cancel);
+    (row, bar)
+}
+
+impl JobHandle {
+    // 完成的比例（0–1）
+    pub fn progress(&self, fraction: f64) {
+        self.job.progress.set(Some(fraction.clamp(0.0, 1.0)));
+        if let Some(jobs) = self.jobs.upgrade() { jobs.notify(); }
+    }
+
+    // 进度无法估计时表示仍在进行（进度条往复移动）
+    pub fn pulse(&self) {
+        self.job.progress.set(None);
+        if let Some(jobs) = self.jobs.upgrade() { jobs.notify(); }
+    }
+
+    // 交给工作线程检查
+    pub fn cancellable(&self) -> gio::Cancellable {
+        self.job.cancellable.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.job.cancellable.is_cancelled()
+    }
+
+    pub fn finish(self) {}
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        let Some(jobs) = self.jobs.upgrade() else { return };
+        jobs.jobs.borrow_mut().retain(|j| j.id != self.job.id);
+        // 在析构中可能正处于其他界面回调之内，留到空闲时刷新
+        let weak = Rc::downgrade(&jobs);
+        glib::idle_add_local_once(move || if let Some(jobs) = weak.upgrade() { jobs.notify() });
+    }
+}
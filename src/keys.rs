@@ -0,0 +1,186 @@
+// 快捷键：内置的按键在窗口的按键处理中实现，配置文件的 [keys] 表可以为任意动作（win.*）另设快捷键，
+// 例如 rotate = "<Ctrl>l"；设为空字符串则取消该动作的内置快捷键
+// 另设的快捷键通过 set_accels_for_action 注册，原来的内置按键随之失效；配置中用到的按键即使原是其他动作的内置按键，
+// 也只触发配置的动作；命令面板显示实际生效的快捷键
+
+use crate::palette;
+use gtk4::gdk;
+use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+// 内置快捷键，与窗口按键处理接受的按键一致；第一个是命令面板中显示的快捷键
+const DEFAULTS: &[(&str, &[&str])] = &[
+    ("open", &["<Ctrl>o"]),
+    ("open-tabs", &["<Ctrl><Shift>o"]),
+    ("reset-view", &["0", "KP_0"]),
+    ("zoom-in", &["plus", "equal", "KP_Add"]),
+    ("zoom-out", &["minus", "KP_Subtract"]),
+    ("actual-size", &["1", "KP_1"]),
+    ("fullscreen", &["F11", "f"]),
+    ("rotate", &["r"]),
+    ("copy", &["<Ctrl>c"]),
+    ("paste", &["<Ctrl>v"]),
+    ("copy-text", &["<Ctrl><Shift>c"]),
+    ("previous-image", &["Left"]),
+    ("next-image", &["Right"]),
+    ("previous-group", &["Up"]),
+    ("next-group", &["Down"]),
+    ("play-burst", &["b"]),
+    ("play-animation", &["p"]),
+    ("slideshow", &["s"]),
+    ("image-info", &["i"]),
+    ("next-tab", &["<Ctrl>Tab"]),
+    ("previous-tab", &["<Ctrl><Shift>Tab"]),
+    ("close-tab", &["<Ctrl>w"]),
+    ("extract-frames", &["<Ctrl>e"]),
+    ("rename", &["F2"]),
+    ("number-files", &["<Shift>F2"]),
+    ("trash", &["Delete"]),
+    ("undo", &["<Ctrl>z"]),
+    ("scale-bar", &["m"]),
+    ("fit-width", &["w"]),
+    ("fit-height", &["e"]),
+    ("close-window", &["q", "Escape"]),
+];
+
+type Accel = (gdk::Key, gdk::ModifierType);
+
+thread_local! {
+    // 配置文件中设置的快捷键；None 表示取消
+    static CONFIGURED: RefCell<HashMap<String, Option<Accel>>> = RefCell::default();
+}
+
+// 比较时只看这几个修饰键；Shift+Tab 产生的是 ISO_Left_Tab，+ 等标点在多数布局上要按 Shift 才能输入
+fn normalize((key, mods): Accel) -> Accel {
+    let key = if key == gdk::Key::ISO_Left_Tab { gdk::Key::Tab } else { key.to_lower() };
+    let mut mods = mods & (gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK | gdk::ModifierType::ALT_MASK);
+    if key.to_unicode().is_some_and(|c| c.is_ascii_punctuation()) { mods.remove(gdk::ModifierType::SHIFT_MASK); }
+    (key, mods)
+}
+
+fn parse(text: &str) -> Option<Accel> {
+    gtk4::accelerator_parse(text).filter(|(key, _)| *key != gdk::Key::VoidSymbol)
+}
+
+// 解析 [keys] 表：未知动作忽略，无法解析的快捷键给出警告后忽略
+fn resolve(keys: &BTreeMap<String, String>, warn: bool) -> HashMap<String, Option<Accel>> {
+    let mut resolved = HashMap::new();
+    for (action, text) in keys {
+        if !palette::is_command(action) { continue; }
+        let accel = match text.trim() {
+            "" => None,
+            text => match parse(text) {
+                Some(accel) => Some(accel),
+                None => {
+                    if warn { eprintln!("配置 [keys] 中 {} 的快捷键无效: {}", action, text); }
+                    continue;
+                }
+            },
+        };
+        resolved.insert(action.clone(), accel);
+    }
+    resolved
+}
+
+// 启动时注册配置的快捷键，之后新建的窗口同样生效
+pub fn apply(app: &gtk4::Application, keys: &BTreeMap<String, String>) {
+    let resolved = resolve(keys, true);
+    for (action, accel) in &resolved {
+        let accels: Vec<String> = accel.iter().map(|&(key, mods)| gtk4::accelerator_name(key, mods).to_string()).collect();
+        let accels: Vec<&str> = accels.iter().map(String::as_str).collect();
+        app.set_accels_for_action(&format!("win.{}", action), &accels);
+    }
+    CONFIGURED.set(resolved);
+}
+
+fn defaults(action: &str) -> impl Iterator<Item = Accel> {
+    DEFAULTS.iter().filter(move |(a, _)| *a == action).flat_map(|(_, texts)| texts.iter()).filter_map(|text| parse(text))
+}
+
+// 动作实际生效的快捷键
+fn effective(configured: &HashMap<String, Option<Accel>>, action: &str) -> Vec<Accel> {
+    match configured.get(action) {
+        Some(accel) => accel.iter().copied().collect(),
+        None => defaults(action).collect(),
+    }
+}
+
+// 命令面板中显示的快捷键，没有时为空
+pub fn label(action: &str) -> String {
+    CONFIGURED.with_borrow(|configured| effective(configured, action).first().copied())
+        .map(|(key, mods)| gtk4::accelerator_get_label(key, mods).to_string())
+        .unwrap_or_default()
+}
+
+// 按键是否由配置接管：已改设的动作原来的内置按键，或配置为某个动作设的快捷键（可能与其他动作的内置按键相同）
+// 是则窗口的按键处理不再执行内置操作，交给注册的快捷键
+pub fn claimed(key: gdk::Key, mods: gdk::ModifierType) -> bool {
+    let pressed = normalize((key, mods));
+    CONFIGURED.with_borrow(|configured| {
+        let replaced = configured.keys().flat_map(|action| defaults(action));
+        let bound = configured.values().flatten().copied();
+        replaced.chain(bound).any(|accel| normalize(accel) == pressed)
+    })
+}
+
+// --dump-keys：每行一个动作及其实际生效的快捷键，多个以逗号分隔
+pub fn dump(keys: &BTreeMap<String, String>) {
+    let configured = resolve(keys, true);
+    for action in palette::command_names() {
+        let accels: Vec<String> = effective(&configured, action).into_iter()
+            .map(|(key, mods)| gtk4::accelerator_name(key, mods).to_string())
+            .collect();
+        println!("{}\t{}", action, if accels.is_empty() { "-".to_string() } else { accels.join(",") });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configure(keys: &[(&str, &str)]) {
+        let keys = keys.iter().map(|&(a, k)| (a.to_string(), k.to_string())).collect();
+        CONFIGURED.set(resolve(&keys, false));
+    }
+
+    #[test]
+    fn rebound_action_gives_up_all_its_builtin_keys() {
+        configure(&[("zoom-in", "<Ctrl>i")]);
+        for key in [gdk::Key::plus, gdk::Key::equal, gdk::Key::KP_Add] {
+            assert!(claimed(key, gdk::ModifierType::empty()));
+        }
+        // + 在美式布局上带 Shift
+        assert!(claimed(gdk::Key::plus, gdk::ModifierType::SHIFT_MASK));
+        assert!(!claimed(gdk::Key::minus, gdk::ModifierType::empty()));
+    }
+
+    #[test]
+    fn binding_on_another_actions_builtin_key_takes_it_over() {
+        configure(&[("fit-width", "f"), ("rotate", "x")]);
+        assert!(claimed(gdk::Key::f, gdk::ModifierType::empty()));
+        assert!(claimed(gdk::Key::x, gdk::ModifierType::empty()));
+        // 全屏的其他内置按键不受影响
+        assert!(!claimed(gdk::Key::F11, gdk::ModifierType::empty()));
+        assert!(!claimed(gdk::Key::f, gdk::ModifierType::CONTROL_MASK));
+    }
+
+    #[test]
+    fn effective_lists_every_builtin_key_until_rebound() {
+        configure(&[]);
+        let names = |action| CONFIGURED.with_borrow(|c| effective(c, action))
+            .into_iter().map(|(key, mods)| gtk4::accelerator_name(key, mods).to_string()).collect::<Vec<_>>();
+        assert_eq!(names("zoom-in"), ["plus", "equal", "KP_Add"]);
+        configure(&[("zoom-in", "<Ctrl>i"), ("trash", "")]);
+        assert_eq!(names("zoom-in"), ["<Control>i"]);
+        assert!(names("trash").is_empty());
+    }
+
+    #[test]
+    fn every_default_parses_and_names_a_command() {
+        for (action, texts) in DEFAULTS {
+            assert!(palette::is_command(action), "{}", action);
+            assert!(texts.iter().all(|text| parse(text).is_some()), "{}", action);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// image-viewer 的库部分：可嵌入其他 GTK4 程序的图片查看控件
+// 解码与视图计算的模块不对外公开；image-viewer 程序直接编译同样的源文件（见 main.rs），
+// 控件只用到其中一部分，其余部分在库中不会被调用
+
+#[allow(dead_code)]
+mod decode;
+#[allow(dead_code)]
+mod header;
+#[allow(dead_code)]
+mod sandbox;
+#[allow(dead_code)]
+mod texture;
+mod view;
+mod widget;
+
+pub use widget::{ImageViewerWidget, LoadError, MAX_ZOOM, MIN_ZOOM};
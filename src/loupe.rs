@@ -0,0 +1,107 @@
+// 取色器：置顶模式下读取指针下方原图像素的颜色，在指针旁的小标签中显示十六进制值
+// 用于把固定在上方的设计稿与下方实际程序的颜色逐点对比
+// 标签直接绘制在绘图区中，不是单独的控件，不会改变窗口的输入区域
+
+use gtk4::cairo;
+use std::cell::Cell;
+
+// 标签相对指针的偏移，避免挡住正在取色的像素
+const BADGE_OFFSET: f64 = 14.0;
+
+#[derive(Default)]
+pub struct Loupe {
+    active: Cell<bool>,
+    // 指针在窗口内的位置，离开窗口时为 None
+    pointer: Cell<Option<(f64, f64)>>,
+}
+
+impl Loupe {
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    pub fn toggle(&self) {
+        self.active.set(!self.active.get());
+    }
+
+    pub fn set_pointer(&self, pointer: Option<(f64, f64)>) {
+        self.pointer.set(pointer);
+    }
+
+    // 在置顶窗口的绘制结束后调用；surface 为未旋转的原图，窗口按实际大小铺满图片
+    pub fn draw(&self, cr: &cairo::Context, width: i32, height: i32, surface: &cairo::ImageSurface, rotation: i32) {
+        if !self.active.get() { return; }
+        let Some((x, y)) = self.pointer.get() else { return };
+        let Some((px, py)) = window_to_image(x, y, width, height, surface.width(), surface.height(), rotation) else { return };
+        let Some(rgba) = sample(surface, px, py) else { return };
+        draw_badge(cr, width, height, (x, y), rgba);
+    }
+}
+
+// 窗口坐标 → 原图像素坐标：先按窗口与旋转后图片的比例还原缩放，再绕中心反向旋转
+fn window_to_image(x: f64, y: f64, width: i32, height: i32, surf_w: i32, surf_h: i32, rotation: i32) -> Option<(i32, i32)> {
+    if width <= 0 || height <= 0 { return None; }
+    let (sw, sh) = (surf_w as f64, surf_h as f64);
+    let odd = rotation % 2 != 0;
+    let (img_w, img_h) = if odd { (sh, sw) } else { (sw, sh) };
+    let (ex, ey) = (x * img_w / width as f64 - img_w / 2.0, y * img_h / height as f64 - img_h / 2.0);
+    let (dx, dy) = match rotation.rem_euclid(4) {
+        0 => (ex, ey),
+        1 => (ey, -ex),
+        2 => (-ex, -ey),
+        _ => (-ey, ex),
+    };
+    let (px, py) = ((dx + sw / 2.0).floor() as i32, (dy + sh / 2.0).floor() as i32);
+    (px >= 0 && py >= 0 && px < surf_w && py < surf_h).then_some((px, py))
+}
+
+// 读取 ARGB32 表面中的像素并还原预乘的透明度，返回 [r, g, b, a]
+fn sample(surface: &cairo::ImageSurface, x: i32, y: i32) -> Option<[u8; 4]> {
+    let offset = y as usize * surface.stride() as usize + x as usize * 4;
+    let mut pixel = None;
+    surface.with_data(|data| {
+        pixel = data.get(offset..offset + 4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]));
+    }).ok()?;
+    let pixel = pixel?;
+    let a = (pixel >> 24) as u8;
+    let channel = |shift: u32| {
+        let c = (pixel >> shift) & 0xff;
+        if a == 0 { 0 } else { ((c * 255 + a as u32 / 2) / a as u32).min(255) as u8 }
+    };
+    Some([channel(16), channel(8), channel(0), a])
+}
+
+fn hex(rgba: [u8; 4]) -> String {
+    let [r, g, b, a] = rgba;
+    if a == 255 { format!("#{:02X}{:02X}{:02X}", r, g, b) } else { format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a) }
+}
+
+// 色块加十六进制值；靠近窗口边缘时翻到指针的另一侧
+fn draw_badge(cr: &cairo::Context, width: i32, height: i32, pointer: (f64, f64), rgba: [u8; 4]) {
+    let text = hex(rgba);
+    cr.save().ok();
+    cr.set_font_size(12.0);
+    if let Ok(ext) = cr.text_extents(&text) {
+        let pad = 5.0;
+        let swatch = ext.height().max(10.0);
+        let (w, h) = (swatch + ext.width() + pad * 3.0, swatch + pad * 2.0);
+        let (mut x, mut y) = (pointer.0 + BADGE_OFFSET, pointer.1 + BADGE_OFFSET);
+        if x + w > width as f64 { x = pointer.0 - BADGE_OFFSET - w; }
+        if y + h > height as f64 { y = pointer.1 - BADGE_OFFSET - h; }
+        let (x, y) = (x.max(0.0), y.max(0.0));
+        cr.rectangle(x, y, w, h);
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.75);
+        cr.fill().ok();
+        let [r, g, b, a] = rgba.map(|c| c as f64 / 255.0);
+        cr.rectangle(x + pad, y + pad, swatch, swatch);
+        cr.set_source_rgba(r, g, b, a);
+        cr.fill_preserve().ok();
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.8);
+        cr.set_line_width(1.0);
+        cr.stroke().ok();
+        cr.move_to(x + swatch + pad * 2.0 - ext.x_bearing(), y + (h - ext.height()) / 2.0 - ext.y_bearing());
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.95);
+        cr.show_text(&text).ok();
+    }
+    cr.restore().ok();
+}
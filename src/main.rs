@@ -4,13 +4,86 @@ use gtk4::prelude::*;
 use gtk4::{gdk, gio, glib, Application, ApplicationWindow, Box, Button, DrawingArea, FileDialog, Label, Orientation, CssProvider, cairo};
 use gtk4_layer_shell::{Layer, LayerShell, Edge};
 use std::cell::{Cell, RefCell};
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use config::Config;
+use model::{Effects, Resize, ViewerModel};
+use report::LoadReport;
+use texture::{decode_texture, rotate_about_center, rotated_texture, texture_from_rgba, texture_surface};
+
+mod above;
+mod animation;
+mod association;
+mod breadcrumb;
+mod burst;
+mod config;
+mod contact;
+mod convert;
+mod decode;
+mod details;
+mod dirconfig;
+mod elastic;
+mod extract;
+mod generation;
+mod gpu;
+mod header;
+mod jobs;
+mod keys;
+mod loupe;
+mod metadata;
+mod model;
+mod notify;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod palette;
+mod pins;
+mod placement;
+mod pointer;
+mod posters;
+mod power;
+mod precheck;
+mod query;
+mod recovery;
+mod rename;
+mod report;
+mod ruler;
+mod sandbox;
+mod scaling;
+mod siblings;
+mod slide;
+mod slideshow;
+mod status;
+mod texture;
+mod thumbs;
+mod tile;
+mod trash;
+mod view;
+mod wheel;
+mod workspace;
+mod xwindow;
 
 const APP_ID: &str = "com.github.image-viewer";
 const TITLEBAR_HEIGHT: i32 = 28;
-const MIN_WIN_WIDTH: i32 = 400;
+// 自动隐藏的标题栏收起后留下的提示条高度
+const TITLEBAR_STRIP: i32 = 2;
+// 自动隐藏的标题栏在指针离开顶部后多久收起
+const TITLEBAR_HIDE_DELAY: Duration = Duration::from_secs(3);
+// 标题栏滑出与收起的时长（毫秒），与 GtkRevealer 的默认值相同；关闭动画时为 0
+const TITLEBAR_TRANSITION_MS: u32 = 250;
+// 窗口窄于该宽度时标题栏改为紧凑布局：按钮收窄，缩放率收进状态框
+const COMPACT_TITLEBAR_BELOW: i32 = 400;
+// 紧凑标题栏下窗口的最小宽度不低于此值
+const MIN_COMPACT_WIDTH: i32 = 240;
 const MIN_WIN_HEIGHT: i32 = 300;
+const ROTATE_TOOLTIP: &str = "旋转（右键：逆时针；滚轮：按滚动方向；Shift+单击：应用到后续图片）";
+// 普通窗口四周留给阴影的透明边距
+const SHADOW_MARGIN: i32 = 12;
+// 置顶窗口缩放时图片短边的下限（原图更小时不强制放大）
+const OVERLAY_MIN_SIDE: f64 = 48.0;
+const SIGTERM: i32 = 15;
 
 #[derive(Clone, Copy, PartialEq)]
 enum WindowMode {
@@ -18,48 +91,921 @@ enum WindowMode {
     Overlay,
 }
 
-// 获取屏幕可用尺寸
-fn get_screen_size() -> (i32, i32) {
-    if let Some(display) = gdk::Display::default() {
-        if let Some(monitor) = display.monitors().item(0) {
-            if let Some(monitor) = monitor.downcast_ref::<gdk::Monitor>() {
-                let geom = monitor.geometry();
-                return (geom.width(), geom.height());
+// 激活时要做的事：按模式打开初始来源，或先执行启动时请求的应用操作（--action）
+#[derive(Clone, Copy, PartialEq)]
+enum StartRequest {
+    Open(WindowMode),
+    Action(&'static str),
+}
+
+// 可从桌面文件或 gapplication action 触发的应用操作
+const LAUNCH_ACTIONS: &[&str] = &["open-overlay", "start-slideshow"];
+
+// 命令行路径后缀指定的初始视图：file.tif#page=3、anim.gif#frame=12、big.png#region=x,y,w,h
+#[derive(Clone, Copy, Debug)]
+enum ViewRequest {
+    Page(usize),
+    Frame(usize),
+    // 设置的封面帧：与 Frame 一样先显示该帧，但动画照常播放
+    Poster(usize),
+    Region(i32, i32, i32, i32),
+}
+
+// 启动时要打开的文件及可选的初始视图
+struct InitialFile {
+    path: String,
+    view: Option<ViewRequest>,
+}
+
+// 启动时图片的来源；以 --overlay 启动时，等第一张图片到达后才创建置顶窗口
+enum Source {
+    // 第一个文件直接载入，其余以标签页打开
+    Files(Vec<InitialFile>),
+    // 从标准输入读取一张图片（文件参数为 -）
+    Stdin,
+    // 读取剪贴板中的图片，剪贴板更新时替换
+    Clipboard,
+    // 按名称恢复保存的固定位置（--restore-pin）
+    Pin(String, pins::Pin),
+    // 从文件列表逐行读取浏览列表（--files-from），None 表示标准输入
+    List(Option<PathBuf>),
+    // 浏览列表就是这些文件，按给定顺序，与所在目录中的其他文件无关
+    // （--select，或文件管理器一次打开多个文件）
+    Selection(Vec<PathBuf>),
+}
+
+// 在后台逐批补全的浏览列表
+enum TreeFeed {
+    // --recursive：目录树的根及其排序方式
+    Walk(PathBuf, config::SortOrder),
+    // --files-from：列表文件，None 表示标准输入
+    List(Option<PathBuf>),
+    // --select：已经给出的文件
+    Selection(Vec<PathBuf>),
+}
+
+// 标准输入在该时间内没有任何数据时报错
+const STDIN_TIMEOUT: Duration = Duration::from_secs(5);
+// 非文件来源在标签与标题栏中显示的名称
+const STDIN_NAME: &str = "(标准输入)";
+const CLIPBOARD_NAME: &str = "(剪贴板)";
+
+// 拆分路径与视图后缀；文件名本身含 # 且存在时按原样处理，无效后缀给出警告后忽略
+fn split_view_fragment(arg: &str) -> InitialFile {
+    let plain = || InitialFile { path: arg.to_string(), view: None };
+    if Path::new(arg).exists() { return plain(); }
+    let Some((path, fragment)) = arg.rsplit_once('#') else { return plain() };
+    let view = match fragment.split_once('=') {
+        Some(("page", v)) => v.parse().ok().filter(|&n| n >= 1).map(ViewRequest::Page),
+        Some(("frame", v)) => v.parse().ok().filter(|&n| n >= 1).map(ViewRequest::Frame),
+        Some(("region", v)) => {
+            let nums: Vec<i32> = v.split(',').filter_map(|n| n.trim().parse().ok()).collect();
+            match nums[..] {
+                [x, y, w, h] if w > 0 && h > 0 => Some(ViewRequest::Region(x, y, w, h)),
+                _ => None,
             }
         }
+        _ => None,
+    };
+    if view.is_none() {
+        eprintln!("忽略无效的视图后缀 #{}（支持 page=N、frame=N、region=x,y,w,h）", fragment);
+    }
+    InitialFile { path: path.to_string(), view }
+}
+
+// 解码方式：完整解码，或在给定内存预算（字节）内缩小解码
+#[derive(Clone, Copy, PartialEq)]
+enum DecodeSize {
+    Full,
+    Downscaled(u64),
+}
+
+// 系统当前可用内存（/proc/meminfo 的 MemAvailable）
+fn available_memory() -> Option<u64> {
+    let info = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = info.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+// 根据文件头尺寸估算解码后的 RGBA 内存占用
+fn estimate_decoded_bytes(path: &Path) -> Option<u64> {
+    let (w, h) = header::read_dimensions(path)?;
+    Some(w as u64 * h as u64 * 4)
+}
+
+// 按内存预算缩小解码（由 gdk-pixbuf 加载器在解码时缩放），边长同时不超过纹理上限，并返回原图尺寸（应用 EXIF 方向后）
+fn decode_downscaled(path: &Path, budget: u64, limit: u32) -> Result<(gdk::Texture, (i32, i32)), String> {
+    let (w, h) = decode::oriented_dimensions(path).ok_or("无法读取图片尺寸")?;
+    let factor = (budget as f64 / (w as f64 * h as f64 * 4.0)).sqrt()
+        .min(limit as f64 / w.max(h) as f64)
+        .min(1.0);
+    let (tw, th) = (((w as f64 * factor) as i32).max(1), ((h as f64 * factor) as i32).max(1));
+    Ok((decode_scaled_to(path, tw, th)?, (w as i32, h as i32)))
+}
+
+// w、h 为应用 EXIF 方向后的尺寸；gdk-pixbuf 按文件中的方向缩放，之后再转正（含镜像）
+// CMYK/YCCK JPEG 不交给 gdk-pixbuf（部分加载器颜色反相或直接失败），由 image 完整解码后再缩小，
+// 解码时的内存占用因此不受预算限制
+fn decode_scaled_to(path: &Path, w: i32, h: i32) -> Result<gdk::Texture, String> {
+    let fit = (w.max(1) as u32, h.max(1) as u32);
+    if sandbox::enabled() {
+        return sandbox::decode_file(path, Some(fit)).map(texture_from_rgba);
+    }
+    if header::jpeg_ink(path).is_some() {
+        let decoded = decode::decode(path)?;
+        let image = decoded.image.resize(fit.0, fit.1, image::imageops::FilterType::Triangle);
+        return Ok(texture_from_rgba(image.to_rgba8()));
+    }
+    let (w, h) = if decode::transposes(path) { (h, w) } else { (w, h) };
+    let pixbuf = gtk4::gdk_pixbuf::Pixbuf::from_file_at_scale(path, w, h, true).map_err(|e| e.to_string())?;
+    let pixbuf = pixbuf.apply_embedded_orientation().unwrap_or(pixbuf);
+    Ok(gdk::Texture::for_pixbuf(&pixbuf))
+}
+
+// 小于该像素数的 JPEG 完整解码本身就很快，不先显示预览
+const PREVIEW_MIN_PIXELS: u64 = 8_000_000;
+
+// 大 JPEG 的预览缩小倍数：libjpeg 几乎无代价地按 1/2、1/4、1/8 解码，取不小于适应窗口缩放率的最小一档
+// 需要 EXIF 旋转或 CMYK 转换的 JPEG 由 image 解码，预览与完整纹理方向不一致，不做预览
+fn jpeg_preview_denominator(path: &Path) -> Option<(u32, (u32, u32))> {
+    if header::sniff_file(path) != Some(header::Format::Jpeg) || decode::needs_fallback(path) { return None; }
+    let (w, h) = header::read_dimensions(path)?;
+    if (w as u64) * (h as u64) < PREVIEW_MIN_PIXELS { return None; }
+    let fit = fit_to_screen_scale(w as i32, h as i32);
+    [8, 4, 2].into_iter().find(|&d| 1.0 / d as f64 >= fit).map(|d| (d, (w, h)))
+}
+
+// 正在写入的文件（渲染输出等）解码失败时的重试间隔：修改时间在 1 秒内的文件最多再试 3 次，共约 1 秒
+// 主线程上的载入用定时器安排重试，工作线程中的完整解码直接等待
+const WRITE_RETRY_DELAYS: [Duration; 3] = [Duration::from_millis(200), Duration::from_millis(300), Duration::from_millis(500)];
+
+fn recently_modified(path: &Path) -> bool {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|age| age < Duration::from_secs(1))
+}
+
+// 解码失败且文件刚被修改时，等写入方写完再重试；会阻塞调用线程，只在工作线程中使用
+fn retry_while_written<T>(path: &Path, mut decode: impl FnMut() -> Result<T, String>) -> Result<T, String> {
+    let mut result = decode();
+    for delay in WRITE_RETRY_DELAYS {
+        if result.is_ok() || !recently_modified(path) { break; }
+        if let Err(e) = &result { eprintln!("{}: 文件刚被修改，{} ms 后重试解码: {}", path.display(), delay.as_millis(), e); }
+        std::thread::sleep(delay);
+        result = decode();
+    }
+    result
+}
+
+// 两个尺寸的宽高比是否一致：允许缩小解码取整造成的 1 像素误差，旋转 90°（EXIF 方向）视为一致
+fn same_shape(a: (i32, i32), b: (i32, i32)) -> bool {
+    let close = |(w0, h0): (i32, i32), (w1, h1): (i32, i32)| {
+        if w0 <= 0 || h0 <= 0 || w1 <= 0 || h1 <= 0 { return false; }
+        let ((sw, sh), (lw, lh)) = if w0 <= w1 { ((w0, h0), (w1, h1)) } else { ((w1, h1), (w0, h0)) };
+        (lh as f64 * sw as f64 / lw as f64 - sh as f64).abs() <= 1.0
+    };
+    close(a, b) || close(a, (b.1, b.0))
+}
+
+// 用完整解码的纹理替换预览；缩放率换算到新纹理的像素，屏幕上的大小与位置不变
+// 文件在显示预览后被改写、完整解码的宽高比与预览不同时，改为按新图片适应屏幕并返回 false，由调用方重新调整窗口
+fn replace_preview(s: &mut ImageState, texture: gdk::Texture, decoder: String, decode_time: Duration) -> bool {
+    let same = same_shape((s.original_width, s.original_height), (texture.width(), texture.height()));
+    let ratio = s.original_width as f64 / texture.width() as f64;
+    if same {
+        s.scale *= ratio;
+        s.min_zoom *= ratio;
+        s.clip = s.clip.map(|(x, y, w, h)| (x / ratio, y / ratio, w / ratio, h / ratio));
+    } else {
+        s.clip = None;
+        s.offset_x = 0.0;
+        s.offset_y = 0.0;
+        s.zoom_mode = ZoomMode::Free;
+    }
+    s.original_width = texture.width();
+    s.original_height = texture.height();
+    if let Some(report) = s.report.as_mut() {
+        report.decoder = decoder;
+        report.decode_time = decode_time;
+        report.downscaled_from = None;
+        report.set_texture(&texture);
+    }
+    s.pixbuf = Some(texture);
+    s.full_size = None;
+    s.quality = TextureQuality::Final;
+    if !same {
+        let (w, h) = get_rotated_size(s);
+        s.min_zoom = fit_to_screen_scale(w, h);
+        s.scale = s.min_zoom;
+    }
+    same
+}
+
+// 后台解码完成后放回纹理：卸载后重新解码的纹理与原来尺寸相同时只放回纹理，其余按替换预览处理
+// 返回尺寸是否与原来一致
+fn restore_texture(s: &mut ImageState, texture: gdk::Texture, decoder: String, decode_time: Duration) -> bool {
+    if s.quality == TextureQuality::Unloaded && (texture.width(), texture.height()) == (s.original_width, s.original_height) {
+        s.pixbuf = Some(texture);
+        s.quality = TextureQuality::Final;
+        return true;
+    }
+    replace_preview(s, texture, decoder, decode_time)
+}
+
+// 解码标准输入读到的数据：优先用 image（应用 EXIF 方向），不支持的格式交给 GDK
+fn decode_stdin(data: &[u8]) -> Result<(gdk::Texture, LoadReport), String> {
+    let started = std::time::Instant::now();
+    let (texture, decoder) = match decode::decode_bytes(data) {
+        Ok(decoded) => (texture_from_rgba(decoded.image.to_rgba8()), "image 解码器（标准输入）"),
+        Err(e) if sandbox::enabled() => return Err(e),
+        Err(_) => {
+            let bytes = glib::Bytes::from(data);
+            (gdk::Texture::from_bytes(&bytes).map_err(|e| e.to_string())?, "GDK 加载器（标准输入）")
+        }
+    };
+    let mut report = LoadReport::from_memory(data, decoder.to_string());
+    report.decode_time = started.elapsed();
+    Ok((texture, report))
+}
+
+// 进入置顶模式的方式，决定退出置顶后恢复普通窗口还是退出程序
+#[derive(Clone, Copy, PartialEq)]
+enum OverlayEntry {
+    Startup,
+    DoubleClick,
+}
+
+thread_local! {
+    // 普通窗口最近进入的显示器（--at-pointer），未记录时使用第一个显示器
+    static ACTIVE_MONITOR: RefCell<Option<gdk::Monitor>> = const { RefCell::new(None) };
+    // 标题栏在窗口中占用的高度：自动隐藏时标题栏浮在图片上，只占提示条的高度
+    static TITLEBAR_SPACE: Cell<i32> = const { Cell::new(TITLEBAR_HEIGHT) };
+    static MIN_WIN_WIDTH: Cell<i32> = const { Cell::new(COMPACT_TITLEBAR_BELOW) };
+}
+
+// 窗口的最小宽度：紧凑标题栏实际需要的宽度，图片较窄时窗口可以缩到这里
+fn min_win_width() -> i32 {
+    MIN_WIN_WIDTH.get()
+}
+
+// 计算窗口尺寸时图片上方要留出的高度
+fn titlebar_space() -> i32 {
+    TITLEBAR_SPACE.get()
+}
+
+// 计算窗口尺寸所用的屏幕尺寸及其来源：窗口所在的显示器，否则第一个显示器
+// 每次调用都重新查询，显示器热插拔后立即生效；嵌套运行或显示器休眠时列表可能为空，此时返回 None
+fn screen_geometry() -> Option<((i32, i32), String)> {
+    let describe = |m: &gdk::Monitor, kind: &str| {
+        let geom = m.geometry();
+        let connector = m.connector().map(|c| c.to_string()).unwrap_or_else(|| "未命名".to_string());
+        ((geom.width(), geom.height()), format!("{}（{}）", kind, connector))
+    };
+    let active = ACTIVE_MONITOR.with_borrow(|m| m.clone()).filter(|m| m.is_valid());
+    let found = match active {
+        Some(monitor) => describe(&monitor, "窗口所在显示器"),
+        None => {
+            let monitor = gdk::Display::default()?.monitors().item(0).and_downcast::<gdk::Monitor>()?;
+            describe(&monitor, "第一个显示器")
+        }
+    };
+    let ((w, h), _) = found;
+    (w > 0 && h > 0).then_some(found)
+}
+
+// 屏幕尺寸未知时返回 None，调用方不做与屏幕相关的限制
+fn get_screen_size() -> Option<(i32, i32)> {
+    screen_geometry().map(|(size, _)| size)
+}
+
+// 获取显示器的逻辑像素密度（每英寸逻辑像素数），物理尺寸缺失或不合理时返回 None
+fn get_monitor_ppi() -> Option<f64> {
+    let display = gdk::Display::default()?;
+    let monitor = display.monitors().item(0)?.downcast::<gdk::Monitor>().ok()?;
+    let (geom, width_mm) = (monitor.geometry(), monitor.width_mm());
+    if width_mm <= 0 { return None; }
+    let ppi = geom.width() as f64 / (width_mm as f64 / 25.4);
+    // 部分显示器上报的毫米尺寸是占位值（如 16×9），据此算出的密度不可信
+    (30.0..=1000.0).contains(&ppi).then_some(ppi)
+}
+
+// 计算"打印尺寸"缩放：使图片按 DPI 以真实物理尺寸显示
+fn calc_print_scale(dpi: Option<(f64, f64)>) -> Option<f64> {
+    let (dpi_x, _) = dpi?;
+    Some(get_monitor_ppi()? / dpi_x)
+}
+
+// 格式化物理尺寸，例如 "8.50 × 11.00 英寸（21.6 × 27.9 厘米）@ 300 dpi"
+fn format_physical_size(w: i32, h: i32, dpi: Option<(f64, f64)>) -> String {
+    match dpi {
+        Some((dx, dy)) => {
+            let (iw, ih) = (w as f64 / dx, h as f64 / dy);
+            let dpi_text = if (dx - dy).abs() < 0.5 { format!("{:.0} dpi", dx) } else { format!("{:.0}×{:.0} dpi", dx, dy) };
+            format!("{:.2} × {:.2} 英寸（{:.1} × {:.1} 厘米）@ {}", iw, ih, iw * 2.54, ih * 2.54, dpi_text)
+        }
+        None => "未知（图片未包含 DPI 信息）".to_string(),
+    }
+}
+
+const MAX_SCALE: f64 = 50.0;
+
+// 适应屏幕的缩放率（不放大）：图片连同标题栏能完整显示在屏幕允许的最大窗口中
+// 全屏与展台模式：按整个显示器适应（不放大），屏幕尺寸未知时为 100%
+fn fit_to_monitor_scale(img_w: i32, img_h: i32) -> f64 {
+    get_screen_size().map_or(1.0, |(screen_w, screen_h)| {
+        view::fit_scale((img_w as f64, img_h as f64), (screen_w as f64, screen_h as f64))
+    })
+}
+
+fn fit_to_screen_scale(img_w: i32, img_h: i32) -> f64 {
+    let (target_w, target_h) = calc_target_size(img_w, img_h);
+    view::fit_scale((img_w as f64, img_h as f64), (target_w as f64, (target_h - titlebar_space()) as f64))
+}
+
+// 滚轮缩小的下限：先停在恰好看到整张图片的 min_zoom，再多允许一步（step 为一格的倍数）；
+// 已经低于下限（如区域视图）时不会被拉回
+fn clamp_zoom(old: f64, new: f64, min_zoom: f64, step: f64) -> f64 {
+    if new < min_zoom && old > min_zoom + 1e-6 { return min_zoom; }
+    new.clamp((min_zoom / step).min(old), MAX_SCALE)
+}
+
+// 像素对齐缩放档位：小于 100% 时为 12.5/25/50%，100% 以上为整数倍
+const FRACTIONAL_PRESETS: [f64; 3] = [0.125, 0.25, 0.5];
+const MAX_PRESET: f64 = 50.0;
+
+// 按滚动方向取下一个缩放档位
+fn next_zoom_preset(scale: f64, zoom_in: bool) -> f64 {
+    const EPS: f64 = 1e-6;
+    if zoom_in {
+        if let Some(&p) = FRACTIONAL_PRESETS.iter().find(|&&p| p > scale + EPS) { return p; }
+        ((scale + EPS).floor() + 1.0).clamp(1.0, MAX_PRESET)
+    } else if scale > 1.0 + EPS {
+        (scale - EPS).ceil() - 1.0
+    } else {
+        FRACTIONAL_PRESETS.iter().rev().find(|&&p| p < scale - EPS).copied().unwrap_or(FRACTIONAL_PRESETS[0])
+    }
+}
+
+// 不超过 scale 的最大档位（用于适应窗口）
+fn floor_zoom_preset(scale: f64) -> f64 {
+    if scale >= 1.0 { return scale.floor().min(MAX_PRESET); }
+    FRACTIONAL_PRESETS.iter().rev().find(|&&p| p <= scale).copied().unwrap_or(FRACTIONAL_PRESETS[0])
+}
+
+// 缩放率文本；恰好为 0.1% 精度的档位（如 12.5%）显示一位小数
+fn format_zoom(scale: f64) -> String {
+    let pct = scale * 100.0;
+    let tenths = (pct * 10.0).round();
+    if tenths % 10.0 != 0.0 && (pct * 10.0 - tenths).abs() < 1e-6 {
+        format!("{:.1}%", pct)
+    } else {
+        format!("{:.0}%", pct)
     }
-    (1920, 1080) // fallback
 }
 
 // 计算目标窗口大小
 fn calc_target_size(img_w: i32, img_h: i32) -> (i32, i32) {
-    let (screen_w, screen_h) = get_screen_size();
-    let max_w = screen_w - 100; // 留边距
-    let max_h = screen_h - 100;
-    let w = img_w.clamp(MIN_WIN_WIDTH, max_w);
-    let h = (img_h + TITLEBAR_HEIGHT).clamp(MIN_WIN_HEIGHT, max_h);
+    // 屏幕尺寸未知时不限制上限
+    let (max_w, max_h) = match get_screen_size() {
+        Some((screen_w, screen_h)) => (screen_w - 100, screen_h - 100), // 留边距
+        None => (i32::MAX, i32::MAX),
+    };
+    let w = img_w.max(min_win_width()).min(max_w.max(min_win_width()));
+    let h = (img_h + titlebar_space()).max(MIN_WIN_HEIGHT).min(max_h.max(MIN_WIN_HEIGHT));
     (w, h)
 }
 
+// 创建仅显示图片的文件选择对话框
+fn image_file_dialog(title: &str) -> FileDialog {
+    let dialog = FileDialog::builder().title(title).modal(true).build();
+    let filter = gtk4::FileFilter::new();
+    filter.add_mime_type("image/*");
+    filter.set_name(Some("图片"));
+    let filters = gio::ListStore::new::<gtk4::FileFilter>();
+    filters.append(&filter);
+    dialog.set_filters(Some(&filters));
+    dialog
+}
+
+// 标题栏按钮的次要操作：右键或长按触发；长按认领事件序列，松开后按钮不再执行单击操作
+// 带有次要操作的按钮加上 has-secondary 样式类，标题栏的右键菜单据此让出事件
+fn connect_secondary(btn: &Button, action: impl Fn(&Button) + 'static) {
+    let action = Rc::new(action);
+    let click = gtk4::GestureClick::builder().button(3).build();
+    click.connect_pressed(clone!(#[strong] action, move |gesture, _, _, _| {
+        gesture.set_state(gtk4::EventSequenceState::Claimed);
+        if let Some(btn) = gesture.widget().and_downcast::<Button>() { action(&btn); }
+    }));
+    btn.add_controller(click);
+    let long_press = gtk4::GestureLongPress::new();
+    long_press.connect_pressed(move |gesture, _, _| {
+        gesture.set_state(gtk4::EventSequenceState::Claimed);
+        if let Some(btn) = gesture.widget().and_downcast::<Button>() { action(&btn); }
+    });
+    btn.add_controller(long_press);
+    btn.add_css_class("has-secondary");
+}
+
+// 自动隐藏的标题栏在 TITLEBAR_HIDE_DELAY 后收起；指针停在标题栏上或焦点在其中（输入框、弹出菜单）时推迟
+fn schedule_titlebar_hide(revealer: &gtk4::Revealer, titlebar: &Box, hovered: &Rc<Cell<bool>>, pending: &Rc<RefCell<Option<glib::SourceId>>>) {
+    if let Some(id) = pending.borrow_mut().take() { id.remove(); }
+    let (revealer, titlebar, hovered, pending_done) = (revealer.clone(), titlebar.clone(), hovered.clone(), pending.clone());
+    let id = glib::timeout_add_local_once(TITLEBAR_HIDE_DELAY, move || {
+        pending_done.borrow_mut().take();
+        let focused = titlebar.root().and_then(|root| root.focus()).is_some_and(|w| w.is_ancestor(&titlebar));
+        if hovered.get() || focused {
+            schedule_titlebar_hide(&revealer, &titlebar, &hovered, &pending_done);
+        } else {
+            revealer.set_reveal_child(false);
+        }
+    });
+    *pending.borrow_mut() = Some(id);
+}
+
+// 在按钮上滚动滚轮：每滚一格调用一次 step，参数为是否向下滚动；不影响按钮的单击与标题栏的拖动
+fn connect_scroll_steps(btn: &Button, step: impl Fn(bool) + 'static) {
+    let scroll = gtk4::EventControllerScroll::new(
+        gtk4::EventControllerScrollFlags::VERTICAL | gtk4::EventControllerScrollFlags::DISCRETE,
+    );
+    scroll.connect_scroll(move |_, _, dy| {
+        if dy != 0.0 { step(dy > 0.0); }
+        glib::Propagation::Stop
+    });
+    btn.add_controller(scroll);
+}
+
+// 最近打开的图片（系统最近文件列表中仍存在的图片），最近的在前
+fn recent_images(limit: usize) -> Vec<PathBuf> {
+    let mut items: Vec<(glib::DateTime, PathBuf)> = gtk4::RecentManager::default().items().into_iter()
+        .filter(|item| item.mime_type().starts_with("image/"))
+        .filter_map(|item| Some((item.modified(), gio::File::for_uri(&item.uri()).path()?)))
+        .filter(|(_, path)| path.is_file())
+        .collect();
+    items.sort_by(|a, b| b.0.cmp(&a.0));
+    items.into_iter().map(|(_, path)| path).take(limit).collect()
+}
+
+fn remember_recent(path: &Path) {
+    gtk4::RecentManager::default().add_item(&gio::File::for_path(path).uri());
+}
+
+// 导出工作线程发回主线程的消息
+enum ExtractMsg {
+    // 又写出了一个文件
+    Progress,
+    Collision(PathBuf),
+    Done(Result<extract::Summary, String>),
+}
+
+// 导出多页/多帧图片：设置命名模式、范围和输出目录后在工作线程中写出
+fn show_extract_dialog(parent: &ApplicationWindow, jobs: &jobs::Jobs, path: PathBuf, toast: ToastFn) {
+    let dialog = gtk4::Window::builder()
+        .title("导出所有帧/页")
+        .transient_for(parent)
+        .modal(true)
+        .resizable(false)
+        .build();
+    let grid = gtk4::Grid::builder().row_spacing(8).column_spacing(12)
+        .margin_top(16).margin_bottom(16).margin_start(16).margin_end(16).build();
+    let pattern_entry = gtk4::Entry::builder().text(extract::DEFAULT_PATTERN).hexpand(true).build();
+    let range_entry = gtk4::Entry::builder().placeholder_text("全部（例如 3-10）").build();
+    let out_dir = Rc::new(RefCell::new(path.parent().map(Path::to_path_buf).unwrap_or_default()));
+    let dir_btn = Button::with_label(&out_dir.borrow().to_string_lossy());
+    let error_lbl = Label::builder().halign(gtk4::Align::Start).visible(false).build();
+    error_lbl.add_css_class("error");
+    let cancel_btn = Button::with_label("取消");
+    let export_btn = Button::with_label("导出");
+    export_btn.add_css_class("suggested-action");
+    let buttons = Box::new(Orientation::Horizontal, 8);
+    buttons.set_halign(gtk4::Align::End);
+    buttons.append(&cancel_btn);
+    buttons.append(&export_btn);
+    grid.attach(&Label::builder().label("命名模式").halign(gtk4::Align::End).build(), 0, 0, 1, 1);
+    grid.attach(&pattern_entry, 1, 0, 1, 1);
+    grid.attach(&Label::builder().label("范围").halign(gtk4::Align::End).build(), 0, 1, 1, 1);
+    grid.attach(&range_entry, 1, 1, 1, 1);
+    grid.attach(&Label::builder().label("输出目录").halign(gtk4::Align::End).build(), 0, 2, 1, 1);
+    grid.attach(&dir_btn, 1, 2, 1, 1);
+    grid.attach(&error_lbl, 0, 3, 2, 1);
+    grid.attach(&buttons, 0, 4, 2, 1);
+    dialog.set_child(Some(&grid));
+
+    dir_btn.connect_clicked(clone!(#[weak] dialog, #[strong] out_dir, move |btn| {
+        let chooser = FileDialog::builder().title("选择输出目录").modal(true).build();
+        chooser.set_initial_folder(Some(&gio::File::for_path(&*out_dir.borrow())));
+        let btn = btn.clone();
+        let out_dir = out_dir.clone();
+        chooser.select_folder(Some(&dialog), gio::Cancellable::NONE, move |r| {
+            if let Some(dir) = r.ok().and_then(|f| f.path()) {
+                btn.set_label(&dir.to_string_lossy());
+                *out_dir.borrow_mut() = dir;
+            }
+        });
+    }));
+    cancel_btn.connect_clicked(clone!(#[weak] dialog, move |_| dialog.close()));
+
+    let (parent, jobs) = (parent.clone(), jobs.clone());
+    export_btn.connect_clicked(clone!(#[weak] dialog, move |_| {
+        let pattern = pattern_entry.text().to_string();
+        if let Err(e) = extract::validate_pattern(&pattern) {
+            error_lbl.set_text(&e);
+            error_lbl.set_visible(true);
+            return;
+        }
+        let Some(range) = extract::parse_range(&range_entry.text()) else {
+            error_lbl.set_text("范围格式无效，应为 5、3-10 或 3-");
+            error_lbl.set_visible(true);
+            return;
+        };
+        dialog.close();
+        run_extract(&parent, &jobs, path.clone(), out_dir.borrow().clone(), pattern, range, toast.clone());
+    }));
+    dialog.present();
+}
+
+// 在工作线程中执行导出，进度显示在标题栏的后台任务中
+fn run_extract(parent: &ApplicationWindow, jobs: &jobs::Jobs, path: PathBuf, out_dir: PathBuf, pattern: String,
+               range: (usize, Option<usize>), toast: ToastFn) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let job = jobs.start(&format!("导出 {} 的帧/页", name));
+    let (tx, rx) = std::sync::mpsc::channel::<ExtractMsg>();
+    let (answer_tx, answer_rx) = std::sync::mpsc::channel::<Option<extract::Collision>>();
+    let cancel_worker = job.cancellable();
+    std::thread::spawn(move || {
+        let progress_tx = tx.clone();
+        let ask_tx = tx.clone();
+        let result = extract::extract_frames(&path, &out_dir, &pattern, range, &cancel_worker,
+            |_| { progress_tx.send(ExtractMsg::Progress).ok(); },
+            |target| {
+                ask_tx.send(ExtractMsg::Collision(target.to_path_buf())).ok()?;
+                answer_rx.recv().ok().flatten()
+            });
+        tx.send(ExtractMsg::Done(result)).ok();
+    });
+
+    let parent = parent.clone();
+    let mut job = Some(job);
+    glib::timeout_add_local(Duration::from_millis(50), move || {
+        loop {
+            let msg = match rx.try_recv() {
+                Ok(msg) => msg,
+                Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                // 工作线程崩溃时没有发出 Done，同样结束任务并提示
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => ExtractMsg::Done(Err("导出线程意外退出".to_string())),
+            };
+            match msg {
+                // 总帧数事先未知，只表示仍在进行
+                ExtractMsg::Progress => if let Some(ref job) = job { job.pulse() },
+                ExtractMsg::Collision(target) => {
+                    // 只询问一次，之后统一覆盖或跳过
+                    let alert = gtk4::AlertDialog::builder()
+                        .message("目标文件已存在")
+                        .detail(format!("{} 已存在，如何处理所有同名文件？", target.display()))
+                        .buttons(["覆盖全部", "全部跳过", "取消导出"])
+                        .default_button(1)
+                        .cancel_button(2)
+                        .build();
+                    let answer_tx = answer_tx.clone();
+                    alert.choose(Some(&parent), gio::Cancellable::NONE, move |r| {
+                        let answer = match r {
+                            Ok(0) => Some(extract::Collision::Overwrite),
+                            Ok(1) => Some(extract::Collision::Skip),
+                            _ => None,
+                        };
+                        answer_tx.send(answer).ok();
+                    });
+                }
+                ExtractMsg::Done(result) => {
+                    if let Some(job) = job.take() { job.finish(); }
+                    match result {
+                        Ok(sum) => {
+                            let mut msg = format!("已导出 {} 个文件", sum.written);
+                            if sum.skipped > 0 { msg.push_str(&format!("，跳过 {} 个", sum.skipped)); }
+                            if sum.cancelled { msg.push_str("（已取消）"); }
+                            toast(&msg);
+                        }
+                        Err(e) => toast(&format!("导出失败: {}", e)),
+                    }
+                    return glib::ControlFlow::Break;
+                }
+            }
+        }
+    });
+}
+
+// 缩略图总览工作线程发回主线程的消息
+enum ContactMsg {
+    Progress(usize, usize),
+    Done(Result<contact::Summary, String>),
+}
+
+// 导出目录的缩略图总览：设置列数、缩略图大小、是否显示文件名和输出目录后在工作线程中渲染
+fn show_contact_dialog(parent: &ApplicationWindow, jobs: &jobs::Jobs, dir: PathBuf, files: Vec<PathBuf>, toast: ToastFn, reveal: RevealFn) {
+    let dialog = gtk4::Window::builder()
+        .title("导出缩略图总览")
+        .transient_for(parent)
+        .modal(true)
+        .resizable(false)
+        .build();
+    let grid = gtk4::Grid::builder().row_spacing(8).column_spacing(12)
+        .margin_top(16).margin_bottom(16).margin_start(16).margin_end(16).build();
+    let columns_spin = gtk4::SpinButton::with_range(1.0, 20.0, 1.0);
+    columns_spin.set_value(contact::DEFAULT_COLUMNS as f64);
+    let size_spin = gtk4::SpinButton::with_range(64.0, 1024.0, 32.0);
+    size_spin.set_value(contact::DEFAULT_THUMB_SIZE as f64);
+    let labels_check = gtk4::CheckButton::builder().label("在缩略图下方显示文件名").active(true).build();
+    let out_dir = Rc::new(RefCell::new(dir.clone()));
+    let dir_btn = Button::with_label(&out_dir.borrow().to_string_lossy());
+    let hint = Label::builder().halign(gtk4::Align::Start).build();
+    hint.add_css_class("dim-label");
+    let cancel_btn = Button::with_label("取消");
+    let export_btn = Button::with_label("导出");
+    export_btn.add_css_class("suggested-action");
+    let buttons = Box::new(Orientation::Horizontal, 8);
+    buttons.set_halign(gtk4::Align::End);
+    buttons.append(&cancel_btn);
+    buttons.append(&export_btn);
+    grid.attach(&Label::builder().label("列数").halign(gtk4::Align::End).build(), 0, 0, 1, 1);
+    grid.attach(&columns_spin, 1, 0, 1, 1);
+    grid.attach(&Label::builder().label("缩略图大小").halign(gtk4::Align::End).build(), 0, 1, 1, 1);
+    grid.attach(&size_spin, 1, 1, 1, 1);
+    grid.attach(&labels_check, 1, 2, 1, 1);
+    grid.attach(&Label::builder().label("输出目录").halign(gtk4::Align::End).build(), 0, 3, 1, 1);
+    grid.attach(&dir_btn, 1, 3, 1, 1);
+    grid.attach(&hint, 0, 4, 2, 1);
+    grid.attach(&buttons, 0, 5, 2, 1);
+    dialog.set_child(Some(&grid));
+
+    // 显示输出宽度，方便按需要的分辨率调整
+    let update_hint = clone!(#[weak] columns_spin, #[weak] size_spin, #[weak] hint, #[strong] files, move || {
+        let (columns, size) = (columns_spin.value_as_int() as f64, size_spin.value_as_int() as f64);
+        let width = columns * (size + (size / 16.0).max(6.0)) + (size / 16.0).max(6.0);
+        hint.set_text(&format!("{} 张图片，每页宽 {} 像素", files.len(), width.ceil()));
+    });
+    update_hint();
+    columns_spin.connect_value_changed(clone!(#[strong] update_hint, move |_| update_hint()));
+    size_spin.connect_value_changed(move |_| update_hint());
+
+    dir_btn.connect_clicked(clone!(#[weak] dialog, #[strong] out_dir, move |btn| {
+        let chooser = FileDialog::builder().title("选择输出目录").modal(true).build();
+        chooser.set_initial_folder(Some(&gio::File::for_path(&*out_dir.borrow())));
+        let btn = btn.clone();
+        let out_dir = out_dir.clone();
+        chooser.select_folder(Some(&dialog), gio::Cancellable::NONE, move |r| {
+            if let Some(dir) = r.ok().and_then(|f| f.path()) {
+                btn.set_label(&dir.to_string_lossy());
+                *out_dir.borrow_mut() = dir;
+            }
+        });
+    }));
+    cancel_btn.connect_clicked(clone!(#[weak] dialog, move |_| dialog.close()));
+
+    let jobs = jobs.clone();
+    export_btn.connect_clicked(clone!(#[weak] dialog, move |_| {
+        let options = contact::Options {
+            columns: columns_spin.value_as_int() as u32,
+            thumb_size: size_spin.value_as_int() as u32,
+            labels: labels_check.is_active(),
+        };
+        let name = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "images".to_string());
+        dialog.close();
+        run_contact(&jobs, files.clone(), out_dir.borrow().clone(), format!("{}-contact-sheet", name),
+                    options, toast.clone(), reveal.clone());
+    }));
+    dialog.present();
+}
+
+// 在工作线程中渲染缩略图总览，进度显示在标题栏的后台任务中
+fn run_contact(jobs: &jobs::Jobs, files: Vec<PathBuf>, out_dir: PathBuf, stem: String,
+               options: contact::Options, toast: ToastFn, reveal: RevealFn) {
+    let job = jobs.start(&format!("生成缩略图总览 {}", stem));
+    let (tx, rx) = std::sync::mpsc::channel::<ContactMsg>();
+    let cancel = job.cancellable();
+    std::thread::spawn(move || {
+        let progress_tx = tx.clone();
+        let result = contact::render(&files, &out_dir, &stem, options, &cancel,
+            |done, total| { progress_tx.send(ContactMsg::Progress(done, total)).ok(); });
+        tx.send(ContactMsg::Done(result)).ok();
+    });
+
+    let mut job = Some(job);
+    glib::timeout_add_local(Duration::from_millis(50), move || {
+        loop {
+            let msg = match rx.try_recv() {
+                Ok(msg) => msg,
+                Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                // 工作线程崩溃时没有发出 Done，同样结束任务并提示
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => ContactMsg::Done(Err("导出线程意外退出".to_string())),
+            };
+            match msg {
+                ContactMsg::Progress(done, total) => if let Some(ref job) = job {
+                    job.progress(done as f64 / total.max(1) as f64);
+                },
+                ContactMsg::Done(result) => {
+                    if let Some(job) = job.take() { job.finish(); }
+                    match result {
+                        Ok(sum) if sum.pages.is_empty() => toast("已取消导出"),
+                        Ok(sum) => {
+                            let first = sum.pages[0].clone();
+                            let mut msg = match sum.pages.len() {
+                                1 => format!("已导出 {}", first.file_name().unwrap_or_default().to_string_lossy()),
+                                n => format!("已导出 {} 页缩略图总览", n),
+                            };
+                            if sum.cancelled { msg.push_str("（已取消，其余未导出）"); }
+                            reveal(&msg, first);
+                        }
+                        Err(e) => toast(&format!("导出失败: {}", e)),
+                    }
+                    return glib::ControlFlow::Break;
+                }
+            }
+        }
+    });
+}
+
 fn print_help() {
-    eprintln!("Usage: image-viewer [OPTIONS] [FILE]");
+    eprintln!("Usage: image-viewer [OPTIONS] [FILE...]");
+    eprintln!("       image-viewer convert <IN> <OUT> [--size WxH] [--rotate DEG]");
+    eprintln!("       image-viewer --query <FILE> [--json]");
     eprintln!();
     eprintln!("Options:");
     eprintln!("  -o, --overlay    Start in overlay (always-on-top) mode");
+    eprintln!("      --kiosk      Locked-down fullscreen mode (only SIGTERM quits)");
+    eprintln!("      --monitor N  Monitor index used by --kiosk fullscreen");
+    eprintln!("      --windows    Open each FILE in its own window instead of tabs");
+    eprintln!("      --recursive  When FILE is a directory, also browse its subdirectories");
+    eprintln!("      --files-from LIST  Browse the paths listed one per line in LIST (- for stdin)");
+    eprintln!("      --select FILE  Browse exactly the given files in order (repeatable)");
+    eprintln!("      --sort ORDER Browse order: name, modified or date (capture date)");
+    eprintln!("      --slideshow SECS  Start a slideshow, advancing every SECS seconds");
+    eprintln!("      --at-pointer Open the window next to the pointer (X11) and size it for its monitor (default)");
+    eprintln!("      --center     Size the window for the first monitor");
+    eprintln!("      --tile       Show 2-6 FILEs in a grid, reloading each when it changes");
+    eprintln!("      --no-notify  With --tile, no desktop notification when a file changes");
+    eprintln!("      --verbose    Log load diagnostics for each image");
+    eprintln!("      --max-texture-size N  Downscale images larger than N pixels per side");
+    eprintln!("      --sandbox-decode  Decode images in a restricted helper process");
+    eprintln!("      --clipboard  Show the image on the clipboard and follow its updates");
+    eprintln!("      --restore-pin NAME  Recreate an overlay saved with Shift+right-click");
+    eprintln!("      --restore    Reopen the windows of the last session that did not exit cleanly");
+    eprintln!("      --list-pins  List saved overlay pins");
+    eprintln!("      --dump-keys  Print each action and its effective shortcut ([keys] in the config)");
+    eprintln!("      --action open-overlay  Choose an image and pin it (in the running instance if any)");
+    eprintln!("      --action start-slideshow  Start a slideshow of the Pictures folder (likewise)");
+    eprintln!("      --register-default    Make this the default viewer for common image types");
+    eprintln!("      --unregister-default  Undo --register-default");
     eprintln!("  -h, --help       Show this help message");
     eprintln!("  -v, --version    Show version");
+    eprintln!();
+    eprintln!("FILE - reads one image from standard input.");
+    eprintln!("FILE may be a directory: its first image opens and the rest can be browsed.");
+    eprintln!("FILE may end with a view fragment:");
+    eprintln!("  file.tif#page=3  anim.gif#frame=12  big.png#region=X,Y,W,H");
 }
 
 fn main() -> glib::ExitCode {
     // 解析命令行参数
     let args: Vec<String> = env::args().collect();
+    // convert 子命令在创建 Application 之前处理，无需图形会话
+    if args.get(1).is_some_and(|a| a == "convert") {
+        return convert::run(&args[2..]);
+    }
+    // 隔离解码的子进程，由 --sandbox-decode 启动，不在帮助中列出
+    if args.get(1).is_some_and(|a| a == sandbox::SUBCOMMAND) {
+        return sandbox::run_worker();
+    }
+    // --query 同样不需要图形会话，出现时其余参数都按它的规则解析
+    if args.iter().any(|a| a == "--query") {
+        return query::run(&args[1..]);
+    }
     let mut start_overlay = false;
-    let mut file_path: Option<String> = None;
+    let mut tile = false;
+    let mut from_stdin = false;
+    let mut from_clipboard = false;
+    let mut restore_pin: Option<String> = None;
+    let mut restore_last = false;
+    let mut files_from: Option<Option<PathBuf>> = None;
+    let mut selected: Vec<PathBuf> = Vec::new();
+    let mut launch_action: Option<&'static str> = None;
+    let mut dump_keys = false;
+    let mut files: Vec<InitialFile> = Vec::new();
+    let mut config = Config::load();
     
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
             "-o" | "--overlay" => start_overlay = true,
+            "--kiosk" => config.kiosk = true,
+            "--verbose" => config.verbose = true,
+            "--sandbox-decode" => sandbox::enable(),
+            "--windows" => config.windows = true,
+            "--recursive" => config.recursive = true,
+            "--center" => config.window_position = placement::StartPosition::Center,
+            "--at-pointer" => config.window_position = placement::StartPosition::Pointer,
+            "--tile" => tile = true,
+            "--no-notify" => config.notify_updates = false,
+            "--clipboard" => from_clipboard = true,
+            "-" => from_stdin = true,
+            "--restore" => restore_last = true,
+            "--restore-pin" => {
+                i += 1;
+                match args.get(i) {
+                    Some(name) => restore_pin = Some(name.clone()),
+                    None => {
+                        eprintln!("--restore-pin requires a pin name");
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            "--files-from" => {
+                i += 1;
+                match args.get(i) {
+                    Some(list) if list == "-" => files_from = Some(None),
+                    Some(list) => files_from = Some(Some(PathBuf::from(list))),
+                    None => {
+                        eprintln!("--files-from requires a file, or - for standard input");
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            "--select" => {
+                i += 1;
+                match args.get(i).and_then(|file| std::path::absolute(file).ok()) {
+                    Some(path) => selected.push(path),
+                    None => {
+                        eprintln!("--select requires a file");
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            "--action" => {
+                i += 1;
+                match args.get(i).and_then(|name| LAUNCH_ACTIONS.iter().find(|a| *a == name)) {
+                    Some(name) => launch_action = Some(name),
+                    None => {
+                        eprintln!("--action requires one of: {}", LAUNCH_ACTIONS.join(", "));
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            "--dump-keys" => dump_keys = true,
+            "--list-pins" => {
+                for name in pins::list() {
+                    println!("{}", name);
+                }
+                return glib::ExitCode::SUCCESS;
+            }
+            "--max-texture-size" => {
+                i += 1;
+                match args.get(i).and_then(|v| v.parse().ok()).filter(|&n: &u32| n > 0) {
+                    Some(n) => config.max_texture_size = Some(n),
+                    None => {
+                        eprintln!("--max-texture-size requires a size in pixels");
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            "--slideshow" => {
+                i += 1;
+                match args.get(i).and_then(|v| v.parse().ok()).filter(|&n: &u32| n > 0) {
+                    Some(n) => {
+                        config.slideshow_interval = n;
+                        config.slideshow = true;
+                    }
+                    None => {
+                        eprintln!("--slideshow requires an interval in seconds");
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            "--monitor" => {
+                i += 1;
+                match args.get(i).and_then(|v| v.parse().ok()) {
+                    Some(n) => config.monitor = Some(n),
+                    None => {
+                        eprintln!("--monitor requires a monitor index");
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            "--register-default" | "--unregister-default" => {
+                let result = if args[i] == "--register-default" {
+                    association::register()
+                } else {
+                    association::unregister()
+                };
+                return match result {
+                    Ok(outcome) => {
+                        eprintln!("{}", outcome.describe());
+                        if outcome.failed.is_empty() { glib::ExitCode::SUCCESS } else { glib::ExitCode::from(1) }
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        glib::ExitCode::from(1)
+                    }
+                };
+            }
             "-h" | "--help" => {
                 print_help();
                 return glib::ExitCode::SUCCESS;
@@ -68,8 +1014,25 @@ fn main() -> glib::ExitCode {
                 eprintln!("image-viewer {}", env!("CARGO_PKG_VERSION"));
                 return glib::ExitCode::SUCCESS;
             }
+            "--sort" => {
+                i += 1;
+                match args.get(i).and_then(|v| config::SortOrder::parse(v)) {
+                    Some(sort) => config.view.sort = Some(sort),
+                    None => {
+                        eprintln!("--sort requires name, modified or date");
+                        return glib::ExitCode::from(1);
+                    }
+                }
+            }
+            arg if arg.starts_with("--sort=") => match config::SortOrder::parse(&arg["--sort=".len()..]) {
+                Some(sort) => config.view.sort = Some(sort),
+                None => {
+                    eprintln!("--sort requires name, modified or date");
+                    return glib::ExitCode::from(1);
+                }
+            },
             arg if !arg.starts_with('-') => {
-                file_path = Some(arg.to_string());
+                files.push(split_view_fragment(arg));
             }
             _ => {
                 eprintln!("Unknown option: {}", args[i]);
@@ -79,94 +1042,633 @@ fn main() -> glib::ExitCode {
         }
         i += 1;
     }
+    // 在其余选项之后处理，输出的是按本次配置实际生效的快捷键
+    if dump_keys {
+        keys::dump(&config.keys);
+        return glib::ExitCode::SUCCESS;
+    }
+    if tile && !(tile::MIN_FILES..=tile::MAX_FILES).contains(&files.len()) {
+        eprintln!("--tile requires {} to {} files", tile::MIN_FILES, tile::MAX_FILES);
+        return glib::ExitCode::from(1);
+    }
+    let mut source = match (from_stdin, from_clipboard) {
+        (false, false) if !selected.is_empty() => {
+            if tile || !files.is_empty() || restore_pin.is_some() || files_from.is_some() {
+                eprintln!("--select cannot be combined with FILE, --tile, --files-from or --restore-pin");
+                return glib::ExitCode::from(1);
+            }
+            Source::Selection(std::mem::take(&mut selected))
+        }
+        (false, false) if files_from.is_some() => {
+            if tile || !files.is_empty() || restore_pin.is_some() {
+                eprintln!("--files-from cannot be combined with FILE, --tile or --restore-pin");
+                return glib::ExitCode::from(1);
+            }
+            Source::List(files_from.unwrap_or_default())
+        }
+        (false, false) if restore_pin.is_some() => {
+            let name = restore_pin.unwrap_or_default();
+            if tile || !files.is_empty() {
+                eprintln!("--restore-pin cannot be combined with FILE or with --tile");
+                return glib::ExitCode::from(1);
+            }
+            match pins::load(&name) {
+                Ok(pin) => {
+                    start_overlay = true;
+                    Source::Pin(name, pin)
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return glib::ExitCode::from(1);
+                }
+            }
+        }
+        (false, false) => Source::Files(files),
+        (true, false) if !tile && files.is_empty() && restore_pin.is_none() && files_from.is_none() && selected.is_empty() => Source::Stdin,
+        (false, true) if !tile && files.is_empty() && restore_pin.is_none() && files_from.is_none() && selected.is_empty() => Source::Clipboard,
+        _ => {
+            eprintln!("-, --clipboard, --files-from, --select and --restore-pin cannot be combined with each other, with FILE or with --tile");
+            return glib::ExitCode::from(1);
+        }
+    };
+    // --restore：恢复上次没有正常退出的会话，不询问；没有这样的会话时退出
+    let pending_restore = if restore_last {
+        if !matches!(&source, Source::Files(f) if f.is_empty()) || tile || start_overlay || launch_action.is_some() {
+            eprintln!("--restore cannot be combined with FILE or other start options");
+            return glib::ExitCode::from(1);
+        }
+        match recovery::find_crashed() {
+            Some(found) => Some(found),
+            None => {
+                eprintln!("没有需要恢复的会话");
+                return glib::ExitCode::from(1);
+            }
+        }
+    } else {
+        None
+    };
+    // 命令行给出的文件先检查，有问题的说明原因后跳过；没有可打开的文件时不必启动窗口，按第一个问题的类型退出
+    // --tile 的格子与文件一一对应，有问题即退出
+    if let Source::Files(files) = &mut source {
+        let mut first = None;
+        files.retain(|f| match precheck::check(Path::new(&f.path)) {
+            Ok(()) => true,
+            Err(problem) => {
+                eprintln!("{}: {}", f.path, problem.message());
+                first.get_or_insert(problem);
+                false
+            }
+        });
+        if let Some(problem) = first && (files.is_empty() || tile) {
+            return glib::ExitCode::from(problem.exit_code());
+        }
+    }
+    // --select 的文件同样先检查；目录不属于浏览列表
+    if let Source::Selection(files) = &mut source {
+        let mut first = None;
+        files.retain(|f| match precheck::check(f) {
+            Ok(()) if f.is_dir() => {
+                eprintln!("{}: --select 只接受文件，跳过目录", f.display());
+                false
+            }
+            Ok(()) => true,
+            Err(problem) => {
+                eprintln!("{}: {}", f.display(), problem.message());
+                first.get_or_insert(problem);
+                false
+            }
+        });
+        if files.is_empty() {
+            return glib::ExitCode::from(first.map_or(1, |problem| problem.exit_code()));
+        }
+    }
     
+    if launch_action.is_some() && (!matches!(&source, Source::Files(f) if f.is_empty()) || tile || start_overlay || config.kiosk) {
+        eprintln!("--action cannot be combined with FILE or other start options");
+        return glib::ExitCode::from(1);
+    }
+
+    // 与显示服务器的连接断开时 GTK 只记录一条消息就结束进程，在写出这条消息时保存会话并等待重新连接
+    let relaunch_args: &'static [&'static str] = if sandbox::enabled() { &["--sandbox-decode"] } else { &[] };
+    glib::log_set_writer_func(move |level, fields| {
+        let field = |key: &str| fields.iter().find(|f| f.key() == key).and_then(|f| f.value_str());
+        if field("GLIB_DOMAIN") == Some("Gdk") && field("MESSAGE").is_some_and(recovery::is_display_lost)
+            && glib::MainContext::default().is_owner()
+        {
+            glib::log_writer_default(level, fields);
+            recovery::display_lost(relaunch_args);
+        }
+        glib::log_writer_default(level, fields)
+    });
+
+    // 没有图形显示时给出明确的错误，而不是在创建窗口时崩溃
+    if let Err(e) = gtk4::init() {
+        eprintln!("无法连接到图形显示（请检查 WAYLAND_DISPLAY 或 DISPLAY）: {}", e);
+        return glib::ExitCode::from(1);
+    }
+    // 主动关闭或其他后端报告连接出错时同样处理
+    if let Some(display) = gdk::Display::default() {
+        display.connect_closed(move |_, is_error| if is_error { recovery::display_lost(relaunch_args) });
+    }
+
+    // 标准输入和剪贴板只能在本进程中读取，固定位置需要独立的置顶窗口，选择的文件组成独立的浏览列表，都不交给已运行的实例
+    let mut flags = gio::ApplicationFlags::HANDLES_OPEN;
+    if !matches!(source, Source::Files(_)) || pending_restore.is_some() { flags |= gio::ApplicationFlags::NON_UNIQUE; }
     let app = Application::builder()
         .application_id(APP_ID)
-        .flags(gio::ApplicationFlags::HANDLES_OPEN)
+        .flags(flags)
         .build();
     
-    let initial_file: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(file_path));
-    let initial_mode: Rc<Cell<WindowMode>> = Rc::new(Cell::new(
-        if start_overlay { WindowMode::Overlay } else { WindowMode::Normal }
-    ));
+    let initial_source: Rc<RefCell<Option<Source>>> = Rc::new(RefCell::new(Some(source)));
+    // 展台模式禁止置顶切换
+    let initial_request: Rc<Cell<StartRequest>> = Rc::new(Cell::new(match launch_action {
+        Some(action) => StartRequest::Action(action),
+        None if start_overlay && !config.kiosk => StartRequest::Open(WindowMode::Overlay),
+        None => StartRequest::Open(WindowMode::Normal),
+    }));
     
-    let initial_file_open = initial_file.clone();
+    let initial_source_open = initial_source.clone();
+    // 文件管理器一次打开多个文件时与 --select 相同，只在这些文件之间浏览；--windows 时仍各开一个窗口
+    let open_windows = config.windows;
     app.connect_open(move |app, files, _| {
-        if let Some(file) = files.first() {
-            if let Some(path) = file.path() {
-                *initial_file_open.borrow_mut() = Some(path.to_string_lossy().to_string());
-            }
-        }
+        let paths: Vec<PathBuf> = files.iter().filter_map(|f| f.path()).collect();
+        let source = if paths.len() > 1 && !open_windows {
+            Source::Selection(paths)
+        } else {
+            Source::Files(paths.into_iter().map(|p| InitialFile { path: p.to_string_lossy().to_string(), view: None }).collect())
+        };
+        *initial_source_open.borrow_mut() = Some(source);
         app.activate();
     });
 
-    let initial_file_activate = initial_file.clone();
-    let initial_mode_activate = initial_mode.clone();
-    app.connect_activate(move |app| {
-        build_ui(app, initial_file_activate.borrow_mut().take(), initial_mode_activate.get());
+    let config = Rc::new(config);
+    // 应用操作与快捷键只在主实例中登记；其他实例的 activate_action 经 D-Bus 转到这里执行
+    let config_startup = config.clone();
+    app.connect_startup(move |app| {
+        keys::apply(app, &config_startup.keys);
+        if config_startup.kiosk { return; }
+        add_launch_actions(app, config_startup.clone());
+        add_workspace_action(app, config_startup.overlay_current_workspace);
     });
-    
-    // 使用空参数运行，避免 GTK 解析我们的自定义参数
-    app.run_with_args::<&str>(&[])
-}
-
+    // 已有实例在运行时把请求的操作转给它，本进程随即退出；否则在激活时执行（冷启动）
+    if let Some(action) = launch_action
+        && app.register(gio::Cancellable::NONE).is_ok()
+        && app.is_remote()
+    {
+        app.activate_action(action, None);
+        if let Some(connection) = app.dbus_connection() {
+            connection.flush_sync(gio::Cancellable::NONE).ok();
+        }
+        return glib::ExitCode::SUCCESS;
+    }
+
+    let initial_source_activate = initial_source.clone();
+    let initial_request_activate = initial_request.clone();
+    // 只在本进程第一次激活时检查上次的会话，之后其他实例转来的文件不再询问
+    let recovery_checked = Cell::new(false);
+    let pending_restore = RefCell::new(pending_restore);
+    app.connect_activate(move |app| {
+        if let Some((file, session)) = pending_restore.borrow_mut().take() {
+            recovery_checked.set(true);
+            recovery::discard(&file);
+            restore_session(app, &config, session);
+            return;
+        }
+        if !recovery_checked.replace(true) && !tile && !config.kiosk {
+            let (app, config) = (app.clone(), config.clone());
+            glib::idle_add_local_once(move || offer_recovery(&app, config));
+        }
+        let mode = match initial_request_activate.get() {
+            StartRequest::Open(mode) => mode,
+            // 只执行一次，之后的激活（其他实例转来的文件）按普通方式打开
+            StartRequest::Action(action) => {
+                initial_request_activate.set(StartRequest::Open(WindowMode::Normal));
+                initial_source_activate.borrow_mut().take();
+                app.activate_action(action, None);
+                return;
+            }
+        };
+        let source = initial_source_activate.borrow_mut().take().unwrap_or(Source::Files(Vec::new()));
+        match source {
+            Source::Files(files) if tile => {
+                tile::build_ui(app, files.into_iter().map(|f| f.path).collect(), config.notify_updates);
+            }
+            // --windows：每个文件一个独立窗口（各自的 ImageState），逐个错开，所有窗口关闭后应用才退出
+            Source::Files(files) if config.windows && files.len() > 1 => {
+                for (i, file) in files.into_iter().enumerate() {
+                    build_ui(app, Source::Files(vec![file]), mode, config.clone(), i as i32 + 1);
+                }
+            }
+            source => build_ui(app, source, mode, config.clone(), 0),
+        }
+    });
+    
+    // 使用空参数运行，避免 GTK 解析我们的自定义参数
+    let code = app.run_with_args::<&str>(&[]);
+    recovery::clean_exit();
+    code
+}
+
+// 上次没有正常退出时询问是否重新打开当时的窗口；无论是否恢复，旧的恢复文件都删除
+fn offer_recovery(app: &Application, config: Rc<Config>) {
+    let Some((file, session)) = recovery::find_crashed() else { return };
+    let images: usize = session.windows.iter().map(|w| w.tabs.len()).sum();
+    let dialog = gtk4::AlertDialog::builder()
+        .message("上次没有正常退出")
+        .detail(format!("可以重新打开当时的 {} 个窗口（共 {} 张图片）。", session.windows.len(), images))
+        .buttons(["恢复", "丢弃"])
+        .default_button(0)
+        .cancel_button(1)
+        .build();
+    let app = app.clone();
+    dialog.choose(app.active_window().as_ref(), gio::Cancellable::NONE, move |choice| {
+        recovery::discard(&file);
+        if choice == Ok(0) { restore_session(&app, &config, session); }
+    });
+}
+
+// 重新打开会话中的各个窗口
+fn restore_session(app: &Application, config: &Rc<Config>, session: recovery::Session) {
+    for (i, window) in session.windows.into_iter().enumerate() {
+        let cascade = i as i32 + 1;
+        if window.overlay {
+            // 置顶窗口按固定位置的方式恢复缩放、方向与位置
+            build_ui(app, Source::Pin("恢复的会话".to_string(), window.view), WindowMode::Overlay, config.clone(), cascade);
+            continue;
+        }
+        // 普通窗口重新打开各标签，原活动标签放在最前
+        let mut tabs = window.tabs;
+        if window.active < tabs.len() {
+            let active = tabs.remove(window.active);
+            tabs.insert(0, active);
+        }
+        let files: Vec<InitialFile> = tabs.into_iter()
+            .filter(|path| Path::new(path).is_file())
+            .map(|path| InitialFile { path, view: None })
+            .collect();
+        if !files.is_empty() {
+            build_ui(app, Source::Files(files), WindowMode::Normal, config.clone(), cascade);
+        }
+    }
+}
+
+// 登记桌面文件附加操作对应的应用操作
+fn add_launch_actions(app: &Application, config: Rc<Config>) {
+    let open_overlay = gio::SimpleAction::new("open-overlay", None);
+    let (app_weak, config_overlay) = (app.downgrade(), config.clone());
+    open_overlay.connect_activate(move |_, _| {
+        if let Some(app) = app_weak.upgrade() { pick_overlay(&app, config_overlay.clone()); }
+    });
+    app.add_action(&open_overlay);
+    let start_slideshow = gio::SimpleAction::new("start-slideshow", None);
+    let app_weak = app.downgrade();
+    start_slideshow.connect_activate(move |_, _| {
+        if let Some(app) = app_weak.upgrade() { pictures_slideshow(&app, &config); }
+    });
+    app.add_action(&start_slideshow);
+}
+
+// 在新窗口中以幻灯片浏览图片目录（XDG_PICTURES_DIR）
+fn pictures_slideshow(app: &Application, config: &Config) {
+    let Some(dir) = glib::user_special_dir(glib::UserDirectory::Pictures).filter(|d| d.is_dir()) else {
+        eprintln!("没有找到图片目录（XDG_PICTURES_DIR）");
+        return;
+    };
+    let file = InitialFile { path: dir.to_string_lossy().to_string(), view: None };
+    let config = Config { slideshow: true, ..config.clone() };
+    build_ui(app, Source::Files(vec![file]), WindowMode::Normal, Rc::new(config), 0);
+}
+
+// 置顶窗口只在当前工作区显示（标题栏右键菜单），对已有的置顶窗口同样生效；合成器不支持时禁用
+fn add_workspace_action(app: &Application, initial: bool) {
+    let supported = workspace::detect().is_some();
+    workspace::set_enabled(initial && supported);
+    let action = gio::SimpleAction::new_stateful("overlay-workspace", None, &workspace::enabled().to_variant());
+    action.set_enabled(supported);
+    action.connect_activate(|action, _| {
+        let on = !workspace::enabled();
+        workspace::set_enabled(on);
+        action.set_state(&on.to_variant());
+    });
+    app.add_action(&action);
+}
+
+// 选择图片后直接以置顶模式打开；冷启动时对话框打开期间还没有窗口，保持应用运行
+fn pick_overlay(app: &Application, config: Rc<Config>) {
+    let hold = app.hold();
+    let app = app.clone();
+    image_file_dialog("选择要置顶的图片").open(app.active_window().as_ref(), gio::Cancellable::NONE, move |r| {
+        let _hold = hold;
+        let Some(path) = r.ok().and_then(|f| f.path()) else { return };
+        remember_recent(&path);
+        let file = InitialFile { path: path.to_string_lossy().to_string(), view: None };
+        build_ui(&app, Source::Files(vec![file]), WindowMode::Overlay, config, 0);
+    });
+}
+
 struct ImageState {
     pixbuf: Option<gdk::Texture>,
     scale: f64,
     offset_x: f64,
     offset_y: f64,
     rotation: i32,
+    // 双指旋转留下的自由角度（弧度），叠加在 rotation 之上，只在普通窗口中绘制
+    tilt: f64,
     original_width: i32,
     original_height: i32,
+    dpi: Option<(f64, f64)>,
+    multi_frame: bool,
+    // 显示的帧/页（从 1 开始）；导出与复制当前帧、设为封面都按此序号，与文件中的帧顺序一致
+    frame: usize,
+    // 仅显示图片中的该区域（图片坐标），缩小后取消
+    clip: Option<(f64, f64, f64, f64)>,
+    // 加载诊断信息
+    report: Option<LoadReport>,
+    // 方向（旋转）最近一次变化的时间，置顶模式据此短暂显示方向提示
+    orientation_changed: Option<std::time::Instant>,
+    // 滚轮缩小与重置使用的缩放率：适应屏幕与 100% 中较小者，载入和旋转时按当前方向计算
+    min_zoom: f64,
+    // 载入时按所在目录确定的视图设置（插值、背景等）
+    view: config::ViewDefaults,
+    // 缩小载入时原图的尺寸；纹理为完整分辨率时为 None
+    full_size: Option<(i32, i32)>,
+    quality: TextureQuality,
+    // 最近一次载入请求的编号；异步完成时与开始时的编号不同说明已有更新的请求，结果丢弃
+    generation: generation::Generation,
+    // 适应宽度或高度时窗口大小改变后按新尺寸重新适应；其他缩放操作恢复为 Free
+    zoom_mode: ZoomMode,
+    // 信息面板显示的文件属性与 EXIF，载入时读取
+    metadata: Option<metadata::Metadata>,
+}
+
+// 缩放方式：Free 为普通缩放，FitWidth/FitHeight 使图片宽度或高度正好填满绘图区
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+enum ZoomMode {
+    #[default]
+    Free,
+    FitWidth,
+    FitHeight,
+}
+
+// 纹理是否为最终结果：大 JPEG 先显示 DCT 缩小解码的预览，后台完整解码后替换
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+enum TextureQuality {
+    #[default]
+    Final,
+    Preview,
+    // 后台标签的纹理已被卸载；切换回来后在后台重新解码，期间绘制占位（卸载的预览仍为 Preview）
+    Unloaded,
 }
 
 // 置顶模式下的窗口位置（layer-shell 使用 margin 定位）
 struct OverlayPosition {
     margin_left: i32,
     margin_top: i32,
+    // 以下两项只在恢复固定位置时设置
+    opacity: f64,
+    // 显示器接口名，边距相对于该显示器
+    monitor: Option<String>,
 }
 
 impl Default for ImageState {
     fn default() -> Self {
-        Self { pixbuf: None, scale: 1.0, offset_x: 0.0, offset_y: 0.0, rotation: 0,
-               original_width: 0, original_height: 0 }
+        Self { pixbuf: None, scale: 1.0, offset_x: 0.0, offset_y: 0.0, rotation: 0, tilt: 0.0,
+               original_width: 0, original_height: 0, dpi: None, multi_frame: false, frame: 1,
+               clip: None, report: None, orientation_changed: None, min_zoom: 0.1,
+               view: config::ViewDefaults::default(), full_size: None, quality: TextureQuality::Final, generation: Default::default(), zoom_mode: ZoomMode::Free,
+               metadata: None }
     }
 }
 
 impl Default for OverlayPosition {
     fn default() -> Self {
-        Self { margin_left: 100, margin_top: 100 }
+        Self { margin_left: 100, margin_top: 100, opacity: 1.0, monitor: None }
+    }
+}
+
+// 切换到指定标签的回调
+type TabSwitchFn = Rc<dyn Fn(usize)>;
+
+// 为活动标签的动画开始播放（参数为是否立即播放，否则停在当前帧）；不是动画时只停止之前的播放
+type PlaybackFn = Rc<dyn Fn(bool)>;
+
+// 在图片底部显示短暂提示
+type ToastFn = Rc<dyn Fn(&str)>;
+// 显示带“打开”按钮的提示，点击后用默认程序打开该文件
+type RevealFn = Rc<dyn Fn(&str, PathBuf)>;
+
+// 刚被修改的文件解码失败后的重试：第几次重试，以及第一次载入前的生成编号（全部失败时恢复）
+#[derive(Clone, Copy)]
+struct LoadRetry {
+    attempt: usize,
+    previous: u64,
+}
+// 载入结束后调用，参数为是否成功；重试期间打开了其他图片时不再调用
+type LoadDone = std::boxed::Box<dyn FnOnce(bool)>;
+// 载入图片（路径、初始视图、解码方式），retry 为 None 时是第一次载入
+type LoadAttempt = dyn Fn(&str, Option<ViewRequest>, DecodeSize, Option<LoadRetry>, LoadDone);
+
+// 不关心载入结果时使用
+fn load_ignored() -> LoadDone {
+    std::boxed::Box::new(|_| {})
+}
+
+// 载入前的内存检查：确认后以选定的解码方式调用回调，取消时不调用
+type LoadThen = std::boxed::Box<dyn FnOnce(DecodeSize)>;
+type ConfirmLoadFn = Rc<dyn Fn(&str, LoadThen)>;
+
+// 刷新标题栏与状态等界面
+type RefreshFn = Rc<dyn Fn()>;
+
+// 处理拖入的文件，返回是否接受
+type DropFn = Rc<dyn Fn(Vec<PathBuf>) -> bool>;
+
+// 为预览或已卸载的纹理请求后台解码（载入编号, 文件路径, 缩小载入时纹理的尺寸）
+type FullDecodeFn = Rc<dyn Fn(u64, &str, Option<(i32, i32)>)>;
+
+// 进入全屏前的视图、载入编号与窗口大小（最大化时为 None）
+type FullscreenSaved = Rc<RefCell<Option<(model::SavedView, u64, Option<(i32, i32)>)>>>;
+
+// 连拍按 10 fps 翻页播放
+const BURST_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+// 打开前检查文件类型时读取的字节数
+const SNIFF_LEN: u64 = 512;
+// 打开按钮的右键菜单中最多列出的最近文件数
+const RECENT_LIMIT: usize = 10;
+// 可撤销的文件操作最多保留的条数，更早的连同其纹理一起释放
+const UNDO_LIMIT: usize = 20;
+// 悬停在上一张/下一张按钮上多久后预览
+const NAV_PREVIEW_DELAY: Duration = Duration::from_millis(400);
+
+// 触摸滑动翻页的最小速度（像素/秒）与最小距离，低于此值视为误触
+const SWIPE_MIN_VELOCITY: f64 = 600.0;
+const SWIPE_MIN_DISTANCE: f64 = 60.0;
+// 双指旋转松开时离最近的直角不超过该角度（约 8°）则吸附到直角
+const TILT_SNAP: f64 = 8.0 * std::f64::consts::PI / 180.0;
+
+// 图片是否放大到超过适应窗口的大小（此时触摸拖动用于平移）
+fn is_zoomed_in(state: &ImageState) -> bool {
+    state.scale > state.min_zoom + 1e-6
+}
+
+// 后台标签中保留纹理的数量（按最近使用），其余标签只保留视图状态
+const LOADED_BACKGROUND_TABS: usize = 2;
+
+// 标签页：活动标签的状态存放在共享的 ImageState 中，后台标签各自持有一份
+struct Tab {
+    path: String,
+    state: Option<ImageState>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Tabs {
+    items: Vec<Tab>,
+    active: usize,
+    clock: u64,
+}
+
+impl Tabs {
+    fn active_path(&self) -> Option<&str> {
+        self.items.get(self.active).map(|t| t.path.as_str())
+    }
+
+    // 当前标签载入了新文件
+    fn set_active_path(&mut self, path: &str) {
+        match self.items.get_mut(self.active) {
+            Some(tab) => tab.path = path.to_string(),
+            None => self.push(path, &mut ImageState::default()),
+        }
+    }
+
+    // 把当前状态存回活动标签，并新建一个空的活动标签
+    fn push(&mut self, path: &str, current: &mut ImageState) {
+        if let Some(tab) = self.items.get_mut(self.active) {
+            tab.state = Some(std::mem::take(current));
+        }
+        self.clock += 1;
+        self.items.push(Tab { path: path.to_string(), state: None, last_used: self.clock });
+        self.active = self.items.len() - 1;
+        self.unload_stale();
+    }
+
+    // 切换活动标签，交换共享状态
+    fn switch_to(&mut self, index: usize, current: &mut ImageState) -> bool {
+        if index >= self.items.len() || index == self.active { return false; }
+        if let Some(tab) = self.items.get_mut(self.active) {
+            tab.state = Some(std::mem::take(current));
+        }
+        self.activate(index, current);
+        true
+    }
+
+    // 关闭活动标签，切换到最近使用的标签；没有剩余标签时返回 false
+    fn close_active(&mut self, current: &mut ImageState) -> bool {
+        self.remove_active(current).is_some() && !self.items.is_empty()
+    }
+
+    // 移出活动标签（连同其状态和纹理），切换到最近使用的标签
+    fn remove_active(&mut self, current: &mut ImageState) -> Option<(usize, Tab)> {
+        if self.active >= self.items.len() { return None; }
+        let index = self.active;
+        let mut tab = self.items.remove(index);
+        tab.state = Some(std::mem::take(current));
+        match (0..self.items.len()).max_by_key(|&i| self.items[i].last_used) {
+            Some(next) => self.activate(next, current),
+            None => self.active = 0,
+        }
+        Some((index, tab))
+    }
+
+    // 把移出的标签放回原位置并设为活动标签
+    fn insert(&mut self, index: usize, tab: Tab, current: &mut ImageState) {
+        if let Some(active) = self.items.get_mut(self.active) {
+            active.state = Some(std::mem::take(current));
+        }
+        let index = index.min(self.items.len());
+        self.items.insert(index, tab);
+        self.activate(index, current);
+    }
+
+    fn activate(&mut self, index: usize, current: &mut ImageState) {
+        self.active = index;
+        self.clock += 1;
+        let tab = &mut self.items[index];
+        tab.last_used = self.clock;
+        // 纹理已被卸载时由调用方交给后台解码（quality 为 Unloaded），这里不阻塞界面
+        *current = tab.state.take().unwrap_or_default();
+        self.unload_stale();
+    }
+
+    // 文件被改名后更新指向它的标签；renamed 中为规范化的（原路径, 新路径）
+    fn rename_paths(&mut self, renamed: &[(PathBuf, PathBuf)]) {
+        for tab in &mut self.items {
+            let Some(location) = canonical_location(Path::new(&tab.path)) else { continue };
+            if let Some((_, to)) = renamed.iter().find(|(from, _)| *from == location) {
+                let name = to.file_name().unwrap_or_default();
+                tab.path = Path::new(&tab.path).with_file_name(name).to_string_lossy().to_string();
+            }
+        }
+    }
+
+    // 只为最近使用的几个后台标签保留纹理
+    fn unload_stale(&mut self) {
+        let mut background: Vec<usize> = (0..self.items.len()).filter(|&i| i != self.active).collect();
+        background.sort_by_key(|&i| std::cmp::Reverse(self.items[i].last_used));
+        for &i in background.iter().skip(LOADED_BACKGROUND_TABS) {
+            // 预览的纹理卸载后仍标记为预览，切换回来时照常完整解码
+            if let Some(ref mut st) = self.items[i].state
+                && st.pixbuf.take().is_some()
+                && st.quality == TextureQuality::Final
+            {
+                st.quality = TextureQuality::Unloaded;
+            }
+        }
     }
 }
 
+// 规范化的文件路径：只规范化所在目录，文件本身被改名或删除后仍可与列表中的路径比较
+fn canonical_location(path: &Path) -> Option<PathBuf> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
 // 更新窗口大小的核心函数
 // 强制窗口自适应（Snap-to-fit）
 fn update_window_size(win: &ApplicationWindow, da: &DrawingArea, scaled_w: i32, scaled_h: i32) {
+    // 全屏时窗口尺寸由合成器决定
+    if win.is_fullscreen() { return; }
     let (target_w, target_h) = calc_target_size(scaled_w, scaled_h);
-    let content_h = target_h - TITLEBAR_HEIGHT;
+    let content_h = target_h - titlebar_space();
+    let inset = shadow_inset(win);
     
     // 利用 resizable 副作用强制窗口收缩
     win.set_resizable(false);
     da.set_content_width(target_w);
     da.set_content_height(content_h);
-    win.set_default_size(target_w, target_h);
+    win.set_default_size(target_w + 2 * inset, target_h + 2 * inset);
     win.set_resizable(true);
 }
 
+// 窗口边缘到可见内容的距离：带阴影的窗口在最大化、全屏或平铺时不留边距
+fn shadow_inset(win: &ApplicationWindow) -> i32 {
+    if !win.has_css_class("shadowed") || win.is_maximized() || win.is_fullscreen() { return 0; }
+    let docked = win.surface().and_downcast::<gdk::Toplevel>()
+        .is_some_and(|t| t.state().intersects(gdk::ToplevelState::TILED | gdk::ToplevelState::MAXIMIZED | gdk::ToplevelState::FULLSCREEN));
+    if docked { 0 } else { SHADOW_MARGIN }
+}
+
 // 检查图片是否触发屏幕边缘限制
 fn is_at_screen_limit(scaled_w: i32, scaled_h: i32) -> bool {
-    let (screen_w, screen_h) = get_screen_size();
+    let Some((screen_w, screen_h)) = get_screen_size() else { return false };
     let max_w = screen_w - 100;
-    let max_h = screen_h - 100 - TITLEBAR_HEIGHT;
+    let max_h = screen_h - 100 - titlebar_space();
     scaled_w >= max_w || scaled_h >= max_h
 }
 
 // 计算旋转后的图片尺寸
 fn get_rotated_size(state: &ImageState) -> (i32, i32) {
-    match state.rotation % 2 {
-        0 => (state.original_width, state.original_height),
-        _ => (state.original_height, state.original_width),
-    }
+    view::rotated_size((state.original_width, state.original_height), state.rotation)
 }
 
 // 获取缩放后的图片尺寸
@@ -175,14 +1677,99 @@ fn get_scaled_size(state: &ImageState) -> (i32, i32) {
     ((w as f64 * state.scale) as i32, (h as f64 * state.scale) as i32)
 }
 
+// 置顶窗口右上角关闭按钮的位置和边长（随很小的图片一起缩小）
+fn overlay_close_rect(width: i32, height: i32) -> (f64, f64, f64) {
+    let size = (width.min(height) as f64 * 0.2).clamp(8.0, 22.0);
+    let margin = (size * 0.25).min(4.0);
+    (width as f64 - size - margin, margin, size)
+}
+
+// 点击位置是否落在关闭按钮上（四周留出少量容差）
+fn hit_overlay_close(x: f64, y: f64, width: i32, height: i32) -> bool {
+    let (bx, by, size) = overlay_close_rect(width, height);
+    let pad = size * 0.2;
+    x >= bx - pad && x <= bx + size + pad && y >= by - pad && y <= by + size + pad
+}
+
+// 绘制半透明的圆形 "×" 按钮
+fn draw_overlay_close(cr: &cairo::Context, width: i32, height: i32) {
+    let (x, y, size) = overlay_close_rect(width, height);
+    let (cx, cy, r) = (x + size / 2.0, y + size / 2.0, size / 2.0);
+    cr.save().ok();
+    cr.arc(cx, cy, r, 0.0, std::f64::consts::TAU);
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.55);
+    cr.fill().ok();
+    let d = r * 0.4;
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.9);
+    cr.set_line_width((size * 0.1).max(1.0));
+    cr.set_line_cap(cairo::LineCap::Round);
+    cr.move_to(cx - d, cy - d);
+    cr.line_to(cx + d, cy + d);
+    cr.move_to(cx + d, cy - d);
+    cr.line_to(cx - d, cy + d);
+    cr.stroke().ok();
+    cr.restore().ok();
+}
+
+// 方向提示显示时长与末尾淡出时长（秒）
+const ORIENTATION_BADGE_SECS: f64 = 2.0;
+const ORIENTATION_FADE_SECS: f64 = 0.5;
+
+// 置顶窗口左下角的方向提示（如 "↻90°"），原始方向时不显示
+// 返回下一次需要重绘的时间：淡出期间逐帧重绘，关闭动画时只在到期后重绘一次以直接隐藏
+fn draw_orientation_badge(cr: &cairo::Context, height: i32, state: &ImageState, always: bool, animate: bool) -> Option<Duration> {
+    if state.rotation % 4 == 0 { return None; }
+    let elapsed = state.orientation_changed.map_or(f64::MAX, |t| t.elapsed().as_secs_f64());
+    let (alpha, redraw) = badge_fade(elapsed, always, animate);
+    if alpha <= 0.0 { return None; }
+    let text = format!("↻{}°", state.rotation % 4 * 90);
+    cr.save().ok();
+    cr.set_font_size(12.0);
+    if let Ok(ext) = cr.text_extents(&text) {
+        let (pad, margin) = (5.0, 6.0);
+        let (w, h) = (ext.width() + pad * 2.0, ext.height() + pad * 2.0);
+        let (x, y) = (margin, height as f64 - margin - h);
+        cr.rectangle(x, y, w, h);
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.6 * alpha);
+        cr.fill().ok();
+        cr.move_to(x + pad - ext.x_bearing(), y + pad - ext.y_bearing());
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.95 * alpha);
+        cr.show_text(&text).ok();
+    }
+    cr.restore().ok();
+    redraw
+}
+
+// 方向提示显示 elapsed 秒后的不透明度，以及多久后需要重绘；
+// 关闭动画时不淡出，到时一步消失
+fn badge_fade(elapsed: f64, always: bool, animate: bool) -> (f64, Option<Duration>) {
+    if always { return (1.0, None); }
+    let remaining = ORIENTATION_BADGE_SECS - elapsed;
+    if remaining <= 0.0 { return (0.0, None); }
+    if animate {
+        ((remaining / ORIENTATION_FADE_SECS).min(1.0), Some(Duration::from_millis(50)))
+    } else {
+        (1.0, Some(Duration::from_secs_f64(remaining)))
+    }
+}
+
 // 创建绘图区域的绘制函数
+// close_hover 为 Some 时，在指针悬停期间绘制置顶窗口的关闭按钮
 fn create_draw_func(
     state: Rc<RefCell<ImageState>>,
     cached_surface: Rc<RefCell<Option<cairo::ImageSurface>>>,
     cached_rotation: Rc<Cell<i32>>,
     is_overlay: bool,
-) -> impl Fn(&DrawingArea, &cairo::Context, i32, i32) {
-    move |_, cr, width, height| {
+    close_hover: Option<Rc<Cell<bool>>>,
+    config: &Config,
+) -> impl Fn(&DrawingArea, &cairo::Context, i32, i32) + use<> {
+    let (always_show_orientation, scaling, animations) = (config.always_show_orientation, config.scaling, config.animations);
+    let prescaled = scaling::Prescaled::default();
+    // 生成缓存时的纹理，纹理被替换（如剪贴板更新）后重新生成
+    let cached_texture: RefCell<Option<gdk::Texture>> = RefCell::new(None);
+    // 方向提示已安排的重绘，每次绘制不再另加定时器
+    let badge_redraw: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    move |da, cr, width, height| {
         let state = state.borrow();
         
         // 置顶模式使用透明背景
@@ -197,54 +1784,299 @@ fn create_draw_func(
         }
         
         if let Some(ref texture) = state.pixbuf {
-            let need_update = cached_rotation.get() != state.rotation || cached_surface.borrow().is_none();
-            if need_update {
-                let (tw, th) = (texture.width(), texture.height());
-                if let Ok(surface) = cairo::ImageSurface::create(cairo::Format::ARgb32, tw, th) {
-                    let snapshot = gtk4::Snapshot::new();
-                    texture.snapshot(&snapshot, tw as f64, th as f64);
-                    if let Some(node) = snapshot.to_node() {
-                        if let Ok(ctx) = cairo::Context::new(&surface) {
-                            node.draw(&ctx);
-                        }
-                    }
-                    *cached_surface.borrow_mut() = Some(surface);
-                    cached_rotation.set(state.rotation);
-                }
+            let need_update = cached_rotation.get() != state.rotation || cached_surface.borrow().is_none()
+                || cached_texture.borrow().as_ref() != Some(texture);
+            if need_update && let Some(surface) = texture_surface(texture) {
+                *cached_surface.borrow_mut() = Some(surface);
+                cached_rotation.set(state.rotation);
+                *cached_texture.borrow_mut() = Some(texture.clone());
             }
             
             if let Some(ref surface) = *cached_surface.borrow() {
-                let (img_w, img_h) = get_rotated_size(&state);
-                let scaled_w = img_w as f64 * state.scale;
-                let scaled_h = img_h as f64 * state.scale;
+                // 尺寸取自缓存表面本身，不假设它与 original_width/height 一致
+                let (surf_w, surf_h) = (surface.width() as f64, surface.height() as f64);
+                let odd = state.rotation % 2 != 0;
+                let (img_w, img_h) = if odd { (surf_h, surf_w) } else { (surf_w, surf_h) };
                 
-                // 置顶模式：图片填满窗口；普通模式：居中+偏移
-                let (x, y) = if is_overlay {
-                    (0.0, 0.0)
+                // 置顶模式：图片按窗口实际大小铺满，窗口尺寸取整或被调整后也不会裁掉角落；
+                // 普通模式：居中+偏移
+                let (cx, cy, sx, sy) = if is_overlay {
+                    (width as f64 / 2.0, height as f64 / 2.0, width as f64 / img_w, height as f64 / img_h)
                 } else {
-                    ((width as f64 - scaled_w) / 2.0 + state.offset_x,
-                     (height as f64 - scaled_h) / 2.0 + state.offset_y)
+                    (width as f64 / 2.0 + state.offset_x, height as f64 / 2.0 + state.offset_y, state.scale, state.scale)
                 };
                 
                 cr.save().ok();
-                cr.translate(x + scaled_w / 2.0, y + scaled_h / 2.0);
-                cr.rotate(state.rotation as f64 * std::f64::consts::FRAC_PI_2);
-                cr.scale(state.scale, state.scale);
-                cr.translate(-state.original_width as f64 / 2.0, -state.original_height as f64 / 2.0);
-                cr.set_source_surface(surface, 0.0, 0.0).ok();
-                cr.source().set_filter(cairo::Filter::Bilinear);
+                rotate_about_center(cr, state.rotation, (surf_w, surf_h), (cx, cy), (sx, sy));
+                prescaled.set_source(cr, scaling, surface, sx.min(sy));
+                cr.source().set_filter(state.view.filter.unwrap_or_default().cairo());
                 cr.paint().ok();
                 cr.restore().ok();
             }
+        } else if state.quality != TextureQuality::Final {
+            // 纹理正在后台重新解码：在图片的位置画出同样大小的占位
+            let (x, y, w, h) = if is_overlay {
+                (0.0, 0.0, width as f64, height as f64)
+            } else {
+                let (w, h) = get_scaled_size(&state);
+                ((width - w) as f64 / 2.0 + state.offset_x, (height - h) as f64 / 2.0 + state.offset_y, w as f64, h as f64)
+            };
+            cr.rectangle(x, y, w, h);
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.06);
+            cr.fill().ok();
+        }
+        
+        let animate = config::animations_enabled(animations);
+        if let Some(delay) = is_overlay.then(|| draw_orientation_badge(cr, height, &state, always_show_orientation, animate)).flatten()
+            && badge_redraw.borrow().is_none()
+        {
+            let (da, pending) = (da.downgrade(), badge_redraw.clone());
+            let id = glib::timeout_add_local_once(delay, move || {
+                pending.borrow_mut().take();
+                if let Some(da) = da.upgrade() { da.queue_draw(); }
+            });
+            *badge_redraw.borrow_mut() = Some(id);
+        }
+        
+        if close_hover.as_ref().is_some_and(|h| h.get()) {
+            draw_overlay_close(cr, width, height);
         }
     }
 }
 
+// 按图片缩放后的大小调整置顶窗口和绘图区
+// 接受文件管理器拖入的文件：文件列表、单个文件或 text/uri-list 文本（file:// 地址，百分号编码由 gio 解码）
+fn file_drop_target(handle: DropFn) -> gtk4::DropTarget {
+    let target = gtk4::DropTarget::new(glib::Type::INVALID, gdk::DragAction::COPY);
+    target.set_types(&[gdk::FileList::static_type(), gio::File::static_type(), String::static_type()]);
+    target.connect_drop(move |_, value, _, _| {
+        let paths: Vec<PathBuf> = if let Ok(list) = value.get::<gdk::FileList>() {
+            list.files().iter().filter_map(|f| f.path()).collect()
+        } else if let Ok(file) = value.get::<gio::File>() {
+            file.path().into_iter().collect()
+        } else if let Ok(text) = value.get::<String>() {
+            paths_from_uri_list(&text)
+        } else {
+            Vec::new()
+        };
+        handle(paths)
+    });
+    target
+}
+
+// text/uri-list 或纯文本中的本地路径：每行一个 file:// 地址或绝对路径，# 开头的行为注释
+fn paths_from_uri_list(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| if l.starts_with("file://") { gio::File::for_uri(l).path() } else { Some(PathBuf::from(l)) })
+        .filter(|p| p.is_absolute())
+        .collect()
+}
+
+fn resize_overlay(win: &ApplicationWindow, scaled_w: i32, scaled_h: i32) {
+    if let Some(da) = win.child().and_downcast::<DrawingArea>() {
+        da.set_content_width(scaled_w.max(50));
+        da.set_content_height(scaled_h.max(50));
+        da.queue_draw();
+    }
+    win.set_default_size(scaled_w.max(50), scaled_h.max(50));
+}
+
+// 按当前的缩放与方向重新调整置顶窗口大小；旋转后宽高互换，边距随之调整，图片中心在屏幕上的位置不变
+fn resize_overlay_to_state(win: &ApplicationWindow, state: &RefCell<ImageState>, overlay_pos: &RefCell<OverlayPosition>) {
+    let (scaled_w, scaled_h) = get_scaled_size(&state.borrow());
+    let (old_w, old_h) = (win.width(), win.height());
+    // 尚未映射的窗口没有尺寸，只设置大小
+    if old_w > 0 && old_h > 0 {
+        let mut pos = overlay_pos.borrow_mut();
+        pos.margin_left = (pos.margin_left + (old_w - scaled_w.max(50)) / 2).max(0);
+        pos.margin_top = (pos.margin_top + (old_h - scaled_h.max(50)) / 2).max(0);
+        win.set_margin(Edge::Left, pos.margin_left);
+        win.set_margin(Edge::Top, pos.margin_top);
+    }
+    resize_overlay(win, scaled_w, scaled_h);
+}
+
+fn monitor_list() -> Vec<gdk::Monitor> {
+    let Some(display) = gdk::Display::default() else { return Vec::new() };
+    let monitors = display.monitors();
+    (0..monitors.n_items()).filter_map(|i| monitors.item(i).and_downcast::<gdk::Monitor>()).collect()
+}
+
+// 按接口名（如 DP-1）查找显示器
+fn monitor_by_connector(connector: &str) -> Option<gdk::Monitor> {
+    monitor_list().into_iter().find(|m| m.connector().is_some_and(|c| c == connector))
+}
+
+// 在 (x, y) 处弹出置顶显示器的选择：各显示器的名称与分辨率，以及“跟随指针”（回调参数为 None）
+fn show_monitor_chooser(anchor: &impl IsA<gtk4::Widget>, (x, y): (f64, f64), on_chosen: impl Fn(Option<gdk::Monitor>) + 'static) {
+    let content = Box::new(Orientation::Vertical, 2);
+    let title = Label::new(Some("置顶到显示器"));
+    title.add_css_class("heading");
+    title.set_margin_bottom(4);
+    content.append(&title);
+    let popover = gtk4::Popover::builder().child(&content).build();
+    popover.set_parent(anchor);
+    popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+    popover.connect_closed(|p| {
+        let p = p.clone();
+        glib::idle_add_local_once(move || p.unparent());
+    });
+    let on_chosen = Rc::new(on_chosen);
+    let mut choices: Vec<(String, Option<gdk::Monitor>)> = monitor_list().into_iter().map(|m| {
+        let geom = m.geometry();
+        let connector = m.connector().map(|c| c.to_string()).unwrap_or_else(|| "未命名".to_string());
+        let label = match m.description() {
+            Some(d) => format!("{} · {} · {}×{}", connector, d, geom.width(), geom.height()),
+            None => format!("{} · {}×{}", connector, geom.width(), geom.height()),
+        };
+        (label, Some(m))
+    }).collect();
+    choices.push(("跟随指针".to_string(), None));
+    for (label, monitor) in choices {
+        let button = Button::builder().label(label).build();
+        button.add_css_class("flat");
+        if let Some(child) = button.child().and_downcast::<Label>() {
+            child.set_xalign(0.0);
+        }
+        button.connect_clicked(clone!(#[strong] on_chosen, #[weak] popover, move |_| {
+            popover.popdown();
+            on_chosen(monitor.clone());
+        }));
+        content.append(&button);
+    }
+    popover.popup();
+}
+
+// 可撤销的文件操作
+enum Undo {
+    // 移到回收站的标签及其原位置
+    Delete(usize, std::boxed::Box<Tab>),
+    // 改名前后的规范化路径
+    Rename(PathBuf, PathBuf),
+}
+
+// 批量编号：确认后以输入的前缀调用 apply，出错时保留窗口并显示错误
+type NumberFn = Rc<dyn Fn(&str) -> Result<(), String>>;
+
+fn show_numbering_dialog(parent: &ApplicationWindow, files: Vec<PathBuf>, prefix: &str, apply: NumberFn) {
+    let dialog = gtk4::Window::builder()
+        .title("按顺序编号")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(420)
+        .default_height(360)
+        .build();
+    let content = Box::new(Orientation::Vertical, 8);
+    content.set_margin_top(16);
+    content.set_margin_bottom(16);
+    content.set_margin_start(16);
+    content.set_margin_end(16);
+    let prefix_entry = gtk4::Entry::builder().text(prefix).placeholder_text("前缀").activates_default(true).build();
+    let changes_lbl = Label::builder().halign(gtk4::Align::Start).valign(gtk4::Align::Start).selectable(true).build();
+    let scroller = gtk4::ScrolledWindow::builder().child(&changes_lbl).vexpand(true).build();
+    let error_lbl = Label::builder().halign(gtk4::Align::Start).wrap(true).visible(false).build();
+    error_lbl.add_css_class("error");
+    let cancel_btn = Button::with_label("取消");
+    let confirm_btn = Button::with_label(&format!("重命名 {} 个文件", files.len()));
+    confirm_btn.add_css_class("destructive-action");
+    let buttons = Box::new(Orientation::Horizontal, 8);
+    buttons.set_halign(gtk4::Align::End);
+    buttons.append(&cancel_btn);
+    buttons.append(&confirm_btn);
+    content.append(&prefix_entry);
+    content.append(&scroller);
+    content.append(&error_lbl);
+    content.append(&buttons);
+    dialog.set_child(Some(&content));
+    dialog.set_default_widget(Some(&confirm_btn));
+
+    // 前缀修改时更新改动列表
+    let update = move |prefix: &str| {
+        let lines: Vec<String> = rename::numbered(&files, prefix.trim()).iter().map(|(from, to)| {
+            let name = |p: &PathBuf| p.file_name().unwrap_or_default().to_string_lossy().to_string();
+            format!("{} → {}", name(from), name(to))
+        }).collect();
+        changes_lbl.set_text(&lines.join("\n"));
+    };
+    update(prefix);
+    prefix_entry.connect_changed(move |e| update(&e.text()));
+
+    cancel_btn.connect_clicked(clone!(#[weak] dialog, move |_| dialog.close()));
+    confirm_btn.connect_clicked(clone!(#[weak] dialog, move |_| {
+        match apply(&prefix_entry.text()) {
+            Ok(()) => dialog.close(),
+            Err(e) => {
+                error_lbl.set_text(&e);
+                error_lbl.set_visible(true);
+            }
+        }
+    }));
+    dialog.present();
+}
+
+// 保存固定位置：输入名称后调用 save，同名时覆盖
+type SavePinFn = Rc<dyn Fn(&str) -> Result<PathBuf, String>>;
+
+// 置顶窗口不接收键盘输入，命名在单独的普通窗口中进行
+fn show_save_pin_dialog(overlay: &ApplicationWindow, save: SavePinFn) {
+    let dialog = gtk4::Window::builder()
+        .title("保存固定位置")
+        .resizable(false)
+        .build();
+    if let Some(app) = overlay.application() { dialog.set_application(Some(&app)); }
+    let content = Box::new(Orientation::Vertical, 8);
+    content.set_margin_top(16);
+    content.set_margin_bottom(16);
+    content.set_margin_start(16);
+    content.set_margin_end(16);
+    let existing = pins::list();
+    let hint = if existing.is_empty() {
+        "之后用 image-viewer --restore-pin 名称 重新打开".to_string()
+    } else {
+        format!("已有：{}（同名将覆盖）", existing.join("、"))
+    };
+    let name_entry = gtk4::Entry::builder().placeholder_text("名称").activates_default(true).hexpand(true).build();
+    let hint_lbl = Label::builder().label(hint).halign(gtk4::Align::Start).wrap(true).build();
+    let error_lbl = Label::builder().halign(gtk4::Align::Start).visible(false).build();
+    error_lbl.add_css_class("error");
+    let cancel_btn = Button::with_label("取消");
+    let save_btn = Button::with_label("保存");
+    save_btn.add_css_class("suggested-action");
+    let buttons = Box::new(Orientation::Horizontal, 8);
+    buttons.set_halign(gtk4::Align::End);
+    buttons.append(&cancel_btn);
+    buttons.append(&save_btn);
+    content.append(&name_entry);
+    content.append(&hint_lbl);
+    content.append(&error_lbl);
+    content.append(&buttons);
+    dialog.set_child(Some(&content));
+    dialog.set_default_widget(Some(&save_btn));
+
+    cancel_btn.connect_clicked(clone!(#[weak] dialog, move |_| dialog.close()));
+    save_btn.connect_clicked(clone!(#[weak] dialog, move |_| {
+        let name = name_entry.text().trim().to_string();
+        match save(&name) {
+            Ok(_) => dialog.close(),
+            Err(e) => {
+                error_lbl.set_text(&e);
+                error_lbl.set_visible(true);
+            }
+        }
+    }));
+    dialog.present();
+}
+
 // 创建置顶模式窗口
 fn create_overlay_window(
     app: &Application,
     state: Rc<RefCell<ImageState>>,
+    model: ViewerModel,
     overlay_pos: Rc<RefCell<OverlayPosition>>,
+    config: &Config,
+    path: Option<String>,
     on_exit_overlay: impl Fn() + 'static,
 ) -> ApplicationWindow {
     let (scaled_w, scaled_h) = {
@@ -274,6 +2106,13 @@ fn create_overlay_window(
         let pos = overlay_pos.borrow();
         window.set_margin(Edge::Left, pos.margin_left);
         window.set_margin(Edge::Top, pos.margin_top);
+        window.set_opacity(pos.opacity);
+        if let Some(ref connector) = pos.monitor {
+            match monitor_by_connector(connector) {
+                Some(monitor) => window.set_monitor(Some(&monitor)),
+                None => eprintln!("找不到显示器 {}，改用默认显示器", connector),
+            }
+        }
     }
     
     // 创建绘图区域
@@ -284,50 +2123,210 @@ fn create_overlay_window(
     let cached_surface: Rc<RefCell<Option<cairo::ImageSurface>>> = Rc::new(RefCell::new(None));
     let cached_rotation: Rc<Cell<i32>> = Rc::new(Cell::new(-1));
     
-    let draw_func = create_draw_func(state.clone(), cached_surface.clone(), cached_rotation.clone(), true);
-    drawing_area.set_draw_func(draw_func);
+    // 悬停时显示关闭按钮（可在配置中关闭）
+    let show_close = config.overlay_close_button;
+    let hover = Rc::new(Cell::new(false));
+    let draw_func = create_draw_func(state.clone(), cached_surface.clone(), cached_rotation.clone(), true,
+                                     show_close.then(|| hover.clone()), config);
+    // 固定时图片不是原始方向，提示一次
+    model.mutate(|s| if s.rotation % 4 != 0 { s.orientation_changed = Some(std::time::Instant::now()); });
+    let loupe = Rc::new(loupe::Loupe::default());
+    drawing_area.set_draw_func(clone!(#[strong] loupe, #[strong] state, move |da, cr, width, height| {
+        draw_func(da, cr, width, height);
+        if let Some(ref surface) = *cached_surface.borrow() {
+            loupe.draw(cr, width, height, surface, state.borrow().rotation);
+        }
+    }));
+    
+    if show_close {
+        let hover_ctrl = gtk4::EventControllerMotion::new();
+        hover_ctrl.connect_enter(clone!(#[strong] hover, #[weak] drawing_area, move |_, _, _| {
+            hover.set(true);
+            drawing_area.queue_draw();
+        }));
+        hover_ctrl.connect_leave(clone!(#[strong] hover, #[weak] drawing_area, move |_| {
+            hover.set(false);
+            drawing_area.queue_draw();
+        }));
+        drawing_area.add_controller(hover_ctrl);
+    }
     
     window.set_child(Some(&drawing_area));
     
-    // 滚轮缩放
+    // 缩放置顶窗口：steps 为正放大、为负缩小；anchor 为保持不动的窗口内坐标，None 时以左上角为准
+    let zoom_overlay = {
+        let model = model.clone();
+        let overlay_pos = overlay_pos.clone();
+        let win = window.clone();
+        let factor = config.zoom_factor();
+        Rc::new(move |steps: f64, anchor: Option<(f64, f64)>| {
+            let Some((ratio, (scaled_w, scaled_h))) = model.zoom_overlay(steps, factor) else { return };
+            
+            // 调整边距，使指针下的图片位置保持不变
+            if let Some((px, py)) = anchor {
+                let mut pos = overlay_pos.borrow_mut();
+                pos.margin_left = (pos.margin_left as f64 + px - px * ratio).max(0.0) as i32;
+                pos.margin_top = (pos.margin_top as f64 + py - py * ratio).max(0.0) as i32;
+                win.set_margin(Edge::Left, pos.margin_left);
+                win.set_margin(Edge::Top, pos.margin_top);
+            }
+            
+            resize_overlay(&win, scaled_w, scaled_h);
+        })
+    };
+    
+    // Ctrl+滚轮以指针为中心缩放；普通滚轮不处理，不拦截滚动
+    let pointer = Rc::new(Cell::new((0.0, 0.0)));
+    let pointer_ctrl = gtk4::EventControllerMotion::new();
+    pointer_ctrl.connect_motion(clone!(#[strong] pointer, #[strong] loupe, #[weak] drawing_area, move |_, x, y| {
+        pointer.set((x, y));
+        loupe.set_pointer(Some((x, y)));
+        if loupe.is_active() { drawing_area.queue_draw(); }
+    }));
+    pointer_ctrl.connect_leave(clone!(#[strong] loupe, #[weak] drawing_area, move |_| {
+        loupe.set_pointer(None);
+        if loupe.is_active() { drawing_area.queue_draw(); }
+    }));
+    drawing_area.add_controller(pointer_ctrl);
     let scroll_ctrl = gtk4::EventControllerScroll::new(gtk4::EventControllerScrollFlags::VERTICAL);
-    let state_scroll = state.clone();
-    let da_scroll = drawing_area.clone();
-    let win_scroll = window.clone();
-    scroll_ctrl.connect_scroll(move |_, _, dy| {
-        let mut s = state_scroll.borrow_mut();
-        if s.pixbuf.is_none() { return glib::Propagation::Proceed; }
-        
-        let factor = if dy < 0.0 { 1.1 } else { 1.0 / 1.1 };
-        s.scale = (s.scale * factor).clamp(0.1, 50.0);
-        
-        let (scaled_w, scaled_h) = get_scaled_size(&s);
-        drop(s);
-        
-        // 更新窗口和绘图区大小
-        da_scroll.set_content_width(scaled_w.max(50));
-        da_scroll.set_content_height(scaled_h.max(50));
-        win_scroll.set_default_size(scaled_w.max(50), scaled_h.max(50));
-        da_scroll.queue_draw();
-        
+    let zoom_scroll = zoom_overlay.clone();
+    let wheel = wheel::WheelZoom::new(config);
+    scroll_ctrl.connect_scroll(move |ctrl, _, dy| {
+        if !ctrl.current_event_state().contains(gdk::ModifierType::CONTROL_MASK) {
+            return glib::Propagation::Proceed;
+        }
+        zoom_scroll(wheel.steps(ctrl, dy), Some(pointer.get()));
         glib::Propagation::Stop
     });
     drawing_area.add_controller(scroll_ctrl);
     
-    // 拖动窗口（移动位置）
+    // 通过 D-Bus 导出的应用动作缩放（org.gtk.Actions.Activate "zoom-overlay"，参数为步数）
+    let zoom_action = gio::SimpleAction::new("zoom-overlay", Some(glib::VariantTy::DOUBLE));
+    zoom_action.connect_activate(move |_, param| {
+        if let Some(steps) = param.and_then(|p| p.get::<f64>()) {
+            zoom_overlay(steps, None);
+        }
+    });
+    app.add_action(&zoom_action);
+    
+    // 旋转置顶窗口（org.gtk.Actions.Activate "rotate-overlay"，参数为 true 顺时针、false 逆时针），
+    // 窗口随新的方向调整大小
+    let rotate_action = gio::SimpleAction::new("rotate-overlay", Some(glib::VariantTy::BOOLEAN));
+    rotate_action.connect_activate(clone!(#[strong] state, #[strong] model, #[strong] overlay_pos, #[weak] window, move |_, param| {
+        let Some(clockwise) = param.and_then(|p| p.get::<bool>()) else { return };
+        if !model.has_image() { return; }
+        model.rotate(clockwise);
+        resize_overlay_to_state(&window, &state, &overlay_pos);
+        recovery::changed();
+    }));
+    app.add_action(&rotate_action);
+    
+    // 取色器开关：Ctrl+单击，或 org.gtk.Actions.Activate "toggle-loupe"
+    let toggle_loupe = Rc::new(clone!(#[strong] loupe, #[weak] drawing_area, move || {
+        loupe.toggle();
+        drawing_area.queue_draw();
+    }));
+    let loupe_action = gio::SimpleAction::new("toggle-loupe", None);
+    loupe_action.connect_activate(clone!(#[strong] toggle_loupe, move |_, _| toggle_loupe()));
+    app.add_action(&loupe_action);
+    
+    // 移到另一台显示器（org.gtk.Actions.Activate "move-overlay"，参数为 next、previous 或接口名），
+    // 置顶窗口不接收键盘输入，可在合成器中把 Super+Shift+方向键绑定到这条命令
+    let move_action = gio::SimpleAction::new("move-overlay", Some(glib::VariantTy::STRING));
+    move_action.connect_activate(clone!(#[strong] overlay_pos, #[weak] window, move |_, param| {
+        let Some(target) = param.and_then(|p| p.get::<String>()) else { return };
+        let monitors = monitor_list();
+        let current = window.surface()
+            .and_then(|surface| surface.display().monitor_at_surface(&surface))
+            .and_then(|m| monitors.iter().position(|x| *x == m));
+        let step = |delta: usize| current.map_or(0, |i| (i + delta) % monitors.len());
+        let monitor = match target.as_str() {
+            _ if monitors.is_empty() => return,
+            "next" => monitors[step(1)].clone(),
+            "previous" => monitors[step(monitors.len() - 1)].clone(),
+            connector => match monitor_by_connector(connector) {
+                Some(m) => m,
+                None => return eprintln!("没有接口名为 {} 的显示器", connector),
+            },
+        };
+        // 边距限制在新显示器内，窗口不会移出屏幕
+        let geom = monitor.geometry();
+        let mut pos = overlay_pos.borrow_mut();
+        pos.margin_left = pos.margin_left.min(geom.width() - window.width()).max(0);
+        pos.margin_top = pos.margin_top.min(geom.height() - window.height()).max(0);
+        pos.monitor = monitor.connector().map(|c| c.to_string());
+        window.set_monitor(Some(&monitor));
+        window.set_margin(Edge::Left, pos.margin_left);
+        window.set_margin(Edge::Top, pos.margin_top);
+        recovery::changed();
+    }));
+    app.add_action(&move_action);
+    
+    // 保存固定位置（org.gtk.Actions.Activate "save-pin"，参数为名称），Shift+右键则弹出命名窗口
+    let save_pin = {
+        let state = state.clone();
+        let overlay_pos = overlay_pos.clone();
+        let win = window.clone();
+        Rc::new(move |name: &str| -> Result<PathBuf, String> {
+            // 标准输入、剪贴板等来源没有可重新打开的文件
+            let file = path.as_deref().map(Path::new).filter(|p| p.is_file())
+                .ok_or("只能固定磁盘上的图片文件")?;
+            let file = file.canonicalize().map_err(|e| e.to_string())?;
+            let (scale, rotation) = {
+                let s = state.borrow();
+                (s.scale, s.rotation)
+            };
+            let pos = overlay_pos.borrow();
+            let monitor = win.surface()
+                .and_then(|surface| surface.display().monitor_at_surface(&surface))
+                .and_then(|m| m.connector())
+                .map(|c| c.to_string());
+            let pin = pins::Pin {
+                path: file.to_string_lossy().to_string(),
+                scale,
+                rotation,
+                margin_left: pos.margin_left,
+                margin_top: pos.margin_top,
+                opacity: win.opacity(),
+                monitor,
+            };
+            pins::save(name, &pin)
+        })
+    };
+    let pin_action = gio::SimpleAction::new("save-pin", Some(glib::VariantTy::STRING));
+    pin_action.connect_activate(clone!(#[strong] save_pin, move |_, param| {
+        let Some(name) = param.and_then(|p| p.get::<String>()) else { return };
+        match save_pin(&name) {
+            Ok(file) => eprintln!("已保存固定位置 {}（{}）", name, file.display()),
+            Err(e) => eprintln!("无法保存固定位置 {}: {}", name, e),
+        }
+    }));
+    app.add_action(&pin_action);
+    window.connect_destroy(clone!(#[weak] app, move |_| {
+        app.remove_action("zoom-overlay");
+        app.remove_action("rotate-overlay");
+        app.remove_action("save-pin");
+        app.remove_action("toggle-loupe");
+        app.remove_action("move-overlay");
+    }));
+    
+    // 拖动窗口（移动位置）；越过系统拖动阈值后才移动，双击退出时手抖不会挪动窗口
     let drag_ctrl = gtk4::GestureDrag::builder().button(1).build();
     let win_drag = window.clone();
     let overlay_pos_drag = overlay_pos.clone();
     let drag_start_pos = Rc::new(Cell::new((0i32, 0i32)));
     let drag_start_clone = drag_start_pos.clone();
+    let move_gate = Rc::new(pointer::DragGate::default());
     
-    drag_ctrl.connect_drag_begin(clone!(#[strong] overlay_pos_drag, move |_, _, _| {
+    drag_ctrl.connect_drag_begin(clone!(#[strong] overlay_pos_drag, #[strong] move_gate, move |_, _, _| {
+        move_gate.reset();
         let pos = overlay_pos_drag.borrow();
         drag_start_clone.set((pos.margin_left, pos.margin_top));
     }));
     
     drag_ctrl.connect_drag_update(clone!(#[strong] overlay_pos_drag, #[strong] win_drag, #[strong] drag_start_pos,
         move |_, dx, dy| {
+            if !move_gate.passed_system(dx, dy) { return; }
             let (start_left, start_top) = drag_start_pos.get();
             let new_left = (start_left as f64 + dx) as i32;
             let new_top = (start_top as f64 + dy) as i32;
@@ -342,15 +2341,26 @@ fn create_overlay_window(
             win_drag.set_margin(Edge::Top, new_top.max(0));
         }
     ));
+    drag_ctrl.connect_drag_end(|_, _, _| recovery::changed());
     drawing_area.add_controller(drag_ctrl);
     
-    // 双击退出置顶模式
+    // 点击关闭按钮或双击退出置顶模式，Ctrl+单击开关取色器
+    // 关闭按钮在按下时立即认领事件序列，拖动手势不会再移动窗口
     let double_click = gtk4::GestureClick::builder().button(1).build();
     let on_exit = Rc::new(on_exit_overlay);
     let on_exit_dbl = on_exit.clone();
     let win_dbl = window.clone();
-    double_click.connect_pressed(move |gesture, n_press, _, _| {
-        if n_press == 2 {
+    let hover_click = hover.clone();
+    double_click.connect_pressed(move |gesture, n_press, x, y| {
+        let on_close_btn = show_close && hover_click.get() && gesture.widget()
+            .is_some_and(|w| hit_overlay_close(x, y, w.width(), w.height()));
+        // 按住 Ctrl 时双击不退出置顶，连续开关取色器不会误关窗口
+        if !on_close_btn && gesture.current_event_state().contains(gdk::ModifierType::CONTROL_MASK) {
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            if n_press == 1 { toggle_loupe(); }
+            return;
+        }
+        if on_close_btn || n_press == 2 {
             gesture.set_state(gtk4::EventSequenceState::Claimed);
             win_dbl.close();
             on_exit_dbl();
@@ -358,41 +2368,151 @@ fn create_overlay_window(
     });
     drawing_area.add_controller(double_click);
     
-    // 右键关闭
+    // 右键关闭，Shift+右键保存固定位置
     let right_click = gtk4::GestureClick::builder().button(3).build();
     let win_right = window.clone();
-    right_click.connect_pressed(move |_, _, _, _| {
+    right_click.connect_pressed(move |gesture, _, _, _| {
+        if gesture.current_event_state().contains(gdk::ModifierType::SHIFT_MASK) {
+            show_save_pin_dialog(&win_right, save_pin.clone());
+            return;
+        }
         win_right.close();
         on_exit();
     });
     drawing_area.add_controller(right_click);
     
+    workspace::pin(&window);
     window
 }
 
-fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: WindowMode) {
+// cascade 为多窗口打开时的窗口序号（从 1 开始），只打开一个窗口时为 0
+fn build_ui(app: &Application, source: Source, initial_mode: WindowMode, config: Rc<Config>, cascade: i32) {
+    let kiosk = config.kiosk;
+    let autohide_titlebar = config.autohide_titlebar && !kiosk;
+    TITLEBAR_SPACE.set(if autohide_titlebar { TITLEBAR_STRIP } else { TITLEBAR_HEIGHT });
     let state = Rc::new(RefCell::new(ImageState::default()));
+    let model = ViewerModel::new(state.clone());
     let mouse_pos = Rc::new(Cell::new((0.0f64, 0.0f64)));
     let current_mode = Rc::new(Cell::new(initial_mode));
     let overlay_pos = Rc::new(RefCell::new(OverlayPosition::default()));
     let overlay_window: Rc<RefCell<Option<ApplicationWindow>>> = Rc::new(RefCell::new(None));
+    let tabs = Rc::new(RefCell::new(Tabs::default()));
+    let dir_views = Rc::new(dirconfig::DirectoryViews::new(&config));
+
+    // 目录参数换成目录中的第一张图片，其余图片用方向键浏览；没有图片的目录稍后提示
+    // --recursive 时第一个目录的整棵目录树在后台列出，--files-from 的列表同样在后台读取（tree_feed）
+    let mut empty_dirs: Vec<PathBuf> = Vec::new();
+    let mut tree_feed: Option<TreeFeed> = None;
+    let source = match source {
+        Source::Files(files) => {
+            let mut resolved = Vec::new();
+            for file in files {
+                let dir = Path::new(&file.path);
+                if !dir.is_dir() {
+                    resolved.push(file);
+                    continue;
+                }
+                let sort = dir_views.for_dir(dir).sort.unwrap_or_default();
+                let is_root = config.recursive && tree_feed.is_none();
+                if is_root {
+                    tree_feed = dir.canonicalize().ok().map(|root| TreeFeed::Walk(root, sort));
+                }
+                match siblings::images(dir, sort).into_iter().next() {
+                    Some(first) => resolved.push(InitialFile { path: first.to_string_lossy().to_string(), view: None }),
+                    // 目录树的根本身没有图片时等待后台遍历的结果
+                    None if is_root => {}
+                    None => empty_dirs.push(dir.to_path_buf()),
+                }
+            }
+            Source::Files(resolved)
+        }
+        // 第一张图片由后台读取的列表载入
+        Source::List(list) => {
+            tree_feed = Some(TreeFeed::List(list));
+            Source::Files(Vec::new())
+        }
+        Source::Selection(files) => {
+            tree_feed = Some(TreeFeed::Selection(files));
+            Source::Files(Vec::new())
+        }
+        source => source,
+    };
+    // 选择的文件不是一个目录，位置无从推断，标题栏中显示 [n/m]
+    let numbered_tree = matches!(tree_feed, Some(TreeFeed::Selection(_)));
     
     // 预读图片尺寸
-    let (init_img_w, init_img_h) = if let Some(ref path) = initial_path {
-        if let Ok(texture) = gdk::Texture::from_filename(path) {
-            (texture.width(), texture.height())
-        } else { (800, 600) }
+    // 只读取文件头：自带的解析器不认识的格式交给 gdk-pixbuf 的文件信息查询，同样不解码像素，
+    // 每次启动第一张图片只在 load_image 中解码一次
+    let started = std::time::Instant::now();
+    let first_file = match source {
+        Source::Files(ref files) => files.first(),
+        _ => None,
+    };
+    let (init_img_w, init_img_h) = if let Some(initial) = first_file {
+        let path = Path::new(&initial.path);
+        // 手机照片常带 EXIF 方向，窗口按转正后的宽高创建
+        let size = decode::oriented_dimensions(path).map(|(w, h)| (w as i32, h as i32, "文件头"))
+            .or_else(|| gtk4::gdk_pixbuf::Pixbuf::file_info(path).map(|(_, w, h)| (w, h, "gdk-pixbuf 文件信息")));
+        if config.verbose && let Some((w, h, via)) = size {
+            eprintln!("初始尺寸 {}×{}（{}，用时 {:.1} ms）", w, h, via, started.elapsed().as_secs_f64() * 1000.0);
+        }
+        size.map_or((800, 600), |(w, h, _)| (w, h))
     } else { (800, 600) };
     let (init_w, init_h) = calc_target_size(init_img_w, init_img_h);
 
     // 加载 CSS (GTK4 兼容语法)
     let css = CssProvider::new();
     css.load_from_string(r#"
+        .nav-btn {
+            min-width: 36px;
+            min-height: 36px;
+            margin: 0 8px;
+            border-radius: 18px;
+            border: none;
+            box-shadow: none;
+            color: #ffffff;
+            background-color: rgba(0, 0, 0, 0.35);
+        }
+        .nav-btn:hover {
+            background-color: rgba(0, 0, 0, 0.6);
+        }
+        .nav-preview label {
+            font-size: 11px;
+        }
+        window.shadowed {
+            background: none;
+        }
+        window.shadowed .viewer-frame {
+            border-radius: 8px;
+            background-color: @window_bg_color;
+            box-shadow: 0 2px 8px rgba(0, 0, 0, 0.45), 0 0 0 1px rgba(0, 0, 0, 0.3);
+        }
+        window.shadowed.maximized .viewer-frame,
+        window.shadowed.fullscreen .viewer-frame,
+        window.shadowed.tiled .viewer-frame {
+            border-radius: 0;
+            box-shadow: none;
+        }
         .titlebar { 
             background-color: #323232;
             padding: 0 6px;
             border-bottom: 1px solid #1a1a1a;
         }
+        .titlebar.compact {
+            padding: 0 2px;
+        }
+        .titlebar.compact .titlebar-btn {
+            min-width: 24px;
+            padding: 4px 4px;
+            margin: 2px 0;
+        }
+        .titlebar.compact .info-label {
+            margin: 0 4px;
+            padding: 3px 4px;
+        }
+        .titlebar-strip {
+            background-color: #323232;
+        }
         .titlebar-btn { 
             min-height: 26px; 
             min-width: 32px; 
@@ -412,6 +2532,14 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
         .titlebar-btn:active {
             background-color: #3a3a3a;
         }
+        .titlebar-btn.sticky-rotation, .titlebar-btn:checked {
+            color: #ffffff;
+            background-color: #2f5f9f;
+        }
+        progressbar.job-progress trough, progressbar.job-progress progress {
+            min-height: 2px;
+            min-width: 16px;
+        }
         .close-btn:hover { 
             background-color: #e81123; 
             color: #ffffff; 
@@ -424,6 +2552,29 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
             font-size: 11px; 
             margin: 0 12px;
         }
+        .breadcrumb-dir {
+            color: #707070;
+            min-height: 0;
+            padding: 1px 4px;
+            border-radius: 4px;
+        }
+        .breadcrumb-dir:hover {
+            color: #ffffff;
+            background-color: #4a4a4a;
+        }
+        .breadcrumb-separator {
+            color: #505050;
+            margin: 0 1px;
+        }
+        .breadcrumb-file {
+            color: #909090;
+            margin-left: 4px;
+        }
+        .rename-entry {
+            min-height: 22px;
+            font-size: 11px;
+            margin: 2px 12px;
+        }
         .info-label { 
             color: #909090; 
             font-size: 10px; 
@@ -432,10 +2583,58 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
             background-color: #3a3a3a;
             border-radius: 4px;
         }
-    "#);
-    gtk4::style_context_add_provider_for_display(
-        &gdk::Display::default().unwrap(), &css, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
-    );
+        .tab-strip {
+            background-color: #2a2a2a;
+            padding: 2px 6px;
+            border-bottom: 1px solid #1a1a1a;
+        }
+        .tab-btn {
+            min-height: 20px;
+            padding: 2px 10px;
+            margin: 0 2px;
+            border-radius: 4px;
+            color: #808080;
+            font-size: 11px;
+        }
+        .tab-btn:hover {
+            background-color: #3a3a3a;
+        }
+        .tab-active {
+            background-color: #4a4a4a;
+            color: #ffffff;
+        }
+        .compare-badge {
+            color: #e0e0e0;
+            font-size: 11px;
+            padding: 3px 8px;
+            margin: 8px;
+            background-color: rgba(40, 40, 40, 0.9);
+            border-radius: 4px;
+        }
+        .toast {
+            color: #e0e0e0;
+            font-size: 12px;
+            padding: 6px 14px;
+            margin-bottom: 24px;
+            background-color: rgba(40, 40, 40, 0.9);
+            border-radius: 6px;
+        }
+        .empty-state {
+            color: #909090;
+            font-size: 14px;
+        }
+    "#);
+    // main 启动前已确认能连接显示；运行中显示断开时直接退出
+    let Some(display) = gdk::Display::default() else {
+        eprintln!("没有可用的图形显示");
+        app.quit();
+        return;
+    };
+    gtk4::style_context_add_provider_for_display(&display, &css, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION);
+    let (texture_limit, limit_source) = gpu::max_texture_size(&display, config.max_texture_size);
+    if config.verbose {
+        eprintln!("纹理尺寸上限：{}（{}）", texture_limit, limit_source);
+    }
 
     let drawing_area = DrawingArea::new();
     drawing_area.set_hexpand(true);
@@ -447,120 +2646,112 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
     let cached_rotation: Rc<Cell<i32>> = Rc::new(Cell::new(-1));
     let cs = cached_surface.clone();
     let cr_rot = cached_rotation.clone();
+    // 上一张图片（路径, 纹理），按住 x 时代替当前图片显示以便对比
+    let previous: Rc<RefCell<Option<(String, gdk::Texture)>>> = Rc::new(RefCell::new(None));
+    let comparing = Rc::new(Cell::new(false));
+    let previous_draw = previous.clone();
+    let comparing_draw = comparing.clone();
+    // 触摸滑动翻页的过渡动画
+    let slide: Rc<RefCell<Option<slide::Slide>>> = Rc::new(RefCell::new(None));
+    let slide_draw = slide.clone();
+    // 比例尺，M 键切换
+    let scale_bar = Rc::new(Cell::new(config.scale_bar));
+    let scale_bar_draw = scale_bar.clone();
+    // 幻灯片（S 键或标题栏按钮）；滚动、拖动与旋转时暂停
+    let slideshow = slideshow::Slideshow::new(&config, &drawing_area);
+    let slideshow_draw = slideshow.clone();
+    let scaling_mode = config.scaling;
+    let prescaled = scaling::Prescaled::default();
     
     drawing_area.set_draw_func(move |_, cr, width, height| {
         let state = state_draw.borrow();
-        cr.set_source_rgb(0.12, 0.12, 0.12);
+        match state.view.background_rgba() {
+            Some(bg) => cr.set_source_rgba(bg.red() as f64, bg.green() as f64, bg.blue() as f64, bg.alpha() as f64),
+            None => cr.set_source_rgb(0.12, 0.12, 0.12),
+        }
         cr.paint().ok();
+        let slide_shift = match *slide_draw.borrow() {
+            Some(ref slide) if !slide.is_done() => {
+                slide.draw_outgoing(cr, width, height);
+                slide.incoming_shift(width)
+            }
+            _ => 0.0,
+        };
         
-        if let Some(ref texture) = state.pixbuf {
+        let texture = if comparing_draw.get() {
+            previous_draw.borrow().as_ref().map(|(_, t)| t.clone())
+        } else {
+            state.pixbuf.clone()
+        };
+        if let Some(ref texture) = texture {
             // 只在旋转变化或首次加载时重新生成原始 surface
             let need_update = cached_rotation.get() != state.rotation || cached_surface.borrow().is_none();
-            if need_update {
-                let (tw, th) = (texture.width(), texture.height());
-                if let Ok(surface) = cairo::ImageSurface::create(cairo::Format::ARgb32, tw, th) {
-                    let snapshot = gtk4::Snapshot::new();
-                    texture.snapshot(&snapshot, tw as f64, th as f64);
-                    if let Some(node) = snapshot.to_node() {
-                        if let Ok(ctx) = cairo::Context::new(&surface) {
-                            node.draw(&ctx);
-                        }
-                    }
-                    *cached_surface.borrow_mut() = Some(surface);
-                    cached_rotation.set(state.rotation);
-                }
+            if need_update && let Some(surface) = texture_surface(texture) {
+                *cached_surface.borrow_mut() = Some(surface);
+                cached_rotation.set(state.rotation);
             }
             
             if let Some(ref surface) = *cached_surface.borrow() {
-                let (img_w, img_h) = match state.rotation % 2 {
-                    0 => (state.original_width as f64, state.original_height as f64),
-                    _ => (state.original_height as f64, state.original_width as f64),
-                };
+                let (img_w, img_h) = view::rotated_size((state.original_width as f64, state.original_height as f64), state.rotation);
                 let scaled_w = img_w * state.scale;
                 let scaled_h = img_h * state.scale;
-                let x = (width as f64 - scaled_w) / 2.0 + state.offset_x;
+                let x = (width as f64 - scaled_w) / 2.0 + state.offset_x + slide_shift;
                 let y = (height as f64 - scaled_h) / 2.0 + state.offset_y;
                 
                 cr.save().ok();
                 // 使用快速滤波器提升性能
                 cr.translate(x + scaled_w / 2.0, y + scaled_h / 2.0);
-                cr.rotate(state.rotation as f64 * std::f64::consts::FRAC_PI_2);
+                cr.rotate(state.rotation as f64 * std::f64::consts::FRAC_PI_2 + state.tilt);
                 cr.scale(state.scale, state.scale);
                 cr.translate(-state.original_width as f64 / 2.0, -state.original_height as f64 / 2.0);
-                cr.set_source_surface(surface, 0.0, 0.0).ok();
-                // 使用双线性滤波保持图片质量
-                cr.source().set_filter(cairo::Filter::Bilinear);
+                if let Some((cx, cy, cw, ch)) = state.clip {
+                    cr.rectangle(cx, cy, cw, ch);
+                    cr.clip();
+                }
+                prescaled.set_source(cr, scaling_mode, surface, state.scale);
+                // 默认使用双线性滤波保持图片质量，目录设置可改为 nearest
+                cr.source().set_filter(state.view.filter.unwrap_or_default().cairo());
                 cr.paint().ok();
                 cr.restore().ok();
             }
+            if scale_bar_draw.get() {
+                ruler::draw(cr, height, state.scale);
+            }
         }
+        slideshow_draw.draw_cue(cr, width, height);
     });
 
     // 窗口和标签引用
-    let zoom_label_ref: Rc<RefCell<Option<Label>>> = Rc::new(RefCell::new(None));
     let window_ref: Rc<RefCell<Option<ApplicationWindow>>> = Rc::new(RefCell::new(None));
     let da_ref: Rc<RefCell<Option<DrawingArea>>> = Rc::new(RefCell::new(None));
     
-    let zoom_lbl = zoom_label_ref.clone();
-    let win_scroll = window_ref.clone();
-    let da_scroll_ref = da_ref.clone();
+    // 像素对齐缩放：滚轮只在固定档位间切换，避免小数倍缩放造成的模糊
+    let pixel_zoom = Rc::new(Cell::new(config.pixel_zoom));
+    // 旋转应用到后续图片：开启时记下的方向（0..4），之后载入的图片按此方向显示，直到关闭
+    let sticky_rotation: Rc<Cell<Option<i32>>> = Rc::new(Cell::new(None));
     
     // 鼠标滚轮缩放
     let scroll_ctrl = gtk4::EventControllerScroll::new(gtk4::EventControllerScrollFlags::VERTICAL);
-    let state_scroll = state.clone();
+    let model_scroll = model.clone();
     let da_scroll = drawing_area.clone();
     let mouse_scroll = mouse_pos.clone();
-    scroll_ctrl.connect_scroll(move |_, _, dy| {
-        let mut state = state_scroll.borrow_mut();
-        if state.pixbuf.is_none() { return glib::Propagation::Proceed; }
-        
-        let (mx, my) = mouse_scroll.get();
-        let (width, height) = (da_scroll.width() as f64, da_scroll.height() as f64);
-        let old_scale = state.scale;
-        let factor = if dy < 0.0 { 1.1 } else { 1.0 / 1.1 };
-        state.scale = (state.scale * factor).clamp(0.1, 50.0);
-        
-        let (img_w, img_h) = match state.rotation % 2 {
-            0 => (state.original_width as f64, state.original_height as f64),
-            _ => (state.original_height as f64, state.original_width as f64),
-        };
-        let scaled_w = (img_w * state.scale) as i32;
-        let scaled_h = (img_h * state.scale) as i32;
-        
-        // 检查是否触发屏幕边缘限制
-        let at_limit = is_at_screen_limit(scaled_w, scaled_h);
-        
-        // 以鼠标位置为中心缩放（仅当图片大于窗口时）
-        if at_limit {
-            let (cx, cy) = (width / 2.0 + state.offset_x, height / 2.0 + state.offset_y);
-            let ratio = state.scale / old_scale;
-            state.offset_x += (mx - cx) * (1.0 - ratio);
-            state.offset_y += (my - cy) * (1.0 - ratio);
-        } else {
-            // 图片小于屏幕，居中显示
-            state.offset_x = 0.0;
-            state.offset_y = 0.0;
-        }
-        
-        // 更新缩放率标签
-        if let Some(ref lbl) = *zoom_lbl.borrow() {
-            lbl.set_text(&format!("{:.0}%", state.scale * 100.0));
-        }
-        
-        // 调整窗口大小（仅当图片未触发屏幕限制时才强制调整）
-        if let (Some(win), Some(da)) = (&*win_scroll.borrow(), &*da_scroll_ref.borrow()) {
-            if !at_limit {
-                // 图片小于屏幕，强制窗口收缩到图片大小
-                update_window_size(win, da, scaled_w, scaled_h);
-            } else {
-                // 图片大于屏幕，只更新内容大小，不强制调整窗口
-                let (target_w, target_h) = calc_target_size(scaled_w, scaled_h);
-                da.set_content_width(target_w);
-                da.set_content_height(target_h - TITLEBAR_HEIGHT);
+    let pixel_zoom_scroll = pixel_zoom.clone();
+    let wheel = wheel::WheelZoom::new(&config);
+    let slideshow_scroll = slideshow.clone();
+    scroll_ctrl.connect_scroll(move |ctrl, _, dy| {
+        if !model_scroll.has_image() { return glib::Propagation::Proceed; }
+        slideshow_scroll.pause();
+        let viewport = (da_scroll.width() as f64, da_scroll.height() as f64);
+        let mut steps = wheel.steps(ctrl, dy);
+        if pixel_zoom_scroll.get() {
+            // 档位之间只按整格切换，触摸板的小增量先累积
+            match wheel.whole_step(steps) {
+                Some(zoom_in) => steps = if zoom_in { 1.0 } else { -1.0 },
+                None => return glib::Propagation::Stop,
             }
         }
-        
-        da_scroll.queue_draw();
+        if steps == 0.0 { return glib::Propagation::Stop; }
+        model_scroll.zoom(steps, wheel.factor(), pixel_zoom_scroll.get(), mouse_scroll.get(), viewport);
         glib::Propagation::Stop
     });
     drawing_area.add_controller(scroll_ctrl);
@@ -571,120 +2762,271 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
     motion_ctrl.connect_motion(move |_, x, y| { mouse_motion.set((x, y)); });
     drawing_area.add_controller(motion_ctrl);
 
-    // 拖拽移动图片
+    // 拖拽移动图片；触摸屏上图片未放大时不平移，留给滑动翻页
+    // 偏移限制在图片边缘以内；开启动画时可以橡皮筋式地拖过边缘，松开后回弹
+    // 位移越过系统拖动阈值后才开始平移，缓慢的两次单击不会带动图片
     let drag_ctrl = gtk4::GestureDrag::builder().button(1).build();
-    let state_drag = state.clone();
-    let da_drag = drawing_area.clone();
+    let pan_gate = Rc::new(pointer::DragGate::default());
     let drag_start = Rc::new(Cell::new((0.0f64, 0.0f64)));
-    let drag_start_clone = drag_start.clone();
-    drag_ctrl.connect_drag_begin(clone!(#[strong] state_drag, move |_, _, _| {
-        let s = state_drag.borrow();
-        drag_start_clone.set((s.offset_x, s.offset_y));
+    // 本次拖动的位移，滑动翻页据此忽略很短的误触
+    let drag_offset = Rc::new(Cell::new((0.0f64, 0.0f64)));
+    // 回弹动画的编号，开始新的拖动时递增，使进行中的回弹停止
+    let settle_id = Rc::new(Cell::new(0u64));
+    let animations = config.animations;
+    drag_ctrl.connect_drag_begin(clone!(#[strong] model, #[strong] drag_start, #[strong] drag_offset, #[strong] settle_id, #[strong] pan_gate, move |_, _, _| {
+        pan_gate.reset();
+        settle_id.set(settle_id.get() + 1);
+        drag_start.set(model.offset());
+        drag_offset.set((0.0, 0.0));
     }));
-    drag_ctrl.connect_drag_update(clone!(#[strong] state_drag, #[strong] da_drag, #[strong] drag_start,
-        move |_, dx, dy| {
-            let mut s = state_drag.borrow_mut();
-            let (sx, sy) = drag_start.get();
-            s.offset_x = sx + dx;
-            s.offset_y = sy + dy;
-            da_drag.queue_draw();
+    drag_ctrl.connect_drag_update(clone!(#[strong] model, #[strong] drag_start, #[strong] drag_offset, #[strong] slideshow, move |gesture, dx, dy| {
+        drag_offset.set((dx, dy));
+        let touch = gesture.device().is_some_and(|d| d.source() == gdk::InputSource::Touchscreen);
+        if touch && !model.read(is_zoomed_in) { return; }
+        // 越过阈值时才算拖动并暂停幻灯片，单击不会打断
+        if !pan_gate.is_dragging() {
+            if !pan_gate.passed_system(dx, dy) { return; }
+            slideshow.pause();
         }
-    ));
+        let Some(da) = gesture.widget() else { return };
+        let (limit_x, limit_y) = model.pan_limits((da.width() as f64, da.height() as f64));
+        let (sx, sy) = drag_start.get();
+        let (x, y) = (sx + dx, sy + dy);
+        let (x, y) = elastic::follow((x, y), (limit_x, limit_y), config::animations_enabled(animations));
+        model.pan_to(x, y);
+    }));
+    drag_ctrl.connect_drag_end(clone!(#[strong] model, move |gesture, _, _| {
+        let Some(da) = gesture.widget() else { return };
+        let current = model.offset();
+        let target = elastic::clamp(current, model.pan_limits((da.width() as f64, da.height() as f64)));
+        if current == target { return; }
+        let settle = elastic::Settle::new(current, target, config::animations_enabled(animations));
+        if let (target, true) = settle.position() {
+            model.pan_to(target.0, target.1);
+            return;
+        }
+        let (model, settle_id) = (model.clone(), settle_id.clone());
+        let id = settle_id.get();
+        let last = Cell::new(current);
+        da.add_tick_callback(move |_, _| {
+            // 又开始拖动，或缩放、切换图片改变了偏移时停止
+            if settle_id.get() != id || model.offset() != last.get() { return glib::ControlFlow::Break; }
+            let ((x, y), done) = settle.position();
+            last.set((x, y));
+            model.pan_to(x, y);
+            if done { glib::ControlFlow::Break } else { glib::ControlFlow::Continue }
+        });
+    }));
     drawing_area.add_controller(drag_ctrl);
 
-    // 双击进入置顶模式
-    let double_click_ctrl = gtk4::GestureClick::builder().button(1).build();
-    let state_dblclick = state.clone();
-    let mode_dblclick = current_mode.clone();
-    let overlay_pos_dblclick = overlay_pos.clone();
-    let overlay_win_dblclick = overlay_window.clone();
-    let window_ref_dblclick = window_ref.clone();
-    let da_ref_dblclick = da_ref.clone();
-    let app_dblclick = app.clone();
-    
-    double_click_ctrl.connect_pressed(move |gesture, n_press, _, _| {
-        if n_press == 2 && state_dblclick.borrow().pixbuf.is_some() {
-            gesture.set_state(gtk4::EventSequenceState::Claimed);
-            mode_dblclick.set(WindowMode::Overlay);
+    // 进入置顶模式：隐藏普通窗口并创建置顶窗口
+    // 退出时按进入方式决定去向：双击进入则恢复普通窗口，--overlay 启动则退出程序；
+    // 普通窗口已被关闭时同样退出
+    let normal_closed = Rc::new(Cell::new(false));
+    let hold_guard: Rc<RefCell<Option<gio::ApplicationHoldGuard>>> = Rc::new(RefCell::new(None));
+    // 双击进入置顶前普通窗口的视图：置顶窗口与普通窗口共用 ImageState，置顶中的缩放会改动普通窗口的缩放
+    let saved_view: Rc<Cell<Option<model::SavedView>>> = Rc::new(Cell::new(None));
+    // 拖放的处理在载入函数之后才定义，置顶窗口创建时从这里取用
+    let drop_files: Rc<RefCell<Option<DropFn>>> = Rc::new(RefCell::new(None));
+    let enter_overlay = {
+        let app = app.clone();
+        let config = config.clone();
+        let state = state.clone();
+        let model = model.clone();
+        let saved_view = saved_view.clone();
+        let mode = current_mode.clone();
+        let overlay_pos = overlay_pos.clone();
+        let overlay_window = overlay_window.clone();
+        let window_ref = window_ref.clone();
+        let da_ref = da_ref.clone();
+        let normal_closed = normal_closed.clone();
+        let hold_guard = hold_guard.clone();
+        let tabs = tabs.clone();
+        let drop_files = drop_files.clone();
+        Rc::new(move |entry: OverlayEntry| {
+            debug_assert!(overlay_window.borrow().is_none(), "置顶窗口已存在");
+            mode.set(WindowMode::Overlay);
+            if let Some(ref win) = *window_ref.borrow() {
+                win.set_visible(false);
+            }
+            
+            let mode_exit = mode.clone();
+            let model_exit = model.clone();
+            let saved_exit = saved_view.clone();
+            let exit_zoom = config.overlay_exit_zoom;
+            let overlay_win_exit = overlay_window.clone();
+            let win_ref_exit = window_ref.clone();
+            let da_ref_exit = da_ref.clone();
+            let normal_closed_exit = normal_closed.clone();
+            let hold_guard_exit = hold_guard.clone();
+            let overlay = create_overlay_window(
+                &app,
+                state.clone(),
+                model.clone(),
+                overlay_pos.clone(),
+                &config,
+                tabs.borrow().active_path().map(str::to_string),
+                move || {
+                    debug_assert!(overlay_win_exit.borrow().is_some(), "退出时置顶窗口记录已丢失");
+                    *overlay_win_exit.borrow_mut() = None;
+                    mode_exit.set(WindowMode::Normal);
+                    let saved = saved_exit.take();
+                    
+                    let restore = entry == OverlayEntry::DoubleClick && !normal_closed_exit.get();
+                    debug_assert!(normal_closed_exit.get() == win_ref_exit.borrow().is_none());
+                    if restore {
+                        // 按配置恢复进入前的视图或沿用置顶时的缩放（图片居中），缩放标签等随之刷新
+                        model_exit.leave_overlay(saved.filter(|_| exit_zoom == config::OverlayExitZoom::Restore));
+                        // 显示普通窗口并触发重绘
+                        if let Some(ref win) = *win_ref_exit.borrow() {
+                            win.set_visible(true);
+                            win.present();
+                            if let Some(ref da) = *da_ref_exit.borrow() {
+                                da.queue_draw();
+                            }
+                        }
+                    } else if let Some(win) = win_ref_exit.borrow_mut().take() {
+                        // 隐藏的普通窗口随置顶窗口一起销毁，应用随之退出
+                        win.destroy();
+                    }
+                    // 释放保活引用，没有其他窗口时应用退出
+                    hold_guard_exit.borrow_mut().take();
+                    recovery::changed();
+                },
+            );
+            if let Some(handle) = drop_files.borrow().clone() && let Some(da) = overlay.child() {
+                da.add_controller(file_drop_target(handle));
+            }
+            overlay.present();
+            *overlay_window.borrow_mut() = Some(overlay);
+            recovery::changed();
+        })
+    };
+
+    // 双击进入置顶模式；target 为置顶窗口所在的显示器，None 时由合成器决定（通常是指针所在的显示器）
+    let pin_on = {
+        let model = model.clone();
+        let overlay_pos = overlay_pos.clone();
+        let window_ref = window_ref.clone();
+        let da_ref = da_ref.clone();
+        let enter_overlay = enter_overlay.clone();
+        let saved_view = saved_view.clone();
+        let overlay_placement = config.placement;
+        Rc::new(move |target: Option<gdk::Monitor>, click_x: f64, click_y: f64| {
+            if !model.has_image() { return; }
+            // 在角落摆放缩小图片之前记下普通窗口的视图
+            saved_view.set(Some(model.save_view()));
             
             // 计算图片在屏幕上的位置
             // 使用双击点作为参考：双击点相对于图片的位置在切换后应保持不变
-            if let Some(ref da) = *da_ref_dblclick.borrow() {
-                if let Some(ref win) = *window_ref_dblclick.borrow() {
-                    let s = state_dblclick.borrow();
-                    let (scaled_w, scaled_h) = get_scaled_size(&s);
-                    let da_w = da.width() as f64;
-                    let da_h = da.height() as f64;
-                    
-                    // 图片在 drawing_area 中的位置
-                    let img_x_in_da = (da_w - scaled_w as f64) / 2.0 + s.offset_x;
-                    let img_y_in_da = (da_h - scaled_h as f64) / 2.0 + s.offset_y;
-                    
-                    // drawing_area 在窗口内的 y 偏移 = 标题栏高度
-                    let da_y_in_win = TITLEBAR_HEIGHT as f64;
-                    
-                    // 计算 overlay 的 margin，使图片在屏幕上位置不变
-                    // Wayland 下无法获取窗口绝对位置，假设窗口大致居中
-                    let (screen_w, screen_h) = get_screen_size();
+            if let Some(ref da) = *da_ref.borrow()
+                && let Some(ref win) = *window_ref.borrow()
+            {
+                let ((scaled_w, scaled_h), (offset_x, offset_y)) =
+                    model.read(|s| (get_scaled_size(s), (s.offset_x, s.offset_y)));
+                let da_w = da.width() as f64;
+                let da_h = da.height() as f64;
+                
+                // 图片在 drawing_area 中的位置
+                let img_x_in_da = (da_w - scaled_w as f64) / 2.0 + offset_x;
+                let img_y_in_da = (da_h - scaled_h as f64) / 2.0 + offset_y;
+                
+                // drawing_area 在窗口内的偏移 = 阴影边距（y 方向另加标题栏高度）
+                let inset = shadow_inset(win);
+                let da_x_in_win = inset as f64;
+                let da_y_in_win = (inset + titlebar_space()) as f64;
+                
+                // 置顶到普通窗口所在的显示器之外时，双击点不在目标屏幕上，改以目标屏幕的中心为参考
+                let current = win.surface().and_then(|s| s.display().monitor_at_surface(&s));
+                let elsewhere = target.as_ref().is_some_and(|t| current.as_ref() != Some(t));
+                let screen = match &target {
+                    Some(m) => Some((m.geometry().width(), m.geometry().height())).filter(|&(w, h)| w > 0 && h > 0),
+                    None => get_screen_size(),
+                };
+                
+                // 计算 overlay 的 margin，使图片在屏幕上位置不变
+                // Wayland 下无法获取窗口绝对位置，假设窗口大致居中；屏幕尺寸未知时沿用上次的位置
+                if let Some((screen_w, screen_h)) = screen {
                     let win_w = win.width();
                     let win_h = win.height();
-                    
+                
                     // 假设窗口居中，计算图片应该在的屏幕位置
                     let approx_win_x = (screen_w - win_w) / 2;
                     let approx_win_y = (screen_h - win_h) / 2;
-                    let margin_left = approx_win_x + (img_x_in_da as i32);
-                    let margin_top = approx_win_y + (da_y_in_win as i32) + (img_y_in_da as i32);
-                    
+                    let mut margin_left = approx_win_x + (da_x_in_win as i32) + (img_x_in_da as i32);
+                    let mut margin_top = approx_win_y + (da_y_in_win as i32) + (img_y_in_da as i32);
+                
+                    // 默认放到角落，并限制为屏幕的一小部分，避免挡住正在操作的窗口：
+                    // X11 下选遮挡最上层其他窗口最少的角落；Wayland 下无法查询其他窗口的位置，只以指针为参考
+                    if overlay_placement == placement::Placement::Corner {
+                        let avoid = win.surface().filter(|s| above::is_x11(&s.display())).and_then(|s| {
+                            let origin = target.as_ref().or(current.as_ref()).map(|m| m.geometry())?;
+                            let r = xwindow::topmost_other_window(&s)?;
+                            Some(placement::Rect::new(r.x - origin.x(), r.y - origin.y(), r.width, r.height))
+                        });
+                        let size = model.update(|s| {
+                            s.scale *= placement::fit_factor(get_scaled_size(s), (screen_w, screen_h));
+                            (get_scaled_size(s), Effects::NONE)
+                        });
+                        let pointer = if elsewhere {
+                            (screen_w as f64 / 2.0, screen_h as f64 / 2.0)
+                        } else {
+                            (approx_win_x as f64 + da_x_in_win + click_x, approx_win_y as f64 + da_y_in_win + click_y)
+                        };
+                        (margin_left, margin_top) = placement::least_covered_corner((screen_w, screen_h), pointer, size, avoid);
+                    }
+                
                     // 更新 overlay 位置
                     {
-                        let mut pos = overlay_pos_dblclick.borrow_mut();
+                        let mut pos = overlay_pos.borrow_mut();
                         pos.margin_left = margin_left.max(0);
                         pos.margin_top = margin_top.max(0);
                     }
-                    drop(s);
                 }
             }
             
-            // 隐藏普通窗口
-            if let Some(ref win) = *window_ref_dblclick.borrow() {
-                win.set_visible(false);
-            }
-            
-            // 创建置顶窗口
-            let mode_exit = mode_dblclick.clone();
-            let win_ref_exit = window_ref_dblclick.clone();
-            let overlay_win_exit = overlay_win_dblclick.clone();
-            let state_exit = state_dblclick.clone();
-            let da_ref_exit = da_ref_dblclick.clone();
-            
-            let overlay = create_overlay_window(
-                &app_dblclick,
-                state_dblclick.clone(),
-                overlay_pos_dblclick.clone(),
-                move || {
-                    mode_exit.set(WindowMode::Normal);
-                    
-                    // 退出时重置 offset，让普通窗口中图片居中
-                    {
-                        let mut s = state_exit.borrow_mut();
-                        s.offset_x = 0.0;
-                        s.offset_y = 0.0;
-                    }
-                    
-                    // 显示普通窗口
-                    if let Some(ref win) = *win_ref_exit.borrow() {
-                        win.set_visible(true);
-                        win.present();
-                        // 触发重绘
-                        if let Some(ref da) = *da_ref_exit.borrow() {
-                            da.queue_draw();
-                        }
+            overlay_pos.borrow_mut().monitor = target.and_then(|m| m.connector()).map(|c| c.to_string());
+            enter_overlay(OverlayEntry::DoubleClick);
+        })
+    };
+    
+    // 置顶用的显示器：配置中的 overlay-monitor，选择后更新并写回配置
+    let overlay_monitor = Rc::new(RefCell::new(config.overlay_monitor.clone()));
+    // 弹出显示器选择，选中后记为默认并置顶
+    let choose_and_pin = {
+        let pin_on = pin_on.clone();
+        let overlay_monitor = overlay_monitor.clone();
+        move |anchor: &DrawingArea, x: f64, y: f64| {
+            let (pin_on, overlay_monitor) = (pin_on.clone(), overlay_monitor.clone());
+            show_monitor_chooser(anchor, (x, y), move |target| {
+                let value = target.as_ref().and_then(|m| m.connector()).map_or_else(|| "pointer".to_string(), |c| c.to_string());
+                if let Err(e) = config::Config::store("overlay-monitor", &value) {
+                    eprintln!("无法保存置顶显示器: {}", e);
+                }
+                *overlay_monitor.borrow_mut() = Some(value);
+                pin_on(target, x, y);
+            });
+        }
+    };
+    
+    let double_click_ctrl = gtk4::GestureClick::builder().button(1).build();
+    let model_dblclick = model.clone();
+    let choose_dblclick = choose_and_pin.clone();
+    double_click_ctrl.connect_pressed(move |gesture, n_press, click_x, click_y| {
+        if n_press == 2 && !kiosk && model_dblclick.has_image() {
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            // 设置的显示器已拔下时与未设置相同：只有一台显示器时直接置顶，否则询问
+            let sticky = overlay_monitor.borrow().clone();
+            match sticky.as_deref() {
+                Some("pointer") => pin_on(None, click_x, click_y),
+                Some(connector) if let Some(monitor) = monitor_by_connector(connector) => pin_on(Some(monitor), click_x, click_y),
+                _ if monitor_list().len() > 1 => {
+                    if let Some(da) = gesture.widget().and_downcast::<DrawingArea>() {
+                        choose_dblclick(&da, click_x, click_y);
                     }
-                    *overlay_win_exit.borrow_mut() = None;
-                },
-            );
-            overlay.present();
-            *overlay_win_dblclick.borrow_mut() = Some(overlay);
+                }
+                _ => pin_on(None, click_x, click_y),
+            }
         }
     });
     drawing_area.add_controller(double_click_ctrl);
@@ -693,58 +3035,342 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
     let titlebar = Box::new(Orientation::Horizontal, 2);
     titlebar.add_css_class("titlebar");
     
-    let open_btn = Button::builder().icon_name("document-open-symbolic").tooltip_text("打开").build();
+    let open_btn = Button::builder().icon_name("document-open-symbolic").tooltip_text("打开（右键：最近打开的文件）").build();
     open_btn.add_css_class("titlebar-btn");
     open_btn.add_css_class("flat");
     
-    let reset_btn = Button::builder().icon_name("zoom-fit-best-symbolic").tooltip_text("恢复").build();
+    let reset_btn = Button::builder().icon_name("zoom-fit-best-symbolic").tooltip_text("恢复（右键：在适应窗口与 100% 之间切换；滚轮：适应窗口、100%、填满）").build();
     reset_btn.add_css_class("titlebar-btn");
     reset_btn.add_css_class("flat");
     
-    let rotate_btn = Button::builder().icon_name("object-rotate-right-symbolic").tooltip_text("旋转").build();
+    let rotate_btn = Button::builder().icon_name("object-rotate-right-symbolic").tooltip_text(ROTATE_TOOLTIP).build();
     rotate_btn.add_css_class("titlebar-btn");
     rotate_btn.add_css_class("flat");
     
-    let copy_btn = Button::builder().icon_name("edit-copy-symbolic").tooltip_text("复制").build();
+    let copy_btn = Button::builder().icon_name("edit-copy-symbolic").tooltip_text("复制（右键：更多复制方式）").build();
     copy_btn.add_css_class("titlebar-btn");
     copy_btn.add_css_class("flat");
     
+    let paste_btn = Button::builder().icon_name("edit-paste-symbolic").tooltip_text("粘贴剪贴板中的图片 (Ctrl+V)").build();
+    paste_btn.add_css_class("titlebar-btn");
+    paste_btn.add_css_class("flat");
+    
+    // 后台任务（导出、文字识别、完整解码等）的进度，没有任务时隐藏
+    let jobs = jobs::Jobs::default();
+    let jobs_btn = jobs.indicator();
+    
+    let slideshow_btn = gtk4::ToggleButton::builder().icon_name("media-playback-start-symbolic").tooltip_text("幻灯片 (S)").build();
+    slideshow_btn.add_css_class("titlebar-btn");
+    slideshow_btn.add_css_class("flat");
+    
     let close_btn = Button::builder().icon_name("window-close-symbolic").tooltip_text("关闭").build();
     close_btn.add_css_class("titlebar-btn");
     close_btn.add_css_class("close-btn");
     close_btn.add_css_class("flat");
     
-    let path_label = Label::new(None);
-    path_label.add_css_class("path-label");
-    path_label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
-    path_label.set_hexpand(true);
-    path_label.set_halign(gtk4::Align::Start);
+    // 路径导航：点击目录段在该目录中打开文件选择对话框
+    let path_crumbs = breadcrumb::Breadcrumb::new();
+    
+    // F2 重命名时路径位置换成输入框
+    let rename_entry = gtk4::Entry::builder().hexpand(true).visible(false).build();
+    rename_entry.add_css_class("rename-entry");
+    let path_box = Box::new(Orientation::Horizontal, 0);
+    path_box.append(path_crumbs.widget());
+    path_box.append(&rename_entry);
     
     let drag_area = gtk4::WindowHandle::new();
     drag_area.set_hexpand(true);
-    drag_area.set_child(Some(&path_label));
+    drag_area.set_child(Some(&path_box));
     
+    // 标题栏只显示缩放率，分辨率、文件大小等状态在点击后弹出的状态框中
+    // 紧凑布局下缩放率换成图标，改在状态框中显示
     let zoom_label = Label::new(Some("100%"));
     zoom_label.add_css_class("info-label");
-    zoom_label.set_tooltip_text(Some("缩放率"));
-    *zoom_label_ref.borrow_mut() = Some(zoom_label.clone());
+    zoom_label.set_tooltip_text(Some("缩放率（点击查看状态）"));
+    let info_icon = gtk4::Image::from_icon_name("view-more-symbolic");
+    info_icon.add_css_class("info-label");
+    info_icon.set_tooltip_text(Some("状态"));
+    info_icon.set_visible(false);
+    let info_anchor = Box::new(Orientation::Horizontal, 0);
+    info_anchor.append(&zoom_label);
+    info_anchor.append(&info_icon);
+    let compact = Rc::new(Cell::new(false));
     
-    let res_label = Label::new(None);
-    res_label.add_css_class("info-label");
-    res_label.set_tooltip_text(Some("分辨率"));
+    // 状态弹出框：上方为属性表（各行在下文定义），下方为操作与详情
+    let info_grid = gtk4::Grid::builder().row_spacing(6).column_spacing(12).build();
+    let status = status::StatusGrid::new();
+    info_grid.attach(status.widget(), 0, 0, 2, 1);
+    let pixel_zoom_btn = Button::with_label("切换像素对齐缩放");
+    info_grid.attach(&pixel_zoom_btn, 0, 1, 2, 1);
+    let print_size_btn = Button::with_label("按打印尺寸显示");
+    print_size_btn.set_sensitive(false);
+    info_grid.attach(&print_size_btn, 0, 2, 2, 1);
+    // 多帧/多页图片的操作：导出全部、导出或复制正在显示的帧、设为封面帧
+    let frame_actions = Box::new(Orientation::Vertical, 6);
+    frame_actions.set_visible(false);
+    let extract_btn = Button::with_label("导出所有帧/页…");
+    let export_frame_btn = Button::with_label("导出当前帧…");
+    let copy_frame_btn = Button::with_label("复制当前帧");
+    let poster_btn = Button::with_label("设为封面帧");
+    poster_btn.set_tooltip_text(Some("以后打开这张图片和生成缩略图时显示这一帧"));
+    let frame_row = Box::new(Orientation::Horizontal, 6);
+    frame_row.set_homogeneous(true);
+    frame_row.append(&export_frame_btn);
+    frame_row.append(&copy_frame_btn);
+    frame_row.append(&poster_btn);
+    frame_actions.append(&extract_btn);
+    frame_actions.append(&frame_row);
+    info_grid.attach(&frame_actions, 0, 3, 2, 1);
+    // 诊断：解码路径、耗时等
+    let diag_expander = gtk4::Expander::new(Some("诊断"));
+    let diag_label = Label::builder().halign(gtk4::Align::Start).selectable(true).build();
+    diag_expander.set_child(Some(&diag_label));
+    info_grid.attach(&diag_expander, 0, 4, 2, 1);
+    // 技术细节：文件中的块/段与元数据，展开时才读取文件
+    let tech_expander = gtk4::Expander::new(Some("技术细节"));
+    let tech_label = Label::builder().halign(gtk4::Align::Start).valign(gtk4::Align::Start).selectable(true).build();
+    tech_label.add_css_class("monospace");
+    let tech_scroller = gtk4::ScrolledWindow::builder()
+        .child(&tech_label)
+        .propagate_natural_width(true)
+        .propagate_natural_height(true)
+        .max_content_height(320)
+        .max_content_width(640)
+        .build();
+    let tech_copy_btn = Button::with_label("复制为文本");
+    tech_copy_btn.set_halign(gtk4::Align::Start);
+    let tech_box = Box::new(Orientation::Vertical, 6);
+    tech_box.append(&tech_scroller);
+    tech_box.append(&tech_copy_btn);
+    tech_expander.set_child(Some(&tech_box));
+    info_grid.attach(&tech_expander, 0, 5, 2, 1);
+    let default_app_btn = Button::with_label("设为默认图片查看器…");
+    default_app_btn.set_visible(!kiosk);
+    info_grid.attach(&default_app_btn, 0, 6, 2, 1);
+    let info_popover = gtk4::Popover::builder().child(&info_grid).build();
+    info_popover.set_parent(&info_anchor);
+    info_anchor.connect_destroy(clone!(#[weak] info_popover, move |_| info_popover.unparent()));
+    let fill_details = {
+        let (tabs, label) = (tabs.clone(), tech_label.clone());
+        Rc::new(move || {
+            let path = tabs.borrow().active_path().map(str::to_string);
+            let text = match path.as_deref().map(Path::new).filter(|p| p.is_file()) {
+                Some(file) => details::read(file).map(|d| d.to_text()).unwrap_or_else(|e| format!("无法读取：{}", e)),
+                None => "没有可读取的文件".to_string(),
+            };
+            label.set_text(&text);
+        })
+    };
+    tech_expander.connect_expanded_notify(clone!(#[strong] fill_details, move |e| {
+        if e.is_expanded() { fill_details(); }
+    }));
+    info_popover.connect_show(clone!(#[weak] tech_expander, move |_| {
+        if tech_expander.is_expanded() { fill_details(); }
+    }));
+    tech_copy_btn.connect_clicked(clone!(#[weak] tech_label, move |btn| btn.clipboard().set_text(&tech_label.text())));
+    let info_click = gtk4::GestureClick::builder().button(1).build();
+    info_click.connect_released(clone!(#[weak] info_popover, move |_, _, _, _| info_popover.popup()));
+    info_anchor.add_controller(info_click);
     
     titlebar.append(&open_btn);
     titlebar.append(&reset_btn);
     titlebar.append(&rotate_btn);
     titlebar.append(&copy_btn);
+    titlebar.append(&paste_btn);
+    titlebar.append(&slideshow_btn);
     titlebar.append(&drag_area);
-    titlebar.append(&res_label);
-    titlebar.append(&zoom_label);
+    titlebar.append(&info_anchor);
+    titlebar.append(&jobs_btn);
     titlebar.append(&close_btn);
 
+    // 窗口变窄时切换紧凑布局；窗口的最小宽度取紧凑布局实际需要的宽度
+    let set_compact = {
+        let (titlebar, zoom_label, info_icon, compact) = (titlebar.clone(), zoom_label.clone(), info_icon.clone(), compact.clone());
+        move |on: bool| {
+            compact.set(on);
+            if on { titlebar.add_css_class("compact") } else { titlebar.remove_css_class("compact") }
+            zoom_label.set_visible(!on);
+            info_icon.set_visible(on);
+        }
+    };
+    set_compact(true);
+    let (chrome_min, _, _, _) = titlebar.measure(Orientation::Horizontal, -1);
+    MIN_WIN_WIDTH.set(chrome_min.clamp(MIN_COMPACT_WIDTH, COMPACT_TITLEBAR_BELOW));
+    set_compact(false);
+    // 在分配大小的过程中不能改动样式，留到空闲时切换
+    drawing_area.connect_resize(clone!(#[strong] compact, move |_, width, _| {
+        let on = width < COMPACT_TITLEBAR_BELOW;
+        if on == compact.get() { return; }
+        let set_compact = set_compact.clone();
+        glib::idle_add_local_once(move || set_compact(on));
+    }));
+
+    // 标签栏：多于一个标签时显示
+    let tab_strip = Box::new(Orientation::Horizontal, 0);
+    tab_strip.add_css_class("tab-strip");
+    tab_strip.set_visible(false);
+
+    // 图片区域叠加层：用于显示提示信息
+    let view_overlay = gtk4::Overlay::new();
+    view_overlay.set_child(Some(&drawing_area));
+    let toast_label = Label::new(None);
+    toast_label.add_css_class("toast");
+    toast_label.set_halign(gtk4::Align::Center);
+    toast_label.set_valign(gtk4::Align::End);
+    toast_label.set_can_target(false);
+    toast_label.set_visible(false);
+    view_overlay.add_overlay(&toast_label);
+    let compare_badge = Label::new(None);
+    compare_badge.add_css_class("compare-badge");
+    compare_badge.set_halign(gtk4::Align::Start);
+    compare_badge.set_valign(gtk4::Align::Start);
+    compare_badge.set_can_target(false);
+    compare_badge.set_visible(false);
+    view_overlay.add_overlay(&compare_badge);
+    // 打开的目录中没有图片时的占位提示
+    let empty_label = Label::new(None);
+    empty_label.add_css_class("empty-state");
+    empty_label.set_halign(gtk4::Align::Center);
+    empty_label.set_valign(gtk4::Align::Center);
+    empty_label.set_wrap(true);
+    empty_label.set_justify(gtk4::Justification::Center);
+    empty_label.set_can_target(false);
+    empty_label.set_visible(false);
+    view_overlay.add_overlay(&empty_label);
+
+    // 图片两侧的上一张/下一张按钮，只在该方向有图片时显示
+    let nav_btn = |icon: &str, halign: gtk4::Align| {
+        let btn = Button::builder().icon_name(icon).halign(halign).valign(gtk4::Align::Center).visible(false).build();
+        btn.add_css_class("nav-btn");
+        btn.set_focusable(false);
+        view_overlay.add_overlay(&btn);
+        btn
+    };
+    let prev_btn = nav_btn("go-previous-symbolic", gtk4::Align::Start);
+    let next_btn = nav_btn("go-next-symbolic", gtk4::Align::End);
+
+    // 删除后的撤销提示，带撤销按钮
+    let undo_toast = Box::new(Orientation::Horizontal, 8);
+    undo_toast.add_css_class("toast");
+    undo_toast.set_halign(gtk4::Align::Center);
+    undo_toast.set_valign(gtk4::Align::End);
+    undo_toast.set_visible(false);
+    let undo_label = Label::new(None);
+    let undo_btn = Button::with_label("撤销");
+    undo_btn.add_css_class("flat");
+    undo_toast.append(&undo_label);
+    undo_toast.append(&undo_btn);
+    view_overlay.add_overlay(&undo_toast);
+
+    // 导出完成后的提示，带打开按钮
+    let reveal_toast = Box::new(Orientation::Horizontal, 8);
+    reveal_toast.add_css_class("toast");
+    reveal_toast.set_halign(gtk4::Align::Center);
+    reveal_toast.set_valign(gtk4::Align::End);
+    reveal_toast.set_visible(false);
+    let reveal_label = Label::new(None);
+    let reveal_btn = Button::with_label("打开");
+    reveal_btn.add_css_class("flat");
+    reveal_toast.append(&reveal_label);
+    reveal_toast.append(&reveal_btn);
+    view_overlay.add_overlay(&reveal_toast);
+
+    let toast_timer: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let show_toast: ToastFn = Rc::new(clone!(#[strong] toast_label, move |text: &str| {
+        toast_label.set_text(text);
+        toast_label.set_visible(true);
+        if let Some(id) = toast_timer.borrow_mut().take() { id.remove(); }
+        let timer = toast_timer.clone();
+        let id = glib::timeout_add_local_once(Duration::from_secs(3), clone!(#[weak] toast_label, move || {
+            timer.borrow_mut().take();
+            toast_label.set_visible(false);
+        }));
+        *toast_timer.borrow_mut() = Some(id);
+    }));
+    let reveal_target: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+    let reveal_timer: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let show_reveal: RevealFn = Rc::new(clone!(#[strong] reveal_toast, #[strong] reveal_target, move |text: &str, path: PathBuf| {
+        reveal_label.set_text(text);
+        *reveal_target.borrow_mut() = Some(path);
+        reveal_toast.set_visible(true);
+        if let Some(id) = reveal_timer.borrow_mut().take() { id.remove(); }
+        let timer = reveal_timer.clone();
+        let id = glib::timeout_add_local_once(Duration::from_secs(10), clone!(#[weak] reveal_toast, move || {
+            timer.borrow_mut().take();
+            reveal_toast.set_visible(false);
+        }));
+        *reveal_timer.borrow_mut() = Some(id);
+    }));
+    reveal_btn.connect_clicked(clone!(#[weak] reveal_toast, move |btn| {
+        reveal_toast.set_visible(false);
+        let Some(path) = reveal_target.borrow_mut().take() else { return };
+        let parent = btn.root().and_downcast::<gtk4::Window>();
+        gtk4::FileLauncher::new(Some(&gio::File::for_path(&path))).launch(parent.as_ref(), gio::Cancellable::NONE, move |r| {
+            if let Err(e) = r { eprintln!("无法打开 {}: {}", path.display(), e); }
+        });
+    }));
+
+    // 切换像素对齐缩放（状态框中的按钮或命令面板），开启时吸附到不大于当前值的档位
+    let toggle_pixel_zoom = {
+        let pixel_zoom = pixel_zoom.clone();
+        let model = model.clone();
+        let toast = show_toast.clone();
+        Rc::new(move || {
+            let on = !pixel_zoom.get();
+            pixel_zoom.set(on);
+            if on { model.snap_to_preset(); } else { model.queue(Effects::view()); }
+            toast(if on { "像素对齐缩放：开" } else { "像素对齐缩放：关" });
+        })
+    };
+    pixel_zoom_btn.connect_clicked(clone!(#[strong] toggle_pixel_zoom, move |_| toggle_pixel_zoom()));
+
+    // 切换旋转应用到后续图片（Shift+单击旋转按钮或命令面板）：开启时记下当前方向，旋转按钮高亮
+    let toggle_sticky_rotation = {
+        let sticky_rotation = sticky_rotation.clone();
+        let model = model.clone();
+        let toast = show_toast.clone();
+        let rotate_btn = rotate_btn.clone();
+        Rc::new(move || {
+            let on = sticky_rotation.get().is_none();
+            sticky_rotation.set(on.then(|| model.read(|s| s.rotation)));
+            if on {
+                rotate_btn.add_css_class("sticky-rotation");
+                rotate_btn.set_tooltip_text(Some("旋转（已应用到后续图片，Shift+单击取消）"));
+            } else {
+                rotate_btn.remove_css_class("sticky-rotation");
+                rotate_btn.set_tooltip_text(Some(ROTATE_TOOLTIP));
+            }
+            model.queue(Effects::view());
+            toast(if on { "旋转应用到后续图片：开" } else { "旋转应用到后续图片：关" });
+        })
+    };
+
     let content = Box::new(Orientation::Vertical, 0);
-    content.append(&titlebar);
-    content.append(&drawing_area);
+    // 自动隐藏时标题栏放进滑动的 Revealer 浮在内容上方，内容顶部只留一条细线提示
+    let titlebar_revealer = gtk4::Revealer::builder()
+        .transition_type(gtk4::RevealerTransitionType::SlideDown)
+        .valign(gtk4::Align::Start)
+        .reveal_child(true)
+        .build();
+    if autohide_titlebar {
+        let strip = Box::new(Orientation::Horizontal, 0);
+        strip.add_css_class("titlebar-strip");
+        strip.set_size_request(-1, TITLEBAR_STRIP);
+        content.append(&strip);
+        titlebar_revealer.set_child(Some(&titlebar));
+    } else {
+        content.append(&titlebar);
+    }
+    content.append(&tab_strip);
+    content.append(&view_overlay);
+    let content_overlay = gtk4::Overlay::new();
+    content_overlay.set_child(Some(&content));
+    if autohide_titlebar { content_overlay.add_overlay(&titlebar_revealer); }
+    // 圆角裁剪与阴影画在包住内容的 frame 上，窗口本身透明，四周留出阴影的边距
+    let frame = Box::new(Orientation::Vertical, 0);
+    frame.add_css_class("viewer-frame");
+    frame.set_overflow(gtk4::Overflow::Hidden);
+    frame.append(&content_overlay);
 
     let window = ApplicationWindow::builder()
         .application(app)
@@ -752,21 +3378,102 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
         .resizable(true)
         .default_width(init_w)
         .default_height(init_h)
-        .child(&content)
+        .child(&frame)
         .build();
     
+    // 合成器支持透明时才画阴影，否则圆角外会露出黑色
+    let display = WidgetExt::display(&window);
+    if !kiosk && display.is_rgba() && display.is_composited() {
+        window.add_css_class("shadowed");
+        window.set_default_size(init_w + 2 * SHADOW_MARGIN, init_h + 2 * SHADOW_MARGIN);
+    }
+    // 边距随最大化、全屏、平铺变化；输入区域只包括可见内容，点击阴影落到下面的窗口
+    let fit_frame = clone!(#[strong] frame, move |win: &ApplicationWindow| {
+        let inset = shadow_inset(win);
+        frame.set_margin_start(inset);
+        frame.set_margin_end(inset);
+        frame.set_margin_top(inset);
+        frame.set_margin_bottom(inset);
+        if let Some(surface) = win.surface() && win.has_css_class("shadowed") {
+            let (w, h) = (surface.width(), surface.height());
+            let rect = cairo::RectangleInt::new(inset, inset, (w - 2 * inset).max(1), (h - 2 * inset).max(1));
+            surface.set_input_region(&cairo::Region::create_rectangle(&rect));
+        }
+    });
+    fit_frame(&window);
+    window.connect_realize(move |win| {
+        let Some(surface) = win.surface() else { return };
+        surface.connect_layout(clone!(#[weak] win, #[strong] fit_frame, move |_, _, _| fit_frame(&win)));
+        if let Some(toplevel) = surface.downcast_ref::<gdk::Toplevel>() {
+            toplevel.connect_state_notify(clone!(#[weak] win, #[strong] fit_frame, move |_| fit_frame(&win)));
+        }
+    });
+
+    // 自动隐藏标题栏：指针靠近窗口顶部、窗口获得或失去焦点时滑出，指针离开顶部 3 秒后收起
+    // 快捷键与命令面板的动作不依赖标题栏；需要标题栏中控件的动作（重命名、图片信息）先调用 show_titlebar
+    let titlebar_hovered = Rc::new(Cell::new(false));
+    let titlebar_hide: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let show_titlebar: Rc<dyn Fn()> = {
+        let (revealer, titlebar) = (titlebar_revealer.clone(), titlebar.clone());
+        let (hovered, pending) = (titlebar_hovered.clone(), titlebar_hide.clone());
+        let animations = config.animations;
+        Rc::new(move || {
+            if !autohide_titlebar { return; }
+            // 每次滑出时重新读取动画设置，运行中关闭桌面动画后立即生效
+            let animate = config::animations_enabled(animations);
+            revealer.set_transition_duration(if animate { TITLEBAR_TRANSITION_MS } else { 0 });
+            revealer.set_reveal_child(true);
+            schedule_titlebar_hide(&revealer, &titlebar, &hovered, &pending);
+        })
+    };
+    if autohide_titlebar {
+        let top_motion = gtk4::EventControllerMotion::new();
+        top_motion.connect_motion(clone!(#[strong] show_titlebar, #[strong] titlebar_hovered, #[weak] titlebar_revealer, move |_, _, y| {
+            let zone = if titlebar_revealer.reveals_child() { TITLEBAR_STRIP + TITLEBAR_HEIGHT } else { TITLEBAR_STRIP + 4 };
+            titlebar_hovered.set(y < zone as f64);
+            if titlebar_hovered.get() { show_titlebar(); }
+        }));
+        top_motion.connect_leave(clone!(#[strong] titlebar_hovered, move |_| titlebar_hovered.set(false)));
+        frame.add_controller(top_motion);
+        window.connect_is_active_notify(clone!(#[strong] show_titlebar, move |_| show_titlebar()));
+        show_titlebar();
+    }
+    
+    // 保持在最前：Wayland 下整个普通窗口作为 layer-shell 的 Top 层表面，键盘按需获得焦点；
+    // X11 下在窗口映射后请求窗口管理器
+    let above_layer = above::enabled() && !kiosk && !above::is_x11(&WidgetExt::display(&window)) && gtk4_layer_shell::is_supported();
+    if above_layer {
+        window.init_layer_shell();
+        window.set_layer(Layer::Top);
+        window.set_keyboard_mode(gtk4_layer_shell::KeyboardMode::OnDemand);
+    } else if above::enabled() && !kiosk {
+        window.connect_map(|w| {
+            if let Some(surface) = w.surface() && above::is_x11(&surface.display()) {
+                above::set_x11(&surface, true);
+            }
+        });
+    }
+    
     // 初始设置内容大小
     drawing_area.set_content_width(init_w);
-    drawing_area.set_content_height(init_h - TITLEBAR_HEIGHT);
+    drawing_area.set_content_height(init_h - titlebar_space());
     
     *window_ref.borrow_mut() = Some(window.clone());
     *da_ref.borrow_mut() = Some(drawing_area.clone());
+    // 窗口不可见时暂停后台刷新
+    let suspension = power::Suspension::default();
+    suspension.attach(&window);
     
-    // 边缘拖动调整窗口大小
+    // 边缘拖动调整窗口大小；判定区在 frame 上，从可见的边缘算起而不是阴影的外沿
     const EDGE_SIZE: f64 = 8.0;
     let win_resize = window.clone();
     let resize_motion = gtk4::EventControllerMotion::new();
     resize_motion.connect_motion(clone!(#[strong] win_resize, move |ctrl, x, y| {
+        // 全屏时窗口大小由合成器决定，不提供边缘调整
+        if win_resize.is_fullscreen() {
+            win_resize.set_cursor(None);
+            return;
+        }
         if let Some(widget) = ctrl.widget() {
             let (w, h) = (widget.width() as f64, widget.height() as f64);
             let (on_l, on_r, on_t, on_b) = (x < EDGE_SIZE, x > w - EDGE_SIZE, y < EDGE_SIZE, y > h - EDGE_SIZE);
@@ -782,9 +3489,15 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
         }
     }));
     
+    // 按下时认领事件序列，使图片不随之平移；越过系统拖动阈值后才交给合成器调整大小，在边缘单击不会拉动窗口
     let win_resize_drag = window.clone();
     let resize_gesture = gtk4::GestureDrag::builder().button(1).build();
-    resize_gesture.connect_drag_begin(clone!(#[strong] win_resize_drag, move |gesture, x, y| {
+    let resize_edge: Rc<Cell<Option<gdk::SurfaceEdge>>> = Rc::new(Cell::new(None));
+    let resize_gate = Rc::new(pointer::DragGate::default());
+    resize_gesture.connect_drag_begin(clone!(#[strong] win_resize_drag, #[strong] resize_edge, #[strong] resize_gate, move |gesture, x, y| {
+        resize_edge.set(None);
+        resize_gate.reset();
+        if win_resize_drag.is_fullscreen() { return; }
         if let Some(widget) = gesture.widget() {
             let (w, h) = (widget.width() as f64, widget.height() as f64);
             let (on_l, on_r, on_t, on_b) = (x < EDGE_SIZE, x > w - EDGE_SIZE, y < EDGE_SIZE, y > h - EDGE_SIZE);
@@ -795,217 +3508,2954 @@ fn build_ui(app: &Application, initial_path: Option<String>, initial_mode: Windo
                 (_, _, true, _) => Some(gdk::SurfaceEdge::North), (_, _, _, true) => Some(gdk::SurfaceEdge::South),
                 _ => None,
             };
-            if let Some(edge) = edge {
-                if let Some(native) = win_resize_drag.native() {
-                    if let Some(surface) = native.surface() {
-                        if let Some(toplevel) = surface.downcast_ref::<gdk::Toplevel>() {
-                            gesture.set_state(gtk4::EventSequenceState::Claimed);
-                            toplevel.begin_resize(edge, gesture.device().as_ref(), 1, x, y, gdk::CURRENT_TIME);
-                        }
-                    }
-                }
+            if edge.is_some() {
+                gesture.set_state(gtk4::EventSequenceState::Claimed);
+                resize_edge.set(edge);
             }
         }
     }));
-    content.add_controller(resize_motion);
-    content.add_controller(resize_gesture);
+    resize_gesture.connect_drag_update(clone!(#[strong] win_resize_drag, move |gesture, dx, dy| {
+        let Some(edge) = resize_edge.get() else { return };
+        if !resize_gate.passed_system(dx, dy) { return; }
+        resize_edge.set(None);
+        let Some((x, y)) = gesture.start_point() else { return };
+        let Some(surface) = win_resize_drag.native().and_then(|n| n.surface()) else { return };
+        if let Some(toplevel) = surface.downcast_ref::<gdk::Toplevel>() {
+            // begin_resize 使用表面坐标，要加上阴影的边距
+            let inset = shadow_inset(&win_resize_drag) as f64;
+            toplevel.begin_resize(edge, gesture.device().as_ref(), 1, x + inset, y + inset, gdk::CURRENT_TIME);
+        }
+    }));
+    if !kiosk {
+        frame.add_controller(resize_motion);
+        frame.add_controller(resize_gesture);
+    }
+
+    // 拖动边缘时不小于最小窗口尺寸
+    window.set_size_request(min_win_width(), MIN_WIN_HEIGHT);
+
+    // 锁定宽高比：用户或窗口管理器改变窗口大小后，按新尺寸重新计算缩放率，
+    // 窗口随之调整为图片的比例（另加标题栏高度），适应模式下图片正好填满窗口
+    if config.lock_aspect && !kiosk {
+        let model = model.clone();
+        let win = window.clone();
+        let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+        drawing_area.connect_resize(move |_, width, height| {
+            if let Some(id) = pending.borrow_mut().take() { id.remove(); }
+            let (model, win, pending_done) = (model.clone(), win.clone(), pending.clone());
+            // 拖动过程中尺寸连续变化，停下后再调整
+            let id = glib::timeout_add_local_once(Duration::from_millis(150), move || {
+                pending_done.borrow_mut().take();
+                if win.is_fullscreen() || win.is_maximized() || !model.has_image() { return; }
+                // 适应宽度或高度时由其自身重新适应
+                if model.read(|s| s.zoom_mode) != ZoomMode::Free { return; }
+                let ((img_w, img_h), (scaled_w, scaled_h)) = model.read(|s| (get_rotated_size(s), get_scaled_size(s)));
+                // 尺寸与当前缩放下程序设置的大小一致（包括超出屏幕时），不是用户调整的
+                let (target_w, target_h) = calc_target_size(scaled_w, scaled_h);
+                if (target_w - width).abs() <= 2 && (target_h - titlebar_space() - height).abs() <= 2 { return; }
+                let fill = (width as f64 / img_w as f64).min(height as f64 / img_h as f64);
+                model.set_scale(fill);
+            });
+            *pending.borrow_mut() = Some(id);
+        });
+    }
 
     let win_close = window.clone();
     close_btn.connect_clicked(move |_| { win_close.close(); });
 
-    // 加载图片函数
-    let path_lbl = path_label.clone();
-    let zoom_lbl = zoom_label.clone();
-    let res_lbl = res_label.clone();
-    let win_load = window_ref.clone();
-    let da_load = da_ref.clone();
-    let load_image = {
-        let state = state.clone();
-        let da = drawing_area.clone();
-        let cs = cs.clone();
-        let cr_rot = cr_rot.clone();
-        Rc::new(move |path: &str| {
-            match gdk::Texture::from_filename(path) {
-                Ok(texture) => {
-                    let mut s = state.borrow_mut();
-                    s.original_width = texture.width();
-                    s.original_height = texture.height();
-                    s.pixbuf = Some(texture);
-                    s.scale = 1.0;
-                    s.offset_x = 0.0;
-                    s.offset_y = 0.0;
-                    s.rotation = 0;
-                    
-                    // 计算适应窗口的缩放
-                    let (target_w, target_h) = calc_target_size(s.original_width, s.original_height);
-                    let content_h = target_h - TITLEBAR_HEIGHT;
-                    s.scale = (target_w as f64 / s.original_width as f64)
-                        .min(content_h as f64 / s.original_height as f64)
-                        .min(1.0);
-                    
-                    let scaled_w = (s.original_width as f64 * s.scale) as i32;
-                    let scaled_h = (s.original_height as f64 * s.scale) as i32;
-                    
-                    zoom_lbl.set_text(&format!("{:.0}%", s.scale * 100.0));
-                    res_lbl.set_text(&format!("{}×{}", s.original_width, s.original_height));
-                    drop(s);
-                    
-                    // 调整窗口大小
-                    if let (Some(win), Some(da_inner)) = (&*win_load.borrow(), &*da_load.borrow()) {
-                        update_window_size(win, da_inner, scaled_w, scaled_h);
-                    }
-                    
-                    // 清除缓存
-                    *cs.borrow_mut() = None;
-                    cr_rot.set(-1);
-                    da.queue_draw();
-                    path_lbl.set_text(path);
-                    path_lbl.set_tooltip_text(Some(path));
-                }
-                Err(e) => eprintln!("加载失败: {}", e),
+    // 当前文件所在目录的图片列表及当前文件的绝对路径；目录变化或 force 时重新列出
+    // 目录逐页异步列出，每读完一页刷新界面（listing_changed）；换到其他目录时取消尚未完成的列出
+    // --recursive 打开目录时整棵目录树的浏览列表，后台遍历时逐步补全
+    let tree: Rc<RefCell<Option<Rc<siblings::Listing>>>> = Rc::new(RefCell::new(None));
+    let dir_scan: Rc<RefCell<Option<Rc<siblings::Scan>>>> = Rc::new(RefCell::new(None));
+    let listing_changed: Rc<RefCell<Option<RefreshFn>>> = Rc::new(RefCell::new(None));
+    let current_listing = {
+        let tabs = tabs.clone();
+        let dir_views = dir_views.clone();
+        let tree = tree.clone();
+        let (cache, listing_changed) = (dir_scan.clone(), listing_changed.clone());
+        Rc::new(move |force: bool| -> Option<(Rc<siblings::Listing>, PathBuf)> {
+            let active = PathBuf::from(tabs.borrow().active_path()?);
+            // 目录树中的图片在整棵树中前后浏览；--files-from 列表中的路径没有规范化，按绝对路径查找
+            if let Some(tree) = tree.borrow().clone() {
+                let found = std::path::absolute(&active).ok().filter(|p| tree.position(p).is_some())
+                    .or_else(|| active.canonicalize().ok().filter(|p| tree.position(p).is_some()));
+                if let Some(path) = found { return Some((tree, path)); }
+            }
+            // 正在查看的文件被删除后无法规范化，改为规范化所在目录，仍从它原来的位置前后浏览
+            let path = active.canonicalize().ok()
+                .or_else(|| {
+                    let parent = active.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+                    Some(parent.canonicalize().ok()?.join(active.file_name()?))
+                })?;
+            let dir = path.parent()?.to_path_buf();
+            let mut cache = cache.borrow_mut();
+            if force || cache.as_ref().is_none_or(|scan| scan.dir != dir) {
+                let sort = dir_views.for_dir(&dir).sort.unwrap_or_default();
+                let changed = listing_changed.clone();
+                *cache = Some(siblings::Scan::start(&dir, sort, move || {
+                    let changed = changed.borrow().clone();
+                    if let Some(changed) = changed { changed(); }
+                }));
             }
+            cache.as_ref().map(|scan| (scan.listing(&path), path))
         })
     };
 
-    // 初始加载图片，如果是 overlay 模式则在加载后启动
-    if let Some(path) = initial_path {
-        let load = load_image.clone();
-        let start_overlay = initial_mode == WindowMode::Overlay;
-        let app_init = app.clone();
-        let state_init = state.clone();
-        let overlay_pos_init = overlay_pos.clone();
-        let overlay_window_init = overlay_window.clone();
-        let current_mode_init = current_mode.clone();
-        let window_ref_init = window_ref.clone();
-        let da_ref_init = da_ref.clone();
-        let window_init = window.clone();
-        
-        glib::idle_add_local_once(move || {
-            load(&path);
-            
-            // 如果是 overlay 模式启动
-            if start_overlay && state_init.borrow().pixbuf.is_some() {
-                current_mode_init.set(WindowMode::Overlay);
-                window_init.set_visible(false);
-                
-                // 计算居中位置
-                let (scaled_w, scaled_h) = {
-                    let s = state_init.borrow();
-                    get_scaled_size(&s)
-                };
-                let (screen_w, screen_h) = get_screen_size();
-                {
-                    let mut pos = overlay_pos_init.borrow_mut();
-                    pos.margin_left = (screen_w - scaled_w) / 2;
-                    pos.margin_top = (screen_h - scaled_h) / 2;
-                }
-                
-                let mode_exit = current_mode_init.clone();
-                let win_ref_exit = window_ref_init.clone();
-                let overlay_win_exit = overlay_window_init.clone();
-                let state_exit = state_init.clone();
-                let da_ref_exit = da_ref_init.clone();
-                
-                let overlay = create_overlay_window(
-                    &app_init,
-                    state_init.clone(),
-                    overlay_pos_init.clone(),
-                    move || {
-                        mode_exit.set(WindowMode::Normal);
-                        {
-                            let mut s = state_exit.borrow_mut();
-                            s.offset_x = 0.0;
-                            s.offset_y = 0.0;
-                        }
-                        if let Some(ref win) = *win_ref_exit.borrow() {
-                            win.set_visible(true);
-                            win.present();
-                            if let Some(ref da) = *da_ref_exit.borrow() {
-                                da.queue_draw();
-                            }
-                        }
-                        *overlay_win_exit.borrow_mut() = None;
-                    },
-                );
-                overlay.present();
-                *overlay_window_init.borrow_mut() = Some(overlay);
+    // 状态框的各行；取值为 None 的行隐藏
+    {
+        let active_file = {
+            let tabs = tabs.clone();
+            move || tabs.borrow().active_path().map(PathBuf::from).filter(|p| p.is_file())
+        };
+        // 缩小载入时同时显示原图与当前纹理的分辨率，物理尺寸按原图计算
+        let full = |s: &ImageState| s.full_size.unwrap_or((s.original_width, s.original_height));
+        status.row("分辨率", move |s| Some(match s.full_size {
+            Some((w, h)) => format!("{} × {} 像素（已缩小载入为 {} × {}）", w, h, s.original_width, s.original_height),
+            None => format!("{} × {} 像素", s.original_width, s.original_height),
+        }));
+        status.row("文件大小", |s| s.metadata.as_ref()?.size());
+        status.row("修改时间", |s| s.metadata.as_ref()?.modified());
+        status.row("格式", |s| s.metadata.as_ref()?.format());
+        status.row("色彩深度", |s| s.metadata.as_ref()?.depth());
+        status.row("物理尺寸", move |s| {
+            let (w, h) = full(s);
+            Some(format_physical_size(w, h, s.dpi))
+        });
+        // 帧数需要读整个文件，按路径缓存
+        let file = active_file.clone();
+        let frames: RefCell<Option<(PathBuf, u32)>> = RefCell::new(None);
+        status.row("帧/页", move |s| {
+            if !s.multi_frame { return None; }
+            let path = file()?;
+            let mut cache = frames.borrow_mut();
+            if cache.as_ref().is_none_or(|(p, _)| *p != path) {
+                *cache = Some((path.clone(), header::frame_count(&path)?));
             }
+            let n = cache.as_ref()?.1;
+            Some(if header::sniff_file(&path) == Some(header::Format::Tiff) { format!("{} 页", n) } else { format!("{} 帧", n) })
         });
-    }
-
-    let win_open = window.clone();
-    let load_open = load_image.clone();
-    open_btn.connect_clicked(move |_| {
-        let dialog = FileDialog::builder().title("选择图片").modal(true).build();
-        let filter = gtk4::FileFilter::new();
-        filter.add_mime_type("image/*");
-        filter.set_name(Some("图片"));
-        let filters = gio::ListStore::new::<gtk4::FileFilter>();
-        filters.append(&filter);
-        dialog.set_filters(Some(&filters));
-        let load = load_open.clone();
-        dialog.open(Some(&win_open), gio::Cancellable::NONE, move |r| {
-            if let Ok(f) = r { if let Some(p) = f.path() { load(&p.to_string_lossy()); } }
+        let listing = current_listing.clone();
+        status.row("连拍", move |_| {
+            let (l, p) = listing(false)?;
+            let (i, group) = (l.position(&p)?, l.burst(&p)?);
+            Some(format!("第 {} / {} 张", i - group.start + 1, group.len()))
         });
-    });
+        let sticky_rotation = sticky_rotation.clone();
+        status.row("方向", move |s| {
+            let degrees = s.rotation * 90 + s.tilt.to_degrees().round() as i32;
+            let text = (degrees != 0).then(|| format!("顺时针 {}°", degrees));
+            if sticky_rotation.get().is_none() { return text; }
+            Some(format!("{}（应用到后续图片）", text.as_deref().unwrap_or("原始方向")))
+        });
+        // 拍摄信息（JPEG 与 TIFF 的 EXIF）
+        status.row("相机", |s| s.metadata.as_ref()?.camera());
+        status.row("曝光", |s| s.metadata.as_ref()?.exposure());
+        status.row("感光度", |s| s.metadata.as_ref()?.iso());
+        status.row("焦距", |s| s.metadata.as_ref()?.focal_length());
+        status.row("拍摄时间", |s| s.metadata.as_ref()?.taken());
+        let compact = compact.clone();
+        status.row("缩放率", move |s| compact.get().then(|| format_zoom(s.scale)));
+        status.row("插值", |s| (s.view.filter == Some(config::Filter::Nearest)).then(|| "最近邻".to_string()));
+        let pixel_zoom = pixel_zoom.clone();
+        status.row("缩放方式", move |s| {
+            let mut modes = Vec::new();
+            match s.zoom_mode {
+                ZoomMode::FitWidth => modes.push("适应宽度"),
+                ZoomMode::FitHeight => modes.push("适应高度"),
+                ZoomMode::Free => {}
+            }
+            if pixel_zoom.get() { modes.push("像素对齐档位"); }
+            (!modes.is_empty()).then(|| modes.join("，"))
+        });
+        let (lock_aspect, win) = (config.lock_aspect && !kiosk, window.clone());
+        status.row("锁定", move |_| {
+            let mut locks = Vec::new();
+            if lock_aspect { locks.push("宽高比"); }
+            let above = win.lookup_action("keep-above").and_then(|a| a.state()).and_then(|v| v.get::<bool>());
+            if above == Some(true) { locks.push("保持在最前"); }
+            (!locks.is_empty()).then(|| locks.join("，"))
+        });
+        info_popover.connect_show(clone!(#[strong] status, #[strong] state, move |_| status.fill(&state.borrow())));
+    }
 
-    // 恢复视图
-    let state_reset = state.clone();
-    let da_reset = drawing_area.clone();
-    let zoom_reset = zoom_label.clone();
-    let win_reset = window_ref.clone();
-    let da_reset_ref = da_ref.clone();
-    reset_btn.connect_clicked(move |_| {
-        let mut s = state_reset.borrow_mut();
-        if s.pixbuf.is_some() {
-            let (img_w, img_h) = match s.rotation % 2 {
-                0 => (s.original_width, s.original_height),
-                _ => (s.original_height, s.original_width),
-            };
-            let (target_w, target_h) = calc_target_size(img_w, img_h);
-            let content_h = target_h - TITLEBAR_HEIGHT;
-            s.scale = (target_w as f64 / img_w as f64).min(content_h as f64 / img_h as f64).min(1.0);
-            s.offset_x = 0.0;
-            s.offset_y = 0.0;
-            
-            let scaled_w = (img_w as f64 * s.scale) as i32;
-            let scaled_h = (img_h as f64 * s.scale) as i32;
-            zoom_reset.set_text(&format!("{:.0}%", s.scale * 100.0));
-            drop(s);
-            
-            if let (Some(win), Some(da)) = (&*win_reset.borrow(), &*da_reset_ref.borrow()) {
-                update_window_size(win, da, scaled_w, scaled_h);
+    // 根据当前状态刷新标题栏与信息面板
+    let refresh_view = {
+        let listing = current_listing.clone();
+        let state = state.clone();
+        let tabs = tabs.clone();
+        let path_crumbs = path_crumbs.clone();
+        let zoom_lbl = zoom_label.clone();
+        let status = status.clone();
+        let print_btn = print_size_btn.clone();
+        let frame_actions = frame_actions.clone();
+        let diag_lbl = diag_label.clone();
+        let suspension = suspension.clone();
+        let win = window.clone();
+        let empty_label = empty_label.clone();
+        let (prev_btn, next_btn) = (prev_btn.clone(), next_btn.clone());
+        let dir_scan = dir_scan.clone();
+        let tree = tree.clone();
+        Rc::new(move || {
+            let s = state.borrow();
+            if s.pixbuf.is_some() { empty_label.set_visible(false); }
+            zoom_lbl.set_text(&format_zoom(s.scale));
+            status.update(&s);
+            print_btn.set_sensitive(calc_print_scale(s.dpi).is_some());
+            frame_actions.set_visible(s.multi_frame);
+            let mut diag: Vec<String> = s.report.as_ref()
+                .map(|r| r.rows().iter().map(|(k, v)| format!("{}：{}", k, v)).collect())
+                .unwrap_or_else(|| vec!["加载信息：无".to_string()]);
+            diag.push(format!("低功耗：{}", suspension.describe()));
+            diag_lbl.set_text(&diag.join("\n"));
+            let path = tabs.borrow().active_path().unwrap_or_default().to_string();
+            // 属于连拍时附上在组内的位置
+            let current = listing(false);
+            let burst = current.as_ref().and_then(|(l, p)| Some((l.position(p)?, l.burst(p)?)));
+            let has = |forward| current.as_ref().is_some_and(|(l, p)| l.step_within(p, forward).is_some());
+            prev_btn.set_visible(!kiosk && has(false));
+            next_btn.set_visible(!kiosk && has(true));
+            let mut suffix = burst.map(|(i, group)| format!(" [{}/{}]", i - group.start + 1, group.len())).unwrap_or_default();
+            // 目录还在列出时显示当前位置与已找到的图片数，位置在列完之前可能变化
+            let scanning = dir_scan.borrow().as_ref()
+                .is_some_and(|scan| !scan.is_complete() && current.as_ref().is_some_and(|(l, _)| l.dir == scan.dir));
+            if scanning && let Some((l, p)) = current.as_ref() && let Some(i) = l.position(p) {
+                suffix.push_str(&format!(" [{}/{}…]", i + 1, l.files.len()));
             }
-            da_reset.queue_draw();
-        }
-    });
+            let in_tree = |l: &Rc<siblings::Listing>| tree.borrow().as_ref().is_some_and(|t| Rc::ptr_eq(t, l));
+            if numbered_tree && let Some((l, p)) = current.as_ref() && in_tree(l) && let Some(i) = l.position(p) {
+                suffix.push_str(&format!(" [{}/{}]", i + 1, l.files.len()));
+            }
+            path_crumbs.set_path(&path, &suffix);
+            let name = Path::new(&path).file_name().map(|n| n.to_string_lossy().to_string());
+            win.set_title(Some(name.as_deref().unwrap_or("Image Viewer")));
+        })
+    };
 
-    // 旋转
-    let state_rotate = state.clone();
-    let da_rotate = drawing_area.clone();
-    rotate_btn.connect_clicked(move |_| {
-        let mut s = state_rotate.borrow_mut();
-        if s.pixbuf.is_some() {
-            s.rotation = (s.rotation + 1) % 4;
-            drop(s);
-            da_rotate.queue_draw();
-        }
-    });
+    suspension.connect_changed(clone!(#[strong] refresh_view, move |_| refresh_view()));
+    *listing_changed.borrow_mut() = Some(refresh_view.clone());
 
-    // 复制到剪贴板
-    let state_copy = state.clone();
-    let win_copy = window.clone();
-    copy_btn.connect_clicked(move |_| {
-        let s = state_copy.borrow();
-        if let Some(ref texture) = s.pixbuf {
-            let clipboard = win_copy.clipboard();
-            let content = gdk::ContentProvider::for_value(&texture.to_value());
-            clipboard.set_content(Some(&content)).ok();
-        }
-    });
+    // 模型的界面副作用：刷新标签、调整窗口尺寸、重绘
+    {
+        let refresh = refresh_view.clone();
+        let window_ref = window_ref.clone();
+        let da_ref = da_ref.clone();
+        model.set_flush(move |effects| {
+            if effects.refresh { refresh(); }
+            if let Some(ref da) = *da_ref.borrow() {
+                match effects.resize {
+                    Some(Resize::Fit(w, h)) => {
+                        if let Some(ref win) = *window_ref.borrow() { update_window_size(win, da, w, h); }
+                    }
+                    Some(Resize::Content(w, h)) => {
+                        da.set_content_width(w);
+                        da.set_content_height(h);
+                    }
+                    None => {}
+                }
+                if effects.redraw { da.queue_draw(); }
+            }
+        });
+    }
+
+    // 重建标签栏按钮
+    let switch_tab_ref: Rc<RefCell<Option<TabSwitchFn>>> = Rc::new(RefCell::new(None));
+    let rebuild_tabs = {
+        let tabs = tabs.clone();
+        let strip = tab_strip.clone();
+        let switch_ref = switch_tab_ref.clone();
+        Rc::new(move || {
+            while let Some(child) = strip.first_child() {
+                strip.remove(&child);
+            }
+            let tabs = tabs.borrow();
+            for (i, tab) in tabs.items.iter().enumerate() {
+                let name = Path::new(&tab.path).file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| tab.path.clone());
+                let lbl = Label::new(Some(&name));
+                lbl.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+                lbl.set_max_width_chars(24);
+                let btn = Button::builder().child(&lbl).tooltip_text(&tab.path).build();
+                btn.add_css_class("tab-btn");
+                btn.add_css_class("flat");
+                if i == tabs.active { btn.add_css_class("tab-active"); }
+                let switch_ref = switch_ref.clone();
+                btn.connect_clicked(move |_| {
+                    if let Some(ref switch) = *switch_ref.borrow() { switch(i); }
+                });
+                strip.append(&btn);
+            }
+            strip.set_visible(tabs.items.len() > 1 && !kiosk);
+        })
+    };
+
+    // 动画播放：显示动图或切换到动图的标签时开始，换成别的图片时停止（替换即丢弃之前的播放器）
+    // 各帧直接替换 ImageState 中的纹理，复制、导出当前帧都取正在显示的帧
+    let player: Rc<RefCell<Option<animation::Player>>> = Rc::new(RefCell::new(None));
+    let start_playback: PlaybackFn = {
+        let (player, state, model, tabs) = (player.clone(), state.clone(), model.clone(), tabs.clone());
+        let (da, cs, overlay_window) = (drawing_area.clone(), cs.clone(), overlay_window.clone());
+        let suspension = suspension.clone();
+        Rc::new(move |play: bool| {
+            *player.borrow_mut() = None;
+            let Some(path) = tabs.borrow().active_path().map(PathBuf::from) else { return };
+            let generation = {
+                let s = state.borrow();
+                // 缩小载入时各帧与纹理尺寸不符；隔离解码时不在本进程中解码动画，都只显示一帧
+                if s.pixbuf.is_none() || s.full_size.is_some() || sandbox::enabled() || !extract::is_animation(&path) { return; }
+                s.generation.current()
+            };
+            let (model, da, cs) = (model.clone(), da.clone(), cs.clone());
+            let overlay_show = overlay_window.clone();
+            let show = move |texture: gdk::Texture, index: usize| -> bool {
+                let shown = model.mutate(|s| {
+                    // 已载入别的图片或切换了标签
+                    if !s.generation.is_current(generation) { return false; }
+                    s.pixbuf = Some(texture);
+                    s.frame = index;
+                    true
+                });
+                if !shown { return false; }
+                *cs.borrow_mut() = None;
+                da.queue_draw();
+                if let Some(overlay_da) = overlay_show.borrow().as_ref().and_then(|w| w.child()) {
+                    overlay_da.queue_draw();
+                }
+                true
+            };
+            // 置顶时普通窗口隐藏着，这时按置顶窗口计
+            let (suspension, overlay_window) = (suspension.clone(), overlay_window.clone());
+            let hidden = move || suspension.is_suspended() && overlay_window.borrow().is_none();
+            let p = animation::Player::new(&path, show, hidden);
+            p.set_paused(!play);
+            *player.borrow_mut() = Some(p);
+        })
+    };
+    let animations = config.animations;
+    // 减少动态效果时动画不自动播放，按 P 播放
+    let autoplay = move || config::animations_enabled(animations);
+
+    // 显示解码好的纹理并让窗口适应图片；name 为文件路径，或标准输入、剪贴板等来源的名称
+    // view 为命令行指定的初始视图
+    let win_load = window_ref.clone();
+    let da_load = da_ref.clone();
+    let show_texture = {
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let da = drawing_area.clone();
+        let cs = cs.clone();
+        let cr_rot = cr_rot.clone();
+        let refresh = refresh_view.clone();
+        let rebuild = rebuild_tabs.clone();
+        let verbose = config.verbose;
+        let previous = previous.clone();
+        let pixel_zoom = pixel_zoom.clone();
+        let sticky_rotation = sticky_rotation.clone();
+        let previous_limit = config.memory_warning_mb.saturating_mul(1024 * 1024);
+        let dir_views = dir_views.clone();
+        let first_shown = Cell::new(false);
+        let start_playback = start_playback.clone();
+        Rc::new(move |texture: gdk::Texture, mut report: LoadReport, name: &str, view: Option<ViewRequest>| {
+            report.set_texture(&texture);
+            recovery::changed();
+            if verbose {
+                eprintln!("已加载 {}", name);
+                if !first_shown.replace(true) {
+                    eprintln!("  从创建窗口到第一张图片：{:.1} ms", started.elapsed().as_secs_f64() * 1000.0);
+                }
+                for (k, v) in report.rows() {
+                    eprintln!("  {}：{}", k, v);
+                }
+                match screen_geometry() {
+                    Some(((w, h), source)) => eprintln!("  屏幕尺寸：{}×{}，来自{}", w, h, source),
+                    None => eprintln!("  屏幕尺寸：未知（没有可用的显示器），窗口尺寸不受屏幕限制"),
+                }
+            }
+            let (scaled_w, scaled_h) = model.mutate(|s| {
+                // 沿用上一张的方向，包括开启后在上一张上又手动调整的
+                if sticky_rotation.get().is_some() && s.pixbuf.is_some() {
+                    sticky_rotation.set(Some(s.rotation));
+                }
+                // 保留被替换的图片供对比，只保留一张且不超过内存阈值
+                let old = s.pixbuf.take().zip(tabs.borrow().active_path().map(str::to_string));
+                *previous.borrow_mut() = old
+                    .filter(|(t, _)| (t.width() as u64) * (t.height() as u64) * 4 <= previous_limit)
+                    .map(|(t, p)| (p, t));
+                s.full_size = report.downscaled_from;
+                s.quality = TextureQuality::Final;
+                s.report = Some(report);
+                s.original_width = texture.width();
+                s.original_height = texture.height();
+                s.pixbuf = Some(texture);
+                s.scale = 1.0;
+                s.zoom_mode = ZoomMode::Free;
+                s.offset_x = 0.0;
+                s.offset_y = 0.0;
+                s.rotation = sticky_rotation.get().unwrap_or(0);
+                s.tilt = 0.0;
+                s.dpi = header::read_dpi(Path::new(name));
+                s.multi_frame = header::has_multiple_frames(Path::new(name));
+                s.metadata = Some(metadata::Metadata::read(Path::new(name)));
+                s.frame = match view {
+                    Some(ViewRequest::Page(n) | ViewRequest::Frame(n) | ViewRequest::Poster(n)) => n,
+                    _ => 1,
+                };
+                s.view = dir_views.for_file(Path::new(name));
+            
+                // 计算适应窗口的缩放（展台模式与全屏时按整个屏幕计算），同时作为缩小的下限
+                let (rotated_w, rotated_h) = get_rotated_size(s);
+                s.min_zoom = if kiosk || win_load.borrow().as_ref().is_some_and(|w| w.is_fullscreen()) {
+                    fit_to_monitor_scale(rotated_w, rotated_h)
+                } else {
+                    fit_to_screen_scale(rotated_w, rotated_h)
+                };
+                s.scale = s.min_zoom;
+                match s.view.zoom {
+                    Some(config::InitialZoom::Percent(p)) => s.scale = (p / 100.0).clamp(0.01, MAX_SCALE),
+                    _ if pixel_zoom.get() => s.scale = floor_zoom_preset(s.scale),
+                    _ => {}
+                }
+                s.clip = None;
+            
+                let mut scaled_w = (rotated_w as f64 * s.scale) as i32;
+                let mut scaled_h = (rotated_h as f64 * s.scale) as i32;
+            
+                // 区域视图：只显示该区域并使其适应窗口，其余部分缩小后才显示
+                if let Some(ViewRequest::Region(x, y, w, h)) = view {
+                    let (iw, ih) = (s.original_width, s.original_height);
+                    let (x0, y0) = (x.clamp(0, iw), y.clamp(0, ih));
+                    let (x1, y1) = (x.saturating_add(w).clamp(0, iw), y.saturating_add(h).clamp(0, ih));
+                    if x1 > x0 && y1 > y0 {
+                        let (rw, rh) = ((x1 - x0) as f64, (y1 - y0) as f64);
+                        let (target_w, target_h) = calc_target_size(rw as i32, rh as i32);
+                        s.scale = (target_w as f64 / rw).min((target_h - titlebar_space()) as f64 / rh).clamp(0.1, 50.0);
+                        // 使区域中心位于视图中心
+                        s.offset_x = (iw as f64 / 2.0 - (x0 as f64 + rw / 2.0)) * s.scale;
+                        s.offset_y = (ih as f64 / 2.0 - (y0 as f64 + rh / 2.0)) * s.scale;
+                        s.clip = Some((x0 as f64, y0 as f64, rw, rh));
+                        scaled_w = (rw * s.scale) as i32;
+                        scaled_h = (rh * s.scale) as i32;
+                    } else {
+                        eprintln!("区域 {},{},{},{} 不在图片范围内，显示整张图片", x, y, w, h);
+                    }
+                }
+                (scaled_w, scaled_h)
+            });
+            
+            tabs.borrow_mut().set_active_path(name);
+            refresh();
+            rebuild();
+            
+            // 调整窗口大小
+            if let (Some(win), Some(da_inner)) = (&*win_load.borrow(), &*da_load.borrow()) {
+                update_window_size(win, da_inner, scaled_w, scaled_h);
+            }
+            
+            // 清除缓存
+            *cs.borrow_mut() = None;
+            cr_rot.set(-1);
+            da.queue_draw();
+            // 指定了帧/页时停在该帧
+            start_playback(!matches!(view, Some(ViewRequest::Page(_) | ViewRequest::Frame(_))) && autoplay());
+        })
+    };
+
+    // 预览之后在后台完整解码，切换回纹理已卸载的标签时同样在这里重新解码；
+    // 同一时间只解码一张，排队期间已被更新的请求取代的直接跳过
+    // 结果按载入编号找到仍显示该预览或占位的状态（当前或后台标签）替换，找不到时丢弃
+    let full_decode: FullDecodeFn = {
+        let latest = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let lock = std::sync::Arc::new(std::sync::Mutex::new(()));
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let da = drawing_area.clone();
+        let cs = cs.clone();
+        let cr_rot = cr_rot.clone();
+        let refresh = refresh_view.clone();
+        let overlay_window = overlay_window.clone();
+        let (win_ref, da_ref) = (window_ref.clone(), da_ref.clone());
+        let verbose = config.verbose;
+        let jobs = jobs.clone();
+        let start_playback = start_playback.clone();
+        Rc::new(move |id: u64, path: &str, fit: Option<(i32, i32)>| {
+            latest.store(id, std::sync::atomic::Ordering::Relaxed);
+            let name = Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string();
+            // 取消后继续显示预览（或占位，再次切换到该标签时重新请求）
+            let reload = model.read(|s| s.quality == TextureQuality::Unloaded);
+            let job = jobs.start(&format!("{} {}", if reload { "重新载入" } else { "完整解码" }, name));
+            let (tx, rx) = std::sync::mpsc::channel::<(Result<gdk::Texture, String>, Duration)>();
+            let (latest, lock, file, cancel) = (latest.clone(), lock.clone(), PathBuf::from(path), job.cancellable());
+            std::thread::spawn(move || {
+                let _turn = lock.lock();
+                if latest.load(std::sync::atomic::Ordering::Relaxed) != id || cancel.is_cancelled() { return; }
+                let started = std::time::Instant::now();
+                // 缩小载入的图片仍按之前的尺寸解码
+                let texture = match fit {
+                    Some((w, h)) => decode_scaled_to(&file, w, h),
+                    None => retry_while_written(&file, || decode_texture(&file)),
+                };
+                if cancel.is_cancelled() { return; }
+                tx.send((texture, started.elapsed())).ok();
+            });
+            let (model, tabs, da, cs, cr_rot) = (model.clone(), tabs.clone(), da.clone(), cs.clone(), cr_rot.clone());
+            let (refresh, overlay_window, path) = (refresh.clone(), overlay_window.clone(), path.to_string());
+            let (win_ref, da_ref, start_playback) = (win_ref.clone(), da_ref.clone(), start_playback.clone());
+            // 定时器结束时随之移除任务；取消后不再等待，解码完的结果由工作线程丢弃
+            glib::timeout_add_local(Duration::from_millis(50), move || {
+                if job.is_cancelled() { return glib::ControlFlow::Break; }
+                let (texture, elapsed) = match rx.try_recv() {
+                    Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                    // 已被取代或已取消，没有解码
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+                    Ok((Ok(texture), elapsed)) => (texture, elapsed),
+                    Ok((Err(e), _)) => {
+                        eprintln!("无法解码 {}，继续显示预览或占位: {}", path, e);
+                        return glib::ControlFlow::Break;
+                    }
+                };
+                let decoder = LoadReport::detect(Path::new(&path)).decoder;
+                let mut resized = None;
+                // 当前标签原先显示的是占位（没有纹理）时为 Some(true)
+                let current = model.mutate(|s| {
+                    let pending = |s: &ImageState| matches!(s.quality, TextureQuality::Preview | TextureQuality::Unloaded);
+                    if s.generation.is_current(id) && pending(s) {
+                        if verbose && s.quality == TextureQuality::Preview {
+                            let first = s.report.as_ref().map_or(0.0, |r| r.decode_time.as_secs_f64() * 1000.0);
+                            eprintln!("已完整解码 {}：预览 {:.1} ms 后首次显示，完整解码 {:.1} ms",
+                                      path, first, elapsed.as_secs_f64() * 1000.0);
+                        }
+                        let placeholder = s.pixbuf.is_none();
+                        if !restore_texture(s, texture, decoder, elapsed) {
+                            eprintln!("{}: 解码的尺寸与之前不一致（文件可能已被改写），重新适应窗口", path);
+                            resized = Some(get_scaled_size(s));
+                        }
+                        Some(placeholder)
+                    } else {
+                        let mut tabs = tabs.borrow_mut();
+                        let background = tabs.items.iter_mut()
+                            .filter_map(|t| t.state.as_mut())
+                            .find(|s| s.generation.is_current(id) && pending(s));
+                        if let Some(s) = background { restore_texture(s, texture, decoder, elapsed); }
+                        None
+                    }
+                });
+                if let Some((w, h)) = resized && let (Some(win), Some(da)) = (&*win_ref.borrow(), &*da_ref.borrow()) {
+                    update_window_size(win, da, w, h);
+                }
+                if let Some(placeholder) = current {
+                    *cs.borrow_mut() = None;
+                    cr_rot.set(-1);
+                    refresh();
+                    da.queue_draw();
+                    if let Some(da) = overlay_window.borrow().as_ref().and_then(|w| w.child()) {
+                        da.queue_draw();
+                    }
+                    // 切换标签时还没有纹理，动画此时才能开始播放
+                    if placeholder { start_playback(autoplay()); }
+                }
+                glib::ControlFlow::Break
+            });
+        })
+    };
+
+    // 加载图片函数，结束后以是否成功调用 done；文件刚被修改而解码失败时稍后重试，最后一次重试结束后才调用
+    // 重试由定时器安排，等待期间不阻塞界面，期间打开了其他图片则不再重试
+    // 定时器经弱引用找回载入函数，避免载入函数通过 load_retry 引用自身
+    let load_retry: Rc<RefCell<Option<Weak<LoadAttempt>>>> = Rc::new(RefCell::new(None));
+    let load_attempt: Rc<LoadAttempt> = {
+        let show = show_texture.clone();
+        let verbose = config.verbose;
+        let model = model.clone();
+        let dir_views = dir_views.clone();
+        let full_decode = full_decode.clone();
+        let load_retry = load_retry.clone();
+        // 预览需要在本进程中读取 EXIF，隔离解码时不做预览
+        let previews = config.jpeg_preview && !kiosk && !sandbox::enabled();
+        Rc::new(move |path: &str, view: Option<ViewRequest>, size: DecodeSize, retry: Option<LoadRetry>, done: LoadDone| {
+            // 没有指定视图时多帧图片显示设置的封面帧
+            let mut view = view.or_else(|| posters::get(Path::new(path)).map(ViewRequest::Poster));
+            // 载入失败时恢复原编号，仍在显示的预览可以继续完成
+            let previous = retry.map_or_else(|| model.read(|s| s.generation.current()), |r| r.previous);
+            let generation = model.mutate(|s| s.generation.begin());
+            let mut report = LoadReport::detect(Path::new(path));
+            let started = std::time::Instant::now();
+            // 文件头中的尺寸（按 EXIF 方向交换宽高），解码后据此检查文件是否在读取期间被改写
+            let header_size = decode::oriented_dimensions(Path::new(path));
+            // 超过纹理尺寸上限的图片不交给渲染器，自动缩小解码
+            let over_limit = header_size
+                .filter(|&(w, h)| size == DecodeSize::Full && gpu::exceeds(w, h, texture_limit));
+            // 大 JPEG 先显示缩小解码的预览；指定视图或目录设置了固定缩放率时按原图坐标计算，不做预览
+            let preview = (previews && size == DecodeSize::Full && over_limit.is_none() && view.is_none()
+                && !matches!(dir_views.for_file(Path::new(path)).zoom, Some(config::InitialZoom::Percent(_))))
+                .then(|| jpeg_preview_denominator(Path::new(path)))
+                .flatten()
+                .and_then(|(d, (w, h))| {
+                    let texture = decode_scaled_to(Path::new(path), w.div_ceil(d) as i32, h.div_ceil(d) as i32).ok()?;
+                    report.decoder = format!("gdk-pixbuf DCT 缩小预览（1/{}，完整解码进行中）", d);
+                    report.preview = Some(d);
+                    report.downscaled_from = Some((w as i32, h as i32));
+                    Some(texture)
+                });
+            if let Some(texture) = preview {
+                report.decode_time = started.elapsed();
+                show(texture, report, path, view);
+                model.mutate(|s| s.quality = TextureQuality::Preview);
+                full_decode(generation, path, None);
+                return done(true);
+            }
+            // CMYK/YCCK JPEG 一律由 image 解码并转换为 RGB，缩小载入时同样如此
+            let ink = header::jpeg_ink(Path::new(path));
+            if let Some(ink) = ink { report.color_space = Some(format!("{} (converted)", ink.name())); }
+            let downscaler = if ink.is_some() { "image 解码后缩小" } else { "gdk-pixbuf 缩小解码" };
+            let decoded = match size {
+                DecodeSize::Full if over_limit.is_some() => {
+                    decode_downscaled(Path::new(path), u64::MAX, texture_limit).map(|(texture, full)| {
+                        eprintln!("{}: {}×{} 超过纹理尺寸上限 {}，缩小载入为 {}×{}",
+                                  path, full.0, full.1, texture_limit, texture.width(), texture.height());
+                        report.decoder = format!("{}（{}×{}，纹理上限 {}）", downscaler, texture.width(), texture.height(), texture_limit);
+                        report.downscaled_from = Some(full);
+                        texture
+                    })
+                }
+                DecodeSize::Full if sandbox::enabled() => {
+                    report.decoder = "image 解码器（隔离进程）".to_string();
+                    decode_texture(Path::new(path))
+                }
+                DecodeSize::Full => {
+                    if ink.is_some() { report.decoder = "image JPEG 解码器".to_string(); }
+                    if decode::orientation(Path::new(path)).is_some() {
+                        report.decoder = "image 解码器（应用 EXIF 方向）".to_string();
+                        report.exif_rotated = true;
+                    }
+                    decode_texture(Path::new(path))
+                }
+                DecodeSize::Downscaled(budget) => decode_downscaled(Path::new(path), budget, texture_limit).map(|(texture, full)| {
+                    report.decoder = format!("{}（{}×{}）", downscaler, texture.width(), texture.height());
+                    report.downscaled_from = Some(full);
+                    texture
+                }),
+            };
+            match decoded {
+                Ok(mut texture) => {
+                    // 解码结果与开始时读到的文件头不符：文件在读取期间被替换或截断
+                    // 窗口按解码结果适应；缩小载入时原图尺寸改用重新读到的文件头，仍不符时按解码结果计
+                    let actual = (texture.width(), texture.height());
+                    let expected = header_size.map(|(w, h)| (w as i32, h as i32));
+                    let matches = |e: (i32, i32)| match report.downscaled_from {
+                        Some(_) => same_shape(e, actual),
+                        None => e == actual || e == (actual.1, actual.0),
+                    };
+                    if let Some(e) = expected && !matches(e) {
+                        eprintln!("{}: 解码得到 {}×{}，与文件头的 {}×{} 不一致（文件可能在读取时被改写），按解码结果调整窗口",
+                                  path, actual.0, actual.1, e.0, e.1);
+                        let now = decode::oriented_dimensions(Path::new(path)).map(|(w, h)| (w as i32, h as i32));
+                        report.downscaled_from = report.downscaled_from.and(now).filter(|&n| same_shape(n, actual));
+                    }
+                    // 指定页/帧时单独解码该帧，失败则保留第一帧
+                    if let Some(ViewRequest::Page(n) | ViewRequest::Frame(n) | ViewRequest::Poster(n)) = view {
+                        match extract::decode_frame(Path::new(path), n) {
+                            Ok(frame) => {
+                                texture = texture_from_rgba(frame);
+                                report.decoder = format!("image 帧解码器（第 {} 帧/页）", n);
+                                report.downscaled_from = None;
+                            }
+                            Err(e) => {
+                                eprintln!("无法显示第 {} 帧/页，改为显示第一帧: {}", n, e);
+                                view = None;
+                            }
+                        }
+                    }
+                    report.decode_time = started.elapsed();
+                    show(texture, report, path, view);
+                    done(true);
+                }
+                Err(e) => {
+                    let attempt = retry.map_or(0, |r| r.attempt);
+                    if let Some(&delay) = WRITE_RETRY_DELAYS.get(attempt) && recently_modified(Path::new(path)) {
+                        eprintln!("{}: 文件刚被修改，{} ms 后重试解码: {}", path, delay.as_millis(), e);
+                        let (load_retry, model, path) = (load_retry.clone(), model.clone(), path.to_string());
+                        let next = LoadRetry { attempt: attempt + 1, previous };
+                        glib::timeout_add_local_once(delay, move || {
+                            if !model.read(|s| s.generation.is_current(generation)) { return; }
+                            let load = load_retry.borrow().as_ref().and_then(Weak::upgrade);
+                            if let Some(load) = load { load(&path, view, size, Some(next), done); }
+                        });
+                        return;
+                    }
+                    eprintln!("加载失败: {}", e);
+                    if verbose {
+                        eprintln!("  MIME 类型：{}", report.mime);
+                        eprintln!("  解码路径：{}", report.decoder);
+                    }
+                    model.mutate(|s| s.generation.abandon(generation, previous));
+                    done(false);
+                }
+            }
+        })
+    };
+    *load_retry.borrow_mut() = Some(Rc::downgrade(&load_attempt));
+    let load_image_at = {
+        let load = load_attempt.clone();
+        Rc::new(move |path: &str, view: Option<ViewRequest>, size: DecodeSize, done: LoadDone| load(path, view, size, None, done))
+    };
+
+    // 估算解码内存超过阈值或可用内存时先询问：缩小载入、仍然载入或取消
+    // 文件头表明不是图片（PDF、压缩包、文本等）时不交给解码器，说明类型并提供用默认程序打开
+    // 文件不存在、没有权限或为空时说明具体原因，并提供选择其他文件
+    let confirm_load: ConfirmLoadFn = {
+        let win = window.clone();
+        let open_btn = open_btn.clone();
+        let limit = config.memory_warning_mb.saturating_mul(1024 * 1024);
+        Rc::new(move |path: &str, then: LoadThen| {
+            if let Err(problem) = precheck::check(Path::new(path)) {
+                eprintln!("{}: {}", path, problem.message());
+                let dialog = gtk4::AlertDialog::builder()
+                    .message(problem.message())
+                    .detail(path)
+                    .buttons(["选择其他文件…", "关闭"])
+                    .default_button(0)
+                    .cancel_button(1)
+                    .build();
+                dialog.choose(Some(&win), gio::Cancellable::NONE, clone!(#[weak] open_btn, move |r| {
+                    if r == Ok(0) { open_btn.emit_clicked(); }
+                }));
+                return;
+            }
+            if let Some(kind) = header::read_prefix(Path::new(path), SNIFF_LEN).and_then(|d| header::not_an_image(&d)) {
+                eprintln!("{}: {}", path, kind.message());
+                let file = gio::File::for_path(path);
+                let dialog = gtk4::AlertDialog::builder()
+                    .message(kind.message())
+                    .detail(Path::new(path).file_name().unwrap_or_default().to_string_lossy())
+                    .buttons(["用默认程序打开", "关闭"])
+                    .default_button(1)
+                    .cancel_button(1)
+                    .build();
+                dialog.choose(Some(&win), gio::Cancellable::NONE, clone!(#[strong] win, move |r| {
+                    if r != Ok(0) { return; }
+                    gtk4::FileLauncher::new(Some(&file)).launch(Some(&win), gio::Cancellable::NONE, |r| {
+                        if let Err(e) = r { eprintln!("无法用默认程序打开: {}", e); }
+                    });
+                }));
+                return;
+            }
+            let Some(estimate) = estimate_decoded_bytes(Path::new(path)) else { return then(DecodeSize::Full) };
+            let available = available_memory();
+            if estimate <= limit && available.is_none_or(|a| estimate <= a) {
+                return then(DecodeSize::Full);
+            }
+            let budget = limit.min(available.map_or(u64::MAX, |a| a / 2));
+            let mib = |b: u64| b / (1024 * 1024);
+            let detail = match available {
+                Some(a) => format!("解码后约需 {} MiB 内存，当前可用 {} MiB。", mib(estimate), mib(a)),
+                None => format!("解码后约需 {} MiB 内存。", mib(estimate)),
+            };
+            let dialog = gtk4::AlertDialog::builder()
+                .message(format!("{} 很大", Path::new(path).file_name().unwrap_or_default().to_string_lossy()))
+                .detail(detail)
+                .buttons(["缩小载入", "仍然载入", "取消"])
+                .default_button(0)
+                .cancel_button(2)
+                .build();
+            dialog.choose(Some(&win), gio::Cancellable::NONE, move |r| match r {
+                Ok(0) => then(DecodeSize::Downscaled(budget)),
+                Ok(1) => then(DecodeSize::Full),
+                _ => {}
+            });
+        })
+    };
+    let load_image = {
+        let load_at = load_image_at.clone();
+        let confirm = confirm_load.clone();
+        Rc::new(move |path: &str| {
+            let load_at = load_at.clone();
+            let owned = path.to_string();
+            confirm(path, std::boxed::Box::new(move |size| load_at(&owned, None, size, load_ignored())));
+        })
+    };
+
+    // 切换到的标签仍是预览（后台时被取代的完整解码）或纹理已被卸载时，在后台重新排队解码
+    let resume_decode = {
+        let (full_decode, model, tabs) = (full_decode.clone(), model.clone(), tabs.clone());
+        Rc::new(move || {
+            let (quality, generation, fit) =
+                model.read(|s| (s.quality, s.generation.current(), s.full_size.map(|_| (s.original_width, s.original_height))));
+            let fit = match quality {
+                TextureQuality::Final => return,
+                TextureQuality::Preview => None,
+                TextureQuality::Unloaded => fit,
+            };
+            if let Some(path) = tabs.borrow().active_path() { full_decode(generation, path, fit); }
+        })
+    };
+
+    // 切换标签：交换共享状态后刷新界面
+    let switch_tab: TabSwitchFn = {
+        let resume_decode = resume_decode.clone();
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let da = drawing_area.clone();
+        let cs = cs.clone();
+        let cr_rot = cr_rot.clone();
+        let refresh = refresh_view.clone();
+        let rebuild = rebuild_tabs.clone();
+        let start_playback = start_playback.clone();
+        Rc::new(move |index: usize| {
+            let switched = model.mutate(|s| tabs.borrow_mut().switch_to(index, s));
+            if switched {
+                *cs.borrow_mut() = None;
+                cr_rot.set(-1);
+                refresh();
+                rebuild();
+                da.queue_draw();
+                start_playback(autoplay());
+                resume_decode();
+            }
+        })
+    };
+    *switch_tab_ref.borrow_mut() = Some(switch_tab.clone());
+
+    // 关闭当前标签，最后一个标签关闭时关闭窗口
+    let close_tab = {
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let da = drawing_area.clone();
+        let cs = cs.clone();
+        let cr_rot = cr_rot.clone();
+        let refresh = refresh_view.clone();
+        let rebuild = rebuild_tabs.clone();
+        let win = window.clone();
+        let start_playback = start_playback.clone();
+        let resume_decode = resume_decode.clone();
+        Rc::new(move || {
+            let remaining = model.mutate(|s| tabs.borrow_mut().close_active(s));
+            if !remaining {
+                win.close();
+                return;
+            }
+            *cs.borrow_mut() = None;
+            cr_rot.set(-1);
+            refresh();
+            rebuild();
+            da.queue_draw();
+            start_playback(autoplay());
+            resume_decode();
+        })
+    };
+
+    // 删除到回收站：移出当前标签但保留其状态与纹理，Ctrl+Z 或提示中的撤销按钮可立即恢复
+    // 重命名同样可以撤销；操作按栈保存，连续按 Ctrl+Z 从最近的一次依次撤销
+    let undo_stack: Rc<RefCell<Vec<Undo>>> = Rc::new(RefCell::new(Vec::new()));
+    let push_undo = {
+        let undo_stack = undo_stack.clone();
+        Rc::new(move |undo: Undo| {
+            let mut stack = undo_stack.borrow_mut();
+            stack.push(undo);
+            if stack.len() > UNDO_LIMIT { stack.remove(0); }
+        })
+    };
+    let undo_timer: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let offer_undo = {
+        let undo_timer = undo_timer.clone();
+        let undo_toast = undo_toast.clone();
+        let undo_label = undo_label.clone();
+        Rc::new(move |text: &str| {
+            undo_label.set_text(text);
+            undo_toast.set_visible(true);
+            if let Some(id) = undo_timer.borrow_mut().take() { id.remove(); }
+            let timer = undo_timer.clone();
+            let id = glib::timeout_add_local_once(Duration::from_secs(10), clone!(#[weak] undo_toast, move || {
+                timer.borrow_mut().take();
+                undo_toast.set_visible(false);
+            }));
+            *undo_timer.borrow_mut() = Some(id);
+        })
+    };
+    let show_view = {
+        let da = drawing_area.clone();
+        let cs = cs.clone();
+        let cr_rot = cr_rot.clone();
+        let refresh = refresh_view.clone();
+        let rebuild = rebuild_tabs.clone();
+        let start_playback = start_playback.clone();
+        let resume_decode = resume_decode.clone();
+        Rc::new(move || {
+            *cs.borrow_mut() = None;
+            cr_rot.set(-1);
+            refresh();
+            rebuild();
+            da.queue_draw();
+            start_playback(autoplay());
+            resume_decode();
+        })
+    };
+    let delete_current = {
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let push_undo = push_undo.clone();
+        let offer_undo = offer_undo.clone();
+        let toast = show_toast.clone();
+        let show_view = show_view.clone();
+        Rc::new(move || {
+            let Some(path) = tabs.borrow().active_path().map(PathBuf::from) else { return };
+            if !model.has_image() { return; }
+            if let Err(e) = trash::trash(&path) {
+                toast(&format!("无法移到回收站: {}", e));
+                return;
+            }
+            let removed = model.mutate(|s| tabs.borrow_mut().remove_active(s));
+            if let Some((index, tab)) = removed { push_undo(Undo::Delete(index, std::boxed::Box::new(tab))); }
+            show_view();
+            
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            offer_undo(&format!("已将 {} 移到回收站", name));
+        })
+    };
+    // 文件改名后更新标签路径、同目录列表、路径栏和窗口标题
+    let after_rename = {
+        let tabs = tabs.clone();
+        let listing = current_listing.clone();
+        let refresh = refresh_view.clone();
+        let rebuild = rebuild_tabs.clone();
+        Rc::new(move |renamed: &[(PathBuf, PathBuf)]| {
+            tabs.borrow_mut().rename_paths(renamed);
+            listing(true);
+            refresh();
+            rebuild();
+        })
+    };
+    let undo_last = {
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let undo_stack = undo_stack.clone();
+        let undo_toast = undo_toast.clone();
+        let toast = show_toast.clone();
+        let show_view = show_view.clone();
+        let after_rename = after_rename.clone();
+        Rc::new(move || {
+            let Some(undo) = undo_stack.borrow_mut().pop() else { return };
+            match undo {
+                Undo::Delete(index, tab) => {
+                    if let Err(e) = trash::restore(Path::new(&tab.path)) {
+                        toast(&format!("无法恢复: {}", e));
+                        undo_stack.borrow_mut().push(Undo::Delete(index, tab));
+                        return;
+                    }
+                    model.mutate(|s| tabs.borrow_mut().insert(index, *tab, s));
+                    show_view();
+                }
+                Undo::Rename(from, to) => {
+                    let original = from.file_name().unwrap_or_default().to_string_lossy().to_string();
+                    if let Err(e) = rename::rename(&to, &original) {
+                        toast(&format!("无法恢复原名: {}", e));
+                        undo_stack.borrow_mut().push(Undo::Rename(from, to));
+                        return;
+                    }
+                    after_rename(&[(to, from)]);
+                }
+            }
+            if let Some(id) = undo_timer.borrow_mut().take() { id.remove(); }
+            undo_toast.set_visible(false);
+        })
+    };
+    let undo_click = undo_last.clone();
+    undo_btn.connect_clicked(move |_| undo_click());
+
+    // F2 重命名当前文件：路径栏换成预填文件名的输入框，回车确认，Esc 或移开焦点取消
+    let end_rename = {
+        let entry = rename_entry.clone();
+        let crumbs = path_crumbs.clone();
+        Rc::new(move || {
+            entry.set_visible(false);
+            crumbs.widget().set_visible(true);
+        })
+    };
+    let begin_rename = {
+        let tabs = tabs.clone();
+        let entry = rename_entry.clone();
+        let crumbs = path_crumbs.clone();
+        let toast = show_toast.clone();
+        let show_titlebar = show_titlebar.clone();
+        Rc::new(move || {
+            let Some(path) = tabs.borrow().active_path().map(PathBuf::from).filter(|p| p.is_file()) else {
+                toast("只能重命名磁盘上的图片文件");
+                return;
+            };
+            show_titlebar();
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            // 预先选中主文件名，直接输入时保留扩展名
+            let stem_len = Path::new(&name).file_stem().map_or(0, |s| s.to_string_lossy().chars().count());
+            entry.set_text(&name);
+            crumbs.widget().set_visible(false);
+            entry.set_visible(true);
+            entry.grab_focus();
+            entry.select_region(0, stem_len as i32);
+        })
+    };
+    {
+        let tabs = tabs.clone();
+        let toast = show_toast.clone();
+        let push_undo = push_undo.clone();
+        let offer_undo = offer_undo.clone();
+        let after_rename = after_rename.clone();
+        let end = end_rename.clone();
+        rename_entry.connect_activate(move |entry| {
+            let Some(path) = tabs.borrow().active_path().map(PathBuf::from) else { return end() };
+            let Some(from) = canonical_location(&path) else { return end() };
+            match rename::rename(&from, &entry.text()) {
+                // 出错时保留输入框，便于修改
+                Err(e) => toast(&e),
+                Ok(to) => {
+                    end();
+                    if to == from { return; }
+                    after_rename(&[(from.clone(), to.clone())]);
+                    let (old, new) = (from.file_name().unwrap_or_default(), to.file_name().unwrap_or_default());
+                    offer_undo(&format!("已将 {} 重命名为 {}", old.to_string_lossy(), new.to_string_lossy()));
+                    push_undo(Undo::Rename(from, to));
+                }
+            }
+        });
+    }
+    let rename_keys = gtk4::EventControllerKey::new();
+    rename_keys.connect_key_pressed(clone!(#[strong] end_rename, move |_, key, _, _| {
+        if key != gdk::Key::Escape { return glib::Propagation::Proceed; }
+        end_rename();
+        glib::Propagation::Stop
+    }));
+    rename_entry.add_controller(rename_keys);
+    let rename_focus = gtk4::EventControllerFocus::new();
+    rename_focus.connect_leave(clone!(#[strong] end_rename, move |_| end_rename()));
+    rename_entry.add_controller(rename_focus);
+
+    // Shift+F2 按浏览顺序为当前目录的图片批量编号，确认前列出所有改动
+    let number_listing = {
+        let listing = current_listing.clone();
+        let window = window.clone();
+        let toast = show_toast.clone();
+        let after_rename = after_rename.clone();
+        Rc::new(move || {
+            let Some((l, _)) = listing(true) else {
+                toast("只能为磁盘上的图片文件编号");
+                return;
+            };
+            let prefix = l.dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let files = l.files.clone();
+            let (toast, after_rename) = (toast.clone(), after_rename.clone());
+            show_numbering_dialog(&window, files.clone(), &prefix, Rc::new(move |prefix: &str| {
+                let plan = rename::numbered(&files, prefix.trim());
+                rename::apply(&plan, prefix)?;
+                after_rename(&plan);
+                toast(&format!("已为 {} 个文件编号", plan.len()));
+                Ok(())
+            }));
+        })
+    };
+
+    // 在新标签中打开图片（当前没有图片时直接载入）
+    let open_tab = {
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let load_at = load_image_at.clone();
+        let confirm = confirm_load.clone();
+        let close = close_tab.clone();
+        Rc::new(move |path: &str, view: Option<ViewRequest>| {
+            let (model, tabs, load_at, close) = (model.clone(), tabs.clone(), load_at.clone(), close.clone());
+            let owned = path.to_string();
+            confirm(path, std::boxed::Box::new(move |size| {
+                // 正在重新解码的标签同样算已有图片
+                let has_image = model.read(|s| s.pixbuf.is_some() || s.quality != TextureQuality::Final);
+                if has_image {
+                    model.mutate(|s| tabs.borrow_mut().push(&owned, s));
+                }
+                // 载入失败（包括所有重试都失败）时丢弃新建的标签，回到之前的图片
+                load_at(&owned, view, size, std::boxed::Box::new(move |ok| if !ok && has_image { close() }));
+            }));
+        })
+    };
+
+    // 第一张图片到达（或确定不会到达）后调用：以 --overlay 启动时此时才创建置顶窗口，
+    // 没有图片时改为显示普通窗口，让错误提示可见
+    let first_image = {
+        let state = state.clone();
+        let overlay_pos = overlay_pos.clone();
+        let enter_overlay = enter_overlay.clone();
+        let current_mode = current_mode.clone();
+        let window = window.clone();
+        let start_overlay = initial_mode == WindowMode::Overlay;
+        let done = Cell::new(false);
+        Rc::new(move || {
+            if done.replace(true) || !start_overlay { return; }
+            if state.borrow().pixbuf.is_none() {
+                current_mode.set(WindowMode::Normal);
+                window.present();
+                return;
+            }
+            // 计算居中位置，屏幕尺寸未知时使用默认边距
+            let (scaled_w, scaled_h) = get_scaled_size(&state.borrow());
+            {
+                let mut pos = overlay_pos.borrow_mut();
+                let (left, top) = match get_screen_size() {
+                    Some((screen_w, screen_h)) => ((screen_w - scaled_w) / 2, (screen_h - scaled_h) / 2),
+                    None => (pos.margin_left, pos.margin_top),
+                };
+                // 多窗口时逐个错开，避免完全重叠（普通窗口在 X11 下于首次显示时错开）
+                pos.margin_left = left.max(0) + cascade * placement::CASCADE_STEP;
+                pos.margin_top = top.max(0) + cascade * placement::CASCADE_STEP;
+            }
+            enter_overlay(OverlayEntry::Startup);
+        })
+    };
+    let show_error = {
+        let win = window.clone();
+        Rc::new(move |message: &str, detail: &str| {
+            eprintln!("{}: {}", message, detail);
+            gtk4::AlertDialog::builder().message(message).detail(detail).build().show(Some(&win));
+        })
+    };
+
+    // 目录中没有图片时显示占位提示
+    let show_empty = {
+        let empty_label = empty_label.clone();
+        Rc::new(move |dir: &Path| {
+            eprintln!("{} 中没有图片", dir.display());
+            empty_label.set_text(&format!("“{}” 中没有图片", dir.display()));
+            empty_label.set_visible(true);
+        })
+    };
+
+    // 初始加载图片
+    // 目录树的根本身没有图片时，第一张图片由后台遍历载入
+    let tree_loads_first = tree_feed.is_some() && matches!(source, Source::Files(ref files) if files.is_empty());
+    match source {
+        Source::Files(files) => {
+            let mut files = files.into_iter();
+            let first = files.next();
+            let load = load_image_at.clone();
+            let confirm = confirm_load.clone();
+            let open_rest = open_tab.clone();
+            let rest: Vec<InitialFile> = files.collect();
+            let first_image = first_image.clone();
+            let show_empty = show_empty.clone();
+            glib::idle_add_local_once(move || {
+                for dir in &empty_dirs {
+                    show_empty(dir);
+                }
+                let Some(InitialFile { path, view }) = first else {
+                    if !tree_loads_first { first_image(); }
+                    return;
+                };
+                confirm(&path.clone(), std::boxed::Box::new(move |size| {
+                    let first_loaded = first_image.clone();
+                    load(&path, view, size, std::boxed::Box::new(move |_| first_loaded()));
+                    for file in &rest {
+                        open_rest(&file.path, file.view);
+                    }
+                    // 其余标签的载入会取代仍在重试的第一张，不再等它结束
+                    if !rest.is_empty() { first_image(); }
+                }));
+            });
+        }
+        Source::Stdin => {
+            // 在工作线程中读取，主线程轮询；只要开始收到数据就不再计时
+            let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<u8>, String>>();
+            let received = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let received_reader = received.clone();
+            std::thread::spawn(move || {
+                use std::io::Read;
+                let mut data = Vec::new();
+                let mut buf = vec![0u8; 64 * 1024];
+                let mut stdin = std::io::stdin().lock();
+                let result = loop {
+                    match stdin.read(&mut buf) {
+                        Ok(0) => break Ok(data),
+                        Ok(n) => {
+                            data.extend_from_slice(&buf[..n]);
+                            received_reader.store(true, std::sync::atomic::Ordering::Relaxed);
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                        Err(e) => break Err(e.to_string()),
+                    }
+                };
+                tx.send(result).ok();
+            });
+            let show = show_texture.clone();
+            let (first_image, show_error) = (first_image.clone(), show_error.clone());
+            let started = std::time::Instant::now();
+            // 读取期间打开了其他文件时不再用标准输入的图片替换
+            let model = model.clone();
+            let generation = model.mutate(|s| s.generation.begin());
+            glib::timeout_add_local(Duration::from_millis(50), move || {
+                let result = match rx.try_recv() {
+                    Err(std::sync::mpsc::TryRecvError::Empty) => {
+                        if received.load(std::sync::atomic::Ordering::Relaxed) || started.elapsed() < STDIN_TIMEOUT {
+                            return glib::ControlFlow::Continue;
+                        }
+                        Err(format!("{} 秒内没有收到任何数据", STDIN_TIMEOUT.as_secs()))
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("读取线程意外退出".to_string()),
+                    Ok(result) => result,
+                };
+                // 先显示窗口再弹出错误，否则置顶启动时错误对话框没有可见的父窗口
+                match result.and_then(|data| decode_stdin(&data)) {
+                    Ok(_) if !model.read(|s| s.generation.is_current(generation)) => first_image(),
+                    Ok((texture, report)) => {
+                        show(texture, report, STDIN_NAME, None);
+                        first_image();
+                    }
+                    Err(e) => {
+                        first_image();
+                        show_error("无法从标准输入读取图片", &e);
+                    }
+                }
+                glib::ControlFlow::Break
+            });
+        }
+        Source::Clipboard => {
+            // 剪贴板更新时替换图片；置顶窗口已存在时调整其大小，而不是再创建一个
+            let clipboard = window.clipboard();
+            let read_clipboard = {
+                let clipboard = clipboard.clone();
+                let show = show_texture.clone();
+                let model = model.clone();
+                let overlay_window = overlay_window.clone();
+                let (first_image, show_error) = (first_image.clone(), show_error.clone());
+                Rc::new(move |initial: bool| {
+                    let mime = clipboard.formats().mime_types().first().map(|m| m.to_string()).unwrap_or_default();
+                    let (show, model, overlay_window) = (show.clone(), model.clone(), overlay_window.clone());
+                    let (first_image, show_error) = (first_image.clone(), show_error.clone());
+                    // 连续更新时先发起的读取可能后完成，只显示最新一次读取的结果
+                    let generation = model.mutate(|s| s.generation.begin());
+                    clipboard.read_texture_async(gio::Cancellable::NONE, move |r| {
+                        if !model.read(|s| s.generation.is_current(generation)) { return; }
+                        match r {
+                            Ok(Some(texture)) => {
+                                let report = LoadReport { mime, decoder: "GDK 剪贴板".to_string(), ..LoadReport::default() };
+                                show(texture, report, CLIPBOARD_NAME, None);
+                                if let Some(ref win) = *overlay_window.borrow() {
+                                    let (w, h) = model.read(get_scaled_size);
+                                    resize_overlay(win, w, h);
+                                }
+                                first_image();
+                            }
+                            // 之后的更新不是图片时保留当前图片
+                            Ok(None) if initial => {
+                                first_image();
+                                show_error("剪贴板中没有图片", "请先复制一张图片");
+                            }
+                            Err(e) if initial => {
+                                first_image();
+                                show_error("无法读取剪贴板中的图片", &e.to_string());
+                            }
+                            _ => {}
+                        }
+                    });
+                })
+            };
+            read_clipboard(true);
+            // 本程序自己复制的内容不重新载入
+            clipboard.connect_changed(move |c| if !c.is_local() { read_clipboard(false) });
+        }
+        // 已在前面换成后台读取的列表
+        Source::List(_) | Source::Selection(_) => {}
+        Source::Pin(name, pin) => {
+            // 载入后套用保存的缩放、方向与位置；展台模式不能置顶，只显示图片
+            let restore = {
+                let (load, confirm) = (load_image_at.clone(), confirm_load.clone());
+                let (model, overlay_pos, enter_overlay) = (model.clone(), overlay_pos.clone(), enter_overlay.clone());
+                let (first_image, show_error) = (first_image.clone(), show_error.clone());
+                let start_overlay = initial_mode == WindowMode::Overlay;
+                Rc::new(move |pin: pins::Pin| {
+                    let (load, model, overlay_pos) = (load.clone(), model.clone(), overlay_pos.clone());
+                    let (enter_overlay, first_image, show_error) = (enter_overlay.clone(), first_image.clone(), show_error.clone());
+                    let path = pin.path.clone();
+                    confirm(&path.clone(), std::boxed::Box::new(move |size| load(&path, None, size, std::boxed::Box::new(move |ok| {
+                        if !ok {
+                            first_image();
+                            show_error("无法载入固定的图片", &pin.path);
+                            return;
+                        }
+                        if !start_overlay { return first_image(); }
+                        model.mutate(|s| {
+                            s.scale = pin.scale.clamp(0.1, 50.0);
+                            s.rotation = pin.rotation.rem_euclid(4);
+                        });
+                        *overlay_pos.borrow_mut() = OverlayPosition {
+                            margin_left: pin.margin_left,
+                            margin_top: pin.margin_top,
+                            opacity: pin.opacity.clamp(0.1, 1.0),
+                            monitor: pin.monitor.clone(),
+                        };
+                        enter_overlay(OverlayEntry::Startup);
+                    }))));
+                })
+            };
+            if Path::new(&pin.path).is_file() {
+                glib::idle_add_local_once(move || restore(pin));
+            } else {
+                // 图片已移动或删除：显示普通窗口，让用户定位文件，定位后更新保存的路径
+                first_image();
+                let dialog = gtk4::AlertDialog::builder()
+                    .message(format!("找不到固定位置 {} 的图片", name))
+                    .detail(format!("{} 已不存在，可能已被移动或删除。", pin.path))
+                    .buttons(["定位文件…", "取消"])
+                    .default_button(0)
+                    .cancel_button(1)
+                    .build();
+                let win = window.clone();
+                dialog.choose(Some(&window), gio::Cancellable::NONE, move |r| {
+                    if !matches!(r, Ok(0)) { return; }
+                    let chooser = image_file_dialog("定位图片");
+                    if let Some(dir) = Path::new(&pin.path).parent().filter(|d| d.is_dir()) {
+                        chooser.set_initial_folder(Some(&gio::File::for_path(dir)));
+                    }
+                    chooser.open(Some(&win), gio::Cancellable::NONE, move |r| {
+                        let Some(file) = r.ok().and_then(|f| f.path()) else { return };
+                        let mut pin = pin;
+                        pin.path = file.to_string_lossy().to_string();
+                        if let Err(e) = pins::save(&name, &pin) {
+                            eprintln!("无法更新固定位置 {}: {}", name, e);
+                        }
+                        restore(pin);
+                    });
+                });
+            }
+        }
+    }
+
+    // --recursive：在后台遍历目录树，逐批补全浏览列表，大目录树不会阻塞启动
+    // --files-from：在后台逐行读取列表；列表可能很长，读取时不检查文件是否存在，载入和浏览时才跳过不存在的
+    // --select：列表已经给出，同样经这里载入第一张存在的图片
+    if let Some(feed) = tree_feed {
+        let (tx, rx) = std::sync::mpsc::channel::<Vec<PathBuf>>();
+        let truncated = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let verbose = config.verbose;
+        // 列表中没有可打开的图片时提示的内容（标题与详情）
+        let (root, list_empty) = match feed {
+            TreeFeed::Walk(root, sort) => {
+                let (walk_root, truncated) = (root.clone(), truncated.clone());
+                std::thread::spawn(move || {
+                    let cut = siblings::walk(&walk_root, sort, siblings::TREE_LIMIT, |files| tx.send(files).is_ok());
+                    truncated.store(cut, std::sync::atomic::Ordering::Relaxed);
+                });
+                (root, None)
+            }
+            TreeFeed::List(list) => {
+                let name = list.as_ref().map_or(STDIN_NAME.to_string(), |p| p.display().to_string());
+                let input: std::io::Result<std::boxed::Box<dyn std::io::Read + Send>> = match list {
+                    Some(path) => std::fs::File::open(path).map(|f| std::boxed::Box::new(f) as _),
+                    None => Ok(std::boxed::Box::new(std::io::stdin())),
+                };
+                let empty = match input {
+                    Ok(input) => {
+                        std::thread::spawn(move || {
+                            let (found, blank) = siblings::read_list(input, |files| tx.send(files).is_ok());
+                            if verbose {
+                                eprintln!("--files-from: 读取了 {} 个路径，跳过 {} 个空行", found, blank);
+                            }
+                        });
+                        ("列表中没有可打开的图片", name)
+                    }
+                    // 发送端随之丢弃，轮询在第一次时即结束并提示
+                    Err(e) => ("无法读取文件列表", format!("{}: {}", name, e)),
+                };
+                (std::env::current_dir().unwrap_or_default(), Some(empty))
+            }
+            // 路径已在命令行中补全为绝对路径，可以分属不同目录
+            TreeFeed::Selection(files) => {
+                let detail = format!("选择的 {} 个文件都已不存在", files.len());
+                tx.send(files).ok();
+                (std::env::current_dir().unwrap_or_default(), Some(("选择的文件中没有可打开的图片", detail)))
+            }
+        };
+        let (load, confirm, first_image) = (load_image_at.clone(), confirm_load.clone(), first_image.clone());
+        let (tree, refresh, toast, show_empty, show_error) =
+            (tree.clone(), refresh_view.clone(), show_toast.clone(), show_empty.clone(), show_error.clone());
+        // 遍历期间用户已打开其他图片时，找到的第一张不再替换它
+        let model = model.clone();
+        let generation = model.mutate(|s| s.generation.begin());
+        let mut files: Vec<PathBuf> = Vec::new();
+        // 还在寻找第一张存在的图片时，下一个要检查的序号；找到后为 None
+        let mut first_pending = Some(0);
+        glib::timeout_add_local(Duration::from_millis(50), move || {
+            let before = files.len();
+            let done = loop {
+                match rx.try_recv() {
+                    Ok(batch) => files.extend(batch),
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break false,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => break true,
+                }
+            };
+            if files.len() > before {
+                *tree.borrow_mut() = Some(Rc::new(siblings::Listing::from_files(&root, files.clone())));
+                if let Some(next) = first_pending {
+                    let found = files[next..].iter().position(|f| f.is_file()).map(|i| next + i);
+                    first_pending = if found.is_some() { None } else { Some(files.len()) };
+                    if let Some(index) = found && verbose && index > 0 {
+                        eprintln!("浏览列表: 跳过 {} 个不存在的文件", index);
+                    }
+                    if let Some(index) = found && tree_loads_first && model.read(|s| s.generation.is_current(generation)) {
+                        let path = files[index].to_string_lossy().to_string();
+                        let (load, first_image) = (load.clone(), first_image.clone());
+                        confirm(&path.clone(), std::boxed::Box::new(move |size| {
+                            load(&path, None, size, std::boxed::Box::new(move |_| first_image()));
+                        }));
+                    }
+                }
+                refresh();
+            }
+            if !done { return glib::ControlFlow::Continue; }
+            if first_pending.is_some() {
+                first_image();
+                match &list_empty {
+                    Some((message, detail)) => show_error(message, detail),
+                    None => show_empty(&root),
+                }
+            } else if truncated.load(std::sync::atomic::Ordering::Relaxed) {
+                toast(&format!("图片过多，只列出了前 {} 张", siblings::TREE_LIMIT));
+            }
+            glib::ControlFlow::Break
+        });
+    }
+
+    // 置顶窗口存在时关闭普通窗口：保持应用运行，只留下置顶窗口
+    let overlay_win_close = overlay_window.clone();
+    let window_ref_close = window_ref.clone();
+    let app_close = app.clone();
+    window.connect_close_request(move |_| {
+        if overlay_win_close.borrow().is_some() {
+            normal_closed.set(true);
+            *hold_guard.borrow_mut() = Some(app_close.hold());
+            window_ref_close.borrow_mut().take();
+        }
+        glib::Propagation::Proceed
+    });
+
+    // 崩溃恢复：记录磁盘上的标签文件与活动标签的视图；窗口都已关闭后不再记录
+    if !kiosk {
+        let (app, state, tabs) = (app.clone(), state.clone(), tabs.clone());
+        let (mode, overlay_pos, overlay_window) = (current_mode.clone(), overlay_pos.clone(), overlay_window.clone());
+        let weak = window.downgrade();
+        recovery::register(Rc::new(move || {
+            let overlay = overlay_window.borrow().clone();
+            let windows = app.windows();
+            let open = weak.upgrade().is_some_and(|w| windows.iter().any(|o| *o == w))
+                || overlay.as_ref().is_some_and(|w| windows.iter().any(|o| o == w));
+            if !open { return recovery::Snapshot::Closed; }
+            let tabs = tabs.borrow();
+            let mut files = Vec::new();
+            let mut active = 0;
+            for (i, tab) in tabs.items.iter().enumerate() {
+                let Ok(file) = Path::new(&tab.path).canonicalize() else { continue };
+                if !file.is_file() { continue; }
+                if i == tabs.active { active = files.len(); }
+                files.push(file.to_string_lossy().to_string());
+            }
+            let Some(path) = files.get(active).cloned() else { return recovery::Snapshot::Empty };
+            let (scale, rotation) = {
+                let s = state.borrow();
+                (s.scale, s.rotation)
+            };
+            let pos = overlay_pos.borrow();
+            let monitor = overlay.as_ref()
+                .and_then(|w| w.surface())
+                .and_then(|surface| surface.display().monitor_at_surface(&surface))
+                .and_then(|m| m.connector())
+                .map(|c| c.to_string());
+            recovery::Snapshot::Window(recovery::WindowSession {
+                tabs: files,
+                active,
+                overlay: mode.get() == WindowMode::Overlay && overlay.is_some(),
+                view: pins::Pin {
+                    path,
+                    scale,
+                    rotation,
+                    margin_left: pos.margin_left,
+                    margin_top: pos.margin_top,
+                    opacity: overlay.as_ref().map_or(1.0, |w| w.opacity()),
+                    monitor,
+                },
+            })
+        }));
+    }
+
+    let win_crumbs = window.clone();
+    let load_crumbs = load_image.clone();
+    path_crumbs.connect_directory_clicked(move |dir| {
+        let dialog = image_file_dialog("选择图片");
+        dialog.set_initial_folder(Some(&gio::File::for_path(dir)));
+        let load = load_crumbs.clone();
+        dialog.open(Some(&win_crumbs), gio::Cancellable::NONE, move |r| {
+            if let Ok(f) = r
+                && let Some(p) = f.path()
+            {
+                remember_recent(&p);
+                load(&p.to_string_lossy());
+            }
+        });
+    });
+
+    let win_open = window.clone();
+    let load_open = load_image.clone();
+    open_btn.connect_clicked(move |_| {
+        let dialog = image_file_dialog("选择图片");
+        let load = load_open.clone();
+        dialog.open(Some(&win_open), gio::Cancellable::NONE, move |r| {
+            if let Ok(f) = r
+                && let Some(p) = f.path()
+            {
+                remember_recent(&p);
+                load(&p.to_string_lossy());
+            }
+        });
+    });
+
+    // 以标签页打开多个文件
+    let win_open_tabs = window.clone();
+    let open_tab_dialog = open_tab.clone();
+    let open_tabs_dialog = Rc::new(move || {
+        let dialog = image_file_dialog("选择图片（在新标签中打开）");
+        let open_tab = open_tab_dialog.clone();
+        dialog.open_multiple(Some(&win_open_tabs), gio::Cancellable::NONE, move |r| {
+            let Ok(files) = r else { return };
+            for i in 0..files.n_items() {
+                if let Some(p) = files.item(i).and_downcast::<gio::File>().and_then(|f| f.path()) {
+                    remember_recent(&p);
+                    open_tab(&p.to_string_lossy(), None);
+                }
+            }
+        });
+    });
+
+    // 拖放文件（图片区域与标题栏）：单个文件替换当前图片，多个文件以标签页打开
+    // 文件夹和不是图片的文件给出提示；置顶窗口中替换显示的图片并调整窗口大小，多个文件时只打开第一个
+    if !kiosk {
+        let handle: DropFn = {
+            let (load_at, confirm) = (load_image_at.clone(), confirm_load.clone());
+            let open_tab = open_tab.clone();
+            let (state, overlay_window, overlay_pos) = (state.clone(), overlay_window.clone(), overlay_pos.clone());
+            let toast = show_toast.clone();
+            Rc::new(move |paths: Vec<PathBuf>| {
+                let overlay = overlay_window.borrow().clone();
+                // 置顶时普通窗口隐藏着，提示改用对话框
+                let tell = |message: &str, detail: &str| match &overlay {
+                    Some(win) => gtk4::AlertDialog::builder().message(message).detail(detail).build().show(Some(win)),
+                    None => toast(&format!("{}：{}", message, detail)),
+                };
+                if paths.is_empty() {
+                    tell("无法打开", "只能打开本地文件");
+                    return false;
+                }
+                let name = |p: &Path| p.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let mut images = Vec::new();
+                for path in paths {
+                    if path.is_dir() {
+                        tell("不能打开文件夹", &name(&path));
+                    } else if let Some(kind) = header::read_prefix(&path, SNIFF_LEN).and_then(|d| header::not_an_image(&d)) {
+                        tell(kind.message(), &name(&path));
+                    } else {
+                        images.push(path.to_string_lossy().to_string());
+                    }
+                }
+                match (images.as_slice(), &overlay) {
+                    ([], _) => return false,
+                    ([first, ..], Some(win)) => {
+                        let (load_at, state, overlay_pos, win) = (load_at.clone(), state.clone(), overlay_pos.clone(), win.clone());
+                        let path = first.clone();
+                        confirm(first, std::boxed::Box::new(move |size| {
+                            load_at(&path, None, size, std::boxed::Box::new(move |ok| {
+                                if ok { resize_overlay_to_state(&win, &state, &overlay_pos); }
+                            }));
+                        }));
+                    }
+                    ([single], None) => {
+                        let (load_at, path) = (load_at.clone(), single.clone());
+                        confirm(single, std::boxed::Box::new(move |size| load_at(&path, None, size, load_ignored())));
+                    }
+                    (many, None) => for p in many { open_tab(p, None); },
+                }
+                true
+            })
+        };
+        drawing_area.add_controller(file_drop_target(handle.clone()));
+        titlebar.add_controller(file_drop_target(handle.clone()));
+        *drop_files.borrow_mut() = Some(handle);
+    }
+
+    // 粘贴：剪贴板中有图片时直接显示（与 --clipboard 相同，不跟随之后的更新）；
+    // 只有文本时按文件路径或 file:// 地址打开其中第一个文件
+    let paste_clipboard: Rc<dyn Fn()> = {
+        let (show, state, model, window) = (show_texture.clone(), state.clone(), model.clone(), window.clone());
+        let (load_at, confirm) = (load_image_at.clone(), confirm_load.clone());
+        let (overlay_window, overlay_pos) = (overlay_window.clone(), overlay_pos.clone());
+        let toast = show_toast.clone();
+        Rc::new(move || {
+            let clipboard = window.clipboard();
+            let formats = clipboard.formats();
+            let mime = formats.mime_types().first().map(|m| m.to_string()).unwrap_or_default();
+            let (show, state, model, overlay_window, overlay_pos) = (show.clone(), state.clone(), model.clone(), overlay_window.clone(), overlay_pos.clone());
+            let (load_at, confirm, toast) = (load_at.clone(), confirm.clone(), toast.clone());
+            // 其他程序复制的图片只列出 image/* 类型，本程序复制的为纹理对象
+            let has_image = formats.contains_type(gdk::Texture::static_type())
+                || formats.mime_types().iter().any(|m| m.starts_with("image/"));
+            if !has_image {
+                clipboard.read_text_async(gio::Cancellable::NONE, move |r| {
+                    let text = r.ok().flatten().unwrap_or_default();
+                    let Some(path) = paths_from_uri_list(&text).into_iter().find(|p| p.is_file()) else {
+                        toast("剪贴板中没有图片或文件路径");
+                        return;
+                    };
+                    let path = path.to_string_lossy().to_string();
+                    let load = path.clone();
+                    confirm(&path, std::boxed::Box::new(move |size| {
+                        load_at(&load, None, size, std::boxed::Box::new(move |ok| {
+                            if ok && let Some(ref win) = *overlay_window.borrow() {
+                                resize_overlay_to_state(win, &state, &overlay_pos);
+                            }
+                        }));
+                    }));
+                });
+                return;
+            }
+            // 读取期间又打开了别的图片时不再替换
+            let generation = model.mutate(|s| s.generation.begin());
+            clipboard.read_texture_async(gio::Cancellable::NONE, move |r| {
+                if !model.read(|s| s.generation.is_current(generation)) { return; }
+                match r {
+                    Ok(Some(texture)) => {
+                        let report = LoadReport { mime, decoder: "GDK 剪贴板".to_string(), ..LoadReport::default() };
+                        show(texture, report, CLIPBOARD_NAME, None);
+                        if let Some(ref win) = *overlay_window.borrow() {
+                            resize_overlay_to_state(win, &state, &overlay_pos);
+                        }
+                    }
+                    Ok(None) => toast("剪贴板中没有图片"),
+                    Err(e) => toast(&format!("无法读取剪贴板中的图片: {}", e)),
+                }
+            });
+        })
+    };
+    paste_btn.connect_clicked(clone!(#[strong] paste_clipboard, move |_| paste_clipboard()));
+
+    // 导出多页/多帧图片
+    let state_extract = state.clone();
+    let tabs_extract = tabs.clone();
+    let win_extract = window.clone();
+    let toast_extract = show_toast.clone();
+    let jobs_extract = jobs.clone();
+    let open_extract = Rc::new(move || {
+        if !state_extract.borrow().multi_frame {
+            toast_extract("当前图片只有一帧");
+            return;
+        }
+        if let Some(path) = tabs_extract.borrow().active_path() {
+            show_extract_dialog(&win_extract, &jobs_extract, PathBuf::from(path), toast_extract.clone());
+        }
+    });
+    let open_extract_btn = open_extract.clone();
+    let info_popover_extract = info_popover.clone();
+    extract_btn.connect_clicked(move |_| {
+        info_popover_extract.popdown();
+        open_extract_btn();
+    });
+
+    // 导出正在显示的帧：按原始分辨率重新解码该帧，在工作线程中解码和写出，不改动当前的显示
+    let export_frame = {
+        let (state, tabs, win, reveal) = (state.clone(), tabs.clone(), window.clone(), show_reveal.clone());
+        let (toast, jobs) = (show_toast.clone(), jobs.clone());
+        Rc::new(move || {
+            let (multi_frame, frame) = { let s = state.borrow(); (s.multi_frame, s.frame) };
+            let Some(path) = tabs.borrow().active_path().map(PathBuf::from).filter(|p| p.is_file()) else { return };
+            if !multi_frame {
+                toast("当前图片只有一帧");
+                return;
+            }
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let dialog = FileDialog::builder()
+                .title("导出当前帧")
+                .modal(true)
+                .initial_name(format!("{}_{:03}.png", stem, frame))
+                .build();
+            if let Some(dir) = path.parent() { dialog.set_initial_folder(Some(&gio::File::for_path(dir))); }
+            let (toast, reveal, jobs) = (toast.clone(), reveal.clone(), jobs.clone());
+            dialog.save(Some(&win), gio::Cancellable::NONE, move |r| {
+                let Some(target) = r.ok().and_then(|f| f.path()) else { return };
+                let job = jobs.start(&format!("导出第 {} 帧", frame));
+                // None 表示已取消，没有写出文件
+                let (tx, rx) = std::sync::mpsc::channel::<Option<Result<(), String>>>();
+                let (source, out, cancel) = (path.clone(), target.clone(), job.cancellable());
+                std::thread::spawn(move || {
+                    let saved = extract::decode_frame(&source, frame).map(|img| {
+                        if cancel.is_cancelled() { return None; }
+                        Some(img.save_with_format(&out, image::ImageFormat::Png).map_err(|e| format!("{}: {}", out.display(), e)))
+                    });
+                    tx.send(saved.unwrap_or_else(|e| Some(Err(e)))).ok();
+                });
+                let (toast, reveal) = (toast.clone(), reveal.clone());
+                let mut job = Some(job);
+                glib::timeout_add_local(Duration::from_millis(50), move || {
+                    let saved = match rx.try_recv() {
+                        Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                        Err(std::sync::mpsc::TryRecvError::Disconnected) => Some(Err("导出线程意外退出".to_string())),
+                        Ok(saved) => saved,
+                    };
+                    if let Some(job) = job.take() { job.finish(); }
+                    match saved {
+                        Some(Ok(())) => reveal(&format!("已导出第 {} 帧", frame), target.clone()),
+                        Some(Err(e)) => toast(&format!("导出失败: {}", e)),
+                        None => toast("已取消导出"),
+                    }
+                    glib::ControlFlow::Break
+                });
+            });
+        })
+    };
+    let set_poster = {
+        let (state, tabs, toast) = (state.clone(), tabs.clone(), show_toast.clone());
+        Rc::new(move || {
+            let (multi_frame, frame) = { let s = state.borrow(); (s.multi_frame, s.frame) };
+            let Some(path) = tabs.borrow().active_path().map(PathBuf::from).filter(|p| p.is_file()) else { return };
+            if !multi_frame {
+                toast("当前图片只有一帧");
+                return;
+            }
+            match posters::set(&path, frame) {
+                Ok(()) if frame > 1 => toast(&format!("已将第 {} 帧设为封面", frame)),
+                Ok(()) => toast("封面恢复为第 1 帧"),
+                Err(e) => toast(&e),
+            }
+        })
+    };
+    // 复制的就是正在显示的帧，与复制按钮相同（按视图旋转）
+    let copy_frame: Rc<dyn Fn()> = Rc::new(clone!(#[weak] copy_btn, move || copy_btn.emit_clicked()));
+    for (btn, action) in [(&export_frame_btn, export_frame.clone() as Rc<dyn Fn()>), (&copy_frame_btn, copy_frame.clone()),
+                          (&poster_btn, set_poster.clone() as Rc<dyn Fn()>)] {
+        btn.connect_clicked(clone!(#[weak] info_popover, move |_| {
+            info_popover.popdown();
+            action();
+        }));
+    }
+
+    // 设为默认程序：确认后注册，并报告更改的类型或失败原因
+    let win_default = window.clone();
+    let info_popover_default = info_popover.clone();
+    default_app_btn.connect_clicked(move |_| {
+        info_popover_default.popdown();
+        let confirm = gtk4::AlertDialog::builder()
+            .message("设为默认图片查看器？")
+            .detail(format!("将以下类型的默认打开方式改为本程序：\n{}", association::MIME_TYPES.join(", ")))
+            .buttons(["取消", "设为默认"])
+            .default_button(1)
+            .cancel_button(0)
+            .build();
+        let win = win_default.clone();
+        confirm.choose(Some(&win_default), gio::Cancellable::NONE, move |r| {
+            if r != Ok(1) { return; }
+            let (message, detail) = match association::register() {
+                Ok(outcome) if outcome.failed.is_empty() => ("已设为默认图片查看器", outcome.describe()),
+                Ok(outcome) => ("部分类型设置失败", outcome.describe()),
+                Err(e) => ("无法设为默认程序", e),
+            };
+            gtk4::AlertDialog::builder().message(message).detail(detail).build().show(Some(&win));
+        });
+    });
+
+    // 识别整张图片中的文字并复制到剪贴板（在工作线程中运行）
+    #[cfg(feature = "ocr")]
+    let copy_text = {
+        let state = state.clone();
+        let win = window.clone();
+        let toast = show_toast.clone();
+        let language = config.ocr_language.clone();
+        let jobs = jobs.clone();
+        let busy = Rc::new(Cell::new(false));
+        Rc::new(move || {
+            if busy.get() { return; }
+            if state.borrow().quality != TextureQuality::Final {
+                toast("完整分辨率的图片仍在解码，请稍后再试");
+                return;
+            }
+            let Some(texture) = state.borrow().pixbuf.clone() else { return };
+            let mut downloader = gdk::TextureDownloader::new(&texture);
+            downloader.set_format(gdk::MemoryFormat::R8g8b8a8);
+            let (bytes, stride) = downloader.download_bytes();
+            let (w, h) = (texture.width(), texture.height());
+            busy.set(true);
+            win.set_cursor_from_name(Some("progress"));
+            toast("正在识别文字…");
+            let job = jobs.start("识别文字");
+            
+            let (tx, rx) = std::sync::mpsc::channel::<Result<String, String>>();
+            let (language, cancel) = (language.clone(), job.cancellable());
+            std::thread::spawn(move || {
+                // 识别本身不能中断，开始前已取消时不再识别
+                if cancel.is_cancelled() { return; }
+                tx.send(ocr::recognize(&bytes, w, h, stride as i32, &language)).ok();
+            });
+            let (busy, toast, win) = (busy.clone(), toast.clone(), win.clone());
+            let mut job = Some(job);
+            glib::timeout_add_local(Duration::from_millis(50), move || {
+                let cancelled = job.as_ref().is_some_and(|j| j.is_cancelled());
+                let result = match rx.try_recv() {
+                    // 取消后不再等待结果，工作线程识别完后自行结束
+                    _ if cancelled => None,
+                    Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => Some(Err("识别线程意外退出".to_string())),
+                    Ok(result) => Some(result),
+                };
+                if let Some(job) = job.take() { job.finish(); }
+                busy.set(false);
+                win.set_cursor(None);
+                let Some(result) = result else {
+                    toast("已取消文字识别");
+                    return glib::ControlFlow::Break;
+                };
+                match result {
+                    Ok(text) if text.is_empty() => toast("未识别到文字"),
+                    Ok(text) => {
+                        win.clipboard().set_text(&text);
+                        toast(&format!("已复制 {} 个字符", text.chars().count()));
+                    }
+                    Err(e) => gtk4::AlertDialog::builder()
+                        .message("文字识别失败")
+                        .detail(e)
+                        .build()
+                        .show(Some(&win)),
+                }
+                glib::ControlFlow::Break
+            });
+        })
+    };
+
+    // 暂停 / 继续播放动画；播放已停止（如载入失败后）时从头播放
+    let toggle_animation: Rc<dyn Fn()> = {
+        let (player, start_playback) = (player.clone(), start_playback.clone());
+        let (state, toast) = (state.clone(), show_toast.clone());
+        Rc::new(move || {
+            let paused = player.borrow().as_ref().map(|p| {
+                p.set_paused(!p.is_paused());
+                p.is_paused()
+            });
+            match paused {
+                Some(true) => toast(&format!("动画已暂停（第 {} 帧）", state.borrow().frame)),
+                Some(false) => toast("继续播放动画"),
+                None => {
+                    start_playback(true);
+                    if player.borrow().is_none() { toast("当前图片不是动画"); }
+                }
+            }
+        })
+    };
+
+    // 连拍翻页播放：从当前图片的下一张播放到组末尾，窗口不可见时暂停
+    let burst_timer: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let stop_burst = {
+        let burst_timer = burst_timer.clone();
+        Rc::new(move || -> bool {
+            let id = burst_timer.borrow_mut().take();
+            id.map(|id| id.remove()).is_some()
+        })
+    };
+    let play_burst = {
+        let listing = current_listing.clone();
+        let load_at = load_image_at.clone();
+        let stop = stop_burst.clone();
+        let toast = show_toast.clone();
+        let suspension = suspension.clone();
+        Rc::new(move || {
+            if stop() {
+                toast("已停止播放连拍");
+                return;
+            }
+            let Some((l, path)) = listing(true) else { return };
+            let (Some(pos), Some(group)) = (l.position(&path), l.burst(&path)) else {
+                toast("当前图片不属于连拍组");
+                return;
+            };
+            toast(&format!("播放连拍（{} 张），再按 B 停止", group.len()));
+            // 已在最后一张时从头播放
+            let mut next = if pos + 1 < group.end { pos + 1 } else { group.start };
+            let (timer, load_at, suspension) = (burst_timer.clone(), load_at.clone(), suspension.clone());
+            let id = glib::timeout_add_local(BURST_FRAME_INTERVAL, move || {
+                if suspension.is_suspended() { return glib::ControlFlow::Continue; }
+                load_at(&l.files[next].to_string_lossy(), None, DecodeSize::Full, load_ignored());
+                next += 1;
+                if next < group.end { return glib::ControlFlow::Continue; }
+                timer.borrow_mut().take();
+                glib::ControlFlow::Break
+            });
+            *burst_timer.borrow_mut() = Some(id);
+        })
+    };
+    // 方向键浏览同目录图片：左右在连拍内移动（不是连拍时移到相邻文件），上下跳到上一组/下一组
+    // peek_target 只找出将要打开的图片及途中跳过的不存在的文件数，不载入
+    let peek_target = {
+        let listing = current_listing.clone();
+        Rc::new(move |key: gdk::Key| -> Option<(PathBuf, usize)> {
+            let step_once = |l: &siblings::Listing, path: &Path| -> Option<PathBuf> {
+                match key {
+                    gdk::Key::Left => l.step_within(path, false),
+                    gdk::Key::Right => l.step_within(path, true),
+                    gdk::Key::Up => l.step_group(path, false),
+                    _ => l.step_group(path, true),
+                }.map(Path::to_path_buf)
+            };
+            // --files-from 的列表读取时没有检查文件是否存在，在这里跳过不存在的
+            let step = |l: &siblings::Listing, path: &Path| -> Option<(PathBuf, usize)> {
+                let mut target = step_once(l, path)?;
+                let mut skipped = 0;
+                while !target.exists() {
+                    skipped += 1;
+                    target = step_once(l, &target)?;
+                }
+                Some((target, skipped))
+            };
+            // 列表可能已过期（文件被删除或新增），找不到时重新扫描一次
+            listing(false).and_then(|(l, p)| step(&l, &p))
+                .or_else(|| listing(true).and_then(|(l, p)| step(&l, &p)))
+        })
+    };
+    let navigate = {
+        let peek = peek_target.clone();
+        let verbose = config.verbose;
+        let load = load_image.clone();
+        let stop = stop_burst.clone();
+        let slideshow = slideshow.clone();
+        let (tabs, dir_views, toast) = (tabs.clone(), dir_views.clone(), show_toast.clone());
+        let date_separators = config.date_separators;
+        Rc::new(move |key: gdk::Key| {
+            stop();
+            let Some((target, skipped)) = peek(key) else { return };
+            // 手动翻页后幻灯片重新计时，不会紧接着再前进一张
+            slideshow.restart();
+            if verbose && skipped > 0 {
+                eprintln!("跳过 {} 个不存在的文件", skipped);
+            }
+            // 按拍摄日期浏览时，跨越日期提示新的一天（拍摄时间已在排序时缓存）
+            let current = tabs.borrow().active_path().map(PathBuf::from);
+            if date_separators && dir_views.for_file(&target).sort == Some(config::SortOrder::Date)
+                && let Some(day) = siblings::capture_day(&target)
+                && current.and_then(|p| siblings::capture_day(&p)).is_some_and(|d| d != day) {
+                toast(&format!("— {} —", day));
+            }
+            load(&target.to_string_lossy());
+        })
+    };
+    prev_btn.connect_clicked(clone!(#[strong] navigate, move |_| navigate(gdk::Key::Left)));
+    next_btn.connect_clicked(clone!(#[strong] navigate, move |_| navigate(gdk::Key::Right)));
+
+    // 幻灯片：打开了多个标签时依次切换标签，否则在浏览列表（目录、--select 等）中前进，到末尾后从头开始
+    // 跳过已不存在的文件；窗口不可见时不前进
+    {
+        let (listing, load, switch_tab, tabs) = (current_listing.clone(), load_image.clone(), switch_tab.clone(), tabs.clone());
+        let suspension = suspension.clone();
+        slideshow.set_advance(move || {
+            let (count, active) = { let t = tabs.borrow(); (t.items.len(), t.active) };
+            if count > 1 {
+                switch_tab((active + 1) % count);
+                return true;
+            }
+            let Some((l, path)) = listing(false) else { return false };
+            let Some(pos) = l.position(&path) else { return false };
+            let len = l.files.len();
+            let Some(target) = (1..len).map(|k| &l.files[(pos + k) % len]).find(|f| f.is_file()) else { return false };
+            load(&target.to_string_lossy());
+            true
+        }, move || suspension.is_suspended());
+    }
+    let toggle_slideshow: Rc<dyn Fn()> = {
+        let slideshow = slideshow.clone();
+        Rc::new(move || if !slideshow.stop() { slideshow.start() })
+    };
+    {
+        let (btn, toast, interval) = (slideshow_btn.clone(), show_toast.clone(), slideshow.interval());
+        slideshow.connect_changed(move |change| {
+            btn.set_active(change == slideshow::Change::Started);
+            match change {
+                slideshow::Change::Started => toast(&format!("幻灯片：每 {} 秒一张，按 S 停止", interval)),
+                slideshow::Change::Stopped => toast("已停止幻灯片"),
+                slideshow::Change::Paused => toast("幻灯片已暂停，按 S 继续"),
+                slideshow::Change::Ended => toast("没有其他图片，幻灯片已停止"),
+            }
+        });
+    }
+    // 按钮状态与幻灯片不一致时才是用户点击（状态变化时会同步按钮）
+    slideshow_btn.connect_toggled(clone!(#[strong] slideshow, #[strong] toggle_slideshow, move |btn| {
+        if btn.is_active() != slideshow.is_running() { toggle_slideshow(); }
+    }));
+    // 关闭窗口时停止，定时器不再持有窗口中的对象
+    window.connect_destroy(clone!(#[strong] slideshow, move |_| { slideshow.stop(); }));
+    if config.slideshow { slideshow.start(); }
+
+    // 打开或关闭图片信息（标题栏隐藏时先显示出来）
+    let toggle_info: Rc<dyn Fn()> = {
+        let show = show_titlebar.clone();
+        Rc::new(clone!(#[weak] info_popover, move || {
+            if info_popover.is_visible() { return info_popover.popdown(); }
+            show();
+            info_popover.popup();
+        }))
+    };
+
+    // 悬停在上一张/下一张按钮上或按住方向键时，在按钮旁预览将要打开的图片：文件名与缩略图
+    // 缩略图在后台生成，还没有时只显示文件名，生成后补上
+    let thumbnails = Rc::new(thumbs::Thumbnails::default());
+    // 每个按钮一个预览弹出框：缩略图在上，文件名在下
+    let preview_for = |btn: &Button, position: gtk4::PositionType| {
+        let picture = gtk4::Picture::builder().can_shrink(true).content_fit(gtk4::ContentFit::Contain).build();
+        picture.set_size_request(thumbs::SIZE as i32, thumbs::SIZE as i32);
+        let name = Label::builder().ellipsize(gtk4::pango::EllipsizeMode::Middle).max_width_chars(24).build();
+        let content = Box::new(Orientation::Vertical, 6);
+        content.append(&picture);
+        content.append(&name);
+        let popover = gtk4::Popover::builder()
+            .autohide(false)
+            .can_focus(false)
+            .can_target(false)
+            .position(position)
+            .child(&content)
+            .build();
+        popover.add_css_class("nav-preview");
+        popover.set_parent(btn);
+        btn.connect_destroy(clone!(#[weak] popover, move |_| popover.unparent()));
+        (popover, picture, name)
+    };
+    let previews = [
+        preview_for(&prev_btn, gtk4::PositionType::Right),
+        preview_for(&next_btn, gtk4::PositionType::Left),
+    ];
+    let hide_preview = {
+        let popovers = previews.clone().map(|(popover, _, _)| popover);
+        Rc::new(move || {
+            for popover in &popovers {
+                popover.popdown();
+            }
+        })
+    };
+    let show_preview = {
+        let peek = peek_target.clone();
+        let (prev_btn, next_btn) = (prev_btn.clone(), next_btn.clone());
+        // 当前预览的文件；缩略图生成后只在仍预览同一文件时显示
+        let shown: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+        Rc::new(move |key: gdk::Key| {
+            let forward = key != gdk::Key::Left;
+            let btn = if forward { &next_btn } else { &prev_btn };
+            let target = peek(key).map(|(target, _)| target).filter(|_| btn.is_visible());
+            previews[!forward as usize].0.popdown();
+            let (popover, picture, name) = &previews[forward as usize];
+            let Some(target) = target else {
+                popover.popdown();
+                return;
+            };
+            name.set_text(&target.file_name().unwrap_or_default().to_string_lossy());
+            picture.set_visible(false);
+            *shown.borrow_mut() = Some(target.clone());
+            let (picture, shown, source) = (picture.clone(), shown.clone(), target.clone());
+            thumbnails.request(&target, move |texture| {
+                if shown.borrow().as_ref() != Some(&source) { return; }
+                picture.set_paintable(Some(texture));
+                picture.set_visible(true);
+            });
+            popover.popup();
+        })
+    };
+    // 悬停片刻后显示，与工具提示的节奏一致；点击后先收起，新图片载入后再预览下一张
+    for (btn, key) in [(&prev_btn, gdk::Key::Left), (&next_btn, gdk::Key::Right)] {
+        let timer: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+        let arm = {
+            let (show, timer) = (show_preview.clone(), timer.clone());
+            move || {
+                let (show, clear) = (show.clone(), timer.clone());
+                let id = glib::timeout_add_local_once(NAV_PREVIEW_DELAY, move || {
+                    clear.borrow_mut().take();
+                    show(key);
+                });
+                if let Some(old) = timer.borrow_mut().replace(id) { old.remove(); }
+            }
+        };
+        let hover = gtk4::EventControllerMotion::new();
+        hover.connect_enter(clone!(#[strong] arm, move |_, _, _| arm()));
+        hover.connect_leave(clone!(#[strong] hide_preview, move |_| {
+            if let Some(id) = timer.borrow_mut().take() { id.remove(); }
+            hide_preview();
+        }));
+        btn.add_controller(hover);
+        btn.connect_clicked(clone!(#[strong] hide_preview, move |_| {
+            hide_preview();
+            arm();
+        }));
+    }
+
+    // 触摸屏上左右快速滑动翻页（图片未放大时），并播放滑动过渡；放大时由拖动手势平移
+    let swipe = gtk4::GestureSwipe::builder().touch_only(true).build();
+    {
+        let model = model.clone();
+        let tabs = tabs.clone();
+        let state = state.clone();
+        let cs = cs.clone();
+        let mode = current_mode.clone();
+        let navigate = navigate.clone();
+        let animations = config.animations;
+        swipe.connect_swipe(move |gesture, vx, vy| {
+            let (dx, _) = drag_offset.get();
+            if mode.get() != WindowMode::Normal || model.read(is_zoomed_in) { return; }
+            if vx.abs() < SWIPE_MIN_VELOCITY || vx.abs() < vy.abs() * 2.0 || dx.abs() < SWIPE_MIN_DISTANCE { return; }
+            let outgoing = cs.borrow().clone().map(|surface| {
+                let s = state.borrow();
+                slide::Outgoing { surface, rotation: s.rotation, scale: s.scale, offset: (s.offset_x, s.offset_y) }
+            });
+            let before = tabs.borrow().active_path().map(str::to_string);
+            let forward = vx < 0.0;
+            navigate(if forward { gdk::Key::Right } else { gdk::Key::Left });
+            // 没有下一张或等待内存确认时不播放过渡
+            if tabs.borrow().active_path().map(str::to_string) == before || !config::animations_enabled(animations) { return; }
+            *slide.borrow_mut() = Some(slide::Slide::new(forward, outgoing));
+            let Some(da) = gesture.widget() else { return };
+            let slide = slide.clone();
+            da.add_tick_callback(move |da, _| {
+                da.queue_draw();
+                let done = slide.borrow().as_ref().is_none_or(slide::Slide::is_done);
+                if done {
+                    slide.borrow_mut().take();
+                    return glib::ControlFlow::Break;
+                }
+                glib::ControlFlow::Continue
+            });
+        });
+    }
+    if !kiosk {
+        drawing_area.add_controller(swipe);
+    }
+
+    // 双指旋转与捏合缩放：两个手势同组，同一对触点可以同时旋转和缩放
+    // 旋转只改变绘制时的角度，缓存的原图表面不受影响；松开时接近直角则吸附，否则保留自由角度
+    let rotate_gesture = gtk4::GestureRotate::new();
+    let tilt_start = Rc::new(Cell::new(0.0f64));
+    rotate_gesture.connect_begin(clone!(#[strong] model, #[strong] tilt_start, #[strong] slideshow, move |gesture, _| {
+        if !model.has_image() {
+            gesture.set_state(gtk4::EventSequenceState::Denied);
+            return;
+        }
+        slideshow.pause();
+        tilt_start.set(model.read(|s| s.tilt));
+    }));
+    rotate_gesture.connect_angle_changed(clone!(#[strong] model, move |_, _, delta| {
+        if model.has_image() { model.set_tilt(tilt_start.get() + delta); }
+    }));
+    rotate_gesture.connect_end(clone!(#[strong] model, move |_, _| {
+        if model.has_image() { model.settle_tilt(TILT_SNAP); }
+    }));
+    let pinch = gtk4::GestureZoom::new();
+    // 上一次的捏合比例，每次只按相对变化缩放
+    let pinch_scale = Rc::new(Cell::new(1.0f64));
+    pinch.connect_begin(clone!(#[strong] pinch_scale, move |_, _| pinch_scale.set(1.0)));
+    {
+        let model = model.clone();
+        let factor = config.zoom_factor();
+        pinch.connect_scale_changed(move |gesture, scale| {
+            let previous = pinch_scale.replace(scale);
+            if !model.has_image() || previous <= 0.0 || scale <= 0.0 { return; }
+            let Some(da) = gesture.widget() else { return };
+            let viewport = (da.width() as f64, da.height() as f64);
+            let center = gesture.bounding_box_center().unwrap_or((viewport.0 / 2.0, viewport.1 / 2.0));
+            model.zoom((scale / previous).ln() / factor.ln(), factor, false, center, viewport);
+        });
+    }
+    pinch.group_with(&rotate_gesture);
+    drawing_area.add_controller(rotate_gesture);
+    drawing_area.add_controller(pinch);
+
+    let toggle_scale_bar = {
+        let scale_bar = scale_bar.clone();
+        let da = drawing_area.clone();
+        let toast = show_toast.clone();
+        Rc::new(move || {
+            let on = !scale_bar.get();
+            scale_bar.set(on);
+            da.queue_draw();
+            toast(if on { "比例尺：开（缩放不超过 100% 时显示）" } else { "比例尺：关" });
+        })
+    };
+
+    // 循环切换标签，backward 时切到上一个
+    let cycle_tab = {
+        let tabs = tabs.clone();
+        let switch = switch_tab.clone();
+        Rc::new(move |backward: bool| {
+            let (len, active) = {
+                let t = tabs.borrow();
+                (t.items.len(), t.active)
+            };
+            if len > 1 {
+                switch(if backward { (active + len - 1) % len } else { (active + 1) % len });
+            }
+        })
+    };
+
+    // 导出当前目录（--recursive 时为整棵目录树）的缩略图总览
+    let open_contact = {
+        let listing = current_listing.clone();
+        let (window, jobs) = (window.clone(), jobs.clone());
+        let (toast, reveal) = (show_toast.clone(), show_reveal.clone());
+        Rc::new(move || {
+            let Some((listing, _)) = listing(true) else { return toast("没有打开的目录") };
+            show_contact_dialog(&window, &jobs, listing.dir.clone(), listing.files.clone(), toast.clone(), reveal.clone());
+        })
+    };
+
+    // 适应宽度（W）/ 适应高度（E）：最大化或全屏时按当前绘图区，否则按窗口在屏幕上能达到的最大尺寸，
+    // 窗口随后跟随图片大小；之后窗口大小改变时按新尺寸重新适应
+    // 适应宽度或高度时图片可以占用的区域：窗口大小固定时为绘图区，否则为窗口在屏幕上能达到的最大尺寸
+    let axis_viewport = {
+        let win = window.clone();
+        let da = drawing_area.clone();
+        Rc::new(move || {
+            if win.is_maximized() || win.is_fullscreen() || get_screen_size().is_none() {
+                (da.width() as f64, da.height() as f64)
+            } else {
+                let (w, h) = calc_target_size(i32::MAX / 2, i32::MAX / 2);
+                (w as f64, (h - titlebar_space()) as f64)
+            }
+        })
+    };
+    let fit_axis = {
+        let model = model.clone();
+        let viewport = axis_viewport.clone();
+        Rc::new(move |mode: ZoomMode| model.fit_axis(mode, viewport(), true))
+    };
+    drawing_area.connect_resize(clone!(#[strong] model, move |_, width, height| {
+        let mode = model.read(|s| s.zoom_mode);
+        if mode != ZoomMode::Free {
+            model.fit_axis(mode, (width as f64, height as f64), false);
+        }
+    }));
+
+    // 键盘缩放与滚轮一格相同：同样的倍数、像素对齐档位与缩放范围，以绘图区中心为基准
+    let zoom_step: Rc<dyn Fn(f64)> = {
+        let (model, da, pixel_zoom) = (model.clone(), drawing_area.clone(), pixel_zoom.clone());
+        let factor = config.zoom_factor();
+        Rc::new(move |steps: f64| {
+            if !model.has_image() { return; }
+            let viewport = (da.width() as f64, da.height() as f64);
+            model.zoom(steps, factor, pixel_zoom.get(), (viewport.0 / 2.0, viewport.1 / 2.0), viewport);
+        })
+    };
+    let actual_size: Rc<dyn Fn()> = {
+        let model = model.clone();
+        Rc::new(move || if model.has_image() { model.actual_size() })
+    };
+
+    // 全屏（F11 / F）：隐藏标题栏，图片按整个显示器适应并居中；再按一次或 Esc 退出，
+    // 恢复进入前的窗口大小与视图（期间换了图片时改为适应窗口）。合成器自行退出全屏时同样恢复
+    let fullscreen_saved: FullscreenSaved = Rc::new(RefCell::new(None));
+    let toggle_fullscreen: Rc<dyn Fn()> = {
+        let (win, model, saved) = (window.clone(), model.clone(), fullscreen_saved.clone());
+        Rc::new(move || {
+            if win.is_fullscreen() {
+                win.unfullscreen();
+                return;
+            }
+            let generation = model.read(|s| s.generation.current());
+            // 最大化的窗口退出全屏后由合成器恢复最大化
+            let size = (!win.is_maximized()).then(|| (win.width(), win.height()));
+            *saved.borrow_mut() = Some((model.save_view(), generation, size));
+            win.fullscreen();
+        })
+    };
+    if !kiosk {
+        let (model, titlebar, saved, pixel_zoom) = (model.clone(), titlebar.clone(), fullscreen_saved.clone(), pixel_zoom.clone());
+        window.connect_fullscreened_notify(move |win| {
+            if win.is_fullscreen() {
+                titlebar.set_visible(false);
+                model.fit_screen();
+                return;
+            }
+            let Some((view, generation, size)) = saved.borrow_mut().take() else { return };
+            titlebar.set_visible(true);
+            if let Some((w, h)) = size { win.set_default_size(w, h); }
+            if model.read(|s| s.generation.is_current(generation)) {
+                model.restore_view(view);
+            } else {
+                model.fit(pixel_zoom.get());
+            }
+        });
+    }
+
+    // 窗口动作（win.*），供命令面板查找和执行；展台模式不注册，避免通过 D-Bus 绕过限制
+    let recent_commands = palette::Recent::default();
+    if !kiosk {
+        let add = |name: &str, f: Rc<dyn Fn()>| {
+            let action = gio::SimpleAction::new(name, None);
+            action.connect_activate(move |_, _| f());
+            window.add_action(&action);
+        };
+        let click = |btn: &Button| -> Rc<dyn Fn()> { Rc::new(clone!(#[weak] btn, move || btn.emit_clicked())) };
+        add("open", click(&open_btn));
+        add("open-tabs", open_tabs_dialog.clone());
+        add("reset-view", click(&reset_btn));
+        add("rotate", click(&rotate_btn));
+        add("copy", click(&copy_btn));
+        add("paste", paste_clipboard.clone());
+        let zoom = zoom_step.clone();
+        add("zoom-in", Rc::new(move || zoom(1.0)));
+        let zoom = zoom_step.clone();
+        add("zoom-out", Rc::new(move || zoom(-1.0)));
+        add("actual-size", actual_size.clone());
+        add("fullscreen", toggle_fullscreen.clone());
+        #[cfg(feature = "ocr")]
+        add("copy-text", copy_text.clone());
+        for (name, key) in [("previous-image", gdk::Key::Left), ("next-image", gdk::Key::Right),
+                            ("previous-group", gdk::Key::Up), ("next-group", gdk::Key::Down)] {
+            let navigate = navigate.clone();
+            add(name, Rc::new(move || navigate(key)));
+        }
+        add("play-burst", play_burst.clone());
+        let cycle = cycle_tab.clone();
+        add("next-tab", Rc::new(move || cycle(false)));
+        let cycle = cycle_tab.clone();
+        add("previous-tab", Rc::new(move || cycle(true)));
+        add("close-tab", close_tab.clone());
+        add("play-animation", toggle_animation.clone());
+        add("slideshow", toggle_slideshow.clone());
+        add("extract-frames", open_extract.clone());
+        add("export-frame", export_frame.clone());
+        add("copy-frame", copy_frame.clone());
+        add("set-poster", set_poster.clone());
+        add("rename", begin_rename.clone());
+        add("number-files", number_listing.clone());
+        add("contact-sheet", open_contact.clone());
+        add("trash", delete_current.clone());
+        add("undo", undo_last.clone());
+        add("pixel-zoom", toggle_pixel_zoom.clone());
+        add("sticky-rotation", toggle_sticky_rotation.clone());
+        add("scale-bar", toggle_scale_bar.clone());
+        add("print-size", click(&print_size_btn));
+        let fit = fit_axis.clone();
+        add("fit-width", Rc::new(move || fit(ZoomMode::FitWidth)));
+        let fit = fit_axis.clone();
+        add("fit-height", Rc::new(move || fit(ZoomMode::FitHeight)));
+        add("image-info", toggle_info.clone());
+        let choose = choose_and_pin.clone();
+        add("pin-to-monitor", Rc::new(clone!(#[weak] drawing_area, move || {
+            choose(&drawing_area, drawing_area.width() as f64 / 2.0, drawing_area.height() as f64 / 2.0);
+        })));
+        add("close-window", Rc::new(clone!(#[weak] window, move || window.close())));
+    }
+
+    // 保持在最前（标题栏右键菜单或命令面板）：X11 下直接请求窗口管理器；
+    // Wayland 下 layer-shell 表面无法变回普通窗口，改为用当前的文件标签新建窗口并关闭这个窗口
+    if !kiosk {
+        let initial = if above::is_x11(&WidgetExt::display(&window)) { above::enabled() } else { above_layer };
+        let initial_sort = config.view.sort.unwrap_or_default();
+        let keep_above = gio::SimpleAction::new_stateful("keep-above", None, &initial.to_variant());
+        let (app, tabs, config, toast) = (app.clone(), tabs.clone(), config.clone(), show_toast.clone());
+        keep_above.connect_activate(clone!(#[weak] window, move |action, _| {
+            let on = !action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+            if above::is_x11(&WidgetExt::display(&window)) {
+                if !window.surface().is_some_and(|surface| above::set_x11(&surface, on)) {
+                    return toast("无法设置保持在最前");
+                }
+                above::set_enabled(on);
+                action.set_state(&on.to_variant());
+                return;
+            }
+            if !gtk4_layer_shell::is_supported() {
+                return toast("合成器不支持 layer-shell，无法保持在最前");
+            }
+            above::set_enabled(on);
+            // 标准输入、剪贴板等来源无法重新打开，只带上磁盘上的文件，活动标签放在最前
+            let files: Vec<InitialFile> = {
+                let tabs = tabs.borrow();
+                let mut paths: Vec<&Tab> = tabs.items.iter().collect();
+                if tabs.active < paths.len() {
+                    let active = paths.remove(tabs.active);
+                    paths.insert(0, active);
+                }
+                paths.into_iter()
+                    .filter(|tab| Path::new(&tab.path).is_file())
+                    .map(|tab| InitialFile { path: tab.path.clone(), view: None })
+                    .collect()
+            };
+            build_ui(&app, Source::Files(files), WindowMode::Normal, config.clone(), 0);
+            window.close();
+        }));
+        window.add_action(&keep_above);
+
+        // 浏览顺序：本窗口内优先于目录文件与配置；目录树（--recursive）已列出的部分不重新排序
+        let sort_action = gio::SimpleAction::new_stateful("sort", Some(glib::VariantTy::STRING), &initial_sort.name().to_variant());
+        let (dir_views, listing, refresh) = (dir_views.clone(), current_listing.clone(), refresh_view.clone());
+        sort_action.connect_activate(move |action, param| {
+            let Some(sort) = param.and_then(|p| p.get::<String>()).and_then(|s| config::SortOrder::parse(&s)) else { return };
+            dir_views.set_sort(sort);
+            action.set_state(&sort.name().to_variant());
+            listing(true);
+            refresh();
+        });
+        window.add_action(&sort_action);
+        
+        // 标题栏右键菜单；在捕获阶段处理，不弹出合成器的窗口菜单
+        let menu = gio::Menu::new();
+        menu.append(Some("保持在最前"), Some("win.keep-above"));
+        menu.append(Some(&workspace::describe()), Some("app.overlay-workspace"));
+        let sort_menu = gio::Menu::new();
+        for sort in config::SortOrder::ALL {
+            sort_menu.append(Some(sort.label()), Some(&format!("win.sort::{}", sort.name())));
+        }
+        menu.append_submenu(Some("浏览顺序"), &sort_menu);
+        #[cfg(feature = "ocr")]
+        menu.append(Some("复制文字"), Some("win.copy-text"));
+        let menu_popover = gtk4::PopoverMenu::from_model(Some(&menu));
+        menu_popover.set_parent(&titlebar);
+        menu_popover.set_has_arrow(false);
+        titlebar.connect_destroy(clone!(#[weak] menu_popover, move |_| menu_popover.unparent()));
+        let menu_click = gtk4::GestureClick::builder().button(3).build();
+        menu_click.set_propagation_phase(gtk4::PropagationPhase::Capture);
+        menu_click.connect_pressed(clone!(#[weak] titlebar, move |gesture, _, x, y| {
+            // 有次要操作的按钮自己处理右键
+            let on_button = titlebar.pick(x, y, gtk4::PickFlags::DEFAULT)
+                .and_then(|w| w.ancestor(Button::static_type()))
+                .is_some_and(|b| b.has_css_class("has-secondary"));
+            if on_button { return; }
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            menu_popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            menu_popover.popup();
+        }));
+        titlebar.add_controller(menu_click);
+    }
+    let open_palette = {
+        let window = window.clone();
+        let anchor = view_overlay.clone();
+        Rc::new(move || palette::show(&window, &anchor, &recent_commands))
+    };
+
+    // 快捷键：Ctrl+O 打开，Ctrl+Shift+O 以标签页打开，Ctrl+Tab 切换标签，Ctrl+W 关闭标签，Ctrl+E 导出帧
+    // Ctrl+Shift+P 命令面板，方向键浏览同目录图片，B 播放连拍，S 幻灯片，W/E 适应宽度/高度
+    // +/- 缩放，0 恢复视图，1 显示 100%，R 旋转，Ctrl+C 复制，Q/Esc 关闭窗口
+    let key_ctrl = gtk4::EventControllerKey::new();
+    key_ctrl.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    let open_btn_key = open_btn.clone();
+    let close_tab_key = close_tab.clone();
+    let window_key = window.clone();
+    let (reset_btn_key, rotate_btn_key, copy_btn_key) = (reset_btn.clone(), rotate_btn.clone(), copy_btn.clone());
+    let (zoom_step_key, actual_size_key) = (zoom_step.clone(), actual_size.clone());
+    // 按住 x 显示上一张图片，缩放和偏移保持一致以便逐像素对比
+    let set_comparing = {
+        let comparing = comparing.clone();
+        let previous = previous.clone();
+        let badge = compare_badge.clone();
+        let cs = cs.clone();
+        let cr_rot = cr_rot.clone();
+        let da = drawing_area.clone();
+        Rc::new(move |on: bool| {
+            if comparing.get() == on { return; }
+            comparing.set(on);
+            if let Some((ref path, _)) = *previous.borrow() {
+                let name = Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string();
+                badge.set_text(&format!("上一张：{}", name));
+            }
+            badge.set_visible(on);
+            *cs.borrow_mut() = None;
+            cr_rot.set(-1);
+            da.queue_draw();
+        })
+    };
+    let state_key = state.clone();
+    let previous_key = previous.clone();
+    let toast_key = show_toast.clone();
+    let set_comparing_press = set_comparing.clone();
+    // 正在按住的左右方向键；按键重复时在按钮旁预览下一张，松开时收起
+    let held_arrow: Rc<Cell<Option<gdk::Key>>> = Rc::new(Cell::new(None));
+    let (held_press, show_preview_key) = (held_arrow.clone(), show_preview.clone());
+    key_ctrl.connect_key_pressed(move |_, key, _, modifiers| {
+        // 输入框（重命名、命令面板）中的按键交给输入框处理
+        if GtkWindowExt::focus(&window_key).is_some_and(|w| w.is::<gtk4::Text>()) {
+            return glib::Propagation::Proceed;
+        }
+        // 配置接管的按键（改设的动作原来的内置按键、配置中用到的按键）不再执行内置操作
+        if keys::claimed(key, modifiers) {
+            return glib::Propagation::Proceed;
+        }
+        if key.to_lower() == gdk::Key::x && !modifiers.contains(gdk::ModifierType::CONTROL_MASK) {
+            let same_size = {
+                let s = state_key.borrow();
+                previous_key.borrow().as_ref()
+                    .map(|(_, t)| (t.width(), t.height()) == (s.original_width, s.original_height))
+            };
+            match same_size {
+                Some(true) => set_comparing_press(true),
+                Some(false) => toast_key("尺寸不同，无法对比"),
+                None => toast_key("没有上一张图片"),
+            }
+            return glib::Propagation::Stop;
+        }
+        if key == gdk::Key::Delete && !kiosk {
+            delete_current();
+            return glib::Propagation::Stop;
+        }
+        if key == gdk::Key::F2 && !kiosk {
+            if modifiers.contains(gdk::ModifierType::SHIFT_MASK) { number_listing() } else { begin_rename() }
+            return glib::Propagation::Stop;
+        }
+        let plain = !modifiers.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK);
+        // 方向键浏览在展台模式下同样可用；展台模式没有标题栏，不预览下一张
+        if plain && matches!(key, gdk::Key::Left | gdk::Key::Right | gdk::Key::Up | gdk::Key::Down) {
+            navigate(key);
+            let sideways = matches!(key, gdk::Key::Left | gdk::Key::Right);
+            if !kiosk && sideways && held_press.replace(Some(key)) == Some(key) {
+                show_preview_key(key);
+            }
+            return glib::Propagation::Stop;
+        }
+        if !kiosk && plain {
+            match key {
+                gdk::Key::b | gdk::Key::B => {
+                    play_burst();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::p | gdk::Key::P => {
+                    toggle_animation();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::s | gdk::Key::S => {
+                    toggle_slideshow();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::i | gdk::Key::I => {
+                    toggle_info();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::r | gdk::Key::R => {
+                    rotate_btn_key.emit_clicked();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::m | gdk::Key::M => {
+                    toggle_scale_bar();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::w | gdk::Key::W => {
+                    fit_axis(ZoomMode::FitWidth);
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::e | gdk::Key::E => {
+                    fit_axis(ZoomMode::FitHeight);
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::plus | gdk::Key::equal | gdk::Key::KP_Add => {
+                    zoom_step_key(1.0);
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::minus | gdk::Key::KP_Subtract => {
+                    zoom_step_key(-1.0);
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::_0 | gdk::Key::KP_0 => {
+                    reset_btn_key.emit_clicked();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::_1 | gdk::Key::KP_1 => {
+                    actual_size_key();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::F11 | gdk::Key::f | gdk::Key::F => {
+                    toggle_fullscreen();
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::q | gdk::Key::Q | gdk::Key::Escape => {
+                    // 弹出菜单中的 Esc 只关闭弹出菜单，全屏时的 Esc 退出全屏
+                    let in_popover = GtkWindowExt::focus(&window_key)
+                        .is_some_and(|w| w.ancestor(gtk4::Popover::static_type()).is_some());
+                    if key == gdk::Key::Escape && in_popover { return glib::Propagation::Proceed; }
+                    if key == gdk::Key::Escape && window_key.is_fullscreen() {
+                        toggle_fullscreen();
+                        return glib::Propagation::Stop;
+                    }
+                    window_key.close();
+                    return glib::Propagation::Stop;
+                }
+                _ => {}
+            }
+        }
+        if !modifiers.contains(gdk::ModifierType::CONTROL_MASK) {
+            return glib::Propagation::Proceed;
+        }
+        let shift = modifiers.contains(gdk::ModifierType::SHIFT_MASK);
+        // 展台模式只允许切换标签
+        if kiosk && !matches!(key, gdk::Key::Tab | gdk::Key::ISO_Left_Tab) {
+            return glib::Propagation::Stop;
+        }
+        match key.to_lower() {
+            gdk::Key::o if shift => open_tabs_dialog(),
+            gdk::Key::o => open_btn_key.emit_clicked(),
+            gdk::Key::Tab | gdk::Key::ISO_Left_Tab => cycle_tab(shift),
+            gdk::Key::p if shift => open_palette(),
+            gdk::Key::w => close_tab_key(),
+            gdk::Key::e => open_extract(),
+            gdk::Key::z => undo_last(),
+            #[cfg(feature = "ocr")]
+            gdk::Key::c if shift => copy_text(),
+            gdk::Key::c if !shift => copy_btn_key.emit_clicked(),
+            gdk::Key::v if !shift => paste_clipboard(),
+            _ => return glib::Propagation::Proceed,
+        }
+        glib::Propagation::Stop
+    });
+    key_ctrl.connect_key_released(move |_, key, _, _| {
+        if key.to_lower() == gdk::Key::x {
+            set_comparing(false);
+        }
+        if held_arrow.get() == Some(key) {
+            held_arrow.set(None);
+            hide_preview();
+        }
+    });
+    window.add_controller(key_ctrl);
+
+    // 恢复视图
+    let model_reset = model.clone();
+    let pixel_zoom_reset = pixel_zoom.clone();
+    reset_btn.connect_clicked(move |_| model_reset.fit(pixel_zoom_reset.get()));
+
+    // 按打印尺寸显示：图片 DPI 映射到显示器物理密度
+    let model_print = model.clone();
+    print_size_btn.connect_clicked(move |_| {
+        // 缩小载入的纹理按原图尺寸换算
+        let (dpi, ratio) = model_print.read(|s| (s.dpi, s.full_size.map_or(1.0, |(w, _)| w as f64 / s.original_width as f64)));
+        if let Some(scale) = calc_print_scale(dpi) {
+            model_print.set_scale(scale * ratio);
+        }
+    });
+
+    // 旋转；手动旋转时暂停幻灯片
+    let rotate_by_hand = {
+        let (model, slideshow) = (model.clone(), slideshow.clone());
+        Rc::new(move |clockwise: bool| {
+            slideshow.pause();
+            model.rotate(clockwise);
+        })
+    };
+    rotate_btn.connect_clicked(clone!(#[strong] rotate_by_hand, move |_| rotate_by_hand(true)));
+
+    // 复制到剪贴板：与视图一致，复制旋转后的像素
+    // 大图旋转较慢，在工作线程中准备；进行中按钮显示转圈，重复点击被忽略
+    // 剪贴板中放的是纹理本身，只有粘贴方请求 PNG 等格式时才由 GDK 编码
+    // 缩小载入的图片先重新完整解码原文件；原文件已无法读取时复制缩小后的纹理并提示
+    {
+        let state = state.clone();
+        let tabs = tabs.clone();
+        let win = window.clone();
+        let toast = show_toast.clone();
+        let busy = Rc::new(Cell::new(false));
+        copy_btn.connect_clicked(move |btn| {
+            if busy.get() { return; }
+            let (texture, rotation, full_size) = {
+                let s = state.borrow();
+                let Some(texture) = s.pixbuf.clone() else { return };
+                (texture, s.rotation, s.full_size)
+            };
+            let source = full_size.and(tabs.borrow().active_path().map(PathBuf::from));
+            busy.set(true);
+            btn.set_sensitive(false);
+            btn.set_child(Some(&gtk4::Spinner::builder().spinning(true).build()));
+            if full_size.is_some() { toast("正在读取原始分辨率的图片…"); }
+            
+            // 第二项表示复制的是缩小后的纹理
+            let (tx, rx) = std::sync::mpsc::channel::<(gdk::Texture, bool)>();
+            std::thread::spawn(move || {
+                let (texture, reduced) = match full_size {
+                    None => (texture, false),
+                    Some(_) => match source.filter(|p| p.is_file()).map(|p| decode_texture(&p)) {
+                        Some(Ok(full)) => (full, false),
+                        _ => (texture, true),
+                    },
+                };
+                tx.send((rotated_texture(&texture, rotation), reduced)).ok();
+            });
+            let (busy, toast, win, btn) = (busy.clone(), toast.clone(), win.clone(), btn.clone());
+            glib::timeout_add_local(Duration::from_millis(50), move || {
+                let result = match rx.try_recv() {
+                    Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("复制线程意外退出".to_string()),
+                    Ok((texture, reduced)) => {
+                        let content = gdk::ContentProvider::for_value(&texture.to_value());
+                        win.clipboard().set_content(Some(&content)).map(|_| (texture, reduced)).map_err(|e| e.to_string())
+                    }
+                };
+                busy.set(false);
+                btn.set_sensitive(true);
+                btn.set_icon_name("edit-copy-symbolic");
+                match result {
+                    Ok((texture, true)) => {
+                        let (full_w, full_h) = full_size.unwrap_or_default();
+                        let (full_w, full_h) = if rotation % 2 != 0 { (full_h, full_w) } else { (full_w, full_h) };
+                        gtk4::AlertDialog::builder()
+                            .message("已复制缩小后的图片")
+                            .detail(format!("原文件已无法读取，剪贴板中的图片为 {}×{}，原图为 {}×{}。",
+                                            texture.width(), texture.height(), full_w, full_h))
+                            .build()
+                            .show(Some(&win));
+                    }
+                    Ok((texture, false)) => {
+                        let bytes = texture.width() as f64 * texture.height() as f64 * 4.0;
+                        toast(&format!("已复制 {}×{}（{:.1} MiB 像素数据）", texture.width(), texture.height(), bytes / (1024.0 * 1024.0)));
+                    }
+                    Err(e) => toast(&format!("复制失败: {}", e)),
+                }
+                glib::ControlFlow::Break
+            });
+        });
+    }
+
+    // 标题栏按钮的次要操作（右键或长按）：逆时针旋转、在适应窗口与 100% 之间切换、更多复制方式、最近打开的文件
+    if !kiosk {
+        connect_secondary(&rotate_btn, clone!(#[strong] rotate_by_hand, move |_| rotate_by_hand(false)));
+        // Shift+单击在按钮自身的单击之前拦下
+        let shift_click = gtk4::GestureClick::builder().button(1).propagation_phase(gtk4::PropagationPhase::Capture).build();
+        shift_click.connect_pressed(clone!(#[strong] toggle_sticky_rotation, move |gesture, _, _, _| {
+            if !gesture.current_event_state().contains(gdk::ModifierType::SHIFT_MASK) { return; }
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            toggle_sticky_rotation();
+        }));
+        rotate_btn.add_controller(shift_click);
+
+        // 缩小载入的纹理按原图尺寸换算 100%
+        let pixel_zoom_scroll = pixel_zoom.clone();
+        let (model_reset, pixel_zoom) = (model.clone(), pixel_zoom.clone());
+        connect_secondary(&reset_btn, move |_| {
+            let (scale, actual) = model_reset.read(|s| (s.scale, s.full_size.map_or(1.0, |(w, _)| w as f64 / s.original_width as f64)));
+            if (scale - actual).abs() < 1e-6 { model_reset.fit(pixel_zoom.get()) } else { model_reset.set_scale(actual) }
+        });
+
+        // 在旋转按钮上滚动按滚动方向旋转，在恢复按钮上滚动按 适应窗口 → 100% → 填满 循环切换
+        connect_scroll_steps(&rotate_btn, move |forward| rotate_by_hand(forward));
+        let (model_zoom, pixel_zoom, viewport) = (model.clone(), pixel_zoom_scroll, axis_viewport.clone());
+        connect_scroll_steps(&reset_btn, move |forward| {
+            if !model_zoom.has_image() { return; }
+            let (mode, scale, actual, (img_w, img_h)) = model_zoom.read(|s| {
+                (s.zoom_mode, s.scale, s.full_size.map_or(1.0, |(w, _)| w as f64 / s.original_width as f64), get_rotated_size(s))
+            });
+            // 当前处于哪一档：填满（适应宽度或高度）、100%，其余都当作适应窗口
+            let current = if mode != ZoomMode::Free { 2 } else if (scale - actual).abs() < 1e-6 { 1 } else { 0 };
+            match (current + if forward { 1 } else { 2 }) % 3 {
+                0 => model_zoom.fit(pixel_zoom.get()),
+                1 => model_zoom.set_scale(actual),
+                // 填满：比视图更宽的图片适应高度，否则适应宽度，另一方向超出的部分可拖动浏览
+                _ => {
+                    let (view_w, view_h) = viewport();
+                    let wider = img_w as f64 * view_h > img_h as f64 * view_w;
+                    model_zoom.fit_axis(if wider { ZoomMode::FitHeight } else { ZoomMode::FitWidth }, (view_w, view_h), true);
+                }
+            }
+        });
+
+        // 复制可见部分：把绘图区当前的画面（含缩放、旋转与平移）渲染成纹理
+        let copy_view = gio::SimpleAction::new("copy-view", None);
+        copy_view.connect_activate(clone!(#[weak] drawing_area, #[strong] show_toast, move |_, _| {
+            let paintable = gtk4::WidgetPaintable::new(Some(&drawing_area));
+            let snapshot = gtk4::Snapshot::new();
+            paintable.snapshot(&snapshot, drawing_area.width() as f64, drawing_area.height() as f64);
+            let renderer = drawing_area.native().and_then(|n| n.renderer());
+            let Some(texture) = snapshot.to_node().zip(renderer).map(|(node, r)| r.render_texture(&node, None)) else {
+                return show_toast("没有可复制的画面");
+            };
+            let content = gdk::ContentProvider::for_value(&texture.to_value());
+            match drawing_area.clipboard().set_content(Some(&content)) {
+                Ok(()) => show_toast(&format!("已复制可见部分 {}×{}", texture.width(), texture.height())),
+                Err(e) => show_toast(&format!("复制失败: {}", e)),
+            }
+        }));
+        window.add_action(&copy_view);
+        let copy_path = gio::SimpleAction::new("copy-path", None);
+        copy_path.connect_activate(clone!(#[weak] window, #[strong] tabs, #[strong] show_toast, move |_, _| {
+            let path = tabs.borrow().active_path().map(Path::new).filter(|p| p.is_file()).and_then(|p| std::path::absolute(p).ok());
+            let Some(path) = path else { return show_toast("当前图片不是磁盘上的文件") };
+            window.clipboard().set_text(&path.to_string_lossy());
+            show_toast("已复制文件路径");
+        }));
+        window.add_action(&copy_path);
+        let copy_menu = gio::Menu::new();
+        copy_menu.append(Some("复制图片"), Some("win.copy"));
+        copy_menu.append(Some("复制可见部分"), Some("win.copy-view"));
+        copy_menu.append(Some("复制文件路径"), Some("win.copy-path"));
+        let copy_popover = gtk4::PopoverMenu::from_model(Some(&copy_menu));
+        copy_popover.set_parent(&copy_btn);
+        copy_btn.connect_destroy(clone!(#[weak] copy_popover, move |_| copy_popover.unparent()));
+        connect_secondary(&copy_btn, move |_| copy_popover.popup());
+
+        // 最近打开的文件：每次弹出时重新读取系统的最近文件列表
+        let open_recent = gio::SimpleAction::new("open-recent", Some(glib::VariantTy::STRING));
+        open_recent.connect_activate(clone!(#[strong] load_image, move |_, param| {
+            let Some(path) = param.and_then(|p| p.get::<String>()) else { return };
+            remember_recent(Path::new(&path));
+            load_image(&path);
+        }));
+        window.add_action(&open_recent);
+        let recent_popover = gtk4::PopoverMenu::from_model(None::<&gio::MenuModel>);
+        recent_popover.set_parent(&open_btn);
+        open_btn.connect_destroy(clone!(#[weak] recent_popover, move |_| recent_popover.unparent()));
+        connect_secondary(&open_btn, clone!(#[strong] show_toast, move |_| {
+            let recent = recent_images(RECENT_LIMIT);
+            if recent.is_empty() { return show_toast("没有最近打开的图片"); }
+            let menu = gio::Menu::new();
+            for path in recent {
+                let label = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let item = gio::MenuItem::new(Some(&label), None);
+                item.set_action_and_target_value(Some("win.open-recent"), Some(&path.to_string_lossy().to_variant()));
+                menu.append_item(&item);
+            }
+            recent_popover.set_menu_model(Some(&menu));
+            recent_popover.popup();
+        }));
+    }
+
+    // 展台模式：全屏、隐藏标题栏与光标、阻止空闲，只能通过 SIGTERM 退出
+    if kiosk {
+        titlebar.set_visible(false);
+        drawing_area.set_cursor_from_name(Some("none"));
+        window.connect_close_request(|_| glib::Propagation::Stop);
+        let monitor = gdk::Display::default().and_then(|d| {
+            d.monitors().item(config.monitor.unwrap_or(0)).and_downcast::<gdk::Monitor>()
+        });
+        match monitor {
+            Some(ref m) => window.fullscreen_on_monitor(m),
+            None => window.fullscreen(),
+        }
+        app.inhibit(Some(&window), gtk4::ApplicationInhibitFlags::IDLE, Some("展台模式"));
+        let app_term = app.clone();
+        glib::unix_signal_add_local(SIGTERM, move || {
+            app_term.quit();
+            glib::ControlFlow::Break
+        });
+    }
+
+    // 窗口首次出现在另一台显示器上时按该显示器重新适应，之后移动窗口只更新记录
+    if !kiosk && config.window_position == placement::StartPosition::Pointer {
+        let model = model.clone();
+        let pixel_zoom = pixel_zoom.clone();
+        window.connect_realize(move |w| {
+            let Some(surface) = w.surface() else { return };
+            let (model, pixel_zoom) = (model.clone(), pixel_zoom.clone());
+            let first = Cell::new(true);
+            surface.connect_enter_monitor(move |_, monitor| {
+                let before = get_screen_size();
+                ACTIVE_MONITOR.set(Some(monitor.clone()));
+                if first.replace(false) && get_screen_size() != before {
+                    model.fit(pixel_zoom.get());
+                }
+            });
+        });
+    }
+    // X11 下首次显示时定位，之后重新显示（如退出置顶）时不再移动：
+    // --at-pointer 时移到指针旁边，限制在指针所在显示器的工作区域内；多窗口打开时再逐个错开。
+    // 居中且只有一个窗口时交给窗口管理器；Wayland 下位置都由合成器决定
+    let at_pointer = config.window_position == placement::StartPosition::Pointer;
+    if !kiosk && (at_pointer || cascade > 0) && above::is_x11(&WidgetExt::display(&window)) {
+        let placed = Cell::new(false);
+        window.connect_map(move |w| {
+            if placed.replace(true) { return; }
+            let Some(surface) = w.surface() else { return };
+            let size = (surface.width(), surface.height());
+            let index = (cascade - 1).max(0);
+            let position = if at_pointer {
+                let Some(pointer) = xwindow::pointer_position(&surface) else { return };
+                let monitor = surface.display().monitors().iter::<gdk::Monitor>().flatten().find(|m| {
+                    let g = m.geometry();
+                    (g.x()..g.x() + g.width()).contains(&pointer.0) && (g.y()..g.y() + g.height()).contains(&pointer.1)
+                });
+                let Some(monitor) = monitor else { return };
+                let area = xwindow::workarea(&monitor);
+                placement::cascade(area, Some(placement::beside_pointer(area, pointer, size)), index, size)
+            } else {
+                let Some(monitor) = surface.display().monitor_at_surface(&surface) else { return };
+                placement::cascade(xwindow::workarea(&monitor), None, index, size)
+            };
+            xwindow::move_to(&surface, position);
+        });
+    }
 
     // overlay 模式时先不显示普通窗口，等图片加载后直接显示 overlay
     if initial_mode != WindowMode::Overlay {
         window.present();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn badge_disappears_in_one_step_without_animations() {
+        let animate = config::animations_enabled(Some(false));
+        // 显示期间一直不透明，只安排一次到期时的重绘
+        let (alpha, redraw) = badge_fade(0.0, false, animate);
+        assert_eq!(alpha, 1.0);
+        assert_eq!(redraw, Some(Duration::from_secs_f64(ORIENTATION_BADGE_SECS)));
+        let (alpha, redraw) = badge_fade(ORIENTATION_BADGE_SECS - 0.1, false, animate);
+        assert_eq!(alpha, 1.0);
+        assert!(redraw.is_some_and(|d| (d.as_secs_f64() - 0.1).abs() < 1e-9));
+        assert_eq!(badge_fade(ORIENTATION_BADGE_SECS, false, animate), (0.0, None));
+    }
+
+    #[test]
+    fn badge_fades_out_when_animated() {
+        let animate = config::animations_enabled(Some(true));
+        let (alpha, redraw) = badge_fade(ORIENTATION_BADGE_SECS - ORIENTATION_FADE_SECS / 2.0, false, animate);
+        assert!((alpha - 0.5).abs() < 1e-9);
+        assert_eq!(redraw, Some(Duration::from_millis(50)));
+        assert_eq!(badge_fade(ORIENTATION_BADGE_SECS + 1.0, false, animate), (0.0, None));
+    }
+
+    #[test]
+    fn pan_released_past_the_edge_lands_in_one_step_without_animations() {
+        // 与拖动手势相同：拖动中停在边缘，不越界
+        let animate = config::animations_enabled(Some(false));
+        let limits = (100.0, 50.0);
+        let current = elastic::follow((180.0, -90.0), limits, animate);
+        let target = elastic::clamp(current, limits);
+        assert_eq!(current, target);
+        // 其他原因留下的越界偏移在松开时一步回到边缘，不安排逐帧回调
+        let settle = elastic::Settle::new((130.0, -53.0), target, animate);
+        assert_eq!(settle.position(), (target, true));
+    }
+
+    #[test]
+    fn pan_release_animates_when_enabled() {
+        let settle = elastic::Settle::new((130.0, -53.0), (100.0, -50.0), config::animations_enabled(Some(true)));
+        assert!(!settle.position().1);
+    }
+
+    fn loaded(current: &mut ImageState) {
+        current.pixbuf = Some(texture_from_rgba(image::RgbaImage::new(4, 3)));
+        (current.original_width, current.original_height) = (4, 3);
+    }
+
+    #[test]
+    fn switching_to_an_unloaded_tab_leaves_decoding_to_the_background() {
+        let (mut tabs, mut current) = (Tabs::default(), ImageState::default());
+        tabs.set_active_path("/nonexistent/0.png");
+        loaded(&mut current);
+        for i in 1..=LOADED_BACKGROUND_TABS + 1 {
+            tabs.push(&format!("/nonexistent/{}.png", i), &mut current);
+            loaded(&mut current);
+        }
+        // 最早的标签超出保留的数量，纹理已卸载；切换过去时只换入视图状态，纹理留给后台解码
+        assert!(tabs.switch_to(0, &mut current));
+        assert!(current.pixbuf.is_none());
+        assert_eq!(current.quality, TextureQuality::Unloaded);
+        assert_eq!((current.original_width, current.original_height), (4, 3));
+    }
+
+    #[test]
+    fn restored_texture_keeps_the_view() {
+        let mut s = ImageState { quality: TextureQuality::Unloaded, scale: 2.5, offset_x: 12.0, ..ImageState::default() };
+        (s.original_width, s.original_height) = (4, 3);
+        assert!(restore_texture(&mut s, texture_from_rgba(image::RgbaImage::new(4, 3)), String::new(), Duration::ZERO));
+        assert_eq!((s.scale, s.offset_x, s.quality), (2.5, 12.0, TextureQuality::Final));
+        assert!(s.pixbuf.is_some());
+    }
+
+    #[test]
+    fn pinned_badge_never_fades() {
+        assert_eq!(badge_fade(f64::MAX, true, false), (1.0, None));
+        assert_eq!(badge_fade(f64::MAX, true, true), (1.0, None));
+    }
+}
@@ -0,0 +1,110 @@
+// 信息面板中的文件与拍摄信息：载入图片时读取一次，保存在 ImageState 中，切换图片时随之更换
+// 文件属性来自 gio 的文件信息查询，色彩类型来自解码器读到的文件头，EXIF 只解析 JPEG 与 TIFF
+// 各取值函数在字段缺失时返回 None，状态表据此隐藏对应的行
+
+use crate::{decode, header, sandbox};
+use gtk4::{gio, glib};
+use gtk4::prelude::*;
+use image::ExtendedColorType;
+use std::path::Path;
+
+#[derive(Default)]
+pub struct Metadata {
+    size: Option<u64>,
+    modified: Option<glib::DateTime>,
+    // 格式的描述，如“JPEG 图像”
+    format: Option<String>,
+    color: Option<ExtendedColorType>,
+    exif: header::Exif,
+}
+
+impl Metadata {
+    // 没有对应文件（标准输入、剪贴板）时所有字段为空
+    pub fn read(path: &Path) -> Self {
+        let info = gio::File::for_path(path)
+            .query_info("standard::size,standard::content-type,time::modified", gio::FileQueryInfoFlags::NONE, gio::Cancellable::NONE)
+            .ok();
+        Self {
+            size: info.as_ref().map(|i| i.size().max(0) as u64),
+            modified: info.as_ref().and_then(|i| i.modification_date_time()),
+            format: info.as_ref().and_then(|i| i.content_type()).map(|t| gio::content_type_get_description(&t).to_string()),
+            // 隔离解码时不在本进程中调用解码器
+            color: (!sandbox::enabled()).then(|| decode::color_type(path)).flatten(),
+            exif: header::read_exif(path).unwrap_or_default(),
+        }
+    }
+
+    pub fn size(&self) -> Option<String> {
+        self.size.map(|size| glib::format_size(size).to_string())
+    }
+
+    pub fn modified(&self) -> Option<String> {
+        let local = self.modified.as_ref()?.to_local().ok()?;
+        local.format("%Y-%m-%d %H:%M:%S").ok().map(|s| s.to_string())
+    }
+
+    pub fn format(&self) -> Option<String> {
+        self.format.clone()
+    }
+
+    // 通道与每通道位数，如“RGB，每通道 8 位”
+    pub fn depth(&self) -> Option<String> {
+        use ExtendedColorType::*;
+        let color = self.color?;
+        let channels = match color {
+            A8 => "Alpha",
+            L1 | L2 | L4 | L8 | L16 => "灰度",
+            La1 | La2 | La4 | La8 | La16 => "灰度 + Alpha",
+            Rgb1 | Rgb2 | Rgb4 | Rgb5x1 | Rgb8 | Rgb16 | Rgb32F | Bgr8 => "RGB",
+            Rgba1 | Rgba2 | Rgba4 | Rgba8 | Rgba16 | Rgba32F | Bgra8 => "RGBA",
+            Cmyk8 | Cmyk16 => "CMYK",
+            _ => return None,
+        };
+        let bits = color.bits_per_pixel() / color.channel_count().max(1) as u16;
+        let float = if matches!(color, Rgb32F | Rgba32F) { "浮点" } else { "" };
+        Some(format!("{}，每通道 {} 位{}", channels, bits, float))
+    }
+
+    // 型号通常已含厂商名，此时只显示型号
+    pub fn camera(&self) -> Option<String> {
+        let exif = &self.exif;
+        match (&exif.make, &exif.model) {
+            (Some(make), Some(model)) if !model.to_lowercase().starts_with(&make.to_lowercase()) => Some(format!("{} {}", make, model)),
+            (_, Some(model)) => Some(model.clone()),
+            (make, None) => make.clone(),
+        }
+    }
+
+    // 曝光时间与光圈，如“1/125 秒，f/2.8”
+    pub fn exposure(&self) -> Option<String> {
+        let time = self.exif.exposure_time.filter(|&(num, _)| num > 0).map(|(num, den)| {
+            if num < den && den % num == 0 {
+                format!("1/{} 秒", den / num)
+            } else {
+                format!("{} 秒", trim(num as f64 / den as f64))
+            }
+        });
+        let aperture = self.exif.f_number.filter(|&f| f > 0.0).map(|f| format!("f/{}", trim(f)));
+        let parts: Vec<String> = time.into_iter().chain(aperture).collect();
+        (!parts.is_empty()).then(|| parts.join("，"))
+    }
+
+    pub fn iso(&self) -> Option<String> {
+        self.exif.iso.map(|iso| format!("ISO {}", iso))
+    }
+
+    pub fn focal_length(&self) -> Option<String> {
+        self.exif.focal_length.map(|f| format!("{} mm", trim(f)))
+    }
+
+    pub fn taken(&self) -> Option<String> {
+        let (y, mo, d, h, mi, s) = self.exif.taken?;
+        Some(format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, mo, d, h, mi, s))
+    }
+}
+
+// 最多保留一位小数，去掉末尾的 0
+fn trim(value: f64) -> String {
+    let text = format!("{:.1}", value);
+    text.strip_suffix(".0").map(str::to_string).unwrap_or(text)
+}
@@ -0,0 +1,508 @@
+// 视图模型：集中管理普通窗口与置顶窗口对 ImageState 的修改，界面代码不直接可变借用状态
+// 每个方法内部只做短暂的借用；重绘、标签刷新、窗口尺寸等界面副作用在借用结束后
+// 合并到一个空闲回调中执行，避免在持有借用时触发其他回调造成重复借用
+
+use crate::{
+    ImageState, MAX_SCALE, OVERLAY_MIN_SIDE, ZoomMode, calc_target_size, clamp_zoom, fit_to_monitor_scale, fit_to_screen_scale,
+    floor_zoom_preset, get_rotated_size, get_scaled_size, is_at_screen_limit, next_zoom_preset, titlebar_space, view,
+};
+use std::cell::{Cell, RefCell};
+use std::f64::consts::FRAC_PI_2;
+use std::rc::Rc;
+
+// 窗口尺寸调整方式
+#[derive(Clone, Copy)]
+pub enum Resize {
+    // 窗口收缩/扩展到图片缩放后的大小
+    Fit(i32, i32),
+    // 图片超出屏幕时只更新内容大小，不强制调整窗口
+    Content(i32, i32),
+}
+
+// 待执行的界面副作用，多次操作在同一空闲回调中合并
+#[derive(Clone, Copy, Default)]
+pub struct Effects {
+    pub redraw: bool,
+    pub refresh: bool,
+    pub resize: Option<Resize>,
+}
+
+impl Effects {
+    pub const REDRAW: Effects = Effects { redraw: true, refresh: false, resize: None };
+    pub const NONE: Effects = Effects { redraw: false, refresh: false, resize: None };
+
+    // 重绘并刷新标签
+    pub fn view() -> Self {
+        Effects { redraw: true, refresh: true, resize: None }
+    }
+
+    fn merge(self, other: Effects) -> Self {
+        Effects {
+            redraw: self.redraw || other.redraw,
+            refresh: self.refresh || other.refresh,
+            resize: other.resize.or(self.resize),
+        }
+    }
+}
+
+type FlushFn = std::boxed::Box<dyn Fn(Effects)>;
+
+// 进入置顶或全屏前普通窗口的视图；置顶窗口与普通窗口共用 ImageState，退出时据此恢复
+#[derive(Clone, Copy)]
+pub struct SavedView {
+    scale: f64,
+    offset: (f64, f64),
+    zoom_mode: ZoomMode,
+    min_zoom: f64,
+}
+
+struct Inner {
+    pending: Cell<Effects>,
+    scheduled: Cell<bool>,
+    flush: RefCell<Option<FlushFn>>,
+}
+
+#[derive(Clone)]
+pub struct ViewerModel {
+    state: Rc<RefCell<ImageState>>,
+    inner: Rc<Inner>,
+}
+
+impl ViewerModel {
+    pub fn new(state: Rc<RefCell<ImageState>>) -> Self {
+        let inner = Inner { pending: Cell::new(Effects::NONE), scheduled: Cell::new(false), flush: RefCell::new(None) };
+        Self { state, inner: Rc::new(inner) }
+    }
+
+    // 设置执行副作用的回调（界面构建完成后调用）
+    pub fn set_flush(&self, flush: impl Fn(Effects) + 'static) {
+        *self.inner.flush.borrow_mut() = Some(std::boxed::Box::new(flush));
+    }
+
+    pub fn read<R>(&self, f: impl FnOnce(&ImageState) -> R) -> R {
+        f(&self.state.borrow())
+    }
+
+    // 修改状态；闭包返回结果与需要的副作用，副作用在借用释放后排队执行
+    pub fn update<R>(&self, f: impl FnOnce(&mut ImageState) -> (R, Effects)) -> R {
+        let (result, effects) = f(&mut self.state.borrow_mut());
+        self.queue(effects);
+        result
+    }
+
+    // 修改不需要界面副作用的状态（载入编号、标签切换等），调用方在返回后自行刷新界面
+    pub fn mutate<R>(&self, f: impl FnOnce(&mut ImageState) -> R) -> R {
+        f(&mut self.state.borrow_mut())
+    }
+
+    pub fn queue(&self, effects: Effects) {
+        let inner = &self.inner;
+        inner.pending.set(inner.pending.get().merge(effects));
+        if inner.scheduled.replace(true) { return; }
+        let inner = self.inner.clone();
+        glib::idle_add_local_once(move || {
+            inner.scheduled.set(false);
+            let effects = inner.pending.replace(Effects::NONE);
+            if let Some(ref flush) = *inner.flush.borrow() {
+                flush(effects);
+            }
+        });
+    }
+
+    pub fn has_image(&self) -> bool {
+        self.read(|s| s.pixbuf.is_some())
+    }
+
+    // 滚轮缩放：steps 为正放大、为负缩小，每格缩放 factor 倍；像素对齐时每次切换一个档位
+    // 图片超出屏幕时以指针为中心，否则居中并让窗口跟随图片大小
+    pub fn zoom(&self, steps: f64, factor: f64, pixel_zoom: bool, pointer: (f64, f64), viewport: (f64, f64)) {
+        let zoom_in = steps > 0.0;
+        self.update(|s| {
+            if s.pixbuf.is_none() { return ((), Effects::NONE); }
+            s.zoom_mode = ZoomMode::Free;
+            let old_scale = s.scale;
+            s.scale = if pixel_zoom {
+                // 档位低于下限时停在原缩放率
+                let preset = next_zoom_preset(s.scale, zoom_in);
+                if preset < old_scale && preset < s.min_zoom / factor { old_scale } else { preset }
+            } else {
+                clamp_zoom(old_scale, s.scale * factor.powf(steps), s.min_zoom, factor)
+            };
+            if !zoom_in { s.clip = None; }
+
+            let (scaled_w, scaled_h) = get_scaled_size(s);
+            let at_limit = is_at_screen_limit(scaled_w, scaled_h);
+            let resize = if at_limit {
+                let center = (viewport.0 / 2.0, viewport.1 / 2.0);
+                (s.offset_x, s.offset_y) = view::zoom_offset((s.offset_x, s.offset_y), pointer, center, s.scale / old_scale);
+                let (target_w, target_h) = calc_target_size(scaled_w, scaled_h);
+                Resize::Content(target_w, target_h - titlebar_space())
+            } else {
+                s.offset_x = 0.0;
+                s.offset_y = 0.0;
+                Resize::Fit(scaled_w, scaled_h)
+            };
+            ((), Effects { resize: Some(resize), ..Effects::view() })
+        })
+    }
+
+    // 缩放置顶窗口：steps 为正放大、为负缩小，每格缩放 factor 倍；最短边不小于 OVERLAY_MIN_SIDE，
+    // 本来就更小的图片不放大。窗口由调用方按返回的缩放倍数与缩放后的尺寸调整，没有图片时返回 None
+    pub fn zoom_overlay(&self, steps: f64, factor: f64) -> Option<(f64, (i32, i32))> {
+        self.mutate(|s| {
+            s.pixbuf.as_ref()?;
+            let old_scale = s.scale;
+            s.scale = clamp_zoom(old_scale, s.scale * factor.powf(steps), s.min_zoom, factor);
+            let (img_w, img_h) = get_rotated_size(s);
+            let floor = (OVERLAY_MIN_SIDE / img_w.min(img_h).max(1) as f64).min(1.0).min(old_scale);
+            s.scale = s.scale.max(floor);
+            Some((s.scale / old_scale, get_scaled_size(s)))
+        })
+    }
+
+    pub fn offset(&self) -> (f64, f64) {
+        self.read(|s| (s.offset_x, s.offset_y))
+    }
+
+    // 拖动时各方向允许的最大偏移：图片超出绘图区部分的一半，未超出时为 0
+    // 区域视图与双指旋转留下的角度下图片范围不是简单的矩形，不做限制
+    pub fn pan_limits(&self, viewport: (f64, f64)) -> (f64, f64) {
+        self.read(|s| {
+            if s.clip.is_some() || s.tilt != 0.0 { return (f64::INFINITY, f64::INFINITY); }
+            let (scaled_w, scaled_h) = get_scaled_size(s);
+            ((scaled_w as f64 - viewport.0).max(0.0) / 2.0, (scaled_h as f64 - viewport.1).max(0.0) / 2.0)
+        })
+    }
+
+    pub fn pan_to(&self, x: f64, y: f64) {
+        self.update(|s| {
+            s.offset_x = x;
+            s.offset_y = y;
+            ((), Effects::REDRAW)
+        })
+    }
+
+    pub fn rotate(&self, clockwise: bool) {
+        self.update(|s| {
+            if s.pixbuf.is_none() { return ((), Effects::NONE); }
+            s.rotation = view::rotate_step(s.rotation, clockwise);
+            s.zoom_mode = ZoomMode::Free;
+            s.orientation_changed = Some(std::time::Instant::now());
+            let (w, h) = get_rotated_size(s);
+            s.min_zoom = fit_to_screen_scale(w, h);
+            ((), Effects::REDRAW)
+        })
+    }
+
+    // 双指旋转过程中的自由角度
+    pub fn set_tilt(&self, tilt: f64) {
+        self.update(|s| {
+            s.tilt = tilt;
+            ((), Effects::REDRAW)
+        })
+    }
+
+    // 双指旋转结束：总角度拆成最近的直角（rotation）与不超过 ±45° 的余量（tilt），
+    // 余量不超过 snap 时吸附到该直角
+    pub fn settle_tilt(&self, snap: f64) {
+        self.update(|s| {
+            let total = s.rotation as f64 * FRAC_PI_2 + s.tilt;
+            let quarters = (total / FRAC_PI_2).round();
+            let rest = total - quarters * FRAC_PI_2;
+            s.tilt = if rest.abs() <= snap { 0.0 } else { rest };
+            let rotation = (quarters as i32).rem_euclid(4);
+            if rotation != s.rotation {
+                s.rotation = rotation;
+                s.zoom_mode = ZoomMode::Free;
+                s.orientation_changed = Some(std::time::Instant::now());
+                let (w, h) = get_rotated_size(s);
+                s.min_zoom = fit_to_screen_scale(w, h);
+            }
+            ((), Effects::view())
+        })
+    }
+
+    // 适应窗口并居中，同时摆正双指旋转留下的角度
+    pub fn fit(&self, pixel_zoom: bool) {
+        self.update(|s| {
+            if s.pixbuf.is_none() { return ((), Effects::NONE); }
+            s.clip = None;
+            s.tilt = 0.0;
+            // 窗口可能已移到另一台显示器，按当前方向与显示器重新计算
+            let (img_w, img_h) = get_rotated_size(s);
+            s.min_zoom = fit_to_screen_scale(img_w, img_h);
+            s.scale = s.min_zoom;
+            s.zoom_mode = ZoomMode::Free;
+            if pixel_zoom { s.scale = floor_zoom_preset(s.scale); }
+            s.offset_x = 0.0;
+            s.offset_y = 0.0;
+            let (scaled_w, scaled_h) = get_scaled_size(s);
+            ((), Effects { resize: Some(Resize::Fit(scaled_w, scaled_h)), ..Effects::view() })
+        })
+    }
+
+    // 适应宽度或高度：图片在该方向上正好填满 viewport，另一方向超出的部分可以拖动浏览
+    // to_start 时视图移到顶端（适应宽度）或左端（适应高度），窗口跟随图片大小；
+    // 否则（窗口大小改变后重新适应）保持另一方向上的相对位置，不再调整窗口
+    pub fn fit_axis(&self, mode: ZoomMode, viewport: (f64, f64), to_start: bool) {
+        self.update(|s| {
+            if s.pixbuf.is_none() || mode == ZoomMode::Free { return ((), Effects::NONE); }
+            let (img_w, img_h) = get_rotated_size(s);
+            let (view_w, view_h) = viewport;
+            let old_scale = s.scale;
+            s.scale = match mode {
+                ZoomMode::FitHeight => view_h / img_h.max(1) as f64,
+                _ => view_w / img_w.max(1) as f64,
+            }.clamp(0.01, MAX_SCALE);
+            s.zoom_mode = mode;
+            s.clip = None;
+            s.tilt = 0.0;
+            let (scaled_w, scaled_h) = get_scaled_size(s);
+            let ratio = s.scale / old_scale;
+            // 偏移为正时图片向右下移动；超出部分的一半即对齐顶端或左端所需的偏移
+            let align = |offset: f64, scaled: i32, view: f64| {
+                let over = (scaled as f64 - view).max(0.0) / 2.0;
+                if to_start { over } else { (offset * ratio).clamp(-over, over) }
+            };
+            if mode == ZoomMode::FitWidth {
+                s.offset_x = 0.0;
+                s.offset_y = align(s.offset_y, scaled_h, view_h);
+            } else {
+                s.offset_y = 0.0;
+                s.offset_x = align(s.offset_x, scaled_w, view_w);
+            }
+            let resize = to_start.then_some(Resize::Fit(scaled_w, scaled_h));
+            ((), Effects { resize, ..Effects::view() })
+        })
+    }
+
+    // 设置缩放率并居中，窗口跟随图片大小
+    pub fn set_scale(&self, scale: f64) {
+        self.update(|s| {
+            s.scale = scale.clamp(0.1, 50.0);
+            s.zoom_mode = ZoomMode::Free;
+            s.offset_x = 0.0;
+            s.offset_y = 0.0;
+            let (scaled_w, scaled_h) = get_scaled_size(s);
+            ((), Effects { resize: Some(Resize::Fit(scaled_w, scaled_h)), ..Effects::view() })
+        })
+    }
+
+    // 按原图尺寸显示 100%；缩小载入的纹理按原图尺寸换算
+    pub fn actual_size(&self) {
+        let actual = self.read(|s| s.full_size.map_or(1.0, |(w, _)| w as f64 / s.original_width as f64));
+        self.set_scale(actual);
+    }
+
+    pub fn save_view(&self) -> SavedView {
+        self.read(|s| SavedView { scale: s.scale, offset: (s.offset_x, s.offset_y), zoom_mode: s.zoom_mode, min_zoom: s.min_zoom })
+    }
+
+    // 进入全屏：按整个显示器适应并居中，同时作为缩小的下限；窗口大小由合成器决定
+    pub fn fit_screen(&self) {
+        self.update(|s| {
+            if s.pixbuf.is_none() { return ((), Effects::NONE); }
+            let (img_w, img_h) = get_rotated_size(s);
+            s.min_zoom = fit_to_monitor_scale(img_w, img_h);
+            s.scale = s.min_zoom;
+            s.zoom_mode = ZoomMode::Free;
+            s.clip = None;
+            s.tilt = 0.0;
+            s.offset_x = 0.0;
+            s.offset_y = 0.0;
+            ((), Effects::view())
+        })
+    }
+
+    // 退出全屏：恢复进入前的缩放与位置，窗口大小另行恢复
+    pub fn restore_view(&self, saved: SavedView) {
+        self.update(|s| {
+            s.scale = saved.scale;
+            (s.offset_x, s.offset_y) = saved.offset;
+            s.zoom_mode = saved.zoom_mode;
+            s.min_zoom = saved.min_zoom;
+            ((), Effects::view())
+        })
+    }
+
+    // 退出置顶后回到普通窗口：有 saved 时恢复进入前的视图，否则保留置顶时的缩放、图片居中并按新缩放调整窗口；
+    // 都经过界面副作用刷新缩放标签等
+    pub fn leave_overlay(&self, saved: Option<SavedView>) {
+        self.update(|s| match saved {
+            Some(view) => {
+                s.scale = view.scale;
+                (s.offset_x, s.offset_y) = view.offset;
+                s.zoom_mode = view.zoom_mode;
+                ((), Effects::view())
+            }
+            None => {
+                s.offset_x = 0.0;
+                s.offset_y = 0.0;
+                s.zoom_mode = ZoomMode::Free;
+                let (scaled_w, scaled_h) = get_scaled_size(s);
+                ((), Effects { resize: Some(Resize::Fit(scaled_w, scaled_h)), ..Effects::view() })
+            }
+        })
+    }
+
+    // 吸附到不大于当前缩放率的档位
+    pub fn snap_to_preset(&self) {
+        self.update(|s| {
+            if s.pixbuf.is_none() { return ((), Effects::NONE); }
+            s.scale = floor_zoom_preset(s.scale);
+            s.zoom_mode = ZoomMode::Free;
+            ((), Effects::view())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::texture_from_rgba;
+    use gtk4::prelude::TextureExt;
+    use std::sync::Mutex;
+
+    // 副作用排队时向默认主上下文添加空闲回调，各测试线程不能同时占用它
+    static MAIN_CONTEXT: Mutex<()> = Mutex::new(());
+
+    fn model_with_image(w: u32, h: u32) -> ViewerModel {
+        let state = ImageState {
+            pixbuf: Some(texture_from_rgba(image::RgbaImage::new(w, h))),
+            original_width: w as i32,
+            original_height: h as i32,
+            ..ImageState::default()
+        };
+        ViewerModel::new(Rc::new(RefCell::new(state)))
+    }
+
+    fn view(model: &ViewerModel) -> (f64, (f64, f64), ZoomMode, f64) {
+        model.read(|s| (s.scale, (s.offset_x, s.offset_y), s.zoom_mode, s.min_zoom))
+    }
+
+    #[test]
+    fn restore_undoes_zoom_after_saving() {
+        let _context = MAIN_CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let model = model_with_image(800, 600);
+        model.fit_axis(ZoomMode::FitWidth, (400.0, 200.0), true);
+        model.pan_to(0.0, 40.0);
+        let before = view(&model);
+        let saved = model.save_view();
+        // 置顶或全屏中的缩放
+        model.set_scale(3.0);
+        model.zoom(-2.0, 1.1, false, (10.0, 10.0), (400.0, 300.0));
+        model.fit_screen();
+        assert_ne!(view(&model), before);
+        model.restore_view(saved);
+        assert_eq!(view(&model), before);
+    }
+
+    #[test]
+    fn save_keeps_zoom_made_before_it() {
+        let _context = MAIN_CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let model = model_with_image(800, 600);
+        model.set_scale(0.5);
+        model.zoom(1.0, 1.25, false, (0.0, 0.0), (400.0, 300.0));
+        model.pan_to(-12.0, 7.5);
+        let before = view(&model);
+        assert_eq!(before.0, 0.625);
+        let saved = model.save_view();
+        model.set_scale(2.0);
+        model.restore_view(saved);
+        assert_eq!(view(&model), before);
+    }
+
+    #[test]
+    fn saved_view_is_a_snapshot() {
+        let _context = MAIN_CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let model = model_with_image(640, 480);
+        model.set_scale(1.5);
+        let first = model.save_view();
+        model.set_scale(0.25);
+        let second = model.save_view();
+        model.restore_view(first);
+        assert_eq!(view(&model).0, 1.5);
+        model.restore_view(second);
+        assert_eq!(view(&model).0, 0.25);
+    }
+
+    #[test]
+    fn leaving_overlay_restores_or_keeps_the_overlay_zoom() {
+        let _context = MAIN_CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let model = model_with_image(800, 600);
+        model.set_scale(0.75);
+        model.pan_to(5.0, 6.0);
+        let saved = model.save_view();
+        model.set_scale(0.3);
+        model.pan_to(1.0, 2.0);
+        model.leave_overlay(Some(saved));
+        assert_eq!(view(&model).0, 0.75);
+        assert_eq!(model.offset(), (5.0, 6.0));
+        // 不恢复时保留置顶中的缩放，图片居中
+        model.set_scale(0.3);
+        model.pan_to(1.0, 2.0);
+        model.leave_overlay(None);
+        assert_eq!(view(&model).0, 0.3);
+        assert_eq!(model.offset(), (0.0, 0.0));
+    }
+
+    // 与载入新图片时一样：开始新的载入编号，替换纹理与尺寸后适应窗口
+    fn load(model: &ViewerModel, w: u32, h: u32) {
+        let texture = texture_from_rgba(image::RgbaImage::new(w, h));
+        model.mutate(|s| {
+            s.generation.begin();
+            s.pixbuf = Some(texture);
+            s.original_width = w as i32;
+            s.original_height = h as i32;
+            s.rotation = 0;
+        });
+        model.fit(false);
+    }
+
+    #[test]
+    fn rapid_zoom_rotate_and_load_never_double_borrow() {
+        let _context = MAIN_CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let context = glib::MainContext::default();
+        let model = model_with_image(800, 600);
+        // 与窗口的副作用回调一样：重绘时读取状态，窗口大小改变后按新的绘图区大小重新适应
+        let flushes = Rc::new(Cell::new(0));
+        {
+            let (reader, flushes) = (model.clone(), flushes.clone());
+            model.set_flush(move |effects| {
+                flushes.set(flushes.get() + 1);
+                if effects.redraw { reader.read(|s| assert!(s.scale > 0.0)); }
+                if let Some(Resize::Fit(w, h) | Resize::Content(w, h)) = effects.resize {
+                    reader.fit_axis(ZoomMode::FitWidth, (w.max(1) as f64, h.max(1) as f64), false);
+                }
+            });
+        }
+        for i in 0..2000u32 {
+            match i % 6 {
+                0 => model.zoom(1.0, 1.25, i % 12 == 0, ((i % 400) as f64, 150.0), (400.0, 300.0)),
+                1 => model.zoom(-1.5, 1.25, false, (10.0, 290.0), (400.0, 300.0)),
+                2 => model.rotate(i % 4 == 2),
+                3 => {
+                    model.set_tilt(0.3);
+                    model.settle_tilt(0.1);
+                }
+                4 => load(&model, 200 + i % 300, 100 + i % 500),
+                _ => model.fit(false),
+            }
+            // 异步载入的结果在两次操作之间到达
+            if i % 5 == 0 {
+                let model = model.clone();
+                glib::idle_add_local_once(move || load(&model, 640, 480));
+            }
+            // 副作用有时立即执行，有时与之后的操作合并
+            if i % 3 == 0 {
+                while context.iteration(false) {}
+            }
+        }
+        while context.iteration(false) {}
+        assert!(flushes.get() > 0);
+        model.read(|s| {
+            let size = s.pixbuf.as_ref().map(|t| (t.width(), t.height()));
+            assert_eq!(size, Some((s.original_width, s.original_height)));
+        });
+    }
+}
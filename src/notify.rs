@@ -0,0 +1,92 @@
+// 平铺模式下监视的文件更新而窗口不在前台时发送桌面通知（“render.png 已更新”），点击通知回到窗口
+// 连续重写的文件最多每隔几秒通知一次，间隔内的更新合并为间隔结束时的一条
+
+use crate::decode;
+use gtk4::prelude::*;
+use gtk4::{gdk_pixbuf, gio, glib, Application, ApplicationWindow};
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// 点击通知时激活的应用动作
+pub const ACTION: &str = "raise-tiles";
+// 同一 ID 的通知互相替换，通知中心只保留最新的一条
+const ID: &str = "file-updated";
+const MIN_INTERVAL: Duration = Duration::from_secs(5);
+const ICON_SIZE: i32 = 96;
+
+pub struct Notifier {
+    app: Application,
+    window: glib::WeakRef<ApplicationWindow>,
+    last: Cell<Option<Instant>>,
+    // 间隔内最后更新的文件，等间隔结束再通知
+    pending: RefCell<Option<String>>,
+    timer: RefCell<Option<glib::SourceId>>,
+}
+
+impl Notifier {
+    pub fn new(app: &Application, window: &ApplicationWindow) -> Rc<Self> {
+        let notifier = Rc::new(Self {
+            app: app.clone(),
+            window: window.downgrade(),
+            last: Cell::new(None),
+            pending: RefCell::new(None),
+            timer: RefCell::new(None),
+        });
+        let raise = gio::SimpleAction::new(ACTION, None);
+        let weak = window.downgrade();
+        raise.connect_activate(move |_, _| {
+            if let Some(window) = weak.upgrade() { window.present(); }
+        });
+        app.add_action(&raise);
+        // 回到窗口后通知已无意义
+        let app = app.clone();
+        window.connect_is_active_notify(move |w| {
+            if w.is_active() { app.withdraw_notification(ID); }
+        });
+        notifier
+    }
+
+    fn window_active(&self) -> bool {
+        self.window.upgrade().is_some_and(|w| w.is_active())
+    }
+
+    // 文件写入完成后调用
+    pub fn updated(self: &Rc<Self>, path: &str) {
+        if self.window_active() { return; }
+        let elapsed = self.last.get().map(|t| t.elapsed());
+        match elapsed {
+            Some(elapsed) if elapsed < MIN_INTERVAL => {
+                *self.pending.borrow_mut() = Some(path.to_string());
+                if self.timer.borrow().is_some() { return; }
+                let this = self.clone();
+                let id = glib::timeout_add_local_once(MIN_INTERVAL - elapsed, move || {
+                    this.timer.borrow_mut().take();
+                    let Some(path) = this.pending.borrow_mut().take() else { return };
+                    if !this.window_active() { this.send(&path); }
+                });
+                *self.timer.borrow_mut() = Some(id);
+            }
+            _ => self.send(path),
+        }
+    }
+
+    fn send(&self, path: &str) {
+        let name = Path::new(path).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string());
+        let notification = gio::Notification::new(&format!("{} 已更新", name));
+        if let Some((w, h)) = decode::oriented_dimensions(Path::new(path)) {
+            notification.set_body(Some(&format!("{} × {}", w, h)));
+        }
+        // 缩略图按比例解码；通知服务器不支持图标时忽略
+        if let Ok(thumbnail) = gdk_pixbuf::Pixbuf::from_file_at_scale(path, ICON_SIZE, ICON_SIZE, true) {
+            let thumbnail = thumbnail.apply_embedded_orientation().unwrap_or(thumbnail);
+            notification.set_icon(&thumbnail);
+        }
+        notification.set_default_action(&format!("app.{}", ACTION));
+        self.app.send_notification(Some(ID), &notification);
+        self.last.set(Some(Instant::now()));
+    }
+}
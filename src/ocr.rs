@@ -0,0 +1,19 @@
+// 文字识别：调用 Tesseract 识别 RGBA 像素中的文字（仅在启用 ocr 特性时编译）
+
+use tesseract::Tesseract;
+
+// 识别文字；语言数据缺失时返回带安装提示的错误
+pub fn recognize(rgba: &[u8], width: i32, height: i32, stride: i32, language: &str) -> Result<String, String> {
+    let tess = Tesseract::new(None, Some(language)).map_err(|_| {
+        format!("未找到 Tesseract 语言数据 \"{}\"。请安装对应的数据包（Arch: tesseract-data-{}，\
+                 Debian/Ubuntu: tesseract-ocr-{}），或通过 TESSDATA_PREFIX 指定数据目录",
+                language, language, language.replace('_', "-"))
+    })?;
+    let mut tess = tess
+        .set_frame(rgba, width, height, 4, stride)
+        .map_err(|e| e.to_string())?
+        .recognize()
+        .map_err(|e| e.to_string())?;
+    let text = tess.get_text().map_err(|e| e.to_string())?;
+    Ok(text.trim().to_string())
+}
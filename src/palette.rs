@@ -0,0 +1,207 @@
+// 命令面板（Ctrl+Shift+P）：搜索并执行窗口上注册的所有动作（win.*）
+// 按名称模糊匹配，最近用过的命令排在前面，全程可只用键盘操作
+
+use gtk4::prelude::*;
+use gtk4::{gdk, glib, ApplicationWindow, Label, ListBox, Orientation, Popover, SearchEntry};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// 动作名与显示名称；未注册或已禁用的动作不显示，快捷键提示取实际生效的快捷键
+type Command = (&'static str, &'static str);
+
+const COMMANDS: &[Command] = &[
+    ("open", "打开图片"),
+    ("open-tabs", "在新标签中打开"),
+    ("reset-view", "恢复视图"),
+    ("zoom-in", "放大"),
+    ("zoom-out", "缩小"),
+    ("actual-size", "显示 100%"),
+    ("fullscreen", "全屏 / 退出全屏"),
+    ("rotate", "旋转 90°"),
+    ("copy", "复制图片"),
+    ("paste", "粘贴图片"),
+    ("copy-view", "复制可见部分"),
+    ("copy-path", "复制文件路径"),
+    ("copy-text", "复制识别出的文字"),
+    ("previous-image", "上一张"),
+    ("next-image", "下一张"),
+    ("previous-group", "上一组"),
+    ("next-group", "下一组"),
+    ("play-burst", "播放 / 停止连拍"),
+    ("play-animation", "暂停 / 继续播放动画"),
+    ("slideshow", "开始 / 停止幻灯片"),
+    ("next-tab", "下一个标签"),
+    ("previous-tab", "上一个标签"),
+    ("close-tab", "关闭标签"),
+    ("extract-frames", "导出所有帧/页"),
+    ("export-frame", "导出当前帧"),
+    ("copy-frame", "复制当前帧"),
+    ("set-poster", "设为封面帧"),
+    ("rename", "重命名"),
+    ("number-files", "按顺序编号"),
+    ("contact-sheet", "导出目录缩略图总览"),
+    ("trash", "移到回收站"),
+    ("undo", "撤销"),
+    ("pixel-zoom", "切换像素对齐缩放"),
+    ("sticky-rotation", "旋转应用到后续图片"),
+    ("scale-bar", "显示 / 隐藏比例尺"),
+    ("print-size", "按打印尺寸显示"),
+    ("fit-width", "适应宽度"),
+    ("fit-height", "适应高度"),
+    ("image-info", "图片信息"),
+    ("keep-above", "保持在最前"),
+    ("pin-to-monitor", "置顶到显示器…"),
+    ("close-window", "关闭窗口"),
+];
+
+pub fn is_command(action: &str) -> bool {
+    COMMANDS.iter().any(|(a, _)| *a == action)
+}
+
+pub fn command_names() -> impl Iterator<Item = &'static str> {
+    COMMANDS.iter().map(|(action, _)| *action)
+}
+
+// 本次运行中最近执行的命令，最近的在前
+#[derive(Clone, Default)]
+pub struct Recent(Rc<RefCell<Vec<&'static str>>>);
+
+impl Recent {
+    fn touch(&self, action: &'static str) {
+        let mut list = self.0.borrow_mut();
+        list.retain(|a| *a != action);
+        list.insert(0, action);
+    }
+
+    fn rank(&self, action: &str) -> Option<usize> {
+        self.0.borrow().iter().position(|a| *a == action)
+    }
+}
+
+// 模糊匹配：查询中的字符按顺序出现在名称中即匹配；连续命中与开头命中得分更高
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += match last {
+            Some(l) if l + 1 == found => 5,
+            _ if found == 0 => 3,
+            _ => 1,
+        };
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+// 按查询排序的命令：有查询时按得分，其次按最近使用；无查询时最近使用的在前，其余保持列表顺序
+fn matches(window: &ApplicationWindow, recent: &Recent, query: &str) -> Vec<Command> {
+    let mut found: Vec<(i32, usize, usize, Command)> = COMMANDS.iter().enumerate()
+        .filter(|(_, (action, _))| window.lookup_action(action).is_some_and(|a| a.is_enabled()))
+        .filter_map(|(i, &(action, title))| {
+            // 动作名也参与匹配，输入英文同样可以找到
+            let score = fuzzy_score(query, title).max(fuzzy_score(query, action))?;
+            Some((score, recent.rank(action).unwrap_or(usize::MAX), i, (action, title)))
+        })
+        .collect();
+    found.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    found.into_iter().map(|(_, _, _, c)| c).collect()
+}
+
+fn row(title: &str, shortcut: &str) -> gtk4::ListBoxRow {
+    let content = gtk4::Box::new(Orientation::Horizontal, 12);
+    content.set_margin_start(6);
+    content.set_margin_end(6);
+    content.set_margin_top(4);
+    content.set_margin_bottom(4);
+    content.append(&Label::builder().label(title).halign(gtk4::Align::Start).hexpand(true).build());
+    let hint = Label::new(Some(shortcut));
+    hint.add_css_class("dim-label");
+    content.append(&hint);
+    gtk4::ListBoxRow::builder().child(&content).build()
+}
+
+// 在 anchor 的顶部居中弹出命令面板
+pub fn show(window: &ApplicationWindow, anchor: &impl IsA<gtk4::Widget>, recent: &Recent) {
+    let entry = SearchEntry::builder().placeholder_text("输入命令…").build();
+    let list = ListBox::new();
+    list.set_selection_mode(gtk4::SelectionMode::Browse);
+    let scroller = gtk4::ScrolledWindow::builder()
+        .child(&list)
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(320)
+        .min_content_width(320)
+        .build();
+    let content = gtk4::Box::new(Orientation::Vertical, 6);
+    content.append(&entry);
+    content.append(&scroller);
+    let popover = Popover::builder().child(&content).has_arrow(false).position(gtk4::PositionType::Bottom).build();
+    popover.set_parent(anchor);
+    popover.set_pointing_to(Some(&gdk::Rectangle::new(anchor.width() / 2, 0, 1, 1)));
+    popover.connect_closed(|p| {
+        // 关闭动画结束后再移除
+        let p = p.clone();
+        glib::idle_add_local_once(move || p.unparent());
+    });
+
+    // 当前显示的命令，与列表行一一对应
+    let shown: Rc<RefCell<Vec<&'static str>>> = Rc::default();
+    let refill = {
+        let (window, recent, list, shown) = (window.clone(), recent.clone(), list.clone(), shown.clone());
+        move |query: &str| {
+            list.remove_all();
+            let found = matches(&window, &recent, query);
+            for &(action, title) in &found {
+                list.append(&row(title, &crate::keys::label(action)));
+            }
+            list.select_row(list.row_at_index(0).as_ref());
+            *shown.borrow_mut() = found.into_iter().map(|(action, _)| action).collect();
+        }
+    };
+    refill("");
+    entry.connect_search_changed(move |e| refill(&e.text()));
+
+    let run = {
+        let (window, recent, popover) = (window.clone(), recent.clone(), popover.clone());
+        Rc::new(move |index: i32| {
+            let Some(action) = usize::try_from(index).ok().and_then(|i| shown.borrow().get(i).copied()) else { return };
+            popover.popdown();
+            recent.touch(action);
+            if let Err(e) = WidgetExt::activate_action(&window, &format!("win.{}", action), None) {
+                eprintln!("无法执行命令 {}: {}", action, e);
+            }
+        })
+    };
+    entry.connect_activate({
+        let (run, list) = (run.clone(), list.clone());
+        move |_| run(list.selected_row().map_or(0, |r| r.index()))
+    });
+    list.connect_row_activated(move |_, row| run(row.index()));
+
+    // 焦点留在输入框中，用上下键移动选中项并滚动到可见
+    let keys = gtk4::EventControllerKey::new();
+    keys.connect_key_pressed(move |_, key, _, _| {
+        let delta = match key {
+            gdk::Key::Up => -1,
+            gdk::Key::Down => 1,
+            _ => return glib::Propagation::Proceed,
+        };
+        let current = list.selected_row().map_or(-1, |r| r.index());
+        if let Some(row) = list.row_at_index((current + delta).max(0)) {
+            list.select_row(Some(&row));
+            if let Some(bounds) = row.compute_bounds(&list) {
+                let (y, h) = (bounds.y() as f64, bounds.height() as f64);
+                scroller.vadjustment().clamp_page(y, y + h);
+            }
+        }
+        glib::Propagation::Stop
+    });
+    entry.add_controller(keys);
+
+    popover.popup();
+    entry.grab_focus();
+}
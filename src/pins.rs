@@ -0,0 +1,82 @@
+// 固定位置：把置顶窗口的完整描述（文件、缩放、方向、位置、不透明度、显示器）按名称保存，
+// 之后用 --restore-pin NAME 原样重建
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Pin {
+    pub path: String,
+    pub scale: f64,
+    #[serde(default)]
+    pub rotation: i32,
+    pub margin_left: i32,
+    pub margin_top: i32,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    // 显示器接口名（如 DP-1）；缺失或找不到时使用默认显示器
+    #[serde(default)]
+    pub monitor: Option<String>,
+}
+
+fn default_opacity() -> f64 {
+    1.0
+}
+
+// 与配置文件放在同一目录下，每个名称一个文件
+fn dir() -> PathBuf {
+    Config::path().with_file_name("pins")
+}
+
+fn file(name: &str) -> PathBuf {
+    dir().join(format!("{}.toml", name))
+}
+
+// 名称直接用作文件名，不允许路径分隔符和隐藏文件
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("名称不能为空".to_string());
+    }
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("名称 {} 无效：不能包含 / 或以 . 开头", name));
+    }
+    Ok(())
+}
+
+pub fn save(name: &str, pin: &Pin) -> Result<PathBuf, String> {
+    validate_name(name)?;
+    let text = toml::to_string(pin).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(dir()).map_err(|e| e.to_string())?;
+    let path = file(name);
+    std::fs::write(&path, text).map_err(|e| format!("无法写入 {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub fn load(name: &str) -> Result<Pin, String> {
+    validate_name(name)?;
+    let path = file(name);
+    let text = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("没有名为 {} 的固定位置（用 --list-pins 查看）", name),
+        _ => format!("无法读取 {}: {}", path.display(), e),
+    })?;
+    toml::from_str(&text).map_err(|e| format!("固定位置 {} 无效: {}", path.display(), e))
+}
+
+// 已保存的名称，按字母排序
+pub fn list() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir()) else { return Vec::new() };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let path = e.path();
+            if path.extension().is_some_and(|x| x == "toml") {
+                path.file_stem().map(|s| s.to_string_lossy().to_string())
+            } else {
+                None
+            }
+        })
+        .collect();
+    names.sort();
+    names
+}
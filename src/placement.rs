@@ -0,0 +1,235 @@
+// 置顶窗口的摆放位置计算（纯函数，只处理矩形与坐标）
+
+use serde::Deserialize;
+
+// 双击进入置顶时的摆放策略
+#[derive(Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Placement {
+    // 放到离指针最远的屏幕角落
+    #[default]
+    Corner,
+    // 保持图片在双击前的屏幕位置
+    Preserve,
+}
+
+// 普通窗口出现的位置（--center / --at-pointer）
+// X11 下可以移动窗口；Wayland 下位置由合成器决定，这里只决定按哪个显示器计算窗口尺寸
+#[derive(Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StartPosition {
+    // X11 下放到指针旁边；按窗口实际出现的显示器（合成器通常选择指针所在的显示器）计算尺寸
+    #[default]
+    Pointer,
+    // 按第一个显示器计算尺寸，由合成器居中放置
+    Center,
+}
+
+// 置顶窗口最多占屏幕宽、高的比例
+pub const MAX_SCREEN_FRACTION: f64 = 0.3;
+
+// 与屏幕边缘保留的间距
+const EDGE_GAP: i32 = 24;
+
+// 新窗口与指针之间的间距，指针不会落在窗口上
+const POINTER_GAP: i32 = 16;
+
+// 同时打开多个窗口时相邻窗口错开的距离
+pub const CASCADE_STEP: i32 = 32;
+
+// 把 (w, h) 缩放到不超过屏幕的 MAX_SCREEN_FRACTION 所需的缩放系数（不放大）
+pub fn fit_factor((w, h): (i32, i32), (screen_w, screen_h): (i32, i32)) -> f64 {
+    if w <= 0 || h <= 0 { return 1.0; }
+    (screen_w as f64 * MAX_SCREEN_FRACTION / w as f64)
+        .min(screen_h as f64 * MAX_SCREEN_FRACTION / h as f64)
+        .min(1.0)
+}
+
+// 屏幕上的矩形，坐标相对于所在显示器的左上角
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Rect { x, y, width, height }
+    }
+
+    // 与另一矩形重叠部分的面积
+    fn overlap(&self, other: &Rect) -> i64 {
+        let w = (self.x + self.width).min(other.x + other.width) - self.x.max(other.x);
+        let h = (self.y + self.height).min(other.y + other.height) - self.y.max(other.y);
+        if w <= 0 || h <= 0 { 0 } else { w as i64 * h as i64 }
+    }
+}
+
+// 在角落中放置 (w, h) 窗口时的左上角坐标（即 layer-shell 的左、上边距）
+// 选择遮挡 avoid（正在使用的窗口）最少的角落，遮挡相同或没有 avoid 时选离指针最远的角落
+pub fn least_covered_corner((screen_w, screen_h): (i32, i32), (px, py): (f64, f64), (w, h): (i32, i32), avoid: Option<Rect>) -> (i32, i32) {
+    let left = EDGE_GAP;
+    let top = EDGE_GAP;
+    let right = (screen_w - w - EDGE_GAP).max(0);
+    let bottom = (screen_h - h - EDGE_GAP).max(0);
+    let covered = |x: i32, y: i32| avoid.map_or(0, |r| r.overlap(&Rect::new(x, y, w, h)));
+    // 比较指针到各角落窗口中心的距离
+    let dist = |x: i32, y: i32| {
+        let (cx, cy) = (x as f64 + w as f64 / 2.0, y as f64 + h as f64 / 2.0);
+        (cx - px).powi(2) + (cy - py).powi(2)
+    };
+    [(left, top), (right, top), (left, bottom), (right, bottom)]
+        .into_iter()
+        .max_by(|a, b| covered(b.0, b.1).cmp(&covered(a.0, a.1)).then(dist(a.0, a.1).total_cmp(&dist(b.0, b.1))))
+        .unwrap_or((left, top))
+}
+
+// 在指针右下方放置 (w, h) 窗口时的左上角坐标；某个方向空间不够时改放到指针的左侧或上方，
+// 两侧都放不下时贴着工作区域 area 的边缘。坐标与 area 相同，为整个屏幕的坐标
+pub fn beside_pointer(area: Rect, (px, py): (i32, i32), (w, h): (i32, i32)) -> (i32, i32) {
+    let along = |p: i32, size: i32, start: i32, len: i32| {
+        let (after, before) = (p + POINTER_GAP, p - POINTER_GAP - size);
+        let pos = if after + size <= start + len || before < start { after } else { before };
+        pos.min(start + len - size).max(start)
+    };
+    (along(px, w, area.x, area.width), along(py, h, area.y, area.height))
+}
+
+// 同时打开的第 index 个 (w, h) 窗口的左上角：从 start 起每个窗口向右下错开 CASCADE_STEP，
+// 再错开就会超出工作区域 area 时回到 start 重新开始。start 为 None 时从 area 的左上角（留出边距）开始
+pub fn cascade(area: Rect, start: Option<(i32, i32)>, index: i32, (w, h): (i32, i32)) -> (i32, i32) {
+    let (sx, sy) = start.unwrap_or((area.x + EDGE_GAP, area.y + EDGE_GAP));
+    // 从 s 起沿一个方向能放下的位置数，至少为 1
+    let fits = |s: i32, size: i32, begin: i32, len: i32| (begin + len - size - s).max(0) / CASCADE_STEP + 1;
+    let steps = index.max(0) % fits(sx, w, area.x, area.width).min(fits(sy, h, area.y, area.height));
+    (sx + steps * CASCADE_STEP, sy + steps * CASCADE_STEP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: (i32, i32) = (1920, 1080);
+
+    #[test]
+    fn fit_factor_limits_to_screen_fraction() {
+        // 宽度受限：1920 × 0.3 / 1920
+        assert_eq!(fit_factor((1920, 100), SCREEN), 0.3);
+        // 高度受限：1080 × 0.3 / 1080
+        assert_eq!(fit_factor((100, 1080), SCREEN), 0.3);
+        // 取两者中较小的系数
+        assert_eq!(fit_factor((3840, 1080), SCREEN), 0.15);
+    }
+
+    #[test]
+    fn fit_factor_never_enlarges() {
+        assert_eq!(fit_factor((100, 100), SCREEN), 1.0);
+        assert_eq!(fit_factor((576, 324), SCREEN), 1.0);
+        assert_eq!(fit_factor((0, 100), SCREEN), 1.0);
+        assert_eq!(fit_factor((100, -1), SCREEN), 1.0);
+    }
+
+    #[test]
+    fn without_a_window_the_corner_opposite_the_pointer_wins() {
+        let size = (400, 300);
+        let (left, top, right, bottom) = (24, 24, 1920 - 400 - 24, 1080 - 300 - 24);
+        assert_eq!(least_covered_corner(SCREEN, (10.0, 10.0), size, None), (right, bottom));
+        assert_eq!(least_covered_corner(SCREEN, (1900.0, 10.0), size, None), (left, bottom));
+        assert_eq!(least_covered_corner(SCREEN, (10.0, 1070.0), size, None), (right, top));
+        assert_eq!(least_covered_corner(SCREEN, (1900.0, 1070.0), size, None), (left, top));
+    }
+
+    #[test]
+    fn oversized_windows_clamp_to_the_screen_edge() {
+        // 比屏幕还大时右、下两边退到 0，不会给出负的边距
+        assert_eq!(least_covered_corner(SCREEN, (1900.0, 1070.0), (2000, 1200), None), (0, 0));
+        assert_eq!(least_covered_corner(SCREEN, (10.0, 10.0), (2000, 1200), None), (24, 24));
+    }
+
+    #[test]
+    fn avoids_the_active_window_before_the_pointer() {
+        let size = (400, 300);
+        // 正在使用的窗口占据右半屏，指针在左上角：避开右侧，选左下角
+        let active = Rect::new(960, 0, 960, 1080);
+        assert_eq!(least_covered_corner(SCREEN, (10.0, 10.0), size, Some(active)), (24, 1080 - 300 - 24));
+        // 窗口只占左上角，指针在右下角：最远的左上角被遮挡，改选其余角落中离指针最远的左下角
+        let active = Rect::new(0, 0, 800, 600);
+        assert_eq!(least_covered_corner(SCREEN, (1900.0, 1070.0), size, Some(active)), (24, 1080 - 300 - 24));
+    }
+
+    #[test]
+    fn full_screen_window_falls_back_to_the_pointer() {
+        let size = (400, 300);
+        let active = Rect::new(0, 0, 1920, 1080);
+        assert_eq!(
+            least_covered_corner(SCREEN, (10.0, 10.0), size, Some(active)),
+            least_covered_corner(SCREEN, (10.0, 10.0), size, None),
+        );
+    }
+
+    #[test]
+    fn overlap_of_disjoint_and_nested_rects() {
+        let a = Rect::new(0, 0, 100, 100);
+        assert_eq!(a.overlap(&Rect::new(100, 0, 50, 50)), 0);
+        assert_eq!(a.overlap(&Rect::new(50, 50, 100, 100)), 2500);
+        assert_eq!(a.overlap(&Rect::new(10, 10, 20, 20)), 400);
+    }
+
+    // 第二台显示器，工作区域去掉了顶部 32 像素的面板
+    const AREA: Rect = Rect { x: 1920, y: 32, width: 1920, height: 1048 };
+
+    #[test]
+    fn window_opens_below_right_of_the_pointer() {
+        assert_eq!(beside_pointer(AREA, (2000, 100), (800, 600)), (2016, 116));
+    }
+
+    #[test]
+    fn window_flips_to_the_side_with_room() {
+        // 右侧放不下，改到指针左侧
+        assert_eq!(beside_pointer(AREA, (3700, 100), (800, 600)), (3700 - 16 - 800, 116));
+        // 下方放不下，改到指针上方
+        assert_eq!(beside_pointer(AREA, (2000, 1000), (800, 600)), (2016, 1000 - 16 - 600));
+    }
+
+    #[test]
+    fn window_stays_inside_the_work_area() {
+        // 两侧都放不下时贴边，不会盖住面板或跑到另一台显示器上
+        let (x, y) = beside_pointer(AREA, (2800, 500), (1900, 1040));
+        assert_eq!((x, y), (1920 + 1920 - 1900, 32 + 1048 - 1040));
+        // 比工作区域还大时对齐左上角
+        assert_eq!(beside_pointer(AREA, (2800, 500), (2500, 1200)), (1920, 32));
+    }
+
+    #[test]
+    fn pointer_is_never_covered_when_there_is_room() {
+        let size = (600, 400);
+        for px in (AREA.x..AREA.x + AREA.width).step_by(97) {
+            for py in (AREA.y..AREA.y + AREA.height).step_by(89) {
+                let (x, y) = beside_pointer(AREA, (px, py), size);
+                let covers = (x..x + size.0).contains(&px) && (y..y + size.1).contains(&py);
+                assert!(!covers, "pointer ({px}, {py}) covered by window at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn cascaded_windows_step_down_and_right() {
+        let size = (800, 600);
+        assert_eq!(cascade(AREA, None, 0, size), (1920 + 24, 32 + 24));
+        assert_eq!(cascade(AREA, None, 1, size), (1920 + 24 + 32, 32 + 24 + 32));
+        assert_eq!(cascade(AREA, Some((2016, 116)), 2, size), (2016 + 64, 116 + 64));
+    }
+
+    #[test]
+    fn cascade_wraps_before_leaving_the_work_area() {
+        let size = (800, 600);
+        // 高度方向先用完：从 56 起放下 600 高的窗口，到 32 + 1048 为止共 (1080 - 600 - 56) / 32 + 1 = 14 个位置
+        let last = cascade(AREA, None, 13, size);
+        assert!(last.1 + size.1 <= AREA.y + AREA.height);
+        assert_eq!(cascade(AREA, None, 14, size), cascade(AREA, None, 0, size));
+        // 比工作区域还大的窗口不再错开
+        assert_eq!(cascade(AREA, None, 3, (2500, 1200)), cascade(AREA, None, 0, (2500, 1200)));
+    }
+}
@@ -0,0 +1,81 @@
+// 单击与拖动的区分：按下后位移超过系统的拖动阈值（gtk-dnd-drag-threshold）才算拖动，之后直到松开都算拖动
+// 阈值在每次判断时从 GtkSettings 读取，在辅助功能设置中改大后立即生效
+// 双击由 GestureClick 按同一设置中的 gtk-double-click-time 与 gtk-double-click-distance 计数，这里不另行判断
+
+use std::cell::Cell;
+
+// 读不到设置（没有显示服务器）时使用 GTK 的默认值
+const DEFAULT_THRESHOLD: f64 = 8.0;
+
+pub fn drag_threshold() -> f64 {
+    gtk4::Settings::default()
+        .map_or(DEFAULT_THRESHOLD, |s| s.gtk_dnd_drag_threshold() as f64)
+        .max(1.0)
+}
+
+// 一次按下到松开之间是否已经成为拖动
+#[derive(Default)]
+pub struct DragGate(Cell<bool>);
+
+impl DragGate {
+    // 在拖动手势开始（按下）时调用
+    pub fn reset(&self) {
+        self.0.set(false);
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.0.get()
+    }
+
+    // 按相对按下位置的位移判断；越过 threshold 后一直返回 true
+    pub fn passed(&self, dx: f64, dy: f64, threshold: f64) -> bool {
+        if !self.0.get() && dx.hypot(dy) >= threshold { self.0.set(true); }
+        self.0.get()
+    }
+
+    // 按系统设置的拖动阈值判断
+    pub fn passed_system(&self, dx: f64, dy: f64) -> bool {
+        self.passed(dx, dy, drag_threshold())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DragGate;
+
+    #[test]
+    fn small_moves_stay_a_click() {
+        let gate = DragGate::default();
+        assert!(!gate.passed(3.0, 0.0, 8.0));
+        assert!(!gate.passed(-5.0, 6.0, 8.0));
+        assert!(!gate.is_dragging());
+    }
+
+    #[test]
+    fn threshold_is_a_distance_and_inclusive() {
+        assert!(DragGate::default().passed(8.0, 0.0, 8.0));
+        assert!(DragGate::default().passed(0.0, -8.0, 8.0));
+        // 对角方向按直线距离：6、8 的位移距离为 10
+        assert!(DragGate::default().passed(6.0, 8.0, 10.0));
+        assert!(!DragGate::default().passed(6.0, 7.9, 10.0));
+    }
+
+    #[test]
+    fn stays_a_drag_until_reset() {
+        let gate = DragGate::default();
+        assert!(gate.passed(20.0, 0.0, 8.0));
+        // 拖回按下的位置也仍是拖动
+        assert!(gate.passed(0.0, 0.0, 8.0));
+        assert!(gate.is_dragging());
+        gate.reset();
+        assert!(!gate.is_dragging());
+        assert!(!gate.passed(2.0, 2.0, 8.0));
+    }
+
+    #[test]
+    fn larger_accessibility_threshold_needs_a_longer_move() {
+        let gate = DragGate::default();
+        assert!(!gate.passed(20.0, 0.0, 32.0));
+        assert!(gate.passed(32.0, 0.0, 32.0));
+    }
+}
@@ -0,0 +1,46 @@
+// 多帧图片的封面帧：按文件记下静止显示时与缩略图中使用的帧（从 1 开始），保存在配置目录的 posters.toml
+// 以规范化后的路径为键；设回第 1 帧即删除记录
+
+use crate::config::Config;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    // 第一次用到时读取
+    static POSTERS: RefCell<Option<BTreeMap<String, usize>>> = const { RefCell::new(None) };
+}
+
+fn file() -> PathBuf {
+    Config::path().with_file_name("posters.toml")
+}
+
+fn key(path: &Path) -> String {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf()).to_string_lossy().into_owned()
+}
+
+fn with<R>(f: impl FnOnce(&mut BTreeMap<String, usize>) -> R) -> R {
+    POSTERS.with_borrow_mut(|posters| {
+        let posters = posters.get_or_insert_with(|| {
+            std::fs::read_to_string(file()).ok().and_then(|text| toml::from_str(&text).ok()).unwrap_or_default()
+        });
+        f(posters)
+    })
+}
+
+// 文件的封面帧；没有设置时为 None（显示第 1 帧）
+pub fn get(path: &Path) -> Option<usize> {
+    let key = key(path);
+    with(|posters| posters.get(&key).copied()).filter(|&frame| frame > 1)
+}
+
+pub fn set(path: &Path, frame: usize) -> Result<(), String> {
+    let key = key(path);
+    let text = with(|posters| {
+        if frame > 1 { posters.insert(key, frame); } else { posters.remove(&key); }
+        toml::to_string(posters).map_err(|e| e.to_string())
+    })?;
+    let target = file();
+    if let Some(dir) = target.parent() { std::fs::create_dir_all(dir).map_err(|e| e.to_string())?; }
+    std::fs::write(&target, text).map_err(|e| format!("无法写入 {}: {}", target.display(), e))
+}
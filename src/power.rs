@@ -0,0 +1,75 @@
+// 低功耗：窗口最小化、被完全遮挡（合成器报告 suspended）或未映射时暂停后台工作
+// 暂停期间的刷新请求按键合并，恢复可见后各执行一次，保证恢复后的第一帧是最新状态
+
+use gtk4::prelude::*;
+use gtk4::ApplicationWindow;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+type Deferred = std::boxed::Box<dyn FnOnce()>;
+type Listener = std::boxed::Box<dyn Fn(bool)>;
+
+#[derive(Default)]
+struct Inner {
+    suspended: Cell<bool>,
+    // 同一个键只保留最后一次请求
+    deferred: RefCell<BTreeMap<String, Deferred>>,
+    listeners: RefCell<Vec<Listener>>,
+}
+
+#[derive(Clone, Default)]
+pub struct Suspension {
+    inner: Rc<Inner>,
+}
+
+impl Suspension {
+    // 跟踪窗口的映射与 suspended 状态；窗口映射前视为暂停
+    pub fn attach(&self, window: &ApplicationWindow) {
+        let update = {
+            let this = self.clone();
+            move |w: &ApplicationWindow| this.set(!w.is_mapped() || w.is_suspended())
+        };
+        update(window);
+        window.connect_suspended_notify(update.clone());
+        window.connect_map(update.clone());
+        window.connect_unmap(update);
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.inner.suspended.get()
+    }
+
+    // 可见时立即执行；暂停时推迟到恢复，同键的旧请求被替换
+    pub fn run_or_defer(&self, key: &str, f: impl FnOnce() + 'static) {
+        if self.is_suspended() {
+            self.inner.deferred.borrow_mut().insert(key.to_string(), std::boxed::Box::new(f));
+        } else {
+            f();
+        }
+    }
+
+    // 暂停状态变化时回调（参数为是否暂停）
+    pub fn connect_changed(&self, f: impl Fn(bool) + 'static) {
+        self.inner.listeners.borrow_mut().push(std::boxed::Box::new(f));
+    }
+
+    fn set(&self, suspended: bool) {
+        if self.inner.suspended.replace(suspended) == suspended { return; }
+        if !suspended {
+            let deferred = std::mem::take(&mut *self.inner.deferred.borrow_mut());
+            for f in deferred.into_values() { f(); }
+        }
+        for f in self.inner.listeners.borrow().iter() { f(suspended); }
+    }
+
+    // 诊断信息中的状态描述
+    pub fn describe(&self) -> String {
+        let pending = self.inner.deferred.borrow().len();
+        match (self.is_suspended(), pending) {
+            (false, _) => "活动".to_string(),
+            (true, 0) => "已暂停（窗口不可见）".to_string(),
+            (true, n) => format!("已暂停（窗口不可见，{} 项刷新待恢复后执行）", n),
+        }
+    }
+}
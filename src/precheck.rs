@@ -0,0 +1,58 @@
+// 打开文件前的检查：不存在、链接失效、没有读取权限、空文件分别给出明确的说明，不再交给解码器报笼统的错误
+// 目录不算问题，按目录打开的规则处理；命令行启动时按问题类型使用不同的退出码，便于脚本区分
+
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Problem {
+    Missing,
+    BrokenLink,
+    PermissionDenied,
+    Empty,
+}
+
+impl Problem {
+    pub fn message(self) -> &'static str {
+        match self {
+            Problem::Missing => "文件不存在",
+            Problem::BrokenLink => "符号链接指向的文件不存在",
+            Problem::PermissionDenied => "没有读取权限",
+            Problem::Empty => "文件是空的",
+        }
+    }
+
+    // 1 留给参数错误；不存在与链接失效同为 2
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Problem::Missing | Problem::BrokenLink => 2,
+            Problem::PermissionDenied => 3,
+            Problem::Empty => 4,
+        }
+    }
+}
+
+pub fn check(path: &Path) -> Result<(), Problem> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => return Err(Problem::PermissionDenied),
+        // 链接本身存在而目标不存在
+        Err(_) if path.symlink_metadata().is_ok() => return Err(Problem::BrokenLink),
+        Err(_) => return Err(Problem::Missing),
+    };
+    if metadata.is_dir() {
+        return match std::fs::read_dir(path) {
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => Err(Problem::PermissionDenied),
+            _ => Ok(()),
+        };
+    }
+    // 管道、设备等特殊文件不检查：打开管道会一直等待写入方，长度也没有意义
+    if !metadata.is_file() { return Ok(()); }
+    // 其他打开错误留给解码时报告
+    if let Err(e) = File::open(path) && e.kind() == ErrorKind::PermissionDenied {
+        return Err(Problem::PermissionDenied);
+    }
+    if metadata.len() == 0 { return Err(Problem::Empty); }
+    Ok(())
+}
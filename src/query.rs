@@ -0,0 +1,144 @@
+// --query：不创建窗口，只读文件头输出图片属性，供脚本使用
+// 默认每行一个 key=value，--json 时输出一个 JSON 对象
+// 退出码：0 成功，2 参数错误，3 文件不存在或无法读取，4 不是可识别的图片
+
+use crate::{decode, details, header};
+use header::Format;
+use image::ImageReader;
+use std::path::{Path, PathBuf};
+
+const EXIT_USAGE: i32 = 2;
+const EXIT_MISSING: i32 = 3;
+const EXIT_NOT_IMAGE: i32 = 4;
+
+struct Properties {
+    width: u32,
+    height: u32,
+    format: String,
+    frames: u32,
+    animated: bool,
+    orientation: u8,
+    icc: Option<String>,
+}
+
+pub fn print_help() {
+    eprintln!("Usage: image-viewer --query <FILE> [--json]");
+    eprintln!();
+    eprintln!("Print the image's width, height, format, animated, frames, orientation (EXIF, 1-8)");
+    eprintln!("and icc (profile description) as key=value lines, or as one JSON object with --json.");
+    eprintln!("Only file headers are read; no window is opened.");
+    eprintln!();
+    eprintln!("Exit status: 0 on success, 2 on usage errors, 3 if FILE is missing or unreadable,");
+    eprintln!("4 if FILE is not a recognized image");
+}
+
+fn format_name(format: Format) -> &'static str {
+    match format {
+        Format::Png => "png",
+        Format::Jpeg => "jpeg",
+        Format::Gif => "gif",
+        Format::WebP => "webp",
+        Format::Tiff => "tiff",
+        Format::Bmp => "bmp",
+    }
+}
+
+// 文件头能识别的格式直接解析；其余交给 image 按内容猜测格式并读取尺寸（同样只读文件头）
+fn query(path: &Path) -> Option<Properties> {
+    let sniffed = header::sniff_file(path);
+    let guessed = || ImageReader::open(path).ok()?.with_guessed_format().ok();
+    let format = match sniffed {
+        Some(format) => format_name(format).to_string(),
+        None => format!("{:?}", guessed()?.format()?).to_lowercase(),
+    };
+    let (width, height) = header::read_dimensions(path).or_else(|| guessed()?.into_dimensions().ok())?;
+    let frames = header::frame_count(path).unwrap_or(1);
+    Some(Properties {
+        width,
+        height,
+        format,
+        frames,
+        // 多页 TIFF 不是动画
+        animated: frames > 1 && sniffed != Some(Format::Tiff),
+        orientation: decode::exif_orientation(path).unwrap_or(1),
+        icc: details::read(path).ok().and_then(|d| d.icc_name().map(str::to_string)),
+    })
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn print(p: &Properties, json: bool) {
+    if json {
+        let icc = p.icc.as_deref().map_or_else(|| "null".to_string(), json_string);
+        println!(
+            "{{\"width\":{},\"height\":{},\"format\":{},\"animated\":{},\"frames\":{},\"orientation\":{},\"icc\":{}}}",
+            p.width, p.height, json_string(&p.format), p.animated, p.frames, p.orientation, icc
+        );
+        return;
+    }
+    println!("width={}", p.width);
+    println!("height={}", p.height);
+    println!("format={}", p.format);
+    println!("animated={}", if p.animated { "yes" } else { "no" });
+    println!("frames={}", p.frames);
+    println!("orientation={}", p.orientation);
+    // 换行会破坏逐行格式
+    println!("icc={}", p.icc.as_deref().unwrap_or("").replace(['\n', '\r'], " "));
+}
+
+pub fn run(args: &[String]) -> glib::ExitCode {
+    let mut file: Option<PathBuf> = None;
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--query" => match iter.next() {
+                Some(path) if file.is_none() => file = Some(PathBuf::from(path)),
+                _ => {
+                    eprintln!("--query requires exactly one FILE");
+                    return glib::ExitCode::from(EXIT_USAGE);
+                }
+            },
+            "--json" => json = true,
+            "-h" | "--help" => {
+                print_help();
+                return glib::ExitCode::SUCCESS;
+            }
+            other => {
+                eprintln!("--query: unexpected argument: {}", other);
+                print_help();
+                return glib::ExitCode::from(EXIT_USAGE);
+            }
+        }
+    }
+    let Some(file) = file else {
+        print_help();
+        return glib::ExitCode::from(EXIT_USAGE);
+    };
+    if let Err(e) = std::fs::File::open(&file) {
+        eprintln!("{}: {}", file.display(), e);
+        return glib::ExitCode::from(EXIT_MISSING);
+    }
+    match query(&file) {
+        Some(properties) => {
+            print(&properties, json);
+            glib::ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("{}: not a recognized image", file.display());
+            glib::ExitCode::from(EXIT_NOT_IMAGE)
+        }
+    }
+}
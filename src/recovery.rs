@@ -0,0 +1,208 @@
+// 崩溃恢复：运行中每 30 秒及状态明显变化后，把每个窗口的标签、当前图片的视图和置顶位置
+// 写入缓存目录下以进程号命名的文件，正常退出时删除
+// 下次启动时若发现进程已不存在的恢复文件（即上次没有正常退出），询问是否恢复；--restore 不询问直接恢复
+//
+// 与显示服务器的连接断开（合成器崩溃或重启）时 GTK 不发出 closed 信号，只记录一条消息就直接结束进程，
+// 窗口无法迁移到新的连接上。能做的是在这条消息写出时接管：立即写入恢复文件，在宽限时间内等同名的
+// Wayland 套接字重新可以连接，连上后以 --restore 重新执行本程序（进程号不变），否则以 DISPLAY_LOST 退出，
+// 由外部脚本决定是否用 --restore 重新启动
+
+use crate::pins::Pin;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+// 格式版本；版本不同的文件直接忽略并删除
+const VERSION: u32 = 1;
+const INTERVAL: Duration = Duration::from_secs(30);
+// 状态变化后稍等片刻再写，合并连续的变化
+const DEBOUNCE: Duration = Duration::from_secs(1);
+// 连接断开后等待合成器重新启动的时间
+const RECONNECT_GRACE: Duration = Duration::from_secs(10);
+const RECONNECT_POLL: Duration = Duration::from_millis(250);
+// 连接断开且合成器没有在宽限时间内恢复时的退出码（EX_TEMPFAIL）
+pub const DISPLAY_LOST: i32 = 75;
+// GTK 在连接断开、即将结束进程时记录的消息（Wayland 与 X11）
+const LOST_MESSAGES: &[&str] = &[
+    "Error reading events from display",
+    "Error flushing display",
+    "dispatching to Wayland display",
+    "Lost connection to Wayland compositor",
+    "lost its connection to the display",
+];
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    version: u32,
+    #[serde(default)]
+    pub windows: Vec<WindowSession>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WindowSession {
+    // 各标签的文件，active 为活动标签的序号
+    pub tabs: Vec<String>,
+    pub active: usize,
+    // 是否处于置顶模式；view 为活动标签的视图，置顶时包含窗口位置
+    pub overlay: bool,
+    pub view: Pin,
+}
+
+// 窗口当前的状态；Closed 的窗口从登记中移除
+pub enum Snapshot {
+    Window(WindowSession),
+    Empty,
+    Closed,
+}
+
+pub type SnapshotFn = Rc<dyn Fn() -> Snapshot>;
+
+thread_local! {
+    static WINDOWS: RefCell<Vec<SnapshotFn>> = const { RefCell::new(Vec::new()) };
+    static PENDING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn dir() -> PathBuf {
+    glib::user_cache_dir().join("image-viewer").join("recovery")
+}
+
+fn own_file() -> PathBuf {
+    dir().join(format!("{}.toml", std::process::id()))
+}
+
+// 登记窗口；第一个窗口登记时开始定期写入
+pub fn register(snapshot: SnapshotFn) {
+    let first = WINDOWS.with_borrow_mut(|windows| {
+        windows.push(snapshot);
+        windows.len() == 1
+    });
+    if first {
+        glib::timeout_add_local(INTERVAL, || {
+            write();
+            glib::ControlFlow::Continue
+        });
+    }
+}
+
+// 状态明显变化（载入图片、进入或退出置顶、移动置顶窗口）后调用
+pub fn changed() {
+    if PENDING.replace(true) { return; }
+    glib::timeout_add_local_once(DEBOUNCE, || {
+        PENDING.set(false);
+        write();
+    });
+}
+
+fn write() {
+    let windows: Vec<WindowSession> = WINDOWS.with_borrow_mut(|windows| {
+        let mut sessions = Vec::new();
+        windows.retain(|snapshot| match snapshot() {
+            Snapshot::Window(session) => {
+                sessions.push(session);
+                true
+            }
+            Snapshot::Empty => true,
+            Snapshot::Closed => false,
+        });
+        sessions
+    });
+    let path = own_file();
+    if windows.is_empty() {
+        std::fs::remove_file(&path).ok();
+        return;
+    }
+    let session = Session { version: VERSION, windows };
+    let result = toml::to_string(&session).map_err(|e| e.to_string()).and_then(|text| {
+        std::fs::create_dir_all(dir()).map_err(|e| e.to_string())?;
+        // 先写临时文件再改名，崩溃在写入途中时不会留下半个文件
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, text).map_err(|e| e.to_string())?;
+        std::fs::rename(&temp, &path).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("无法写入恢复文件 {}: {}", path.display(), e);
+    }
+}
+
+// 正常退出：删除本进程的恢复文件
+pub fn clean_exit() {
+    std::fs::remove_file(own_file()).ok();
+}
+
+// 连接断开后以 --restore 重新执行时进程号不变，本进程自己的恢复文件同样算作上次的会话
+fn process_alive(pid: &str) -> bool {
+    pid != std::process::id().to_string() && Path::new("/proc").join(pid).exists()
+}
+
+// 上次没有正常退出的会话中最新的一个；无法读取或版本不同的文件顺带删除
+pub fn find_crashed() -> Option<(PathBuf, Session)> {
+    let entries = std::fs::read_dir(dir()).ok()?;
+    let mut found: Vec<(std::time::SystemTime, PathBuf, Session)> = Vec::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        let Some(pid) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else { continue };
+        if path.extension().is_none_or(|x| x != "toml") || process_alive(&pid) { continue; }
+        let session = std::fs::read_to_string(&path).ok()
+            .and_then(|text| toml::from_str::<Session>(&text).ok())
+            .filter(|s| s.version == VERSION && !s.windows.is_empty());
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        match (session, modified) {
+            (Some(session), Some(modified)) => found.push((modified, path, session)),
+            _ => discard(&path),
+        }
+    }
+    found.sort_by_key(|(modified, _, _)| *modified);
+    let (_, path, session) = found.pop()?;
+    // 更早的崩溃会话不再询问
+    for (_, older, _) in found {
+        discard(&older);
+    }
+    Some((path, session))
+}
+
+pub fn discard(path: &Path) {
+    std::fs::remove_file(path).ok();
+}
+
+pub fn is_display_lost(message: &str) -> bool {
+    LOST_MESSAGES.iter().any(|m| message.contains(m))
+}
+
+// 合成器的套接字是否可以连接；没有 WAYLAND_DISPLAY（X11）时为 None
+fn wayland_reachable() -> Option<bool> {
+    let name = std::env::var_os("WAYLAND_DISPLAY")?;
+    let socket = match Path::new(&name) {
+        p if p.is_absolute() => p.to_path_buf(),
+        p => glib::user_runtime_dir().join(p),
+    };
+    Some(std::os::unix::net::UnixStream::connect(socket).is_ok())
+}
+
+// 与显示服务器的连接已断开：保存会话，等合成器恢复后重新执行，否则退出
+pub fn display_lost(extra_args: &[&str]) -> ! {
+    // 没有登记窗口时（展台、平铺）不写，以免删掉仍有用的恢复文件；没有可恢复的内容时不必等待
+    if WINDOWS.with_borrow(|windows| windows.is_empty()) {
+        eprintln!("与显示服务器的连接已断开");
+        std::process::exit(DISPLAY_LOST);
+    }
+    write();
+    if !own_file().exists() {
+        eprintln!("与显示服务器的连接已断开，没有需要恢复的图片");
+        std::process::exit(DISPLAY_LOST);
+    }
+    eprintln!("与显示服务器的连接已断开，会话已保存，等待 {} 秒内重新连接", RECONNECT_GRACE.as_secs());
+    let deadline = std::time::Instant::now() + RECONNECT_GRACE;
+    // 旧的合成器可能还没有完全退出，先等一会再检查
+    std::thread::sleep(Duration::from_secs(1));
+    while wayland_reachable() == Some(false) && std::time::Instant::now() < deadline {
+        std::thread::sleep(RECONNECT_POLL);
+    }
+    if wayland_reachable() == Some(true) && let Ok(exe) = std::env::current_exe() {
+        use std::os::unix::process::CommandExt;
+        let e = std::process::Command::new(exe).arg("--restore").args(extra_args).exec();
+        eprintln!("无法重新启动: {}", e);
+    }
+    eprintln!("显示服务器没有恢复，可用 image-viewer --restore 重新打开");
+    std::process::exit(DISPLAY_LOST)
+}
@@ -0,0 +1,90 @@
+// 重命名：单个文件就地改名，或把目录中的图片按浏览顺序批量编号（前缀_001.jpg …）
+
+use gio::prelude::*;
+use std::path::{Path, PathBuf};
+
+fn validate(name: &str) -> Result<(), String> {
+    if name.is_empty() { return Err("文件名不能为空".to_string()); }
+    if name == "." || name == ".." || name.contains('/') {
+        return Err(format!("无效的文件名：{}", name));
+    }
+    Ok(())
+}
+
+fn set_name(path: &Path, name: &str) -> Result<(), String> {
+    gio::File::for_path(path)
+        .set_display_name(name, gio::Cancellable::NONE)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// 把 path 改名为同目录下的 new_name，返回新路径；目标已存在时不覆盖
+pub fn rename(path: &Path, new_name: &str) -> Result<PathBuf, String> {
+    let new_name = new_name.trim();
+    validate(new_name)?;
+    let target = path.with_file_name(new_name);
+    if target == path { return Ok(target); }
+    if target.symlink_metadata().is_ok() {
+        return Err(format!("已存在同名文件 {}", new_name));
+    }
+    set_name(path, new_name).map_err(|e| format!("无法重命名: {}", e))?;
+    Ok(target)
+}
+
+// 按顺序编号的新名称，扩展名保持不变；序号至少三位，文件更多时随数量加宽
+pub fn numbered(files: &[PathBuf], prefix: &str) -> Vec<(PathBuf, PathBuf)> {
+    let width = files.len().to_string().len().max(3);
+    files.iter().enumerate().map(|(i, file)| {
+        let ext = file.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+        (file.clone(), file.with_file_name(format!("{}_{:0width$}{}", prefix, i + 1, ext)))
+    }).collect()
+}
+
+// 执行批量改名：先全部改为临时名称再改为目标名称，编号互相交换的文件不会冲突
+// 目标与列表之外的文件重名时不做任何改动；中途失败时尽量恢复已改的文件
+pub fn apply(plan: &[(PathBuf, PathBuf)], prefix: &str) -> Result<(), String> {
+    validate(prefix.trim())?;
+    let plan: Vec<&(PathBuf, PathBuf)> = plan.iter().filter(|(from, to)| from != to).collect();
+    let taken: Vec<String> = plan.iter()
+        .filter(|(_, to)| to.symlink_metadata().is_ok() && !plan.iter().any(|(from, _)| from == to))
+        .map(|(_, to)| to.file_name().unwrap_or_default().to_string_lossy().to_string())
+        .collect();
+    if !taken.is_empty() {
+        return Err(format!("以下文件已存在：{}", taken.join("、")));
+    }
+
+    // 已完成的改名（当前路径, 原名称），用于回滚
+    let mut done: Vec<(PathBuf, String)> = Vec::new();
+    let rollback = |done: &[(PathBuf, String)]| {
+        for (current, original) in done.iter().rev() {
+            if let Err(e) = set_name(current, original) {
+                eprintln!("无法恢复 {}: {}", current.display(), e);
+            }
+        }
+    };
+    let mut staged = Vec::new();
+    for (i, (from, _)) in plan.iter().enumerate() {
+        let original = from.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let temp = format!(".{}.renaming-{}-{}", original, std::process::id(), i);
+        if let Err(e) = set_name(from, &temp) {
+            rollback(&done);
+            return Err(format!("无法重命名 {}: {}", original, e));
+        }
+        let temp = from.with_file_name(&temp);
+        done.push((temp.clone(), original));
+        staged.push(temp);
+    }
+    for (i, ((_, to), temp)) in plan.iter().zip(&staged).enumerate() {
+        let name = to.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if let Err(e) = set_name(temp, &name) {
+            // 已改为目标名称的文件从目标名称恢复
+            let mut current: Vec<(PathBuf, String)> = done.clone();
+            for (j, (_, to)) in plan.iter().enumerate().take(i) {
+                current[j].0 = to.clone();
+            }
+            rollback(&current);
+            return Err(format!("无法重命名为 {}: {}", name, e));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+// 加载诊断：记录图片由哪条解码路径处理，用于排查格式相关的问题
+
+use crate::header::{self, Format};
+use gdk4::prelude::*;
+use gtk4::{gdk, gdk_pixbuf, gio};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Clone, Default)]
+pub struct LoadReport {
+    // 按内容与文件名猜测的 MIME 类型
+    pub mime: String,
+    // 实际使用的解码路径
+    pub decoder: String,
+    pub decode_time: Duration,
+    // 最终纹理的像素格式
+    pub texture_format: String,
+    // 非 RGB 源色彩空间（如 CMYK）的说明
+    pub color_space: Option<String>,
+    pub exif_rotated: bool,
+    pub icc_converted: bool,
+    // 按内存预算缩小解码时原图的尺寸
+    pub downscaled_from: Option<(i32, i32)>,
+    // 先显示的 JPEG 缩小预览的倍数（1/n）；解码耗时为预览首次显示前的耗时
+    pub preview: Option<u32>,
+}
+
+impl LoadReport {
+    // 检测 MIME 类型并推断 GdkTexture 会使用的加载器
+    pub fn detect(path: &Path) -> Self {
+        let mut prefix = Vec::new();
+        if let Ok(file) = std::fs::File::open(path) {
+            use std::io::Read;
+            let _ = file.take(4096).read_to_end(&mut prefix);
+        }
+        let (content_type, _) = gio::content_type_guess(Some(path), &prefix);
+        let mime = gio::content_type_get_mime_type(&content_type)
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| content_type.to_string());
+        // GTK 对 PNG/JPEG/TIFF 使用内置加载器，其余格式交给 gdk-pixbuf
+        let decoder = match header::sniff(&prefix) {
+            Some(Format::Png) => "GTK 内置 PNG 加载器".to_string(),
+            Some(Format::Jpeg) => "GTK 内置 JPEG 加载器".to_string(),
+            Some(Format::Tiff) => "GTK 内置 TIFF 加载器".to_string(),
+            _ => match gdk_pixbuf::Pixbuf::file_info(path).and_then(|(f, _, _)| f.name()) {
+                Some(name) => format!("gdk-pixbuf（{} 加载器）", name),
+                None => "gdk-pixbuf（未找到匹配的加载器）".to_string(),
+            },
+        };
+        Self { mime, decoder, ..Self::default() }
+    }
+
+    // 没有文件路径的来源（标准输入、剪贴板）：按内容猜测 MIME 类型
+    pub fn from_memory(data: &[u8], decoder: String) -> Self {
+        let (content_type, _) = gio::content_type_guess(None::<&Path>, data);
+        let mime = gio::content_type_get_mime_type(&content_type)
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| content_type.to_string());
+        Self { mime, decoder, ..Self::default() }
+    }
+
+    pub fn set_texture(&mut self, texture: &gdk::Texture) {
+        self.texture_format = format!("{:?}", texture.format());
+    }
+
+    // 逐行输出（标签, 值），供信息面板和 --verbose 日志共用
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let yes_no = |b: bool| if b { "是" } else { "否" }.to_string();
+        let mut rows = vec![
+            ("MIME 类型", self.mime.clone()),
+            ("解码路径", self.decoder.clone()),
+            ("解码耗时", format!("{:.1} ms", self.decode_time.as_secs_f64() * 1000.0)),
+            ("纹理格式", self.texture_format.clone()),
+            ("色彩空间", self.color_space.clone().unwrap_or_else(|| "RGB".to_string())),
+            ("EXIF 旋转", yes_no(self.exif_rotated)),
+            ("ICC 转换", yes_no(self.icc_converted)),
+        ];
+        if let Some(d) = self.preview {
+            rows.push(("快速预览", format!("先显示 1/{} 缩小解码", d)));
+        }
+        rows
+    }
+}
@@ -0,0 +1,96 @@
+// 比例尺：视图左下角的一段横线，长度对应整数个图片像素（如 "500 px"），缩放不超过 100% 时显示
+// 只绘制在窗口中，复制和导出的图片不受影响
+
+use gtk4::cairo;
+
+// 比例尺在屏幕上的最大长度，实际长度在其 40%–100% 之间
+const MAX_LENGTH: f64 = 120.0;
+const MARGIN: f64 = 10.0;
+
+// 不超过 max 的最大“整数”：1、2、5 乘以 10 的幂
+pub fn round_length(max: f64) -> f64 {
+    if max.is_nan() || max <= 0.0 { return 0.0; }
+    let mut magnitude = 10f64.powf(max.log10().floor());
+    // log10 的舍入误差可能使 1000 落到 100 这一档，或使略小于 1000 的数落到 1000 这一档
+    if magnitude * 10.0 <= max { magnitude *= 10.0; }
+    if magnitude > max { magnitude /= 10.0; }
+    [5.0, 2.0, 1.0].into_iter()
+        .map(|m| m * magnitude)
+        .find(|&v| v <= max)
+        .unwrap_or(magnitude)
+}
+
+pub fn draw(cr: &cairo::Context, height: i32, scale: f64) {
+    if scale <= 0.0 || scale > 1.0 { return; }
+    let pixels = round_length(MAX_LENGTH / scale);
+    let length = pixels * scale;
+    if length < 1.0 { return; }
+    let text = format!("{} px", pixels);
+    let (x, y) = (MARGIN, height as f64 - MARGIN);
+    cr.save().ok();
+    // 深色描边衬底，在任何颜色的图片上都能看清
+    for (width, alpha, color) in [(4.0, 0.6, 0.0), (2.0, 0.95, 1.0)] {
+        cr.set_source_rgba(color, color, color, alpha);
+        cr.set_line_width(width);
+        cr.move_to(x, y - 6.0);
+        cr.line_to(x, y);
+        cr.line_to(x + length, y);
+        cr.line_to(x + length, y - 6.0);
+        cr.stroke().ok();
+    }
+    cr.set_font_size(11.0);
+    cr.move_to(x + 4.0, y - 6.0);
+    cr.text_path(&text);
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    cr.set_line_width(3.0);
+    cr.stroke_preserve().ok();
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.95);
+    cr.fill().ok();
+    cr.restore().ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::round_length;
+
+    #[test]
+    fn picks_one_two_five_steps() {
+        for (max, expected) in [
+            (1.0, 1.0), (1.9, 1.0), (2.0, 2.0), (4.99, 2.0), (5.0, 5.0), (9.99, 5.0),
+            (10.0, 10.0), (19.0, 10.0), (20.0, 20.0), (50.0, 50.0), (99.0, 50.0),
+            (120.0, 100.0), (480.0, 200.0), (1200.0, 1000.0), (0.3, 0.2), (0.05, 0.05),
+        ] {
+            assert_eq!(round_length(max), expected, "max = {max}");
+        }
+    }
+
+    #[test]
+    fn exact_powers_of_ten_stay_in_their_decade() {
+        for e in -6..=15 {
+            for m in [1.0, 2.0, 5.0] {
+                let v = m * 10f64.powi(e);
+                assert_eq!(round_length(v), v, "max = {v:e}");
+            }
+        }
+    }
+
+    #[test]
+    fn never_exceeds_max_just_below_a_step() {
+        for e in -6..=15 {
+            for m in [1.0, 2.0, 5.0, 10.0] {
+                let step = m * 10f64.powi(e);
+                let below = f64::from_bits(step.to_bits() - 1);
+                let length = round_length(below);
+                assert!(length <= below && length > 0.0, "max = {below:e}, got {length:e}");
+                assert!(length >= below * 0.4, "max = {below:e}, got {length:e}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_empty_ranges() {
+        assert_eq!(round_length(0.0), 0.0);
+        assert_eq!(round_length(-5.0), 0.0);
+        assert_eq!(round_length(f64::NAN), 0.0);
+    }
+}
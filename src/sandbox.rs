@@ -0,0 +1,242 @@
+// 隔离解码（--sandbox-decode）：不可信的图片交给单独的子进程（同一程序的 decode-worker 子命令）解码，
+// 子进程启动后先用 landlock 禁止访问文件系统、用 seccomp 只放行解码需要的系统调用（内核支持时），
+// 再从标准输入读取文件内容，把 RGBA 像素写回标准输出；子进程崩溃按解码失败处理
+//
+// 请求：u32 最大宽、u32 最大高（都为 0 表示原尺寸）、u64 数据长度、数据
+// 回复：b'O'、u32 宽、u32 高、宽×高×4 字节 RGBA；或 b'E'、u32 长度、错误信息
+// 数字均为小端
+
+use crate::decode;
+use image::RgbaImage;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+pub const SUBCOMMAND: &str = "decode-worker";
+
+// 子进程回复的图片上限（约 1 GiB 像素数据），防止异常的回复让主进程分配过多内存
+const MAX_PIXELS: u64 = 1 << 28;
+// 超过该时间仍未解码完成的子进程被终止，避免构造的文件让解码一直不结束
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+// 在子进程中解码文件；fit 为缩小后的最大尺寸（保持宽高比），None 时原尺寸
+pub fn decode_file(path: &Path, fit: Option<(u32, u32)>) -> Result<RgbaImage, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    decode_bytes(&data, fit)
+}
+
+pub fn decode_bytes(data: &[u8], fit: Option<(u32, u32)>) -> Result<RgbaImage, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("找不到解码进程: {}", e))?;
+    let mut child = Command::new(exe)
+        .arg(SUBCOMMAND)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| format!("无法启动解码进程: {}", e))?;
+    // 子进程的管道先取出，子进程本身交给看门狗线程共用；看门狗只在回收（wait）之前终止它，
+    // 回收后 pid 可能已被其他进程重用，不能再按 pid 发信号
+    let (stdin, stdout) = (child.stdin.take(), child.stdout.take());
+    let child = Arc::new(Mutex::new(child));
+    let (done, finished) = mpsc::channel::<()>();
+    let watchdog = {
+        let child = child.clone();
+        std::thread::spawn(move || {
+            let expired = finished.recv_timeout(TIMEOUT) == Err(mpsc::RecvTimeoutError::Timeout);
+            if expired { child.lock().unwrap_or_else(|e| e.into_inner()).kill().ok(); }
+            expired
+        })
+    };
+    // 子进程读完请求才开始回复，先写完再读不会互相等待；超时被终止后读写随之出错
+    let (max_w, max_h) = fit.unwrap_or((0, 0));
+    let sent = stdin.map(|mut stdin| {
+        stdin.write_all(&max_w.to_le_bytes())?;
+        stdin.write_all(&max_h.to_le_bytes())?;
+        stdin.write_all(&(data.len() as u64).to_le_bytes())?;
+        stdin.write_all(data)
+    });
+    let reply = match sent {
+        Some(Ok(())) => stdout.map(|mut stdout| read_reply(&mut stdout)),
+        _ => None,
+    };
+    // 先停下看门狗再回收子进程
+    done.send(()).ok();
+    let expired = watchdog.join().unwrap_or(false);
+    let status = child.lock().unwrap_or_else(|e| e.into_inner()).wait().map_err(|e| e.to_string())?;
+    match reply {
+        Some(Ok(reply)) => reply,
+        _ if expired => Err(format!("解码超过 {} 秒，已终止解码进程", TIMEOUT.as_secs())),
+        // 回复不完整：子进程崩溃或被 seccomp 终止
+        _ => {
+            use std::os::unix::process::ExitStatusExt;
+            Err(match status.signal() {
+                Some(signal) => format!("解码进程崩溃（信号 {}），按解码失败处理", signal),
+                None => format!("解码进程异常退出（{}），按解码失败处理", status),
+            })
+        }
+    }
+}
+
+fn read_u32(input: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+// 外层的错误表示回复不完整，内层为子进程报告的解码结果
+fn read_reply(input: &mut impl Read) -> std::io::Result<Result<RgbaImage, String>> {
+    let mut tag = [0; 1];
+    input.read_exact(&mut tag)?;
+    if tag[0] == b'E' {
+        let len = read_u32(input)?;
+        let mut message = Vec::new();
+        input.take(len as u64).read_to_end(&mut message)?;
+        return Ok(Err(String::from_utf8_lossy(&message).into_owned()));
+    }
+    let (w, h) = (read_u32(input)?, read_u32(input)?);
+    if tag[0] != b'O' || w as u64 * h as u64 > MAX_PIXELS {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+    let mut pixels = vec![0; w as usize * h as usize * 4];
+    input.read_exact(&mut pixels)?;
+    Ok(RgbaImage::from_raw(w, h, pixels).ok_or_else(|| "解码进程回复的像素数据不完整".to_string()))
+}
+
+// decode-worker 子命令：只处理一个请求
+pub fn run_worker() -> glib::ExitCode {
+    restrict();
+    let mut stdin = std::io::stdin().lock();
+    let request = (|| -> std::io::Result<((u32, u32), Vec<u8>)> {
+        let fit = (read_u32(&mut stdin)?, read_u32(&mut stdin)?);
+        let mut len = [0; 8];
+        stdin.read_exact(&mut len)?;
+        let mut data = Vec::new();
+        (&mut stdin).take(u64::from_le_bytes(len)).read_to_end(&mut data)?;
+        Ok((fit, data))
+    })();
+    let Ok(((max_w, max_h), data)) = request else { return glib::ExitCode::from(2) };
+    let decoded = decode::decode_bytes_here(&data).map(|d| {
+        if max_w > 0 && max_h > 0 && (d.image.width() > max_w || d.image.height() > max_h) {
+            d.image.resize(max_w, max_h, image::imageops::FilterType::Triangle).to_rgba8()
+        } else {
+            d.image.to_rgba8()
+        }
+    });
+    let mut stdout = std::io::stdout().lock();
+    let written = match decoded {
+        Ok(img) => {
+            let mut header = vec![b'O'];
+            header.extend_from_slice(&img.width().to_le_bytes());
+            header.extend_from_slice(&img.height().to_le_bytes());
+            stdout.write_all(&header).and_then(|_| stdout.write_all(img.as_raw()))
+        }
+        Err(e) => {
+            let mut reply = vec![b'E'];
+            reply.extend_from_slice(&(e.len() as u32).to_le_bytes());
+            reply.extend_from_slice(e.as_bytes());
+            stdout.write_all(&reply)
+        }
+    };
+    match written.and_then(|_| stdout.flush()) {
+        Ok(()) => glib::ExitCode::SUCCESS,
+        Err(_) => glib::ExitCode::from(1),
+    }
+}
+
+// 先禁止获得新权限（landlock 与非特权 seccomp 的前提），再分别应用；内核不支持的部分跳过
+fn restrict() {
+    // SAFETY: prctl 只设置本进程的标志
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        eprintln!("解码进程：无法设置 no_new_privs，不启用 landlock 与 seccomp");
+        return;
+    }
+    landlock();
+    if let Err(e) = seccomp() {
+        eprintln!("解码进程：无法启用 seccomp: {}", e);
+    }
+}
+
+// 处理全部文件系统访问权限、不加任何规则的规则集，即禁止打开任何文件；已打开的标准输入输出不受影响
+fn landlock() {
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+    const CREATE_RULESET_VERSION: u32 = 1;
+    // SAFETY: 查询版本时不传入规则集
+    let abi = unsafe {
+        libc::syscall(libc::SYS_landlock_create_ruleset, std::ptr::null::<RulesetAttr>(), 0usize, CREATE_RULESET_VERSION)
+    };
+    // 各版本新增的访问权限：1 有 13 种，2 加上 REFER，3 加上 TRUNCATE
+    let rights = match abi {
+        ..=0 => return,
+        1 => 13,
+        2 => 14,
+        _ => 15,
+    };
+    let attr = RulesetAttr { handled_access_fs: (1u64 << rights) - 1 };
+    // SAFETY: attr 在调用期间有效，大小与内核的结构一致
+    let fd = unsafe { libc::syscall(libc::SYS_landlock_create_ruleset, &attr, size_of::<RulesetAttr>(), 0u32) };
+    if fd < 0 { return; }
+    // SAFETY: fd 为刚创建的规则集，应用后关闭
+    unsafe {
+        libc::syscall(libc::SYS_landlock_restrict_self, fd as libc::c_int, 0u32);
+        libc::close(fd as libc::c_int);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+// 解码只需要读写已打开的管道、分配内存和线程同步；其余系统调用返回 EPERM（包括打开文件、创建进程、网络）
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn seccomp() -> Result<(), std::io::Error> {
+    const ALLOWED: &[libc::c_long] = &[
+        libc::SYS_read, libc::SYS_write, libc::SYS_close, libc::SYS_lseek,
+        libc::SYS_mmap, libc::SYS_munmap, libc::SYS_mremap, libc::SYS_mprotect, libc::SYS_brk, libc::SYS_madvise,
+        libc::SYS_futex, libc::SYS_sched_yield, libc::SYS_sched_getaffinity, libc::SYS_getrandom, libc::SYS_clock_gettime,
+        libc::SYS_clone, libc::SYS_clone3, libc::SYS_set_robust_list, libc::SYS_rseq, libc::SYS_gettid,
+        // 崩溃时 Rust 的 SIGSEGV 处理函数要恢复默认处理，否则出错的指令会反复执行
+        libc::SYS_rt_sigreturn, libc::SYS_rt_sigaction, libc::SYS_rt_sigprocmask, libc::SYS_sigaltstack,
+        libc::SYS_exit, libc::SYS_exit_group,
+    ];
+    let stmt = |code: u32, k: u32| libc::sock_filter { code: code as u16, jt: 0, jf: 0, k };
+    let jump = |k: u32, jt: u8, jf: u8| libc::sock_filter { code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16, jt, jf, k };
+    // seccomp_data 中 nr 在偏移 0，arch 在偏移 4
+    let mut filter = vec![
+        stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, 4),
+        jump(AUDIT_ARCH, 1, 0),
+        stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+        stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, 0),
+    ];
+    for &nr in ALLOWED {
+        filter.push(jump(nr as u32, 0, 1));
+        filter.push(stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ALLOW));
+    }
+    filter.push(stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ERRNO | libc::EPERM as u32));
+    let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_mut_ptr() };
+    // SAFETY: program 指向的过滤器在调用期间有效，内核会复制一份
+    let result = unsafe { libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &program as *const libc::sock_fprog) };
+    if result != 0 { return Err(std::io::Error::last_os_error()); }
+    Ok(())
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn seccomp() -> Result<(), std::io::Error> {
+    Ok(())
+}
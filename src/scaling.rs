@@ -0,0 +1,136 @@
+// 缩小显示的质量选项：cairo 在 sRGB 空间求平均，亮线细节缩小后会变淡甚至消失
+// quality 模式在缩放率低于 50% 时改为在线性光空间做区域平均，结果缓存为预缩放表面
+// 代价是每次缩放率变化都要遍历整张原图，因此超过 MAX_QUALITY_PIXELS 的图片自动使用 fast
+
+use gtk4::cairo;
+use serde::Deserialize;
+use std::cell::RefCell;
+
+#[derive(Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scaling {
+    // 直接由 cairo 双线性缩放
+    #[default]
+    Fast,
+    // 低缩放率时在线性光空间缩小
+    Quality,
+}
+
+// 低于该缩放率时才启用线性光缩小
+const QUALITY_BELOW: f64 = 0.5;
+// 超过该像素数的图片回退到 fast 模式
+const MAX_QUALITY_PIXELS: u64 = 16_000_000;
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+// 目标像素 i 覆盖的源像素区间（至少一个像素）
+fn span(i: usize, n: usize, target: usize) -> (usize, usize) {
+    let start = i * n / target;
+    (start, ((i + 1) * n / target).max(start + 1))
+}
+
+// 在线性光空间按区域平均缩小 ARGB32（预乘 alpha）表面
+fn downscale_linear(source: &cairo::ImageSurface, scale: f64) -> Option<cairo::ImageSurface> {
+    let (w, h) = (source.width() as usize, source.height() as usize);
+    let tw = ((w as f64 * scale).round() as usize).max(1);
+    let th = ((h as f64 * scale).round() as usize).max(1);
+    let stride = source.stride() as usize;
+    let to_linear: Vec<f32> = (0..256).map(|i| srgb_to_linear(i as f32 / 255.0)).collect();
+    let cols: Vec<(usize, usize)> = (0..tw).map(|x| span(x, w, tw)).collect();
+
+    let mut out = cairo::ImageSurface::create(cairo::Format::ARgb32, tw as i32, th as i32).ok()?;
+    let out_stride = out.stride() as usize;
+    {
+        let mut dst = out.data().ok()?;
+        // 每个目标像素累加 (R, G, B) 的线性值 × alpha 以及 alpha
+        let mut acc = vec![0f32; tw * 4];
+        source.with_data(|src| {
+            for ty in 0..th {
+                let (y0, y1) = span(ty, h, th);
+                acc.fill(0.0);
+                for y in y0..y1 {
+                    let row = &src[y * stride..y * stride + w * 4];
+                    for (a, &(x0, x1)) in acc.chunks_exact_mut(4).zip(&cols) {
+                        for px in row[x0 * 4..x1 * 4].chunks_exact(4) {
+                            // ARGB32 为本机字节序的 0xAARRGGBB
+                            let v = u32::from_ne_bytes([px[0], px[1], px[2], px[3]]);
+                            let alpha = v >> 24;
+                            if alpha == 0 { continue; }
+                            let af = alpha as f32 / 255.0;
+                            for (c, shift) in [16, 8, 0].into_iter().enumerate() {
+                                let premul = (v >> shift) & 0xff;
+                                let straight = if alpha == 255 { premul } else { ((premul * 255 + alpha / 2) / alpha).min(255) };
+                                a[c] += to_linear[straight as usize] * af;
+                            }
+                            a[3] += af;
+                        }
+                    }
+                }
+                let out_row = &mut dst[ty * out_stride..ty * out_stride + tw * 4];
+                for ((o, a), &(x0, x1)) in out_row.chunks_exact_mut(4).zip(acc.chunks_exact(4)).zip(&cols) {
+                    let v = if a[3] > 0.0 {
+                        let alpha = a[3] / ((x1 - x0) * (y1 - y0)) as f32;
+                        let encode = |lin: f32| (linear_to_srgb(lin / a[3]) * alpha * 255.0 + 0.5) as u32;
+                        ((alpha * 255.0 + 0.5) as u32) << 24 | encode(a[0]) << 16 | encode(a[1]) << 8 | encode(a[2])
+                    } else {
+                        0
+                    };
+                    o.copy_from_slice(&v.to_ne_bytes());
+                }
+            }
+        }).ok()?;
+    }
+    out.mark_dirty();
+    Some(out)
+}
+
+struct Entry {
+    // 持有源表面，保证按指针比较时不会与新表面混淆
+    source: cairo::ImageSurface,
+    scale: f64,
+    surface: cairo::ImageSurface,
+}
+
+// 预缩放缓存，只保留最近一次的结果
+#[derive(Default)]
+pub struct Prescaled {
+    entry: RefCell<Option<Entry>>,
+}
+
+impl Prescaled {
+    // 返回预缩放表面及其相对源表面的 (x, y) 比例；不需要或不适用时返回 None
+    fn get(&self, mode: Scaling, source: &cairo::ImageSurface, scale: f64) -> Option<(cairo::ImageSurface, f64, f64)> {
+        if mode != Scaling::Quality || scale >= QUALITY_BELOW { return None; }
+        if source.width() as u64 * source.height() as u64 > MAX_QUALITY_PIXELS { return None; }
+        let mut entry = self.entry.borrow_mut();
+        let cached = entry.as_ref()
+            .is_some_and(|e| e.source.to_raw_none() == source.to_raw_none() && e.scale == scale);
+        if !cached {
+            let surface = downscale_linear(source, scale)?;
+            *entry = Some(Entry { source: source.clone(), scale, surface });
+        }
+        let e = entry.as_ref()?;
+        let rx = e.surface.width() as f64 / source.width() as f64;
+        let ry = e.surface.height() as f64 / source.height() as f64;
+        Some((e.surface.clone(), rx, ry))
+    }
+
+    // 设置绘制源：cr 当前坐标系为原图像素，需要时换成预缩放表面并抵消其比例
+    pub fn set_source(&self, cr: &cairo::Context, mode: Scaling, surface: &cairo::ImageSurface, scale: f64) {
+        match self.get(mode, surface, scale) {
+            Some((small, rx, ry)) => {
+                cr.scale(1.0 / rx, 1.0 / ry);
+                cr.set_source_surface(&small, 0.0, 0.0).ok();
+            }
+            None => {
+                cr.set_source_surface(surface, 0.0, 0.0).ok();
+            }
+        }
+    }
+}
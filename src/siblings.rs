@@ -0,0 +1,329 @@
+// 同目录图片列表：按自然顺序排列，并划分连拍分组，供方向键浏览
+
+use crate::burst;
+use crate::config::SortOrder;
+use crate::header;
+use gio::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+pub struct Listing {
+    pub dir: PathBuf,
+    pub files: Vec<PathBuf>,
+    pub groups: Vec<Range<usize>>,
+}
+
+// 按文件名判断是否为图片，不读取文件内容
+fn is_image(path: &Path) -> bool {
+    let (content_type, _) = gio::content_type_guess(Some(path), &[]);
+    gio::content_type_is_mime_type(&content_type, "image/*")
+}
+
+// --recursive 时最多收集的图片数，超出的部分不再列出
+pub const TREE_LIMIT: usize = 10_000;
+
+fn name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+// 目录中的图片（不含子目录），按 sort 排列
+pub fn images(dir: &Path, sort: SortOrder) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && is_image(p))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by(|a, b| burst::natural_cmp(&name(a), &name(b)));
+    // 稳定排序：时间相同的保持文件名顺序
+    match sort {
+        SortOrder::Name => {}
+        SortOrder::Modified => files.sort_by_cached_key(|f| std::fs::metadata(f).and_then(|m| m.modified()).ok()),
+        SortOrder::Date => files.sort_by_cached_key(|f| capture_time(f)),
+    }
+    files
+}
+
+// 拍摄时间的缓存：路径 → (读取时的修改时间, 拍摄时间)；文件被修改后重新读取
+// 目录树在工作线程中遍历，因此用 Mutex 而不是 thread_local
+type CaptureCache = Mutex<HashMap<PathBuf, (SystemTime, Option<i64>)>>;
+static CAPTURE_TIMES: OnceLock<CaptureCache> = OnceLock::new();
+
+// 拍摄时间（Unix 秒）：EXIF 时间按本地时区换算，没有时使用修改时间
+// 只读取文件头中的日期，结果按修改时间缓存，重复扫描同一目录时只需 stat
+pub fn capture_time(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let cache = CAPTURE_TIMES.get_or_init(Mutex::default);
+    if let Some(&(at, time)) = cache.lock().ok()?.get(path) && at == modified {
+        return time;
+    }
+    let exif = header::capture_date(path).and_then(|(y, mo, d, h, mi, s)| {
+        glib::DateTime::from_local(y, mo as i32, d as i32, h as i32, mi as i32, s as f64).ok()
+    });
+    let time = exif.map(|t| t.to_unix())
+        .or_else(|| modified.duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| d.as_secs() as i64));
+    cache.lock().ok()?.insert(path.to_path_buf(), (modified, time));
+    time
+}
+
+// 拍摄日期（本地时区），如 "2024-06-12"，用于浏览时提示跨越了哪一天
+pub fn capture_day(path: &Path) -> Option<String> {
+    let time = glib::DateTime::from_unix_local(capture_time(path)?).ok()?;
+    time.format("%Y-%m-%d").ok().map(|s| s.to_string())
+}
+
+// 递归列出目录树中的图片：先列出目录本身的图片，再按名称顺序逐个深入子目录
+// 每个目录的结果单独交给 emit，调用方可以边遍历边使用；emit 返回 false 时停止
+// 不跟随指向目录的符号链接，避免循环；达到 limit 时截断并返回 true
+pub fn walk(root: &Path, sort: SortOrder, limit: usize, mut emit: impl FnMut(Vec<PathBuf>) -> bool) -> bool {
+    let mut pending = vec![root.to_path_buf()];
+    let mut found = 0;
+    while let Some(dir) = pending.pop() {
+        let mut files = images(&dir, sort);
+        let truncated = found + files.len() > limit;
+        files.truncate(limit - found);
+        found += files.len();
+        if !files.is_empty() && !emit(files) { return false; }
+        if truncated { return true; }
+        let mut subdirs: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                    .map(|e| e.path())
+                    .filter(|p| !name(p).starts_with('.'))
+                    .collect()
+            })
+            .unwrap_or_default();
+        subdirs.sort_by(|a, b| burst::natural_cmp(&name(b), &name(a)));
+        pending.extend(subdirs);
+    }
+    false
+}
+
+// 逐行读取文件列表（--files-from）：相对路径按当前目录补全，跳过空行，不检查文件是否存在
+// 已读到的行在输入暂时没有更多数据时立即交给 emit，管道另一端边找边输出时也能先显示前面的图片
+// emit 返回 false 时停止；返回读到的路径数与跳过的空行数
+pub fn read_list(input: impl std::io::Read, mut emit: impl FnMut(Vec<PathBuf>) -> bool) -> (usize, usize) {
+    use std::io::BufRead;
+    use std::os::unix::ffi::OsStrExt;
+    let mut reader = std::io::BufReader::new(input);
+    let (mut found, mut blank) = (0, 0);
+    let mut batch = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        // 路径按原始字节处理，非 UTF-8 的文件名也能打开
+        while line.last().is_some_and(|&b| b == b'\n' || b == b'\r') { line.pop(); }
+        if line.iter().all(u8::is_ascii_whitespace) {
+            blank += 1;
+        } else if let Ok(path) = std::path::absolute(std::ffi::OsStr::from_bytes(&line)) {
+            batch.push(path);
+            found += 1;
+        }
+        if !batch.is_empty() && reader.buffer().is_empty() && !emit(std::mem::take(&mut batch)) {
+            return (found, blank);
+        }
+    }
+    if !batch.is_empty() { emit(batch); }
+    (found, blank)
+}
+
+impl Listing {
+    // 由已排好序的文件构造列表，用于跨越多个目录的列表
+    pub fn from_files(dir: &Path, files: Vec<PathBuf>) -> Self {
+        let names: Vec<String> = files.iter().map(|f| name(f)).collect();
+        let groups = burst::groups(&names);
+        Self { dir: dir.to_path_buf(), files, groups }
+    }
+
+    pub fn position(&self, path: &Path) -> Option<usize> {
+        self.files.iter().position(|f| f == path)
+    }
+
+    fn group_of(&self, index: usize) -> usize {
+        self.groups.iter().position(|g| g.contains(&index)).unwrap_or(0)
+    }
+
+    // 所在连拍的范围；单独的文件返回 None
+    pub fn burst(&self, path: &Path) -> Option<Range<usize>> {
+        let index = self.position(path)?;
+        let group = self.groups[self.group_of(index)].clone();
+        (group.len() > 1).then_some(group)
+    }
+
+    // 在连拍内前后移动，到达两端时停止；单独的文件移到相邻的文件（组）
+    pub fn step_within(&self, path: &Path, forward: bool) -> Option<&Path> {
+        let index = self.position(path)?;
+        let group = &self.groups[self.group_of(index)];
+        let target = if forward { index + 1 } else { index.checked_sub(1)? };
+        let bounds = if group.len() > 1 { group.clone() } else { 0..self.files.len() };
+        bounds.contains(&target).then(|| self.files[target].as_path())
+    }
+
+    // 移到上一组/下一组的第一个文件
+    pub fn step_group(&self, path: &Path, forward: bool) -> Option<&Path> {
+        let group = self.group_of(self.position(path)?);
+        let target = if forward { group + 1 } else { group.checked_sub(1)? };
+        self.groups.get(target).map(|g| self.files[g.start].as_path())
+    }
+}
+
+// 异步列出目录时每次读取的条目数
+const PAGE: i32 = 500;
+
+// 在主线程中逐页列出目录，很大的目录也不会阻塞界面；每读完一页更新列表并调用 changed
+// 按名称排序时每页合并进已排好的部分；按修改时间或拍摄日期排序时先按名称排列，
+// 列完后在工作线程中排序（complete 之前为 false）
+// 丢弃时取消尚未完成的列出
+pub struct Scan {
+    pub dir: PathBuf,
+    cancellable: gio::Cancellable,
+    files: RefCell<Vec<PathBuf>>,
+    listing: RefCell<Rc<Listing>>,
+    // 当前文件还没读到时临时放在最前的列表
+    provisional: RefCell<Option<(PathBuf, Rc<Listing>)>>,
+    complete: Cell<bool>,
+}
+
+impl Scan {
+    pub fn start(dir: &Path, sort: SortOrder, changed: impl Fn() + 'static) -> Rc<Self> {
+        let scan = Rc::new(Self {
+            dir: dir.to_path_buf(),
+            cancellable: gio::Cancellable::new(),
+            files: RefCell::default(),
+            listing: RefCell::new(Rc::new(Listing::from_files(dir, Vec::new()))),
+            provisional: RefCell::default(),
+            complete: Cell::new(false),
+        });
+        let changed: Rc<dyn Fn()> = Rc::new(changed);
+        let weak = Rc::downgrade(&scan);
+        gio::File::for_path(dir).enumerate_children_async(
+            "standard::name,standard::type",
+            gio::FileQueryInfoFlags::NONE,
+            glib::Priority::DEFAULT_IDLE,
+            Some(&scan.cancellable),
+            move |r| match r {
+                Ok(enumerator) => next_page(enumerator, weak, sort, changed),
+                // 无法列出的目录当作没有其他图片
+                Err(_) => if let Some(scan) = weak.upgrade() && !scan.cancellable.is_cancelled() {
+                    scan.complete.set(true);
+                    changed();
+                },
+            },
+        );
+        scan
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete.get()
+    }
+
+    // 包含 path 的浏览列表；列出期间还没读到 path 时它暂时排在最前
+    pub fn listing(&self, path: &Path) -> Rc<Listing> {
+        let listing = self.listing.borrow().clone();
+        if self.complete.get() || listing.position(path).is_some() { return listing; }
+        let mut provisional = self.provisional.borrow_mut();
+        if let Some((p, l)) = provisional.as_ref() && p == path { return l.clone(); }
+        let files = std::iter::once(path.to_path_buf()).chain(listing.files.iter().cloned()).collect();
+        let l = Rc::new(Listing::from_files(&self.dir, files));
+        *provisional = Some((path.to_path_buf(), l.clone()));
+        l
+    }
+
+    fn publish(&self, files: Vec<PathBuf>) {
+        *self.listing.borrow_mut() = Rc::new(Listing::from_files(&self.dir, files));
+        self.provisional.borrow_mut().take();
+    }
+}
+
+impl Drop for Scan {
+    fn drop(&mut self) {
+        self.cancellable.cancel();
+    }
+}
+
+fn next_page(enumerator: gio::FileEnumerator, scan: Weak<Scan>, sort: SortOrder, changed: Rc<dyn Fn()>) {
+    let Some(cancellable) = scan.upgrade().map(|s| s.cancellable.clone()) else { return };
+    let next = enumerator.clone();
+    enumerator.next_files_async(PAGE, glib::Priority::DEFAULT_IDLE, Some(&cancellable), move |r| {
+        let Some(this) = scan.upgrade() else { return };
+        if this.cancellable.is_cancelled() { return; }
+        let infos = r.unwrap_or_default();
+        let done = infos.is_empty();
+        let mut page: Vec<PathBuf> = infos.iter()
+            .filter(|info| info.file_type() == gio::FileType::Regular)
+            .map(|info| this.dir.join(info.name()))
+            .filter(|p| is_image(p))
+            .collect();
+        if !page.is_empty() {
+            page.sort_by(|a, b| burst::natural_cmp(&name(a), &name(b)));
+            let mut files = this.files.borrow_mut();
+            *files = merge_by_name(std::mem::take(&mut *files), page);
+            this.publish(files.clone());
+        }
+        if !done {
+            next_page(next, scan, sort, changed.clone());
+        } else if sort == SortOrder::Name {
+            this.complete.set(true);
+        } else {
+            sort_in_background(this.clone(), sort, changed.clone());
+            return;
+        }
+        changed();
+    });
+}
+
+// 合并两组已按名称排好的文件
+fn merge_by_name(old: Vec<PathBuf>, page: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut merged = Vec::with_capacity(old.len() + page.len());
+    let (mut a, mut b) = (old.into_iter().peekable(), page.into_iter().peekable());
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        let next = if burst::natural_cmp(&name(y), &name(x)).is_lt() { b.next() } else { a.next() };
+        merged.extend(next);
+    }
+    merged.extend(a);
+    merged.extend(b);
+    merged
+}
+
+// 修改时间与拍摄日期需要逐个读取文件，在工作线程中排序；稳定排序，时间相同的保持文件名顺序
+fn sort_in_background(scan: Rc<Scan>, sort: SortOrder, changed: Rc<dyn Fn()>) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut files = scan.files.borrow().clone();
+    std::thread::spawn(move || {
+        match sort {
+            SortOrder::Name => {}
+            SortOrder::Modified => files.sort_by_cached_key(|f| std::fs::metadata(f).and_then(|m| m.modified()).ok()),
+            SortOrder::Date => files.sort_by_cached_key(|f| capture_time(f)),
+        }
+        tx.send(files).ok();
+    });
+    let scan = Rc::downgrade(&scan);
+    glib::timeout_add_local(Duration::from_millis(50), move || {
+        let files = match rx.try_recv() {
+            Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+            Ok(files) => files,
+        };
+        if let Some(scan) = scan.upgrade() && !scan.cancellable.is_cancelled() {
+            *scan.files.borrow_mut() = files.clone();
+            scan.publish(files);
+            scan.complete.set(true);
+            changed();
+        }
+        glib::ControlFlow::Break
+    });
+}
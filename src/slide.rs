@@ -0,0 +1,61 @@
+// 触摸滑动翻页时的过渡：旧图片滑出、新图片从另一侧滑入
+
+use gtk4::cairo;
+use std::time::{Duration, Instant};
+
+const DURATION: Duration = Duration::from_millis(220);
+
+// 滑出的旧图片按离开时的视图绘制
+pub struct Outgoing {
+    pub surface: cairo::ImageSurface,
+    pub rotation: i32,
+    pub scale: f64,
+    pub offset: (f64, f64),
+}
+
+pub struct Slide {
+    started: Instant,
+    // 向后翻页时内容向左移动
+    forward: bool,
+    outgoing: Option<Outgoing>,
+}
+
+impl Slide {
+    pub fn new(forward: bool, outgoing: Option<Outgoing>) -> Self {
+        Self { started: Instant::now(), forward, outgoing }
+    }
+
+    // 缓出的进度，0 → 1
+    fn progress(&self) -> f64 {
+        let t = (self.started.elapsed().as_secs_f64() / DURATION.as_secs_f64()).min(1.0);
+        1.0 - (1.0 - t).powi(3)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.started.elapsed() >= DURATION
+    }
+
+    fn direction(&self) -> f64 {
+        if self.forward { 1.0 } else { -1.0 }
+    }
+
+    // 新图片的水平位移
+    pub fn incoming_shift(&self, width: i32) -> f64 {
+        (1.0 - self.progress()) * width as f64 * self.direction()
+    }
+
+    pub fn draw_outgoing(&self, cr: &cairo::Context, width: i32, height: i32) {
+        let Some(ref out) = self.outgoing else { return };
+        let (surf_w, surf_h) = (out.surface.width() as f64, out.surface.height() as f64);
+        let shift = -self.progress() * width as f64 * self.direction();
+        cr.save().ok();
+        cr.translate(width as f64 / 2.0 + out.offset.0 + shift, height as f64 / 2.0 + out.offset.1);
+        cr.rotate(out.rotation as f64 * std::f64::consts::FRAC_PI_2);
+        cr.scale(out.scale, out.scale);
+        cr.translate(-surf_w / 2.0, -surf_h / 2.0);
+        if cr.set_source_surface(&out.surface, 0.0, 0.0).is_ok() {
+            cr.paint().ok();
+        }
+        cr.restore().ok();
+    }
+}
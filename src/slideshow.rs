@@ -0,0 +1,167 @@
+// 幻灯片：每隔 slideshow-interval 秒前进一张，到末尾后从头开始
+// 滚动、拖动、旋转等操作暂停幻灯片，手动翻页只重新计时，不会紧接着再自动前进一张
+// 每次自动前进时可以闪一下边框、响一下提示音（各自开关，默认都关闭）；
+// 边框只画在窗口的绘图区上，复制与导出的图片不受影响
+
+use crate::config::{self, Config};
+use gtk4::prelude::*;
+use gtk4::{cairo, glib, DrawingArea};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// 边框闪烁的时长与宽度
+const FLASH: Duration = Duration::from_millis(200);
+const FLASH_WIDTH: f64 = 6.0;
+
+// 开始或停止的原因，用于提示与同步标题栏按钮
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Started,
+    // 用户停止
+    Stopped,
+    // 滚动、拖动或旋转
+    Paused,
+    // 没有可以前进到的图片
+    Ended,
+}
+
+type AdvanceFn = Rc<dyn Fn() -> bool>;
+type ChangedFn = Rc<dyn Fn(Change)>;
+
+pub struct Slideshow {
+    interval: u32,
+    flash: bool,
+    sound: bool,
+    animations: Option<bool>,
+    // 绘图区的绘制回调持有本对象，这里只保留弱引用
+    canvas: glib::WeakRef<DrawingArea>,
+    timer: RefCell<Option<glib::SourceId>>,
+    // 前进一张，返回 false 时停止；窗口不可见（hidden）时不前进，只等下一次
+    advance: RefCell<Option<(AdvanceFn, AdvanceFn)>>,
+    changed: RefCell<Option<ChangedFn>>,
+    // 最近一次边框闪烁开始的时刻
+    flashed: Cell<Option<Instant>>,
+}
+
+impl Slideshow {
+    pub fn new(config: &Config, canvas: &DrawingArea) -> Rc<Self> {
+        Rc::new(Self {
+            interval: config.slideshow_interval.max(1),
+            flash: config.slideshow_flash,
+            sound: config.slideshow_sound,
+            animations: config.animations,
+            canvas: canvas.downgrade(),
+            timer: RefCell::default(),
+            advance: RefCell::default(),
+            changed: RefCell::default(),
+            flashed: Cell::new(None),
+        })
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn set_advance(&self, advance: impl Fn() -> bool + 'static, hidden: impl Fn() -> bool + 'static) {
+        *self.advance.borrow_mut() = Some((Rc::new(advance), Rc::new(hidden)));
+    }
+
+    pub fn connect_changed(&self, f: impl Fn(Change) + 'static) {
+        *self.changed.borrow_mut() = Some(Rc::new(f));
+    }
+
+    fn notify(&self, change: Change) {
+        let changed = self.changed.borrow().clone();
+        if let Some(changed) = changed { changed(change); }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.timer.borrow().is_some()
+    }
+
+    pub fn start(self: &Rc<Self>) {
+        if self.is_running() { return; }
+        self.schedule();
+        self.notify(Change::Started);
+    }
+
+    fn cancel(&self) -> bool {
+        let id = self.timer.borrow_mut().take();
+        id.map(|id| id.remove()).is_some()
+    }
+
+    // 返回之前是否在播放
+    pub fn stop(&self) -> bool {
+        let running = self.cancel();
+        if running { self.notify(Change::Stopped); }
+        running
+    }
+
+    pub fn pause(&self) {
+        if self.cancel() { self.notify(Change::Paused); }
+    }
+
+    // 手动翻页后从此刻重新计时
+    pub fn restart(self: &Rc<Self>) {
+        if self.cancel() { self.schedule(); }
+    }
+
+    fn schedule(self: &Rc<Self>) {
+        let weak = Rc::downgrade(self);
+        let id = glib::timeout_add_seconds_local(self.interval, move || {
+            let Some(show) = weak.upgrade() else { return glib::ControlFlow::Break };
+            let Some((advance, hidden)) = show.advance.borrow().clone() else { return glib::ControlFlow::Continue };
+            if hidden() { return glib::ControlFlow::Continue; }
+            if advance() {
+                show.cue();
+                return glib::ControlFlow::Continue;
+            }
+            // 单次返回 Break 即移除定时器，只清除记录
+            show.timer.borrow_mut().take();
+            show.notify(Change::Ended);
+            glib::ControlFlow::Break
+        });
+        *self.timer.borrow_mut() = Some(id);
+    }
+
+    // 前进后的提示；关闭了动画时边框不淡出，以固定的样子显示同样长的时间后消失
+    fn cue(&self) {
+        let Some(canvas) = self.canvas.upgrade() else { return };
+        if self.sound { canvas.error_bell(); }
+        if !self.flash { return; }
+        let started = Instant::now();
+        self.flashed.set(Some(started));
+        canvas.queue_draw();
+        if config::animations_enabled(self.animations) {
+            canvas.add_tick_callback(move |da, _| {
+                da.queue_draw();
+                if started.elapsed() < FLASH { glib::ControlFlow::Continue } else { glib::ControlFlow::Break }
+            });
+        } else {
+            let canvas = self.canvas.clone();
+            glib::timeout_add_local_once(FLASH, move || {
+                if let Some(canvas) = canvas.upgrade() { canvas.queue_draw(); }
+            });
+        }
+    }
+
+    // 在绘图区的边缘画出闪烁的边框
+    pub fn draw_cue(&self, cr: &cairo::Context, width: i32, height: i32) {
+        let Some(started) = self.flashed.get() else { return };
+        let elapsed = started.elapsed();
+        if elapsed >= FLASH { return self.flashed.set(None); }
+        let alpha = if config::animations_enabled(self.animations) {
+            1.0 - elapsed.as_secs_f64() / FLASH.as_secs_f64()
+        } else {
+            1.0
+        };
+        cr.save().ok();
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.8 * alpha);
+        cr.set_line_width(FLASH_WIDTH);
+        let inset = FLASH_WIDTH / 2.0;
+        cr.rectangle(inset, inset, width as f64 - FLASH_WIDTH, height as f64 - FLASH_WIDTH);
+        cr.stroke().ok();
+        cr.restore().ok();
+    }
+}
@@ -0,0 +1,58 @@
+// 状态弹出框中的属性表：每行一个标题和一个取值函数，取值为 None 时隐藏该行
+// 随视图刷新（模型的 refresh 副作用）更新，弹出框未显示时跳过；新增一项只需调用一次 row
+
+use crate::ImageState;
+use gtk4::prelude::*;
+use gtk4::{Grid, Label};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type ValueFn = std::boxed::Box<dyn Fn(&ImageState) -> Option<String>>;
+
+struct Row {
+    title: Label,
+    value: Label,
+    get: ValueFn,
+}
+
+#[derive(Clone)]
+pub struct StatusGrid {
+    grid: Grid,
+    rows: Rc<RefCell<Vec<Row>>>,
+}
+
+impl StatusGrid {
+    pub fn new() -> Self {
+        let grid = Grid::builder().row_spacing(6).column_spacing(12).build();
+        Self { grid, rows: Rc::default() }
+    }
+
+    pub fn widget(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn row(&self, title: &str, get: impl Fn(&ImageState) -> Option<String> + 'static) {
+        let mut rows = self.rows.borrow_mut();
+        let line = rows.len() as i32;
+        let title = Label::builder().label(title).halign(gtk4::Align::End).build();
+        title.add_css_class("dim-label");
+        let value = Label::builder().halign(gtk4::Align::Start).selectable(true).build();
+        self.grid.attach(&title, 0, line, 1, 1);
+        self.grid.attach(&value, 1, line, 1, 1);
+        rows.push(Row { title, value, get: std::boxed::Box::new(get) });
+    }
+
+    // 弹出框未显示时不更新；显示时由调用方用 fill 立即填写一次
+    pub fn update(&self, s: &ImageState) {
+        if self.grid.is_mapped() { self.fill(s); }
+    }
+
+    pub fn fill(&self, s: &ImageState) {
+        for row in self.rows.borrow().iter() {
+            let value = (row.get)(s);
+            row.title.set_visible(value.is_some());
+            row.value.set_visible(value.is_some());
+            row.value.set_text(value.as_deref().unwrap_or_default());
+        }
+    }
+}
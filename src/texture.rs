@@ -0,0 +1,159 @@
+// 纹理的解码、构建、旋转与绘制：图形界面与可嵌入的控件共用
+
+use crate::{decode, sandbox};
+use gtk4::prelude::*;
+use gtk4::{cairo, gdk, glib};
+use std::path::Path;
+
+// 完整解码为纹理；CMYK/YCCK JPEG 与带 EXIF 方向的图片改由 image 解码（与 convert 子命令共用）
+// 隔离解码时一律由子进程中的 image 解码，不经过 GDK 的加载器
+pub fn decode_texture(path: &Path) -> Result<gdk::Texture, String> {
+    if sandbox::enabled() || decode::needs_fallback(path) {
+        let decoded = decode::decode(path)?;
+        return Ok(texture_from_rgba(decoded.image.to_rgba8()));
+    }
+    gdk::Texture::from_filename(path).map_err(|e| e.to_string())
+}
+
+// 由 RGBA 像素构建纹理
+pub fn texture_from_rgba(img: image::RgbaImage) -> gdk::Texture {
+    let (w, h) = img.dimensions();
+    let bytes = glib::Bytes::from_owned(img.into_raw());
+    gdk::MemoryTexture::new(w as i32, h as i32, gdk::MemoryFormat::R8g8b8a8, &bytes, w as usize * 4).upcast()
+}
+
+// 按视图的旋转（顺时针 90° 的倍数）生成新纹理，未旋转时原样返回
+pub fn rotated_texture(texture: &gdk::Texture, rotation: i32) -> gdk::Texture {
+    if rotation % 4 == 0 { return texture.clone(); }
+    let mut downloader = gdk::TextureDownloader::new(texture);
+    downloader.set_format(gdk::MemoryFormat::R8g8b8a8);
+    let (bytes, stride) = downloader.download_bytes();
+    let (w, h) = (texture.width() as usize, texture.height() as usize);
+    let mut pixels = Vec::with_capacity(w * h * 4);
+    for row in bytes.chunks(stride).take(h) {
+        pixels.extend_from_slice(&row[..w * 4]);
+    }
+    let Some(img) = image::RgbaImage::from_raw(w as u32, h as u32, pixels) else { return texture.clone() };
+    texture_from_rgba(rotate_rgba(&img, rotation))
+}
+
+// 把像素顺时针旋转 rotation 个 90°
+pub fn rotate_rgba(img: &image::RgbaImage, rotation: i32) -> image::RgbaImage {
+    match rotation.rem_euclid(4) {
+        0 => img.clone(),
+        1 => image::imageops::rotate90(img),
+        2 => image::imageops::rotate180(img),
+        _ => image::imageops::rotate270(img),
+    }
+}
+
+// 绘制旋转后图片的变换：未旋转的 (surf_w, surf_h) 表面顺时针旋转 rotation 个 90° 后以 center 为中心，
+// scale 为旋转后横向、纵向的缩放（置顶窗口按窗口实际大小铺满时两者可以略有不同）
+pub fn rotate_about_center(cr: &cairo::Context, rotation: i32, (surf_w, surf_h): (f64, f64), (cx, cy): (f64, f64), (sx, sy): (f64, f64)) {
+    cr.translate(cx, cy);
+    cr.rotate(rotation as f64 * std::f64::consts::FRAC_PI_2);
+    // 旋转 90°/270° 后窗口的横向对应图片的纵向
+    if rotation % 2 != 0 { cr.scale(sy, sx) } else { cr.scale(sx, sy) }
+    cr.translate(-surf_w / 2.0, -surf_h / 2.0);
+}
+
+// 把纹理绘制到 cairo 图像表面，供绘制函数缓存
+pub fn texture_surface(texture: &gdk::Texture) -> Option<cairo::ImageSurface> {
+    let (tw, th) = (texture.width(), texture.height());
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, tw, th).ok()?;
+    let snapshot = gtk4::Snapshot::new();
+    texture.snapshot(&snapshot, tw as f64, th as f64);
+    if let Some(node) = snapshot.to_node()
+        && let Ok(ctx) = cairo::Context::new(&surface)
+    {
+        node.draw(&ctx);
+    }
+    Some(surface)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+    // 4×2 的图片，左上红、右上绿、左下蓝、右下白
+    fn quadrants() -> image::RgbaImage {
+        image::RgbaImage::from_fn(4, 2, |x, y| image::Rgba(match (x < 2, y < 1) {
+            (true, true) => RED,
+            (false, true) => GREEN,
+            (true, false) => BLUE,
+            (false, false) => WHITE,
+        }))
+    }
+
+    // 顺时针旋转后左上、右上、左下、右下角的颜色
+    fn expected_corners(rotation: i32) -> [[u8; 4]; 4] {
+        match rotation {
+            0 => [RED, GREEN, BLUE, WHITE],
+            1 => [BLUE, RED, WHITE, GREEN],
+            2 => [WHITE, BLUE, GREEN, RED],
+            _ => [GREEN, WHITE, RED, BLUE],
+        }
+    }
+
+    #[test]
+    fn rotate_rgba_moves_corners_clockwise() {
+        for rotation in 0..4 {
+            let rotated = rotate_rgba(&quadrants(), rotation);
+            let (w, h) = rotated.dimensions();
+            assert_eq!((w, h), if rotation % 2 == 0 { (4, 2) } else { (2, 4) });
+            let corners = [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)].map(|(x, y)| rotated.get_pixel(x, y).0);
+            assert_eq!(corners, expected_corners(rotation), "rotation {rotation}");
+        }
+        assert_eq!(rotate_rgba(&quadrants(), -1), rotate_rgba(&quadrants(), 3));
+    }
+
+    // 按置顶窗口的方式把图片铺满离屏表面，读回四个角的像素；
+    // 表面为旋转后的图片按 (kx, ky) 缩放、取整的大小，两者不同时模拟被调整过比例的窗口
+    fn render_overlay(rotation: i32, (kx, ky): (f64, f64)) -> [[u8; 4]; 4] {
+        let img = quadrants();
+        let mut source = cairo::ImageSurface::create(cairo::Format::ARgb32, 4, 2).unwrap();
+        {
+            let stride = source.stride() as usize;
+            let mut data = source.data().unwrap();
+            for (x, y, p) in img.enumerate_pixels() {
+                // ARGB32 在小端机器上按 B、G、R、A 存放
+                let [r, g, b, a] = p.0;
+                let at = y as usize * stride + x as usize * 4;
+                data[at..at + 4].copy_from_slice(&[b, g, r, a]);
+            }
+        }
+        let (img_w, img_h) = if rotation % 2 == 0 { (4.0, 2.0) } else { (2.0, 4.0) };
+        let (width, height) = ((img_w * kx).round() as i32, (img_h * ky).round() as i32);
+        let target = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).unwrap();
+        {
+            let cr = cairo::Context::new(&target).unwrap();
+            let (sx, sy) = (width as f64 / img_w, height as f64 / img_h);
+            rotate_about_center(&cr, rotation, (4.0, 2.0), (width as f64 / 2.0, height as f64 / 2.0), (sx, sy));
+            cr.set_source_surface(&source, 0.0, 0.0).unwrap();
+            cr.source().set_filter(cairo::Filter::Nearest);
+            cr.paint().unwrap();
+        }
+        let stride = target.stride() as usize;
+        let data = target.take_data().unwrap();
+        let pixel = |x: i32, y: i32| {
+            let at = y as usize * stride + x as usize * 4;
+            [data[at + 2], data[at + 1], data[at], data[at + 3]]
+        };
+        [pixel(0, 0), pixel(width - 1, 0), pixel(0, height - 1), pixel(width - 1, height - 1)]
+    }
+
+    #[test]
+    fn overlay_transform_fills_the_window_for_every_rotation() {
+        for rotation in 0..4 {
+            // 整数倍、取整后的非整数倍与横纵不同的缩放，四个角都不应被裁掉或留空
+            for scale in [(10.0, 10.0), (7.3, 7.3), (12.0, 5.0)] {
+                assert_eq!(render_overlay(rotation, scale), expected_corners(rotation), "rotation {rotation}, scale {scale:?}");
+            }
+        }
+    }
+}
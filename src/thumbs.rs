@@ -0,0 +1,94 @@
+// 浏览预览用的小缩略图：在工作线程中解码并缩小，主线程轮询结果，缓存最近用过的若干张
+// 取用时不等待：还没有缩略图时返回 None 并在后台生成，生成后回调
+
+use crate::decode;
+use gtk4::gdk;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+// 缩略图的最长边
+pub const SIZE: u32 = 160;
+const CAPACITY: usize = 32;
+
+type ReadyFn = std::boxed::Box<dyn FnOnce(&gdk::Texture)>;
+
+#[derive(Default)]
+pub struct Thumbnails {
+    cache: RefCell<HashMap<PathBuf, gdk::Texture>>,
+    // 最近用过的在末尾，超出容量时从开头淘汰
+    order: RefCell<VecDeque<PathBuf>>,
+    // 正在生成的缩略图及生成后的回调；无法解码的文件记入 failed，不再重试
+    pending: RefCell<HashMap<PathBuf, Vec<ReadyFn>>>,
+    failed: RefCell<HashSet<PathBuf>>,
+}
+
+impl Thumbnails {
+    pub fn get(&self, path: &Path) -> Option<gdk::Texture> {
+        let texture = self.cache.borrow().get(path).cloned()?;
+        let mut order = self.order.borrow_mut();
+        order.retain(|p| p != path);
+        order.push_back(path.to_path_buf());
+        Some(texture)
+    }
+
+    // 已有缩略图时立即回调；否则在后台生成，完成后回调（无法解码时不回调）
+    pub fn request(self: &Rc<Self>, path: &Path, ready: impl FnOnce(&gdk::Texture) + 'static) {
+        if let Some(texture) = self.get(path) {
+            ready(&texture);
+            return;
+        }
+        if self.failed.borrow().contains(path) { return; }
+        let mut pending = self.pending.borrow_mut();
+        if let Some(waiting) = pending.get_mut(path) {
+            waiting.push(std::boxed::Box::new(ready));
+            return;
+        }
+        pending.insert(path.to_path_buf(), vec![std::boxed::Box::new(ready)]);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let source = path.to_path_buf();
+        // 设了封面帧的动图用封面帧作缩略图
+        let poster = crate::posters::get(path);
+        std::thread::spawn(move || {
+            let thumb = match poster {
+                Some(frame) => crate::extract::decode_frame(&source, frame).map(|img| image::DynamicImage::from(img).thumbnail(SIZE, SIZE).to_rgba8()),
+                None => decode::decode(&source).map(|d| d.image.thumbnail(SIZE, SIZE).to_rgba8()),
+            };
+            tx.send(thumb).ok();
+        });
+        let (this, path) = (self.clone(), path.to_path_buf());
+        glib::timeout_add_local(Duration::from_millis(30), move || {
+            let thumb = match rx.try_recv() {
+                Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("解码线程意外退出".to_string()),
+                Ok(thumb) => thumb,
+            };
+            let waiting = this.pending.borrow_mut().remove(&path).unwrap_or_default();
+            match thumb {
+                Ok(img) => {
+                    let texture = crate::texture_from_rgba(img);
+                    this.insert(path.clone(), texture.clone());
+                    for ready in waiting {
+                        ready(&texture);
+                    }
+                }
+                Err(_) => { this.failed.borrow_mut().insert(path.clone()); }
+            }
+            glib::ControlFlow::Break
+        });
+    }
+
+    fn insert(&self, path: PathBuf, texture: gdk::Texture) {
+        let mut order = self.order.borrow_mut();
+        order.push_back(path.clone());
+        self.cache.borrow_mut().insert(path, texture);
+        while order.len() > CAPACITY {
+            if let Some(old) = order.pop_front() {
+                self.cache.borrow_mut().remove(&old);
+            }
+        }
+    }
+}
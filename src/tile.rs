@@ -0,0 +1,289 @@
+// 平铺模式：--tile 把 2–6 个文件排成网格，每个格子独立监视文件变化并自动重新载入
+// 单击格子放大到整个窗口，可像普通模式一样缩放、平移；Esc 返回网格
+
+use crate::notify::Notifier;
+use crate::power::Suspension;
+use crate::{decode_texture, get_screen_size, texture_surface};
+use glib::clone;
+use gtk4::prelude::*;
+use gtk4::{cairo, gdk, gio, glib, Application, ApplicationWindow, DrawingArea};
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+pub const MIN_FILES: usize = 2;
+pub const MAX_FILES: usize = 6;
+
+const GAP: f64 = 4.0;
+const LABEL_HEIGHT: f64 = 18.0;
+// 文件变化后稍等片刻再载入，合并连续写入产生的多次通知
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+
+struct TileCell {
+    path: String,
+    name: String,
+    surface: Option<cairo::ImageSurface>,
+    error: Option<String>,
+    // 持有监视器，释放后不再收到通知
+    _monitor: Option<gio::FileMonitor>,
+    reload_timer: Option<glib::SourceId>,
+}
+
+// 放大查看的格子及其缩放、偏移
+struct Focus {
+    index: usize,
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+#[derive(Default)]
+struct Tiles {
+    cells: Vec<TileCell>,
+    focus: Option<Focus>,
+}
+
+impl TileCell {
+    // 重新解码；失败时保留旧图（文件可能正在写入），只在没有图时显示错误
+    fn load(&mut self) {
+        match decode_texture(Path::new(&self.path)) {
+            Ok(texture) => {
+                self.surface = texture_surface(&texture);
+                self.error = None;
+            }
+            Err(e) => {
+                eprintln!("无法载入 {}: {}", self.path, e);
+                if self.surface.is_none() { self.error = Some(e); }
+            }
+        }
+    }
+}
+
+// 列数与行数：尽量接近正方形
+fn grid(n: usize) -> (usize, usize) {
+    let cols = (n as f64).sqrt().ceil() as usize;
+    (cols, n.div_ceil(cols))
+}
+
+// 第 i 个格子的矩形 (x, y, w, h)
+fn cell_rect(i: usize, n: usize, width: f64, height: f64) -> (f64, f64, f64, f64) {
+    let (cols, rows) = grid(n);
+    let (cw, ch) = (width / cols as f64, height / rows as f64);
+    ((i % cols) as f64 * cw, (i / cols) as f64 * ch, cw, ch)
+}
+
+fn cell_at(x: f64, y: f64, n: usize, width: f64, height: f64) -> Option<usize> {
+    (0..n).find(|&i| {
+        let (cx, cy, cw, ch) = cell_rect(i, n, width, height);
+        x >= cx && x < cx + cw && y >= cy && y < cy + ch
+    })
+}
+
+// 适应区域的缩放（不放大）
+fn fit_scale(surface: &cairo::ImageSurface, w: f64, h: f64) -> f64 {
+    (w / surface.width() as f64).min(h / surface.height() as f64).min(1.0)
+}
+
+fn paint_surface(cr: &cairo::Context, surface: &cairo::ImageSurface, x: f64, y: f64, scale: f64) {
+    cr.save().ok();
+    cr.translate(x, y);
+    cr.scale(scale, scale);
+    cr.set_source_surface(surface, 0.0, 0.0).ok();
+    cr.source().set_filter(cairo::Filter::Bilinear);
+    cr.paint().ok();
+    cr.restore().ok();
+}
+
+fn draw_label(cr: &cairo::Context, text: &str, x: f64, y: f64) {
+    cr.set_source_rgb(0.85, 0.85, 0.85);
+    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+    cr.set_font_size(12.0);
+    cr.move_to(x, y + 13.0);
+    cr.show_text(text).ok();
+}
+
+fn draw(tiles: &Tiles, cr: &cairo::Context, width: f64, height: f64) {
+    cr.set_source_rgb(0.12, 0.12, 0.12);
+    cr.paint().ok();
+
+    if let Some(ref focus) = tiles.focus {
+        let cell = &tiles.cells[focus.index];
+        if let Some(ref surface) = cell.surface {
+            let (sw, sh) = (surface.width() as f64 * focus.scale, surface.height() as f64 * focus.scale);
+            let x = (width - sw) / 2.0 + focus.offset_x;
+            let y = (height - sh) / 2.0 + focus.offset_y;
+            paint_surface(cr, surface, x, y, focus.scale);
+        }
+        draw_label(cr, &cell.name, GAP * 2.0, GAP);
+        return;
+    }
+
+    let n = tiles.cells.len();
+    for (i, cell) in tiles.cells.iter().enumerate() {
+        let (x, y, w, h) = cell_rect(i, n, width, height);
+        cr.save().ok();
+        cr.rectangle(x, y, w, h);
+        cr.clip();
+
+        // 图片区域位于文件名下方
+        let (ix, iy) = (x + GAP, y + GAP + LABEL_HEIGHT);
+        let (iw, ih) = ((w - GAP * 2.0).max(1.0), (h - GAP * 2.0 - LABEL_HEIGHT).max(1.0));
+        match (&cell.surface, &cell.error) {
+            (Some(surface), _) => {
+                let scale = fit_scale(surface, iw, ih);
+                let (sw, sh) = (surface.width() as f64 * scale, surface.height() as f64 * scale);
+                paint_surface(cr, surface, ix + (iw - sw) / 2.0, iy + (ih - sh) / 2.0, scale);
+            }
+            (None, Some(err)) => draw_label(cr, err, ix, iy),
+            (None, None) => {}
+        }
+
+        cr.set_source_rgb(0.35, 0.35, 0.35);
+        cr.set_line_width(1.0);
+        cr.rectangle(x + 0.5, y + 0.5, w - 1.0, h - 1.0);
+        cr.stroke().ok();
+        draw_label(cr, &cell.name, x + GAP * 2.0, y + GAP);
+        cr.restore().ok();
+    }
+}
+
+fn reload(tiles: &Weak<RefCell<Tiles>>, da: &glib::WeakRef<DrawingArea>, index: usize) {
+    let Some(tiles) = tiles.upgrade() else { return };
+    let mut t = tiles.borrow_mut();
+    t.cells[index].reload_timer = None;
+    t.cells[index].load();
+    if let Some(da) = da.upgrade() { da.queue_draw(); }
+}
+
+// 监视文件变化，合并短时间内的多次通知后重新载入该格子
+// 窗口不可见时不解码，恢复可见时只重新载入一次
+// 窗口不在前台时另发桌面通知（notifier 为 None 表示已关闭通知）
+fn watch(path: &str, index: usize, tiles: Weak<RefCell<Tiles>>, da: &DrawingArea, suspension: &Suspension, notifier: Option<Rc<Notifier>>) -> Option<gio::FileMonitor> {
+    let monitor = gio::File::for_path(path)
+        .monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE)
+        .inspect_err(|e| eprintln!("无法监视 {}: {}", path, e))
+        .ok()?;
+    let da = da.downgrade();
+    let suspension = suspension.clone();
+    let path = path.to_string();
+    monitor.connect_changed(move |_, _, _, event| {
+        if !matches!(event, gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created) { return; }
+        if let Some(ref notifier) = notifier { notifier.updated(&path); }
+        if suspension.is_suspended() {
+            let (tiles, da) = (tiles.clone(), da.clone());
+            suspension.run_or_defer(&format!("tile-{}", index), move || reload(&tiles, &da, index));
+            return;
+        }
+        let Some(tiles_rc) = tiles.upgrade() else { return };
+        let mut t = tiles_rc.borrow_mut();
+        if let Some(id) = t.cells[index].reload_timer.take() { id.remove(); }
+        let (tiles, da) = (tiles.clone(), da.clone());
+        t.cells[index].reload_timer = Some(glib::timeout_add_local_once(RELOAD_DELAY, move || reload(&tiles, &da, index)));
+    });
+    Some(monitor)
+}
+
+pub fn build_ui(app: &Application, paths: Vec<String>, notify: bool) {
+    let tiles = Rc::new(RefCell::new(Tiles::default()));
+    let suspension = Suspension::default();
+    let drawing_area = DrawingArea::new();
+    drawing_area.set_hexpand(true);
+    drawing_area.set_vexpand(true);
+
+    drawing_area.set_draw_func(clone!(#[strong] tiles, move |_, cr, w, h| {
+        draw(&tiles.borrow(), cr, w as f64, h as f64);
+    }));
+
+    // 网格中单击格子放大
+    let click = gtk4::GestureClick::builder().button(1).build();
+    click.connect_released(clone!(#[strong] tiles, move |gesture, n_press, x, y| {
+        let Some(da) = gesture.widget() else { return };
+        let mut t = tiles.borrow_mut();
+        if n_press != 1 || t.focus.is_some() { return; }
+        let (w, h) = (da.width() as f64, da.height() as f64);
+        let Some(index) = cell_at(x, y, t.cells.len(), w, h) else { return };
+        let Some(scale) = t.cells[index].surface.as_ref().map(|s| fit_scale(s, w, h)) else { return };
+        t.focus = Some(Focus { index, scale, offset_x: 0.0, offset_y: 0.0 });
+        da.queue_draw();
+    }));
+    drawing_area.add_controller(click);
+
+    // 放大后的缩放与平移，与普通模式一致
+    let pointer = Rc::new(Cell::new((0.0f64, 0.0f64)));
+    let motion = gtk4::EventControllerMotion::new();
+    motion.connect_motion(clone!(#[strong] pointer, move |_, x, y| pointer.set((x, y))));
+    drawing_area.add_controller(motion);
+
+    let scroll = gtk4::EventControllerScroll::new(gtk4::EventControllerScrollFlags::VERTICAL);
+    scroll.connect_scroll(clone!(#[strong] tiles, #[strong] pointer, #[weak] drawing_area, #[upgrade_or] glib::Propagation::Proceed, move |_, _, dy| {
+        let mut t = tiles.borrow_mut();
+        let Some(ref mut focus) = t.focus else { return glib::Propagation::Proceed };
+        let old_scale = focus.scale;
+        let factor = if dy < 0.0 { 1.1 } else { 1.0 / 1.1 };
+        focus.scale = (focus.scale * factor).clamp(0.1, 50.0);
+        // 以指针为中心缩放
+        let (mx, my) = pointer.get();
+        let cx = drawing_area.width() as f64 / 2.0 + focus.offset_x;
+        let cy = drawing_area.height() as f64 / 2.0 + focus.offset_y;
+        let ratio = focus.scale / old_scale;
+        focus.offset_x += (mx - cx) * (1.0 - ratio);
+        focus.offset_y += (my - cy) * (1.0 - ratio);
+        drawing_area.queue_draw();
+        glib::Propagation::Stop
+    }));
+    drawing_area.add_controller(scroll);
+
+    let drag = gtk4::GestureDrag::builder().button(1).build();
+    let drag_start = Rc::new(Cell::new((0.0f64, 0.0f64)));
+    drag.connect_drag_begin(clone!(#[strong] tiles, #[strong] drag_start, move |_, _, _| {
+        if let Some(ref focus) = tiles.borrow().focus {
+            drag_start.set((focus.offset_x, focus.offset_y));
+        }
+    }));
+    drag.connect_drag_update(clone!(#[strong] tiles, #[strong] drag_start, #[weak] drawing_area, move |_, dx, dy| {
+        if let Some(ref mut focus) = tiles.borrow_mut().focus {
+            let (sx, sy) = drag_start.get();
+            focus.offset_x = sx + dx;
+            focus.offset_y = sy + dy;
+            drawing_area.queue_draw();
+        }
+    }));
+    drawing_area.add_controller(drag);
+
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("image-viewer")
+        .child(&drawing_area)
+        .build();
+    if let Some((screen_w, screen_h)) = get_screen_size() {
+        window.set_default_size(screen_w * 3 / 4, screen_h * 3 / 4);
+    }
+    suspension.attach(&window);
+
+    let notifier = notify.then(|| Notifier::new(app, &window));
+    {
+        let mut t = tiles.borrow_mut();
+        for (index, path) in paths.into_iter().enumerate() {
+            let name = Path::new(&path).file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone());
+            let monitor = watch(&path, index, Rc::downgrade(&tiles), &drawing_area, &suspension, notifier.clone());
+            let mut cell = TileCell { path, name, surface: None, error: None, _monitor: monitor, reload_timer: None };
+            cell.load();
+            t.cells.push(cell);
+        }
+    }
+
+    // Esc 返回网格
+    let key_ctrl = gtk4::EventControllerKey::new();
+    key_ctrl.connect_key_pressed(clone!(#[strong] tiles, #[weak] drawing_area, #[upgrade_or] glib::Propagation::Proceed, move |_, key, _, _| {
+        if key != gdk::Key::Escape { return glib::Propagation::Proceed; }
+        if tiles.borrow_mut().focus.take().is_none() { return glib::Propagation::Proceed; }
+        drawing_area.queue_draw();
+        glib::Propagation::Stop
+    }));
+    window.add_controller(key_ctrl);
+
+    window.present();
+}
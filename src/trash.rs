@@ -0,0 +1,34 @@
+// 回收站：把文件移入回收站，并按原路径从回收站恢复
+
+use gio::prelude::*;
+use std::path::Path;
+
+pub fn trash(path: &Path) -> Result<(), String> {
+    gio::File::for_path(path).trash(gio::Cancellable::NONE).map_err(|e| e.to_string())
+}
+
+// 在 trash:/// 中查找原路径为 path 的最近一次删除的条目，并移回原处
+pub fn restore(path: &Path) -> Result<(), String> {
+    let trash = gio::File::for_uri("trash:///");
+    let entries = trash
+        .enumerate_children("standard::name,trash::orig-path,trash::deletion-date",
+                            gio::FileQueryInfoFlags::NONE, gio::Cancellable::NONE)
+        .map_err(|e| format!("无法读取回收站: {}", e))?;
+    let mut found: Option<(String, String)> = None;
+    while let Some(info) = entries.next_file(gio::Cancellable::NONE).map_err(|e| e.to_string())? {
+        let orig = info.attribute_byte_string("trash::orig-path");
+        if orig.as_deref().map(Path::new) != Some(path) { continue; }
+        let date = info.attribute_string("trash::deletion-date").map(|d| d.to_string()).unwrap_or_default();
+        // 日期为 ISO 8601 格式，可直接按字符串比较
+        if found.as_ref().is_none_or(|(_, best)| date > *best) {
+            found = Some((info.name().to_string_lossy().to_string(), date));
+        }
+    }
+    let (name, _) = found.ok_or("回收站中找不到该文件")?;
+    if path.exists() {
+        return Err(format!("{} 已存在", path.display()));
+    }
+    trash.child(name)
+        .move_(&gio::File::for_path(path), gio::FileCopyFlags::NONE, gio::Cancellable::NONE, None)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,65 @@
+// 普通窗口与可嵌入控件（widget.rs）共用的视图计算：旋转后的尺寸、适应缩放率、以某点为中心缩放时的偏移
+// 偏移是图片中心相对绘图区中心的位移，正值向右下；旋转以顺时针的直角数（0..4）表示
+
+// 顺时针旋转 rotation 个直角后的尺寸
+pub fn rotated_size<T>((w, h): (T, T), rotation: i32) -> (T, T) {
+    if rotation % 2 == 0 { (w, h) } else { (h, w) }
+}
+
+// 再旋转一个直角后的方向
+pub fn rotate_step(rotation: i32, clockwise: bool) -> i32 {
+    (rotation + if clockwise { 1 } else { 3 }).rem_euclid(4)
+}
+
+// 把 (w, h) 完整放进 (view_w, view_h) 的缩放率，不放大
+pub fn fit_scale((w, h): (f64, f64), (view_w, view_h): (f64, f64)) -> f64 {
+    (view_w / w).min(view_h / h).min(1.0)
+}
+
+// 缩放率变为原来的 ratio 倍后的偏移：绘图区中 point 处的图片内容保持不动，center 为绘图区中心
+pub fn zoom_offset((ox, oy): (f64, f64), (px, py): (f64, f64), (cx, cy): (f64, f64), ratio: f64) -> (f64, f64) {
+    ((px - cx) * (1.0 - ratio) + ox * ratio, (py - cy) * (1.0 - ratio) + oy * ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarter_turns_swap_the_sides() {
+        assert_eq!(rotated_size((400, 300), 0), (400, 300));
+        assert_eq!(rotated_size((400, 300), 1), (300, 400));
+        assert_eq!(rotated_size((400.0, 300.0), 2), (400.0, 300.0));
+        assert_eq!(rotated_size((400.0, 300.0), 3), (300.0, 400.0));
+    }
+
+    #[test]
+    fn rotate_step_wraps_both_ways() {
+        assert_eq!(rotate_step(0, true), 1);
+        assert_eq!(rotate_step(3, true), 0);
+        assert_eq!(rotate_step(0, false), 3);
+        assert_eq!(rotate_step(1, false), 0);
+    }
+
+    #[test]
+    fn fit_scale_uses_the_tighter_side_and_never_enlarges() {
+        assert_eq!(fit_scale((2000.0, 1000.0), (1000.0, 1000.0)), 0.5);
+        assert_eq!(fit_scale((1000.0, 2000.0), (1000.0, 1000.0)), 0.5);
+        assert_eq!(fit_scale((100.0, 50.0), (1000.0, 1000.0)), 1.0);
+    }
+
+    #[test]
+    fn zooming_keeps_the_content_under_the_point() {
+        let (center, offset, scale) = ((400.0, 300.0), (30.0, -20.0), 0.5);
+        let point = (650.0, 120.0);
+        // point 处在图片（以图片中心为原点、未缩放）中的坐标
+        let content = |offset: (f64, f64), scale: f64| {
+            ((point.0 - center.0 - offset.0) / scale, (point.1 - center.1 - offset.1) / scale)
+        };
+        let before = content(offset, scale);
+        let after = content(zoom_offset(offset, point, center, 1.5), scale * 1.5);
+        assert!((before.0 - after.0).abs() < 1e-9 && (before.1 - after.1).abs() < 1e-9);
+        // 以中心缩放时偏移按比例缩放
+        assert_eq!(zoom_offset(offset, center, center, 2.0), (60.0, -40.0));
+    }
+}
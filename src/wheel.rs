@@ -0,0 +1,66 @@
+// 滚轮缩放：把滚动事件换算为缩放档数
+// 离散滚轮每格一档，100 ms 内连续滚动逐步加速（最多 2 倍）；触摸板等平滑滚动按增量比例换算
+
+use crate::config::Config;
+use gtk4::{gdk, EventControllerScroll};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+// 连续滚动的判定间隔与加速上限
+const ACCEL_WINDOW: Duration = Duration::from_millis(100);
+const ACCEL_GROWTH: f64 = 1.25;
+const MAX_ACCEL: f64 = 2.0;
+// 平滑滚动时相当于一格滚轮的像素数
+const PIXELS_PER_NOTCH: f64 = 20.0;
+
+pub struct WheelZoom {
+    // 每档的缩放倍数，如 1.1
+    factor: f64,
+    invert: bool,
+    last: Cell<Option<Instant>>,
+    accel: Cell<f64>,
+    // 像素对齐缩放按整档切换，平滑滚动的零头在此累积
+    pending: Cell<f64>,
+}
+
+impl WheelZoom {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            factor: config.zoom_factor(),
+            invert: config.invert_scroll,
+            last: Cell::new(None),
+            accel: Cell::new(1.0),
+            pending: Cell::new(0.0),
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    // 本次事件的缩放档数，正数为放大
+    pub fn steps(&self, ctrl: &EventControllerScroll, dy: f64) -> f64 {
+        let notches = match ctrl.unit() {
+            gdk::ScrollUnit::Surface => dy / PIXELS_PER_NOTCH,
+            _ => {
+                let now = Instant::now();
+                let consecutive = self.last.replace(Some(now)).is_some_and(|t| now - t < ACCEL_WINDOW);
+                let accel = if consecutive { (self.accel.get() * ACCEL_GROWTH).min(MAX_ACCEL) } else { 1.0 };
+                self.accel.set(accel);
+                dy * accel
+            }
+        };
+        if self.invert { notches } else { -notches }
+    }
+
+    // 累积到整档后返回 ±1，用于像素对齐缩放
+    pub fn whole_step(&self, steps: f64) -> Option<bool> {
+        let total = self.pending.get() + steps;
+        if total.abs() < 1.0 {
+            self.pending.set(total);
+            return None;
+        }
+        self.pending.set(0.0);
+        Some(total > 0.0)
+    }
+}
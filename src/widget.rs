@@ -0,0 +1,382 @@
+// 可嵌入其他 GTK4 程序的图片查看控件：绘图区、视图状态与手势，以及载入、缩放、旋转、复制等操作
+// 控件不假定自己拥有顶层窗口：不调整窗口大小，也不使用 layer-shell，这些留在 image-viewer 程序中
+// 解码、纹理、旋转后的尺寸、适应缩放率与以指针为中心的缩放都与 image-viewer 程序共用（decode、texture、view 模块），
+// 这里只有控件自己的状态与手势
+
+use crate::{decode, texture, view};
+use gtk4::prelude::*;
+use gtk4::{cairo, gdk, glib};
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+/// 缩放率的上下限
+pub const MIN_ZOOM: f64 = 0.01;
+pub const MAX_ZOOM: f64 = 50.0;
+
+// 滚轮一格与 zoom_in / zoom_out 的缩放倍数
+const ZOOM_STEP: f64 = 1.1;
+
+/// 载入图片失败的原因
+#[derive(Debug, Clone)]
+pub struct LoadError {
+    message: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<String> for LoadError {
+    fn from(message: String) -> Self {
+        LoadError { message }
+    }
+}
+
+type LoadedFn = Rc<dyn Fn(&ImageViewerWidget)>;
+type ZoomFn = Rc<dyn Fn(&ImageViewerWidget, f64)>;
+
+struct View {
+    texture: Option<gdk::Texture>,
+    path: Option<PathBuf>,
+    scale: f64,
+    // 图片中心相对绘图区中心的偏移（像素）
+    offset: (f64, f64),
+    // 顺时针旋转的直角数（0..4）
+    rotation: i32,
+    // 适应控件大小：控件大小变化时重新计算缩放率
+    fit: bool,
+}
+
+struct Inner {
+    area: gtk4::DrawingArea,
+    widget: gtk4::Widget,
+    view: RefCell<View>,
+    // 绘制用的 cairo 表面及生成它的纹理
+    surface: RefCell<Option<(gdk::Texture, cairo::ImageSurface)>>,
+    loaded: RefCell<Vec<LoadedFn>>,
+    zoom_changed: RefCell<Vec<ZoomFn>>,
+}
+
+/// 图片查看控件
+///
+/// 用 [`widget`](Self::widget) 取得要放进界面的 GTK 控件。控件内可以用滚轮缩放（以指针为中心）、
+/// 拖动平移、双指捏合缩放，双击在适应控件与 100% 之间切换。克隆得到的是同一个控件。
+#[derive(Clone)]
+pub struct ImageViewerWidget {
+    inner: Rc<Inner>,
+}
+
+impl Default for ImageViewerWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImageViewerWidget {
+    /// 创建一个空的控件
+    pub fn new() -> Self {
+        let area = gtk4::DrawingArea::builder().hexpand(true).vexpand(true).focusable(true).build();
+        let inner = Rc::new(Inner {
+            widget: area.clone().upcast(),
+            area,
+            view: RefCell::new(View { texture: None, path: None, scale: 1.0, offset: (0.0, 0.0), rotation: 0, fit: true }),
+            surface: RefCell::new(None),
+            loaded: RefCell::default(),
+            zoom_changed: RefCell::default(),
+        });
+        let this = ImageViewerWidget { inner };
+        this.connect_gestures();
+        this
+    }
+
+    /// 放进界面的 GTK 控件
+    pub fn widget(&self) -> &gtk4::Widget {
+        &self.inner.widget
+    }
+
+    /// 解码并显示文件（应用 EXIF 方向）；视图回到适应控件、未旋转
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        let path = path.as_ref();
+        let texture = texture::decode_texture(path)?;
+        self.show(texture, Some(path.to_path_buf()));
+        Ok(())
+    }
+
+    /// 解码并显示内存中的图片数据，例如聊天消息中的附件
+    pub fn load_bytes(&self, data: &[u8]) -> Result<(), LoadError> {
+        let texture = match decode::decode_bytes(data) {
+            Ok(decoded) => texture::texture_from_rgba(decoded.image.to_rgba8()),
+            Err(e) => gdk::Texture::from_bytes(&glib::Bytes::from(data)).map_err(|_| LoadError::from(e))?,
+        };
+        self.show(texture, None);
+        Ok(())
+    }
+
+    /// 显示已有的纹理
+    pub fn load_texture(&self, texture: &gdk::Texture) {
+        self.show(texture.clone(), None);
+    }
+
+    /// 清空控件
+    pub fn clear(&self) {
+        {
+            let mut view = self.inner.view.borrow_mut();
+            view.texture = None;
+            view.path = None;
+        }
+        self.inner.surface.borrow_mut().take();
+        self.inner.area.queue_draw();
+    }
+
+    /// 当前显示的纹理（未旋转）
+    pub fn texture(&self) -> Option<gdk::Texture> {
+        self.inner.view.borrow().texture.clone()
+    }
+
+    /// 当前图片的文件路径；由内存数据或纹理载入时为 None
+    pub fn path(&self) -> Option<PathBuf> {
+        self.inner.view.borrow().path.clone()
+    }
+
+    /// 当前缩放率，1.0 为 100%
+    pub fn zoom(&self) -> f64 {
+        self.inner.view.borrow().scale
+    }
+
+    /// 以控件中心为基准设置缩放率，限制在 [`MIN_ZOOM`]..=[`MAX_ZOOM`]
+    pub fn set_zoom(&self, scale: f64) {
+        let center = self.center();
+        self.zoom_at(scale, center);
+    }
+
+    pub fn zoom_in(&self) {
+        self.set_zoom(self.zoom() * ZOOM_STEP);
+    }
+
+    pub fn zoom_out(&self) {
+        self.set_zoom(self.zoom() / ZOOM_STEP);
+    }
+
+    /// 适应控件大小（不放大小图），之后随控件大小变化保持适应
+    pub fn fit(&self) {
+        {
+            let mut view = self.inner.view.borrow_mut();
+            view.fit = true;
+            view.offset = (0.0, 0.0);
+        }
+        self.refit();
+        self.inner.area.queue_draw();
+    }
+
+    /// 是否处于适应控件大小的状态
+    pub fn is_fitted(&self) -> bool {
+        self.inner.view.borrow().fit
+    }
+
+    /// 旋转 90°；适应控件时按旋转后的尺寸重新适应
+    pub fn rotate(&self, clockwise: bool) {
+        let fit = {
+            let mut view = self.inner.view.borrow_mut();
+            if view.texture.is_none() { return; }
+            view.rotation = view::rotate_step(view.rotation, clockwise);
+            view.fit
+        };
+        if fit { self.refit(); }
+        self.inner.area.queue_draw();
+    }
+
+    /// 顺时针旋转的角度：0、90、180 或 270
+    pub fn rotation(&self) -> u32 {
+        self.inner.view.borrow().rotation as u32 * 90
+    }
+
+    /// 把旋转后的图片复制到控件所在显示的剪贴板；没有图片时返回 false
+    pub fn copy_to_clipboard(&self) -> bool {
+        let (texture, rotation) = {
+            let view = self.inner.view.borrow();
+            let Some(texture) = view.texture.clone() else { return false };
+            (texture, view.rotation)
+        };
+        self.inner.area.clipboard().set_texture(&texture::rotated_texture(&texture, rotation));
+        true
+    }
+
+    /// 载入新图片（load_file、load_bytes、load_texture 成功）后调用
+    pub fn connect_loaded(&self, f: impl Fn(&ImageViewerWidget) + 'static) {
+        self.inner.loaded.borrow_mut().push(Rc::new(f));
+    }
+
+    /// 缩放率改变后调用，参数为新的缩放率；包括适应控件时随控件大小变化的情况
+    pub fn connect_zoom_changed(&self, f: impl Fn(&ImageViewerWidget, f64) + 'static) {
+        self.inner.zoom_changed.borrow_mut().push(Rc::new(f));
+    }
+
+    fn show(&self, texture: gdk::Texture, path: Option<PathBuf>) {
+        {
+            let mut view = self.inner.view.borrow_mut();
+            *view = View { texture: Some(texture), path, scale: 1.0, offset: (0.0, 0.0), rotation: 0, fit: true };
+        }
+        self.inner.surface.borrow_mut().take();
+        self.refit();
+        self.inner.area.queue_draw();
+        let handlers = self.inner.loaded.borrow().clone();
+        for f in handlers {
+            f(self);
+        }
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (self.inner.area.width() as f64 / 2.0, self.inner.area.height() as f64 / 2.0)
+    }
+
+    // 旋转后的图片尺寸
+    fn rotated_size(view: &View) -> Option<(f64, f64)> {
+        let texture = view.texture.as_ref()?;
+        Some(view::rotated_size((texture.width() as f64, texture.height() as f64), view.rotation))
+    }
+
+    // 适应控件时按当前大小重新计算缩放率；控件尚未分配大小时不变
+    fn refit(&self) {
+        let (width, height) = (self.inner.area.width() as f64, self.inner.area.height() as f64);
+        let changed = {
+            let mut view = self.inner.view.borrow_mut();
+            let Some(size) = Self::rotated_size(&view) else { return };
+            if !view.fit || width <= 0.0 || height <= 0.0 { return; }
+            let scale = view::fit_scale(size, (width, height));
+            let changed = (scale - view.scale).abs() > 1e-9;
+            view.scale = scale;
+            changed
+        };
+        if changed { self.emit_zoom(); }
+    }
+
+    // 以控件中的点 (x, y) 为基准缩放，该点下的图片内容保持不动
+    fn zoom_at(&self, scale: f64, point: (f64, f64)) {
+        let scale = scale.clamp(MIN_ZOOM, MAX_ZOOM);
+        let center = self.center();
+        {
+            let mut view = self.inner.view.borrow_mut();
+            if view.texture.is_none() || (scale - view.scale).abs() < 1e-9 { return; }
+            view.offset = view::zoom_offset(view.offset, point, center, scale / view.scale);
+            view.scale = scale;
+            view.fit = false;
+        }
+        self.inner.area.queue_draw();
+        self.emit_zoom();
+    }
+
+    fn emit_zoom(&self) {
+        let scale = self.zoom();
+        let handlers = self.inner.zoom_changed.borrow().clone();
+        for f in handlers {
+            f(self, scale);
+        }
+    }
+
+    fn connect_gestures(&self) {
+        let weak = Rc::downgrade(&self.inner);
+        let upgrade = |weak: &Weak<Inner>| weak.upgrade().map(|inner| ImageViewerWidget { inner });
+        let area = &self.inner.area;
+
+        area.set_draw_func(clone_weak(&weak, move |this, cr, width, height| this.draw(cr, width, height)));
+        let w = weak.clone();
+        area.connect_resize(move |_, _, _| if let Some(this) = upgrade(&w) { this.refit() });
+
+        // 滚轮以指针为中心缩放
+        let scroll = gtk4::EventControllerScroll::new(gtk4::EventControllerScrollFlags::VERTICAL);
+        let motion = gtk4::EventControllerMotion::new();
+        let pointer = Rc::new(std::cell::Cell::new(None::<(f64, f64)>));
+        let p = pointer.clone();
+        motion.connect_motion(move |_, x, y| p.set(Some((x, y))));
+        let p = pointer.clone();
+        motion.connect_leave(move |_| p.set(None));
+        let w = weak.clone();
+        scroll.connect_scroll(move |_, _, dy| {
+            let Some(this) = upgrade(&w) else { return glib::Propagation::Proceed };
+            if this.texture().is_none() || dy == 0.0 { return glib::Propagation::Proceed; }
+            let at = pointer.get().unwrap_or_else(|| this.center());
+            let factor = if dy < 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+            this.zoom_at(this.zoom() * factor, at);
+            glib::Propagation::Stop
+        });
+        area.add_controller(motion);
+        area.add_controller(scroll);
+
+        // 拖动平移
+        let drag = gtk4::GestureDrag::builder().button(1).build();
+        let start = Rc::new(std::cell::Cell::new((0.0, 0.0)));
+        let (w, s) = (weak.clone(), start.clone());
+        drag.connect_drag_begin(move |_, _, _| {
+            if let Some(this) = upgrade(&w) { s.set(this.inner.view.borrow().offset); }
+        });
+        let w = weak.clone();
+        drag.connect_drag_update(move |_, dx, dy| {
+            let Some(this) = upgrade(&w) else { return };
+            let (ox, oy) = start.get();
+            this.inner.view.borrow_mut().offset = (ox + dx, oy + dy);
+            this.inner.area.queue_draw();
+        });
+        area.add_controller(drag);
+
+        // 双指捏合缩放
+        let pinch = gtk4::GestureZoom::new();
+        let begin_scale = Rc::new(std::cell::Cell::new(1.0));
+        let (w, b) = (weak.clone(), begin_scale.clone());
+        pinch.connect_begin(move |_, _| {
+            if let Some(this) = upgrade(&w) { b.set(this.zoom()); }
+        });
+        let w = weak.clone();
+        pinch.connect_scale_changed(move |gesture, delta| {
+            let Some(this) = upgrade(&w) else { return };
+            let at = gesture.bounding_box_center().unwrap_or_else(|| this.center());
+            this.zoom_at(begin_scale.get() * delta, at);
+        });
+        area.add_controller(pinch);
+
+        // 双击在适应控件与 100% 之间切换
+        let click = gtk4::GestureClick::builder().button(1).build();
+        let w = weak.clone();
+        click.connect_pressed(move |gesture, n_press, x, y| {
+            if n_press != 2 { return; }
+            let Some(this) = upgrade(&w) else { return };
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            if this.is_fitted() && (this.zoom() - 1.0).abs() > 1e-9 { this.zoom_at(1.0, (x, y)) } else { this.fit() }
+        });
+        area.add_controller(click);
+    }
+
+    fn draw(&self, cr: &cairo::Context, width: i32, height: i32) {
+        let view = self.inner.view.borrow();
+        let Some(texture) = view.texture.clone() else { return };
+        let mut cache = self.inner.surface.borrow_mut();
+        if cache.as_ref().is_none_or(|(t, _)| *t != texture) {
+            *cache = texture::texture_surface(&texture).map(|surface| (texture.clone(), surface));
+        }
+        let Some((_, surface)) = cache.as_ref() else { return };
+        let (ox, oy) = view.offset;
+        let center = (width as f64 / 2.0 + ox, height as f64 / 2.0 + oy);
+        let size = (surface.width() as f64, surface.height() as f64);
+        texture::rotate_about_center(cr, view.rotation, size, center, (view.scale, view.scale));
+        if cr.set_source_surface(surface, 0.0, 0.0).is_ok() {
+            cr.source().set_filter(if view.scale < 1.0 { cairo::Filter::Good } else { cairo::Filter::Fast });
+            cr.paint().ok();
+        }
+    }
+}
+
+// 绘制函数只持有弱引用，控件释放后不再绘制
+fn clone_weak(
+    weak: &Weak<Inner>,
+    f: impl Fn(&ImageViewerWidget, &cairo::Context, i32, i32) + 'static,
+) -> impl Fn(&gtk4::DrawingArea, &cairo::Context, i32, i32) + 'static {
+    let weak = weak.clone();
+    move |_, cr, width, height| {
+        if let Some(inner) = weak.upgrade() { f(&ImageViewerWidget { inner }, cr, width, height); }
+    }
+}
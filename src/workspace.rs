@@ -0,0 +1,164 @@
+// 置顶窗口只在固定时所在的工作区显示：layer-shell 表面默认出现在所有工作区，
+// 这里借助合成器的 IPC（Hyprland 的事件套接字、sway 的 swaymsg）得知工作区切换，在其他工作区隐藏置顶窗口
+// 没有可用 IPC 的合成器保持在所有工作区显示
+
+use gtk4::ApplicationWindow;
+use gtk4::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Compositor {
+    Hyprland,
+    Sway,
+}
+
+impl Compositor {
+    pub fn name(self) -> &'static str {
+        match self {
+            Compositor::Hyprland => "Hyprland",
+            Compositor::Sway => "sway",
+        }
+    }
+
+    // 当前获得焦点的工作区名称
+    fn current(self) -> Option<String> {
+        match self {
+            // 输出形如 "workspace ID 3 (3) on monitor DP-1:"
+            Compositor::Hyprland => {
+                let mut socket = UnixStream::connect(hyprland_dir()?.join(".socket.sock")).ok()?;
+                socket.write_all(b"activeworkspace").ok()?;
+                let mut reply = String::new();
+                socket.read_to_string(&mut reply).ok()?;
+                let line = reply.lines().next()?;
+                let start = line.find('(')? + 1;
+                let end = line.rfind(") on monitor")?;
+                (start <= end).then(|| line[start..end].to_string())
+            }
+            // 可读格式中获得焦点的工作区为 "Workspace 名称 (focused)"
+            Compositor::Sway => {
+                let output = Command::new("swaymsg").args(["-p", "-t", "get_workspaces"]).output().ok()?;
+                String::from_utf8_lossy(&output.stdout).lines()
+                    .find_map(|l| l.strip_prefix("Workspace ")?.strip_suffix(" (focused)").map(str::to_string))
+            }
+        }
+    }
+
+    // 在工作线程中等待工作区切换，每次切换发送新的工作区名称；连接断开时结束
+    fn watch(self, tx: mpsc::Sender<String>) {
+        match self {
+            Compositor::Hyprland => {
+                let Some(socket) = hyprland_dir().and_then(|dir| UnixStream::connect(dir.join(".socket2.sock")).ok()) else { return };
+                for line in BufReader::new(socket).lines().map_while(Result::ok) {
+                    // workspace>>名称，或切换显示器时的 focusedmon>>显示器,名称
+                    let name = line.strip_prefix("workspace>>")
+                        .or_else(|| line.strip_prefix("focusedmon>>").and_then(|rest| rest.split_once(',')).map(|(_, ws)| ws));
+                    if let Some(name) = name && tx.send(name.to_string()).is_err() { return; }
+                }
+            }
+            // swaymsg 的事件是 JSON，这里只用作通知，收到后重新查询当前工作区
+            Compositor::Sway => {
+                let Ok(mut child) = Command::new("swaymsg").args(["-m", "-t", "subscribe", "[\"workspace\"]"])
+                    .stdout(Stdio::piped()).stderr(Stdio::null()).spawn() else { return };
+                let Some(stdout) = child.stdout.take() else { return };
+                for _ in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    if let Some(name) = self.current() && tx.send(name).is_err() { break; }
+                }
+                child.kill().ok();
+                child.wait().ok();
+            }
+        }
+    }
+}
+
+// 新版 Hyprland 的套接字在 $XDG_RUNTIME_DIR/hypr 下，旧版在 /tmp/hypr 下
+fn hyprland_dir() -> Option<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    [glib::user_runtime_dir().join("hypr"), PathBuf::from("/tmp/hypr")].into_iter()
+        .map(|dir| dir.join(&signature))
+        .find(|dir| dir.join(".socket2.sock").exists())
+}
+
+// 根据环境变量判断合成器；不支持的合成器返回 None
+pub fn detect() -> Option<Compositor> {
+    if hyprland_dir().is_some() { return Some(Compositor::Hyprland); }
+    if std::env::var_os("SWAYSOCK").is_some() { return Some(Compositor::Sway); }
+    None
+}
+
+// 开关与菜单中的说明：支持时注明所用的合成器，不支持时说明保持在所有工作区显示
+pub fn describe() -> String {
+    match detect() {
+        Some(compositor) => format!("置顶窗口只在当前工作区显示（{}）", compositor.name()),
+        None => "置顶窗口只在当前工作区显示（合成器不支持，保持在所有工作区显示）".to_string(),
+    }
+}
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    static WATCHING: Cell<bool> = const { Cell::new(false) };
+    static CURRENT: RefCell<Option<String>> = const { RefCell::new(None) };
+    // 置顶窗口及固定时所在的工作区
+    static PINNED: RefCell<Vec<(glib::WeakRef<ApplicationWindow>, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn enabled() -> bool {
+    ENABLED.get()
+}
+
+// 开关对已有的置顶窗口同样生效
+pub fn set_enabled(on: bool) {
+    ENABLED.set(on);
+    apply();
+}
+
+// 记下置顶窗口当前所在的工作区；第一次调用时开始监听工作区切换
+pub fn pin(window: &ApplicationWindow) {
+    let Some(compositor) = detect() else { return };
+    let Some(workspace) = CURRENT.with_borrow(|c| c.clone()).or_else(|| compositor.current()) else { return };
+    CURRENT.set(Some(workspace.clone()));
+    PINNED.with_borrow_mut(|pinned| pinned.push((window.downgrade(), workspace)));
+    // 关闭后不再参与显示与隐藏，否则切回工作区时会重新显示已关闭的窗口
+    window.connect_destroy(|window| {
+        PINNED.with_borrow_mut(|pinned| pinned.retain(|(w, _)| w.upgrade().is_some_and(|w| &w != window)));
+    });
+    if WATCHING.replace(true) { return; }
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || compositor.watch(tx));
+    glib::timeout_add_local(Duration::from_millis(200), move || {
+        let mut latest = None;
+        loop {
+            match rx.try_recv() {
+                Ok(name) => latest = Some(name),
+                Err(mpsc::TryRecvError::Empty) => break,
+                // 连接断开后置顶窗口全部恢复显示，下次固定时重新连接
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    WATCHING.set(false);
+                    CURRENT.set(None);
+                    apply();
+                    return glib::ControlFlow::Break;
+                }
+            }
+        }
+        if latest.is_some() {
+            CURRENT.set(latest);
+            apply();
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+fn apply() {
+    let current = CURRENT.with_borrow(|c| c.clone());
+    PINNED.with_borrow_mut(|pinned| pinned.retain(|(window, workspace)| {
+        let Some(window) = window.upgrade() else { return false };
+        let visible = !ENABLED.get() || current.as_ref().is_none_or(|c| c == workspace);
+        if window.is_visible() != visible { window.set_visible(visible); }
+        true
+    }));
+}
@@ -0,0 +1,184 @@
+// X11 下查询指针与其他顶层窗口的位置、移动窗口：置顶时避开正在使用的窗口，新窗口出现在指针旁边
+// 与 above.rs 相同，Xlib 函数在运行时查找；Wayland 没有对应的协议，调用方只以指针为参考或交给合成器
+// 对外的坐标都是 GDK 的逻辑像素，X11 的坐标是设备像素，按表面的缩放系数换算
+
+use crate::above::symbol;
+use crate::placement::Rect;
+use gtk4::gdk;
+use gtk4::prelude::*;
+use std::ffi::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_void};
+
+// Xlib 的 XWindowAttributes
+#[repr(C)]
+struct WindowAttributes {
+    x: c_int,
+    y: c_int,
+    width: c_int,
+    height: c_int,
+    border_width: c_int,
+    depth: c_int,
+    visual: *mut c_void,
+    root: c_ulong,
+    class: c_int,
+    bit_gravity: c_int,
+    win_gravity: c_int,
+    backing_store: c_int,
+    backing_planes: c_ulong,
+    backing_pixel: c_ulong,
+    save_under: c_int,
+    colormap: c_ulong,
+    map_installed: c_int,
+    map_state: c_int,
+    all_event_masks: c_long,
+    your_event_mask: c_long,
+    do_not_propagate_mask: c_long,
+    override_redirect: c_int,
+    screen: *mut c_void,
+}
+
+const IS_VIEWABLE: c_int = 2;
+const XA_WINDOW: c_ulong = 33;
+const SUCCESS: c_int = 0;
+// 读取窗口列表时最多取的项数
+const MAX_CLIENTS: c_long = 1024;
+
+// surface 的 Xlib Display 与窗口 ID
+fn handles(surface: &gdk::Surface) -> Option<(*mut c_void, c_ulong)> {
+    type GetXDisplay = unsafe extern "C" fn(*mut c_void) -> *mut c_void;
+    type GetXid = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+    let (Some(get_xdisplay), Some(get_xid)) = (symbol(c"gdk_x11_display_get_xdisplay"), symbol(c"gdk_x11_surface_get_xid")) else {
+        return None;
+    };
+    // SAFETY: 两个符号由 GTK 的 X11 后端导出，签名与其头文件一致；
+    // surface 与其 display 属于 X11 后端（调用方已用 is_x11 检查）
+    let (xdisplay, xid) = unsafe {
+        let get_xdisplay = std::mem::transmute::<*mut c_void, GetXDisplay>(get_xdisplay);
+        let get_xid = std::mem::transmute::<*mut c_void, GetXid>(get_xid);
+        (get_xdisplay(surface.display().as_ptr() as *mut c_void), get_xid(surface.as_ptr() as *mut c_void))
+    };
+    (!xdisplay.is_null() && xid != 0).then_some((xdisplay, xid))
+}
+
+// 指针在整个屏幕上的位置
+pub fn pointer_position(surface: &gdk::Surface) -> Option<(i32, i32)> {
+    type RootWindow = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+    type QueryPointer = unsafe extern "C" fn(
+        *mut c_void, c_ulong, *mut c_ulong, *mut c_ulong, *mut c_int, *mut c_int, *mut c_int, *mut c_int, *mut c_uint,
+    ) -> c_int;
+    let (xdisplay, _) = handles(surface)?;
+    let (Some(root_window), Some(query)) = (symbol(c"XDefaultRootWindow"), symbol(c"XQueryPointer")) else { return None };
+    // SAFETY: 符号由 libX11 导出，签名与 Xlib.h 一致；输出参数都指向本函数中的局部变量
+    let (x, y) = unsafe {
+        let root_window = std::mem::transmute::<*mut c_void, RootWindow>(root_window);
+        let query = std::mem::transmute::<*mut c_void, QueryPointer>(query);
+        let root = root_window(xdisplay);
+        let (mut root_return, mut child) = (0, 0);
+        let (mut x, mut y, mut win_x, mut win_y, mut mask) = (0, 0, 0, 0, 0);
+        // 指针在其他屏幕（多屏幕的 X 显示）上时返回 False
+        if query(xdisplay, root, &mut root_return, &mut child, &mut x, &mut y, &mut win_x, &mut win_y, &mut mask) == 0 {
+            return None;
+        }
+        (x, y)
+    };
+    let scale = surface.scale_factor().max(1);
+    Some((x / scale, y / scale))
+}
+
+// 把顶层窗口移到屏幕上的 (x, y)；窗口管理器按 ICCCM 把它作为外框的位置处理
+pub fn move_to(surface: &gdk::Surface, (x, y): (i32, i32)) -> bool {
+    type MoveWindow = unsafe extern "C" fn(*mut c_void, c_ulong, c_int, c_int) -> c_int;
+    type Flush = unsafe extern "C" fn(*mut c_void) -> c_int;
+    let Some((xdisplay, xid)) = handles(surface) else { return false };
+    let (Some(move_window), Some(flush)) = (symbol(c"XMoveWindow"), symbol(c"XFlush")) else { return false };
+    let scale = surface.scale_factor().max(1);
+    // SAFETY: 符号由 libX11 导出，签名与 Xlib.h 一致；xdisplay 与 xid 来自仍然有效的 surface
+    unsafe {
+        let move_window = std::mem::transmute::<*mut c_void, MoveWindow>(move_window);
+        let flush = std::mem::transmute::<*mut c_void, Flush>(flush);
+        move_window(xdisplay, xid, x * scale, y * scale);
+        flush(xdisplay);
+    }
+    true
+}
+
+// 显示器除面板、停靠栏以外的工作区域（_NET_WORKAREA），取不到时为整个显示器
+pub fn workarea(monitor: &gdk::Monitor) -> Rect {
+    type GetWorkarea = unsafe extern "C" fn(*mut c_void, *mut gdk::ffi::GdkRectangle);
+    let geometry = monitor.geometry();
+    let fallback = Rect::new(geometry.x(), geometry.y(), geometry.width(), geometry.height());
+    let Some(get_workarea) = symbol(c"gdk_x11_monitor_get_workarea") else { return fallback };
+    let mut area = gdk::ffi::GdkRectangle { x: 0, y: 0, width: 0, height: 0 };
+    // SAFETY: 符号由 GTK 的 X11 后端导出，签名与 gdkx11monitor.h 一致；monitor 属于 X11 后端
+    unsafe {
+        let get_workarea = std::mem::transmute::<*mut c_void, GetWorkarea>(get_workarea);
+        get_workarea(monitor.as_ptr() as *mut c_void, &mut area);
+    }
+    if area.width > 0 && area.height > 0 { Rect::new(area.x, area.y, area.width, area.height) } else { fallback }
+}
+
+// 除 surface 自身以外、叠放次序最上面的可见窗口（即置顶前正在使用的窗口）
+// 找不到 X11 函数、窗口管理器不支持 _NET_CLIENT_LIST_STACKING 或没有其他窗口时返回 None
+pub fn topmost_other_window(surface: &gdk::Surface) -> Option<Rect> {
+    type InternAtom = unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_ulong;
+    type RootWindow = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+    type GetWindowProperty = unsafe extern "C" fn(
+        *mut c_void, c_ulong, c_ulong, c_long, c_long, c_int, c_ulong,
+        *mut c_ulong, *mut c_int, *mut c_ulong, *mut c_ulong, *mut *mut c_uchar,
+    ) -> c_int;
+    type GetWindowAttributes = unsafe extern "C" fn(*mut c_void, c_ulong, *mut WindowAttributes) -> c_int;
+    type TranslateCoordinates = unsafe extern "C" fn(*mut c_void, c_ulong, c_ulong, c_int, c_int, *mut c_int, *mut c_int, *mut c_ulong) -> c_int;
+    type Free = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+    let (xdisplay, own) = handles(surface)?;
+    let (
+        Some(intern_atom), Some(root_window), Some(get_property), Some(get_attributes), Some(translate), Some(free),
+    ) = (
+        symbol(c"XInternAtom"),
+        symbol(c"XDefaultRootWindow"),
+        symbol(c"XGetWindowProperty"),
+        symbol(c"XGetWindowAttributes"),
+        symbol(c"XTranslateCoordinates"),
+        symbol(c"XFree"),
+    ) else {
+        return None;
+    };
+    // SAFETY: 以上符号由 libX11 导出，签名与其头文件一致；xdisplay 在调用期间保持有效；
+    // XGetWindowProperty 返回的数组由 XFree 释放，长度不超过 nitems
+    unsafe {
+        let intern_atom = std::mem::transmute::<*mut c_void, InternAtom>(intern_atom);
+        let root_window = std::mem::transmute::<*mut c_void, RootWindow>(root_window);
+        let get_property = std::mem::transmute::<*mut c_void, GetWindowProperty>(get_property);
+        let get_attributes = std::mem::transmute::<*mut c_void, GetWindowAttributes>(get_attributes);
+        let translate = std::mem::transmute::<*mut c_void, TranslateCoordinates>(translate);
+        let free = std::mem::transmute::<*mut c_void, Free>(free);
+
+        let root = root_window(xdisplay);
+        let stacking = intern_atom(xdisplay, c"_NET_CLIENT_LIST_STACKING".as_ptr(), 0);
+        let (mut kind, mut format, mut count, mut after) = (0, 0, 0, 0);
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+        let status = get_property(
+            xdisplay, root, stacking, 0, MAX_CLIENTS, 0, XA_WINDOW,
+            &mut kind, &mut format, &mut count, &mut after, &mut data,
+        );
+        if status != SUCCESS || data.is_null() { return None; }
+        // 格式 32 的属性在客户端以 long 数组返回；列表从下到上排列
+        let windows = if kind == XA_WINDOW && format == 32 {
+            std::slice::from_raw_parts(data as *const c_ulong, count as usize).to_vec()
+        } else {
+            Vec::new()
+        };
+        free(data as *mut c_void);
+
+        // 最小化或位于其他工作区的窗口不可见，跳过
+        let found = windows.into_iter().rev().filter(|&w| w != own).find_map(|w| {
+            let mut attrs = std::mem::zeroed::<WindowAttributes>();
+            if get_attributes(xdisplay, w, &mut attrs) == 0 || attrs.map_state != IS_VIEWABLE { return None; }
+            let (mut x, mut y, mut child) = (0, 0, 0);
+            if translate(xdisplay, w, root, 0, 0, &mut x, &mut y, &mut child) == 0 { return None; }
+            (attrs.width > 0 && attrs.height > 0).then_some(Rect::new(x, y, attrs.width, attrs.height))
+        })?;
+        // X11 的坐标是设备像素，GDK 的显示器几何是逻辑像素
+        let scale = surface.scale_factor().max(1);
+        Some(Rect::new(found.x / scale, found.y / scale, found.width / scale, found.height / scale))
+    }
+}